@@ -3,24 +3,48 @@ use std::vec;
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coin, to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError,
-    StdResult, Storage, Uint128,
+    coin, from_binary, to_binary, Addr, Binary, CosmosMsg, Deps, DepsMut, DistributionMsg, Env,
+    MessageInfo, Order, Response, StakingMsg, StdError, StdResult, Storage, Uint128,
 };
 
 use cw2::set_contract_version;
+use cw20::Cw20ReceiveMsg;
 
 use crate::error::ContractError;
 use crate::state::{
-    AddressesSet, AssetsInPool, SudoParams, ADDRESSES_SET, ASSETS_IN_POOL, SUDO_PARAMS,
+    load_collateral_staking, AddressesSet, ADDRESSES_SET, ASSETS_IN_POOL, COLLATERAL_STAKING,
+    DELEGATIONS, PAYOUTS, STAKING_CONFIG,
 };
-use ultra_base::active_pool::{ExecuteMsg, InstantiateMsg, ParamsResponse, QueryMsg};
+use ultra_base::active_pool::{
+    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, ParamsResponse, QueryMsg,
+    ReconcileResponse,
+};
+use ultra_base::address_registry::ProtocolContract;
+use ultra_base::asset::{Asset, AssetInfo};
+use ultra_base::coll_surplus_pool::Cw20HookMsg as CollSurplusPoolCw20HookMsg;
+use ultra_base::collateral_staking::{
+    delegation_plan, undelegation_plan, CollateralStaking, Rebalance, StakingConfig,
+    ValidatorDelegation,
+};
+use ultra_base::default_pool::{
+    Cw20HookMsg as DefaultPoolCw20HookMsg, ExecuteMsg as DefaultPoolExecuteMsg,
+};
+use ultra_base::migration::{migrate_contract, Migration};
+use ultra_base::querier::{query_collateral, query_protocol_address};
+use ultra_pool::access::{self, Role};
+use ultra_pool::events::PoolEvent;
+use ultra_pool::funds::{
+    collateral_denom, must_pay_collateral, must_receive_collateral, validate_collateral,
+};
+use ultra_pool::ledger::AssetsInPool;
+use ultra_pool::ownership;
+use ultra_pool::registry;
+use ultra_pool::state::{load_collateral, SudoParams, COLLATERAL, SUDO_PARAMS};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:active-pool";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub const NATIVE_JUNO_DENOM: &str = "ujuno";
-
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -38,18 +62,36 @@ pub fn instantiate(
     };
 
     // initial assets in pool
-    let assets_in_pool = AssetsInPool {
-        juno: Uint128::zero(),
-        ultra_debt: Uint128::zero(),
-    };
+    let assets_in_pool = AssetsInPool::default();
 
-    // save sudo params and initial assets in pool in contract storage
+    // native or cw20 collateral held by the pool
+    let collateral = validate_collateral(deps.api, msg.collateral)?;
+
+    // save sudo params, collateral and initial assets in pool in contract storage
     SUDO_PARAMS.save(deps.storage, &sudo_params)?;
+    COLLATERAL.save(deps.storage, &collateral)?;
     ASSETS_IN_POOL.save(deps.storage, &assets_in_pool)?;
 
+    // staking is disabled until the owner sets a staking config
+    COLLATERAL_STAKING.save(deps.storage, &CollateralStaking::default())?;
+
     Ok(Response::default())
 }
 
+// State migrations, ordered by the version introducing them
+const MIGRATIONS: &[Migration] = &[];
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // check the stored contract name and version, then apply the pending state migrations
+    Ok(migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+    )?)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -67,26 +109,66 @@ pub fn execute(
             // Call the `execute_decrease_ultra_debt` function
             execute_decrease_ultra_debt(deps, env, info, amount)
         }
-        ExecuteMsg::SendJUNO { recipient, amount } => {
-            // Call the `execute_send_juno` function
-            execute_send_juno(deps, env, info, recipient, amount)
+        ExecuteMsg::SendCollateral { recipient, amount } => {
+            // Call the `execute_send_collateral` function
+            execute_send_collateral(deps, env, info, recipient, amount)
+        }
+        ExecuteMsg::ReceiveCollateral {} => {
+            // Call the `execute_receive_collateral` function
+            execute_receive_collateral(deps, env, info)
+        }
+        ExecuteMsg::Receive(cw20_msg) => {
+            // Call the `execute_receive` function
+            execute_receive(deps, env, info, cw20_msg)
+        }
+        ExecuteMsg::SweepSurplus { recipient } => {
+            // Call the `execute_sweep_surplus` function
+            execute_sweep_surplus(deps, env, info, recipient)
+        }
+        ExecuteMsg::SetStakingConfig { config } => {
+            // Call the `execute_set_staking_config` function
+            execute_set_staking_config(deps, env, info, config)
+        }
+        ExecuteMsg::Rebalance {} => {
+            // Call the `execute_rebalance` function
+            execute_rebalance(deps, env, info)
+        }
+        ExecuteMsg::ClaimStakingRewards {} => {
+            // Call the `execute_claim_staking_rewards` function
+            execute_claim_staking_rewards(deps, env, info)
+        }
+        ExecuteMsg::ClaimPayout {} => {
+            // Call the `execute_claim_payout` function
+            execute_claim_payout(deps, env, info)
+        }
+        ExecuteMsg::SendStakingReward { recipient, amount } => {
+            // Call the `execute_send_staking_reward` function
+            execute_send_staking_reward(deps, env, info, recipient, amount)
+        }
+        ExecuteMsg::SetAddressRegistry { address_registry } => {
+            // Call the `execute_set_address_registry` function
+            execute_set_address_registry(deps, env, info, address_registry)
+        }
+        ExecuteMsg::SyncAddresses {} => {
+            // Call the `execute_sync_addresses` function
+            execute_sync_addresses(deps, env, info)
+        }
+        ExecuteMsg::ProposeOwner { owner } => {
+            // Call the `execute_propose_owner` function
+            execute_propose_owner(deps, env, info, owner)
+        }
+        ExecuteMsg::AcceptOwner {} => {
+            // Call the `execute_accept_owner` function
+            execute_accept_owner(deps, env, info)
+        }
+        ExecuteMsg::RenounceOwnership {} => {
+            // Call the `execute_renounce_ownership` function
+            execute_renounce_ownership(deps, env, info)
+        }
+        ExecuteMsg::LockAddresses {} => {
+            // Call the `execute_lock_addresses` function
+            execute_lock_addresses(deps, env, info)
         }
-        ExecuteMsg::SetAddresses {
-            borrower_operations_address,
-            trove_manager_address,
-            stability_pool_address,
-            default_pool_address,
-        } =>
-            // Call the `execute_set_addresses` function
-            execute_set_addresses(
-                deps,
-                env,
-                info,
-                borrower_operations_address,
-                trove_manager_address,
-                stability_pool_address,
-                default_pool_address,
-            ),
     }
 }
 
@@ -96,7 +178,6 @@ pub fn execute_increase_ultra_debt(
     info: MessageInfo, // Information about the message that triggered this function call
     amount: Uint128, // The amount to increase the ultra debt by
 ) -> Result<Response, ContractError> {
-
     // Check that the message sender is either the BO or the TM
     only_bo_or_tm(deps.storage, &info)?;
 
@@ -104,18 +185,13 @@ pub fn execute_increase_ultra_debt(
     let mut assets_in_pool = ASSETS_IN_POOL.load(deps.storage)?;
 
     // Increase the ultra debt by the specified amount
-    assets_in_pool.ultra_debt += amount;
+    assets_in_pool.increase_debt(amount)?;
 
     // Save the updated assets in the pool
     ASSETS_IN_POOL.save(deps.storage, &assets_in_pool)?;
 
-    // Create a response object with information about the action taken
-    let res = Response::new()
-        .add_attribute("action", "increase_ultra_debt")
-        .add_attribute("amount", amount);
-
-    // Return the response
-    Ok(res)
+    // Return the response with information about the action taken
+    Ok(PoolEvent::IncreaseULTRADebt { amount }.into_response())
 }
 
 pub fn execute_decrease_ultra_debt(
@@ -124,7 +200,6 @@ pub fn execute_decrease_ultra_debt(
     info: MessageInfo,
     amount: Uint128,
 ) -> Result<Response, ContractError> {
-
     // Ensure that the caller is either the Board of Directors, Treasury Manager, or Shareholder Proxy
     only_bo_or_tm_or_sp(deps.storage, &info)?;
 
@@ -132,69 +207,446 @@ pub fn execute_decrease_ultra_debt(
     let mut assets_in_pool = ASSETS_IN_POOL.load(deps.storage)?;
 
     // Check that the new value of ultra_debt will not overflow, then update the value
-    assets_in_pool.ultra_debt = assets_in_pool
-        .ultra_debt
-        .checked_sub(amount)
-        .map_err(StdError::overflow)?;
+    assets_in_pool.decrease_debt(amount)?;
 
     // Save the updated value of the assets in the pool
     ASSETS_IN_POOL.save(deps.storage, &assets_in_pool)?;
 
     // Create and return the response object
-    let res = Response::new()
-        .add_attribute("action", "decrease_ultra_debt")
-        .add_attribute("amount", amount);
-    Ok(res)
+    Ok(PoolEvent::DecreaseULTRADebt { amount }.into_response())
 }
 
-pub fn execute_send_juno(
-    deps: DepsMut, // a set of dependencies
-    _env: Env, // an environment object
+pub fn execute_send_collateral(
+    deps: DepsMut,     // a set of dependencies
+    env: Env,          // an environment object
     info: MessageInfo, // a message info object
-    recipient: Addr, // the recipient address
-    amount: Uint128, // the amount of JUNO tokens to send
+    recipient: Addr,   // the recipient address
+    amount: Uint128,   // the amount of collateral to send
 ) -> Result<Response, ContractError> {
     only_bo_or_tm_or_sp(deps.storage, &info)?; // check that the caller is BO, TM, or SP
 
     let mut assets_in_pool = ASSETS_IN_POOL.load(deps.storage)?; // retrieve assets in pool from storage
-    assets_in_pool.juno = assets_in_pool
-        .juno
-        .checked_sub(amount) // subtract the specified amount of JUNO tokens from the pool
-        .map_err(StdError::overflow)?; // return error if there is an overflow
+
+    // only the collateral that is neither delegated nor unbonding can be sent right away
+    let mut staking = load_collateral_staking(deps.storage)?;
+    staking.mature_unbondings(env.block.time)?;
+    let liquid = staking.liquid_coll(assets_in_pool.coll)?;
+
+    assets_in_pool.decrease_coll(amount)?; // subtract the specified amount of collateral from the pool
     ASSETS_IN_POOL.save(deps.storage, &assets_in_pool)?; // save updated assets in pool to storage
 
-    let send_msg = BankMsg::Send { // construct a BankMsg::Send message
-        to_address: recipient.to_string(),
-        amount: vec![coin(amount.u128(), NATIVE_JUNO_DENOM.to_string())],
-    };
-    let res = Response::new() // create a new response
-        .add_message(send_msg) // add the BankMsg::Send message to the response
-        .add_attribute("action", "send_juno") // add an attribute to the response
-        .add_attribute("recipient", recipient) // add an attribute to the response
-        .add_attribute("amount", amount); // add an attribute to the response
+    // the rest is queued for the recipient until a rebalance brings it back from unbonding,
+    // except for the protocol contracts which record the collateral they receive
+    let collateral = load_collateral(deps.storage)?;
+    let addresses_set = ADDRESSES_SET.load(deps.storage)?;
+    let queued = amount.saturating_sub(liquid);
+    if !queued.is_zero() {
+        if is_protocol_contract(deps.as_ref(), &addresses_set, &recipient)? {
+            return Err(ContractError::InsufficientLiquidJUNO { liquid });
+        }
+        staking.queue_payout(queued)?;
+        PAYOUTS.update(deps.storage, &recipient, |payout| {
+            payout
+                .unwrap_or_default()
+                .checked_add(queued)
+                .map_err(StdError::overflow)
+        })?;
+    }
+    COLLATERAL_STAKING.save(deps.storage, &staking)?;
+
+    // the default pool has to record the collateral it receives during redistribution,
+    // so it is notified through its receive entry, or its cw20 hook, instead of a plain transfer.
+    // The coll surplus pool takes cw20 collateral through its hook as well
+    let sent = amount - queued;
+    let mut res = PoolEvent::SendCollateral {
+        recipient: recipient.clone(),
+        amount,
+    }
+    .into_response(); // create a new response
+    if !sent.is_zero() {
+        let send_msg: CosmosMsg = if recipient == addresses_set.default_pool_address {
+            let receive_msg = if collateral.is_native_token() {
+                to_binary(&DefaultPoolExecuteMsg::ReceiveCollateral {})?
+            } else {
+                to_binary(&DefaultPoolCw20HookMsg::ReceiveCollateral {})?
+            };
+            collateral_asset(collateral, sent).into_execute_msg(&recipient, receive_msg)?
+        } else if !collateral.is_native_token() && is_coll_surplus_pool(deps.as_ref(), &recipient)?
+        {
+            let receive_msg = to_binary(&CollSurplusPoolCw20HookMsg::ReceiveCollateral {})?;
+            collateral_asset(collateral, sent).into_execute_msg(&recipient, receive_msg)?
+        } else {
+            collateral_asset(collateral, sent).into_msg(&deps.querier, &recipient)?
+        };
+        res = res.add_message(send_msg); // add the send message to the response
+    }
+    if !queued.is_zero() {
+        res = res.add_attribute("queued", queued);
+    }
     Ok(res) // return the response
 }
 
-// This function updates the set of contract addresses that the current contract depends on.
-// It only allows the contract owner to update these addresses.
-pub fn execute_set_addresses(
-    deps: DepsMut, // The contract dependencies, including the storage and the API
-    _env: Env, // The contract environment, which provides information about the blockchain
-    info: MessageInfo, // Information about the message that triggered this contract execution
-    borrower_operations_address: String, // The new address of the borrower operations contract
-    trove_manager_address: String, // The new address of the trove manager contract
-    stability_pool_address: String, // The new address of the stability pool contract
-    default_pool_address: String, // The new address of the default pool contract
+pub fn execute_receive_collateral(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // Only borrower operations and the default pool move collateral into the pool
+    only_bo_or_dp(deps.storage, &info)?;
+
+    // Only the collateral can be received, any other coin would never be accounted for
+    let collateral = load_collateral(deps.storage)?;
+    let amount = must_pay_collateral(&info, &collateral)?;
+
+    record_collateral(deps.storage, info.sender, amount)
+}
+
+// This function records the cw20 collateral sent through the cw20 hook,
+// the sender of the tokens standing for the caller
+pub fn execute_receive(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    // Only the collateral token can be received
+    let collateral = load_collateral(deps.storage)?;
+    let amount = must_receive_collateral(&info, &collateral, cw20_msg.amount)?;
+
+    let sender_info = MessageInfo {
+        sender: deps.api.addr_validate(&cw20_msg.sender)?,
+        funds: vec![],
+    };
+    match from_binary(&cw20_msg.msg)? {
+        Cw20HookMsg::ReceiveCollateral {} => {
+            // Only borrower operations and the default pool move collateral into the pool
+            only_bo_or_dp(deps.storage, &sender_info)?;
+            record_collateral(deps.storage, sender_info.sender, amount)
+        }
+    }
+}
+
+// This function records `amount` collateral received from `sender`
+fn record_collateral(
+    store: &mut dyn Storage,
+    sender: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut assets_in_pool = ASSETS_IN_POOL.load(store)?;
+    assets_in_pool.increase_coll(amount)?;
+    ASSETS_IN_POOL.save(store, &assets_in_pool)?;
+
+    Ok(PoolEvent::ReceiveCollateral { sender, amount }.into_response())
+}
+
+// This function sends the collateral held by the pool but not recorded
+// (e.g. plain bank transfers to the pool) to the given recipient.
+pub fn execute_sweep_surplus(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    recipient: String,
+) -> Result<Response, ContractError> {
+    // Only the owner can sweep the surplus
+    only_owner(deps.storage, &info)?;
+
+    // In staking mode the unbondings are matured off the configured unbonding period, so the
+    // JUNO the chain releases before then could pass for surplus
+    if STAKING_CONFIG.may_load(deps.storage)?.is_some() {
+        return Err(ContractError::StakingEnabled {});
+    }
+
+    let recipient = deps.api.addr_validate(&recipient)?;
+
+    // Compare the recorded collateral with the actual bank balance
+    let reconcile = query_reconcile(deps.as_ref(), env)?;
+    if reconcile.surplus.is_zero() {
+        return Err(ContractError::NoSurplusToSweep {});
+    }
+
+    let sweep_amount = reconcile.surplus;
+    let send_msg = collateral_asset(load_collateral(deps.storage)?, sweep_amount)
+        .into_msg(&deps.querier, &recipient)?;
+    let res = Response::new()
+        .add_message(send_msg)
+        .add_attribute("action", "sweep_surplus")
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", sweep_amount);
+    Ok(res)
+}
+
+// This function enables the staking mode with the given config, or disables it once all the
+// JUNO is undelegated and unbonded.
+pub fn execute_set_staking_config(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    config: Option<StakingConfig>,
+) -> Result<Response, ContractError> {
+    // Only the owner can change the staking config
+    only_owner(deps.storage, &info)?;
+
+    let config = match config {
+        Some(config) => config,
+        None => {
+            // Staking is unwound by a rebalance with a zero staked ratio beforehand
+            let mut staking = load_collateral_staking(deps.storage)?;
+            staking.mature_unbondings(env.block.time)?;
+            if !staking.delegated.is_zero() || !staking.unbonding.is_empty() {
+                return Err(ContractError::StakingNotUnwound {});
+            }
+            COLLATERAL_STAKING.save(deps.storage, &staking)?;
+            STAKING_CONFIG.remove(deps.storage);
+            return Ok(Response::new().add_attribute("action", "disable_staking"));
+        }
+    };
+
+    // The validators must exist and bond the collateral
+    config.validate()?;
+    let denom = deps.querier.query_bonded_denom()?;
+    if denom != collateral_denom(&load_collateral(deps.storage)?)? {
+        return Err(ContractError::InvalidBondedDenom { denom });
+    }
+    for validator in &config.validators {
+        if deps.querier.query_validator(validator)?.is_none() {
+            return Err(ContractError::ValidatorNotFound {
+                validator: validator.clone(),
+            });
+        }
+    }
+    STAKING_CONFIG.save(deps.storage, &config)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_staking_config")
+        .add_attribute("validators", config.validators.join(","))
+        .add_attribute("staked_ratio", config.staked_ratio.to_string())
+        .add_attribute("min_rebalance", config.min_rebalance);
+    Ok(res)
+}
+
+// This function delegates the liquid JUNO above the buffer, or undelegates JUNO to refill it.
+// Anyone can call it as the amounts only depend on the recorded collateral and the config.
+pub fn execute_rebalance(
+    mut deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = STAKING_CONFIG.may_load(deps.storage)?;
+    let collateral = load_collateral(deps.storage)?;
+    let denom = collateral_denom(&collateral)?;
+
+    // Unbondings completed since the last rebalance are liquid again
+    let mut staking = load_collateral_staking(deps.storage)?;
+    staking.mature_unbondings(env.block.time)?;
+
+    // The plans start from the delegations left by the slashings since the last rebalance
+    let slashed = reconcile_slashing(deps.branch(), &env, &mut staking)?;
+    let coll = ASSETS_IN_POOL.load(deps.storage)?.coll;
+
+    let validators = config
+        .as_ref()
+        .map(|config| config.validators.clone())
+        .unwrap_or_default();
+    let mut res = Response::new().add_attribute("action", "rebalance");
+    if !slashed.is_zero() {
+        res = res.add_attribute("slashed", slashed);
+    }
+    match staking.rebalance(coll, config.as_ref())? {
+        Rebalance::Delegate(amount) => {
+            let plan = delegation_plan(&validators, amount);
+            let (rewards, withdraw_msgs) =
+                withdraw_plan_rewards(deps.as_ref(), &env, &mut staking, coll, &plan)?;
+            res = res.add_messages(withdraw_msgs);
+            staking.delegate(amount)?;
+            for delegation in plan {
+                DELEGATIONS.update(deps.storage, &delegation.validator, |delegated| {
+                    delegated
+                        .unwrap_or_default()
+                        .checked_add(delegation.amount)
+                        .map_err(StdError::overflow)
+                })?;
+                res = res.add_message(StakingMsg::Delegate {
+                    validator: delegation.validator,
+                    amount: coin(delegation.amount.u128(), denom),
+                });
+            }
+            res = res
+                .add_attribute("delegated", amount)
+                .add_attribute("rewards", rewards);
+        }
+        Rebalance::Undelegate(amount) => {
+            let unbonding_period = config.map_or(0, |config| config.unbonding_period);
+            staking.undelegate(amount, env.block.time.plus_seconds(unbonding_period))?;
+            let delegations = query_delegations(deps.as_ref())?;
+            let plan = undelegation_plan(&delegations, &validators, amount);
+            let (rewards, withdraw_msgs) =
+                withdraw_plan_rewards(deps.as_ref(), &env, &mut staking, coll, &plan)?;
+            res = res.add_messages(withdraw_msgs);
+            for undelegation in plan {
+                let delegated = DELEGATIONS
+                    .load(deps.storage, &undelegation.validator)?
+                    .checked_sub(undelegation.amount)
+                    .map_err(StdError::overflow)?;
+                if delegated.is_zero() {
+                    DELEGATIONS.remove(deps.storage, &undelegation.validator);
+                } else {
+                    DELEGATIONS.save(deps.storage, &undelegation.validator, &delegated)?;
+                }
+                res = res.add_message(StakingMsg::Undelegate {
+                    validator: undelegation.validator,
+                    amount: coin(undelegation.amount.u128(), denom),
+                });
+            }
+            res = res
+                .add_attribute("undelegated", amount)
+                .add_attribute("rewards", rewards);
+        }
+        Rebalance::Balanced => {}
+    }
+    COLLATERAL_STAKING.save(deps.storage, &staking)?;
+
+    Ok(res)
+}
+
+// This function withdraws the staking rewards from every validator of the pool and distributes
+// them to the troves.
+pub fn execute_claim_staking_rewards(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // Without staking config the pool has nothing delegated to claim from
+    if STAKING_CONFIG.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::StakingDisabled {});
+    }
+
+    let coll = ASSETS_IN_POOL.load(deps.storage)?.coll;
+    let mut staking = load_collateral_staking(deps.storage)?;
+    let validators: Vec<String> = query_delegations(deps.as_ref())?
+        .into_iter()
+        .map(|delegation| delegation.validator)
+        .collect();
+    let (rewards, withdraw_msgs) =
+        withdraw_accumulated_rewards(deps.as_ref(), &env, &mut staking, coll, &validators)?;
+    COLLATERAL_STAKING.save(deps.storage, &staking)?;
+
+    let res = Response::new()
+        .add_attribute("action", "claim_staking_rewards")
+        .add_attribute("rewards", rewards)
+        .add_attribute(
+            "reward_per_unit_coll",
+            staking.reward_per_unit_coll.to_string(),
+        )
+        .add_messages(withdraw_msgs);
+    Ok(res)
+}
+
+// This function sends the collateral queued for the caller, once the JUNO undelegated by the
+// rebalances is back in the bank balance of the pool.
+pub fn execute_claim_payout(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
 ) -> Result<Response, ContractError> {
-    // Ensure that only the contract owner can update the addresses set
+    let amount = PAYOUTS
+        .may_load(deps.storage, &info.sender)?
+        .ok_or(ContractError::NoPayout {})?;
+
+    let coll = ASSETS_IN_POOL.load(deps.storage)?.coll;
+    let mut staking = load_collateral_staking(deps.storage)?;
+    staking.mature_unbondings(env.block.time)?;
+    let liquid = staking.liquid_payouts(coll)?;
+    if amount > liquid {
+        return Err(ContractError::InsufficientLiquidJUNO { liquid });
+    }
+    staking.pay_payout(amount)?;
+    COLLATERAL_STAKING.save(deps.storage, &staking)?;
+    PAYOUTS.remove(deps.storage, &info.sender);
+
+    let send_msg = collateral_asset(load_collateral(deps.storage)?, amount)
+        .into_msg(&deps.querier, &info.sender)?;
+    let res = Response::new()
+        .add_attribute("action", "claim_payout")
+        .add_attribute("recipient", info.sender.to_string())
+        .add_attribute("amount", amount)
+        .add_message(send_msg);
+    Ok(res)
+}
+
+// This function pays out the staking rewards of a trove, as computed by the trove manager from
+// the reward per unit of collateral snapshotted for the trove.
+pub fn execute_send_staking_reward(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    recipient: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    // Check that the caller is BO or TM
+    only_bo_or_tm(deps.storage, &info)?;
+
+    let mut staking = load_collateral_staking(deps.storage)?;
+    staking.pay_reward(amount)?;
+    COLLATERAL_STAKING.save(deps.storage, &staking)?;
+
+    let send_msg = collateral_asset(load_collateral(deps.storage)?, amount)
+        .into_msg(&deps.querier, &recipient)?;
+    let res = Response::new()
+        .add_attribute("action", "send_staking_reward")
+        .add_attribute("recipient", recipient.to_string())
+        .add_attribute("amount", amount)
+        .add_message(send_msg);
+    Ok(res)
+}
+
+// This function points the contract to the address registry its peers are resolved from.
+
+// It only allows the contract owner to update it.
+pub fn execute_set_address_registry(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address_registry: String,
+) -> Result<Response, ContractError> {
+    // Ensure that only the contract owner can update the address registry
     only_owner(deps.storage, &info)?;
 
-    // Validate and convert the new addresses to their HEX representation
+    // Ensure that the addresses set has not been locked for good
+    ownership::ensure_addresses_unlocked(deps.storage)?;
+
+    // Validate and save the address registry
+    let address_registry = deps.api.addr_validate(&address_registry)?;
+    Ok(registry::set_address_registry(
+        deps.storage,
+        address_registry,
+    )?)
+}
+
+// This function resolves the set of contract addresses that the current contract depends on
+// from the address registry and caches it in the contract storage.
+// Anyone can call it as the addresses come from the registry set by the owner.
+pub fn execute_sync_addresses(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // Ensure that the addresses set has not been locked for good
+    ownership::ensure_addresses_unlocked(deps.storage)?;
+
+    // Resolve the peers from the address registry, the default pool being the one registered
+    // along with this pool for its collateral
+    let address_registry = registry::load_address_registry(deps.storage)?;
+    let resolve = |contract| query_protocol_address(&deps.querier, &address_registry, contract);
+    let collateral = query_collateral(
+        &deps.querier,
+        &address_registry,
+        &load_collateral(deps.storage)?,
+    )?;
     let new_addresses_set = AddressesSet {
-        borrower_operations_address: deps.api.addr_validate(&borrower_operations_address)?,
-        trove_manager_address: deps.api.addr_validate(&trove_manager_address)?,
-        stability_pool_address: deps.api.addr_validate(&stability_pool_address)?,
-        default_pool_address: deps.api.addr_validate(&default_pool_address)?,
+        borrower_operations_address: resolve(ProtocolContract::BorrowerOperations)?,
+        trove_manager_address: resolve(ProtocolContract::TroveManager)?,
+        stability_pool_address: resolve(ProtocolContract::StabilityPool)?,
+        default_pool_address: collateral.contracts.default_pool,
     };
 
     // Save the new addresses set in the contract storage
@@ -202,97 +654,266 @@ pub fn execute_set_addresses(
 
     // Build and return the response with the updated addresses set
     let res = Response::new()
-        .add_attribute("action", "set_addresses")
-        .add_attribute("borrower_operations_address", borrower_operations_address)
-        .add_attribute("trove_manager_address", trove_manager_address)
-        .add_attribute("stability_pool_address", stability_pool_address)
-        .add_attribute("default_pool_address", default_pool_address);
+        .add_attribute("action", "sync_addresses")
+        .add_attribute(
+            "borrower_operations_address",
+            new_addresses_set.borrower_operations_address,
+        )
+        .add_attribute(
+            "trove_manager_address",
+            new_addresses_set.trove_manager_address,
+        )
+        .add_attribute(
+            "stability_pool_address",
+            new_addresses_set.stability_pool_address,
+        )
+        .add_attribute(
+            "default_pool_address",
+            new_addresses_set.default_pool_address,
+        );
     Ok(res)
 }
 
+// This function proposes a new owner, who only becomes owner once it accepts.
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    owner: String,
+) -> Result<Response, ContractError> {
+    // Ensure that only the contract owner can propose a new owner
+    only_owner(deps.storage, &info)?;
+
+    // Validate the proposed owner and record it as pending
+    let owner = deps.api.addr_validate(&owner)?;
+    Ok(ownership::propose_owner(deps.storage, owner)?)
+}
+
+// This function makes the pending owner, who must be the caller, the new owner.
+pub fn execute_accept_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    Ok(ownership::accept_owner(deps.storage, &info)?)
+}
+
+// This function leaves the contract without owner, only governance can set a new one afterwards.
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // Ensure that only the contract owner can renounce the ownership
+    only_owner(deps.storage, &info)?;
+
+    Ok(ownership::renounce_ownership(deps.storage)?)
+}
+
+// This function permanently prevents the addresses set from being updated.
+pub fn execute_lock_addresses(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    // Ensure that only the contract owner can lock the addresses set
+    only_owner(deps.storage, &info)?;
+
+    // The addresses must be configured before they are locked for good
+    if ADDRESSES_SET.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::AddressesNotSet {});
+    }
+    Ok(ownership::lock_addresses(deps.storage)?)
+}
+
 /// Checks to enforce that only borrower operations or default pool can call
 fn only_bo_or_dp(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
     // Load the set of addresses
     let addresses_set = ADDRESSES_SET.load(store)?;
-    // Check if the caller is not the borrower operations address or the default pool address
-    if addresses_set.borrower_operations_address != info.sender.as_ref()
-        && addresses_set.default_pool_address != info.sender.as_ref()
-    {
-        // Return an error if the caller is not authorized
-        return Err(ContractError::CallerIsNeitherBONorDP {});
-    }
-    // Return the caller's address if the caller is authorized
-    Ok(info.sender.clone())
+    // Return the caller's address if the caller is authorized, an error otherwise
+    access::only_roles(
+        &addresses_set,
+        info,
+        &[Role::BorrowerOperations, Role::DefaultPool],
+        ContractError::CallerIsNeitherBONorDP {},
+    )
 }
 
 /// Checks to enforce that only borrower operations or trove manager or stability pool can call
 fn only_bo_or_tm_or_sp(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
     // Load the set of addresses
     let addresses_set = ADDRESSES_SET.load(store)?;
-    // Check if the caller is not the borrower operations address, the trove manager address, or the stability pool address
-    if addresses_set.borrower_operations_address != info.sender.as_ref()
-        && addresses_set.trove_manager_address != info.sender.as_ref()
-        && addresses_set.stability_pool_address != info.sender.as_ref()
-    {
-        // Return an error if the caller is not authorized
-        return Err(ContractError::CallerIsNeitherBONorTMNorSP {});
-    }
-    // Return the caller's address if the caller is authorized
-    Ok(info.sender.clone())
-}
-
-/// This function checks if the caller of the contract is either the borrower operations address or the trove manager address.
-/// If the caller is not one of these addresses, it returns an error.
-///
-/// # Arguments
-///
-/// * `store`: A reference to the contract's storage
-/// * `info`: Information about the message that called the contract
-///
-/// # Returns
-///
-/// * An `Addr` representing the caller of the contract if the caller is authorized
-/// * An error if the caller is not authorized
+    // Return the caller's address if the caller is authorized, an error otherwise
+    access::only_roles(
+        &addresses_set,
+        info,
+        &[
+            Role::BorrowerOperations,
+            Role::TroveManager,
+            Role::StabilityPool,
+        ],
+        ContractError::CallerIsNeitherBONorTMNorSP {},
+    )
+}
+
+/// Checks to enforce that only borrower operations or trove manager can call
 fn only_bo_or_tm(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
-    // Load the addresses set from storage
+    // Load the set of addresses
     let addresses_set = ADDRESSES_SET.load(store)?;
+    // Return the caller's address if the caller is authorized, an error otherwise
+    access::only_roles(
+        &addresses_set,
+        info,
+        &[Role::BorrowerOperations, Role::TroveManager],
+        ContractError::CallerIsNeitherBONorTM {},
+    )
+}
+
+/// Checks to enforce that only the owner can call
+fn only_owner(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
+    access::only_owner(store, info, ContractError::UnauthorizedOwner {})
+}
 
-    // Check if the caller is the borrower operations address or the trove manager address.
-    // If the caller is not one of these addresses, return an error.
-    if addresses_set.borrower_operations_address != info.sender.as_ref()
-        && addresses_set.trove_manager_address != info.sender.as_ref()
-    {
-        return Err(ContractError::CallerIsNeitherBONorTM {});
+/// Checks whether `recipient` is a protocol contract, which records the collateral it receives
+/// and so can't have it queued
+fn is_protocol_contract(
+    deps: Deps,
+    addresses_set: &AddressesSet,
+    recipient: &Addr,
+) -> Result<bool, ContractError> {
+    let peers = [
+        &addresses_set.borrower_operations_address,
+        &addresses_set.trove_manager_address,
+        &addresses_set.stability_pool_address,
+        &addresses_set.default_pool_address,
+    ];
+    if peers.contains(&recipient) {
+        return Ok(true);
     }
+    // The gas pool and the coll surplus pool of the collateral are only known to the address
+    // registry. The gas pool never claims payouts, it pays the collateral gas compensation
+    // out right away
+    let address_registry = registry::load_address_registry(deps.storage)?;
+    let gas_pool =
+        query_protocol_address(&deps.querier, &address_registry, ProtocolContract::GasPool)?;
+    if gas_pool == *recipient {
+        return Ok(true);
+    }
+    is_coll_surplus_pool(deps, recipient)
+}
 
-    // If the caller is authorized, return the caller's address
-    Ok(info.sender.clone())
-}
-
-/// This function checks if the caller of the contract is the owner of the contract.
-/// If the caller is not the owner, it returns an error.
-///
-/// # Arguments
-///
-/// * `store`: A reference to the contract's storage
-/// * `info`: Information about the message that called the contract
-///
-/// # Returns
-///
-/// * An `Addr` representing the caller of the contract if the caller is authorized
-/// * An error if the caller is not authorized
-fn only_owner(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
-    // Load the contract's parameters from storage
-    let params = SUDO_PARAMS.load(store)?;
+/// Checks whether `recipient` is the coll surplus pool registered for the collateral
+fn is_coll_surplus_pool(deps: Deps, recipient: &Addr) -> Result<bool, ContractError> {
+    let address_registry = registry::load_address_registry(deps.storage)?;
+    let collateral = query_collateral(
+        &deps.querier,
+        &address_registry,
+        &load_collateral(deps.storage)?,
+    )?;
+    Ok(collateral.contracts.coll_surplus_pool == *recipient)
+}
+
+/// Brings `DELEGATIONS` and the delegated JUNO down to the delegations reported by the chain,
+/// and writes the JUNO lost to slashing off the collateral of the troves. Returns the JUNO
+/// slashed
+fn reconcile_slashing(
+    deps: DepsMut,
+    env: &Env,
+    staking: &mut CollateralStaking,
+) -> Result<Uint128, ContractError> {
+    let mut slashed = Uint128::zero();
+    for delegation in query_delegations(deps.as_ref())? {
+        let amount = deps
+            .querier
+            .query_delegation(env.contract.address.clone(), &delegation.validator)?
+            .map(|delegation| delegation.amount.amount)
+            .unwrap_or_default();
+        if amount >= delegation.amount {
+            continue;
+        }
+        slashed = slashed
+            .checked_add(delegation.amount - amount)
+            .map_err(StdError::overflow)?;
+        if amount.is_zero() {
+            DELEGATIONS.remove(deps.storage, &delegation.validator);
+        } else {
+            DELEGATIONS.save(deps.storage, &delegation.validator, &amount)?;
+        }
+    }
+    if slashed.is_zero() {
+        return Ok(slashed);
+    }
+
+    let mut assets_in_pool = ASSETS_IN_POOL.load(deps.storage)?;
+    let written_off = staking.write_off_slash(slashed, assets_in_pool.coll)?;
+    assets_in_pool.decrease_coll(written_off)?;
+    ASSETS_IN_POOL.save(deps.storage, &assets_in_pool)?;
+    Ok(slashed)
+}
 
-    // Check if the caller is the owner of the contract.
-    // If the caller is not the owner, return an error.
-    if params.owner != info.sender.as_ref() {
-        return Err(ContractError::UnauthorizedOwner {});
+/// Shares between the troves the staking rewards accumulated with `validators`. Returns the
+/// rewards distributed, and the messages withdrawing them from the validators holding any
+fn withdraw_accumulated_rewards(
+    deps: Deps,
+    env: &Env,
+    staking: &mut CollateralStaking,
+    coll: Uint128,
+    validators: &[String],
+) -> Result<(Uint128, Vec<DistributionMsg>), ContractError> {
+    let collateral = load_collateral(deps.storage)?;
+    let denom = collateral_denom(&collateral)?;
+    let mut rewards = Uint128::zero();
+    let mut withdraw_msgs = vec![];
+    for validator in validators {
+        let delegation = deps
+            .querier
+            .query_delegation(env.contract.address.clone(), validator)?;
+        let accumulated = delegation
+            .into_iter()
+            .flat_map(|delegation| delegation.accumulated_rewards)
+            .filter(|reward| reward.denom == denom)
+            .try_fold(Uint128::zero(), |total, reward| {
+                total.checked_add(reward.amount)
+            })
+            .map_err(StdError::overflow)?;
+        if !accumulated.is_zero() {
+            rewards = rewards
+                .checked_add(accumulated)
+                .map_err(StdError::overflow)?;
+            withdraw_msgs.push(DistributionMsg::WithdrawDelegatorReward {
+                validator: validator.clone(),
+            });
+        }
     }
+    if staking.distribute_rewards(rewards, coll)? {
+        Ok((rewards, withdraw_msgs))
+    } else {
+        Ok((Uint128::zero(), withdraw_msgs))
+    }
+}
 
-    // If the caller is authorized, return the caller's address
-    Ok(info.sender.clone())
+/// Shares the staking rewards accumulated with the validators of `plan`, withdrawn before their
+/// delegations change
+fn withdraw_plan_rewards(
+    deps: Deps,
+    env: &Env,
+    staking: &mut CollateralStaking,
+    coll: Uint128,
+    plan: &[ValidatorDelegation],
+) -> Result<(Uint128, Vec<DistributionMsg>), ContractError> {
+    let validators: Vec<String> = plan
+        .iter()
+        .map(|delegation| delegation.validator.clone())
+        .collect();
+    withdraw_accumulated_rewards(deps, env, staking, coll, &validators)
+}
+
+/// Returns `amount` of the collateral held by the pool
+fn collateral_asset(collateral: AssetInfo, amount: Uint128) -> Asset {
+    Asset {
+        info: collateral,
+        amount,
+    }
 }
 
 // This line sets the entry point for the code depending on whether the "library"
@@ -300,14 +921,20 @@ fn only_owner(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractE
 #[cfg_attr(not(feature = "library"), entry_point)]
 
 // This function processes different types of messages and returns the result as a binary value.
-pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
     // Match the type of the message.
     match msg {
         // If the message is a request to get the parameters, call `query_params` and return the result as a binary value.
         QueryMsg::GetParams {} => to_binary(&query_params(deps)?),
 
-        // If the message is a request to get the JUNO state, call `query_juno_state` and return the result as a binary value.
-        QueryMsg::GetJUNO {} => to_binary(&query_juno_state(deps)?),
+        // If the message is a request to get the ownership status, call `query_ownership` and return the result as a binary value.
+        QueryMsg::GetOwnership {} => to_binary(&ownership::query_ownership(deps.storage)?),
+
+        // If the message is a request to get the collateral state, call `query_coll_state` and return the result as a binary value.
+        QueryMsg::GetCollateral {} => to_binary(&query_coll_state(deps)?),
+
+        // If the message is a request to get the collateral type, load it and return it as a binary value.
+        QueryMsg::GetCollateralInfo {} => to_binary(&load_collateral(deps.storage)?),
 
         // If the message is a request to get the ULTRADebt state, call `query_ultra_debt_state` and return the result as a binary value.
         QueryMsg::GetULTRADebt {} => to_binary(&query_ultra_debt_state(deps)?),
@@ -325,18 +952,43 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
 
         // If the message is a request to get the trove manager address, call `query_trove_manager_address` and return the result as a binary value.
         QueryMsg::GetTroveManagerAddress {} => to_binary(&query_trove_manager_address(deps)?),
+
+        // If the message is a request to reconcile the recorded collateral with the balance, call `query_reconcile` and return the result as a binary value.
+        QueryMsg::Reconcile {} => to_binary(&query_reconcile(deps, env)?),
+
+        // If the message is a request to get the staking config, return it if staking is enabled.
+        QueryMsg::GetStakingConfig {} => to_binary(&STAKING_CONFIG.may_load(deps.storage)?),
+
+        // If the message is a request to get the collateral staking, call `query_collateral_staking` and return the result as a binary value.
+        QueryMsg::GetCollateralStaking {} => to_binary(&query_collateral_staking(deps, &env)?),
+
+        // If the message is a request to get the delegations, call `query_delegations` and return the result as a binary value.
+        QueryMsg::GetDelegations {} => to_binary(&query_delegations(deps)?),
+
+        // If the message is a request to get the next rebalance, call `query_rebalance` and return the result as a binary value.
+        QueryMsg::GetRebalance {} => to_binary(&query_rebalance(deps, &env)?),
+
+        // If the message is a request to get the collateral queued for an address, load it and return it as a binary value.
+        QueryMsg::GetPayout { address } => {
+            let address = deps.api.addr_validate(&address)?;
+            to_binary(
+                &PAYOUTS
+                    .may_load(deps.storage, &address)?
+                    .unwrap_or_default(),
+            )
+        }
     }
 }
 
-// This function retrieves the JUNO state from storage and returns it as a result.
-pub fn query_juno_state(deps: Deps) -> StdResult<Uint128> {
+// This function retrieves the collateral state from storage and returns it as a result.
+pub fn query_coll_state(deps: Deps) -> StdResult<Uint128> {
     // Load the assets in the pool from storage.
     let info = ASSETS_IN_POOL.load(deps.storage)?;
 
-    // Retrieve the JUNO state from the assets in the pool.
-    let res = info.juno;
+    // Retrieve the collateral state from the assets in the pool.
+    let res = info.coll;
 
-    // Return the JUNO state.
+    // Return the collateral state.
     Ok(res)
 }
 
@@ -404,3 +1056,58 @@ pub fn query_trove_manager_address(deps: Deps) -> StdResult<Addr> {
     // Return the trove manager address as a StdResult.
     Ok(trove_manager_address)
 }
+
+// This function compares the collateral recorded in the pool with the pool's actual balance.
+pub fn query_reconcile(deps: Deps, env: Env) -> StdResult<ReconcileResponse> {
+    // Load the assets in the pool from storage.
+    let assets_in_pool = ASSETS_IN_POOL.load(deps.storage)?;
+
+    // Query the actual collateral balance held by the pool.
+    let balance =
+        load_collateral(deps.storage)?.query_pool(&deps.querier, env.contract.address.clone())?;
+
+    // The delegated and unbonding collateral is out of the bank balance, the staking rewards
+    // held for the troves and the queued payouts are in it without being recorded.
+    let staking = query_collateral_staking(deps, &env)?;
+    let held = balance
+        .checked_add(staking.delegated)?
+        .checked_add(staking.total_unbonding()?)?;
+    let owed = assets_in_pool
+        .coll
+        .checked_add(staking.rewards_held)?
+        .checked_add(staking.queued_payouts)?;
+
+    // Return both values along with their difference.
+    let res = ReconcileResponse {
+        recorded_coll: assets_in_pool.coll,
+        balance,
+        surplus: held.saturating_sub(owed),
+        deficit: owed.saturating_sub(held),
+    };
+    Ok(res)
+}
+
+// This function returns the collateral staking, without the unbondings completed by now.
+pub fn query_collateral_staking(deps: Deps, env: &Env) -> StdResult<CollateralStaking> {
+    let mut staking = load_collateral_staking(deps.storage)?;
+    staking.mature_unbondings(env.block.time)?;
+    Ok(staking)
+}
+
+// This function lists the JUNO delegated to each validator.
+pub fn query_delegations(deps: Deps) -> StdResult<Vec<ValidatorDelegation>> {
+    DELEGATIONS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| {
+            let (validator, amount) = item?;
+            Ok(ValidatorDelegation { validator, amount })
+        })
+        .collect()
+}
+
+// This function returns the delegation or undelegation the next rebalance would make.
+pub fn query_rebalance(deps: Deps, env: &Env) -> StdResult<Rebalance> {
+    let config = STAKING_CONFIG.may_load(deps.storage)?;
+    let coll = ASSETS_IN_POOL.load(deps.storage)?.coll;
+    query_collateral_staking(deps, env)?.rebalance(coll, config.as_ref())
+}
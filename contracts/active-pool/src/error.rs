@@ -1,11 +1,23 @@
-use cosmwasm_std::StdError;
+use cosmwasm_std::{StdError, Uint128};
 use thiserror::Error;
+use ultra_base::collateral_staking::CollateralStakingError;
+use ultra_base::migration::MigrationError;
+use ultra_pool::PoolError;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Pool(#[from] PoolError),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
+    #[error("{0}")]
+    CollateralStaking(#[from] CollateralStakingError),
+
     #[error("UnauthorizedOwner")]
     UnauthorizedOwner {},
 
@@ -17,4 +29,31 @@ pub enum ContractError {
 
     #[error("ActivePool: Caller is neither BorrowerOperations nor TroveManager")]
     CallerIsNeitherBONorTM {},
+
+    #[error("ActivePool: No unaccounted JUNO to sweep")]
+    NoSurplusToSweep {},
+
+    #[error("ActivePool: Addresses must be set before they are locked")]
+    AddressesNotSet {},
+
+    #[error("ActivePool: Validator {validator} not found")]
+    ValidatorNotFound { validator: String },
+
+    #[error("ActivePool: Staking requires JUNO to be the bonded denom, not {denom}")]
+    InvalidBondedDenom { denom: String },
+
+    #[error("ActivePool: Staking must be disabled to sweep the surplus")]
+    StakingEnabled {},
+
+    #[error("ActivePool: Staking is not enabled")]
+    StakingDisabled {},
+
+    #[error("ActivePool: All JUNO must be undelegated and unbonded to disable staking")]
+    StakingNotUnwound {},
+
+    #[error("ActivePool: Not enough liquid JUNO, {liquid} available")]
+    InsufficientLiquidJUNO { liquid: Uint128 },
+
+    #[error("ActivePool: No collateral queued for the caller")]
+    NoPayout {},
 }
@@ -1,7 +1,10 @@
-use cosmwasm_std::{Addr, Uint128};
-use cw_storage_plus::Item;
+use cosmwasm_std::{Addr, StdResult, Storage, Uint128};
+use cw_storage_plus::{Item, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use ultra_base::collateral_staking::{CollateralStaking, StakingConfig};
+use ultra_pool::access::{Role, RoleAddresses};
+use ultra_pool::ledger::AssetsInPool;
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct AddressesSet {
@@ -11,18 +14,29 @@ pub struct AddressesSet {
     pub default_pool_address: Addr,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct AssetsInPool {
-    pub juno: Uint128,
-    pub ultra_debt: Uint128,
-}
-
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct SudoParams {
-    pub name: String,
-    pub owner: Addr,
+impl RoleAddresses for AddressesSet {
+    fn address_of(&self, role: Role) -> Option<&Addr> {
+        match role {
+            Role::BorrowerOperations => Some(&self.borrower_operations_address),
+            Role::TroveManager => Some(&self.trove_manager_address),
+            Role::StabilityPool => Some(&self.stability_pool_address),
+            Role::DefaultPool => Some(&self.default_pool_address),
+            Role::ActivePool => None,
+        }
+    }
 }
 
-pub const SUDO_PARAMS: Item<SudoParams> = Item::new("sudo-params");
 pub const ADDRESSES_SET: Item<AddressesSet> = Item::new("addresses_set");
 pub const ASSETS_IN_POOL: Item<AssetsInPool> = Item::new("assets_in_pool");
+pub const STAKING_CONFIG: Item<StakingConfig> = Item::new("staking_config");
+pub const COLLATERAL_STAKING: Item<CollateralStaking> = Item::new("collateral_staking");
+
+/// Loads the collateral staking, pools deployed before it existed have staking disabled
+pub fn load_collateral_staking(store: &dyn Storage) -> StdResult<CollateralStaking> {
+    Ok(COLLATERAL_STAKING.may_load(store)?.unwrap_or_default())
+}
+
+/// JUNO delegated to each validator
+pub const DELEGATIONS: Map<&str, Uint128> = Map::new("delegations");
+/// Collateral queued for each recipient until it is liquid
+pub const PAYOUTS: Map<&Addr, Uint128> = Map::new("payouts");
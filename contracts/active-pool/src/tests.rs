@@ -1,30 +1,185 @@
-use crate::{
-    contract::{instantiate, NATIVE_JUNO_DENOM},
-    ContractError,
-};
+use crate::contract::{instantiate, query};
+use crate::ContractError;
 
-use ultra_base::active_pool::{ExecuteMsg, InstantiateMsg, ParamsResponse, QueryMsg, SudoMsg};
+use ultra_base::active_pool::{
+    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, ParamsResponse, QueryMsg,
+    ReconcileResponse, SudoMsg,
+};
+use ultra_base::address_registry::{
+    ContractAddress, ExecuteMsg as AddressRegistryExecuteMsg,
+    InstantiateMsg as AddressRegistryInstantiateMsg, ProtocolContract,
+};
+use ultra_base::asset::{native_asset_info, AssetInfo, UJUNO_DENOM};
+use ultra_base::collateral::{Collateral, CollateralContracts, CollateralParams};
+use ultra_base::collateral_staking::{
+    CollateralStaking, CollateralStakingError, Rebalance, StakingConfig, ValidatorDelegation,
+    MAX_UNBONDINGS,
+};
+use ultra_base::ownership::OwnershipResponse;
+use ultra_pool::PoolError;
 
-use cosmwasm_std::{Addr, Empty, Uint128};
-use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{
+    coins, from_binary, to_binary, Addr, Decimal, Decimal256, Empty, Uint128, Validator,
+};
+use cw20::{BalanceResponse, Cw20Coin, Cw20QueryMsg, MinterResponse};
+use cw_multi_test::{
+    App, Contract, ContractWrapper, Executor, StakingInfo, StakingSudo, SudoMsg as AppSudoMsg,
+};
 
 const SOME: &str = "someone";
 const OWNER: &str = "owner";
+const NEW_OWNER: &str = "new-owner";
 const BO: &str = "borrower-operations";
 const TM: &str = "trove-manager";
 const SP: &str = "stability-pool";
 const DP: &str = "default-pool";
-
+const COLL_SURPLUS_POOL: &str = "coll-surplus-pool";
+const GAS_POOL: &str = "gas-pool";
+const SORTED_TROVES: &str = "sorted-troves";
+const PRICE_FEED: &str = "price-feed";
+const NEW_TM: &str = "new-trove-manager";
+const VALIDATOR1: &str = "validator1";
+const VALIDATOR2: &str = "validator2";
+const UNBONDING_PERIOD: u64 = 21 * 24 * 60 * 60;
+const YEARLY_REWARD_PERCENT: u64 = 10;
+const SECONDS_PER_YEAR: u64 = 365 * 24 * 60 * 60;
+const SECONDS_PER_WEEK: u64 = 7 * 24 * 60 * 60;
 fn active_pool_contract() -> Box<dyn Contract<Empty>> {
     let contract = ContractWrapper::new(
         crate::contract::execute,
         crate::contract::instantiate,
         crate::contract::query,
     )
-    .with_sudo(crate::sudo::sudo);
+    .with_sudo(crate::sudo::sudo)
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+fn address_registry_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        address_registry::contract::execute,
+        address_registry::contract::instantiate,
+        address_registry::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn ultra_token_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        ultra_token::contract::execute,
+        ultra_token::contract::instantiate,
+        ultra_token::contract::query,
+    );
     Box::new(contract)
 }
 
+/// Instantiates a cw20 collateral token held by the given accounts
+fn instantiate_collateral_token(app: &mut App, initial_balances: Vec<Cw20Coin>) -> Addr {
+    let code_id = app.store_code(ultra_token_contract());
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(SOME),
+        &ultra_token::msg::InstantiateMsg {
+            name: String::from("Collateral"),
+            symbol: String::from("COLL"),
+            decimals: 6,
+            initial_balances,
+            mint: Some(MinterResponse {
+                minter: OWNER.to_string(),
+                cap: None,
+            }),
+            marketing: None,
+        },
+        &[],
+        "collateral token",
+        None,
+    )
+    .unwrap()
+}
+
+fn query_token_balance(app: &App, token_addr: &Addr, address: &Addr) -> Uint128 {
+    let res: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            token_addr,
+            &Cw20QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    res.balance
+}
+
+/// Instantiates an address registry holding the peers of the active pool, registered as the
+/// pool of its collateral
+fn instantiate_address_registry(app: &mut App, active_pool_addr: &Addr) -> Addr {
+    let code_id = app.store_code(address_registry_contract());
+    let address_registry_addr = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(SOME),
+            &AddressRegistryInstantiateMsg {
+                name: String::from("Address Registry"),
+                owner: OWNER.to_string(),
+            },
+            &[],
+            "address registry",
+            None,
+        )
+        .unwrap();
+
+    let addresses = [
+        (ProtocolContract::BorrowerOperations, BO),
+        (ProtocolContract::TroveManager, TM),
+        (ProtocolContract::StabilityPool, SP),
+        (ProtocolContract::GasPool, GAS_POOL),
+    ];
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &AddressRegistryExecuteMsg::SetAddresses {
+            addresses: addresses
+                .into_iter()
+                .map(|(contract, address)| ContractAddress {
+                    contract,
+                    address: address.to_string(),
+                })
+                .collect(),
+        },
+        &[],
+    )
+    .unwrap();
+    let asset: AssetInfo = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr, &QueryMsg::GetCollateralInfo {})
+        .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &AddressRegistryExecuteMsg::RegisterCollateral {
+            collateral: Collateral {
+                asset,
+                contracts: CollateralContracts {
+                    active_pool: active_pool_addr.clone(),
+                    default_pool: Addr::unchecked(DP),
+                    coll_surplus_pool: Addr::unchecked(COLL_SURPLUS_POOL),
+                    sorted_troves: Addr::unchecked(SORTED_TROVES),
+                    price_feed: Addr::unchecked(PRICE_FEED),
+                },
+                params: CollateralParams {
+                    mcr: Decimal256::percent(110),
+                    debt_ceiling: Uint128::new(1_000_000),
+                },
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    address_registry_addr
+}
+
 fn instantiate_active_pool(app: &mut App, msg: InstantiateMsg) -> Addr {
     let code_id = app.store_code(active_pool_contract());
     app.instantiate_contract(
@@ -45,6 +200,7 @@ fn test_instantiate() {
     let msg = InstantiateMsg {
         name: String::from("Active Pool"),
         owner: OWNER.to_string(),
+        collateral: native_asset_info(UJUNO_DENOM.to_string()),
     };
 
     let active_pool_addr = instantiate_active_pool(&mut app, msg);
@@ -57,31 +213,69 @@ fn test_instantiate() {
     assert_eq!(response.owner, Addr::unchecked(OWNER));
 
     assert_eq!(response.name, "Active Pool");
+
+    let collateral: AssetInfo = app
+        .wrap()
+        .query_wasm_smart(&active_pool_addr, &QueryMsg::GetCollateralInfo {})
+        .unwrap();
+    assert_eq!(collateral, native_asset_info(UJUNO_DENOM.to_string()));
+
+    // A cw20 token can be held by the pool as well
+    let collateral = AssetInfo::Cw20Token {
+        contract_addr: Addr::unchecked("collateral-token"),
+    };
+    let active_pool_addr = instantiate_active_pool(
+        &mut app,
+        InstantiateMsg {
+            name: String::from("Active Pool"),
+            owner: OWNER.to_string(),
+            collateral: collateral.clone(),
+        },
+    );
+    let response: AssetInfo = app
+        .wrap()
+        .query_wasm_smart(&active_pool_addr, &QueryMsg::GetCollateralInfo {})
+        .unwrap();
+    assert_eq!(response, collateral);
 }
 
 #[test]
-fn test_set_addresses() {
+fn test_sync_addresses() {
     let mut app = App::default();
 
     let msg = InstantiateMsg {
         name: String::from("Active Pool"),
         owner: OWNER.to_string(),
+        collateral: native_asset_info(UJUNO_DENOM.to_string()),
     };
 
     let active_pool_addr = instantiate_active_pool(&mut app, msg);
+    let address_registry_addr = instantiate_address_registry(&mut app, &active_pool_addr);
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            active_pool_addr.clone(),
+            &ExecuteMsg::SyncAddresses {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Pool(PoolError::AddressRegistryNotSet {})
+    );
 
-    let set_addresses_msg = ExecuteMsg::SetAddresses {
-        borrower_operations_address: BO.to_string(),
-        default_pool_address: DP.to_string(),
-        stability_pool_address: SP.to_string(),
-        trove_manager_address: TM.to_string(),
+    let set_address_registry_msg = ExecuteMsg::SetAddressRegistry {
+        address_registry: address_registry_addr.to_string(),
     };
 
     let err: ContractError = app
         .execute_contract(
             Addr::unchecked(SOME),
             active_pool_addr.clone(),
-            &set_addresses_msg,
+            &set_address_registry_msg,
             &[],
         )
         .unwrap_err()
@@ -92,7 +286,16 @@ fn test_set_addresses() {
     app.execute_contract(
         Addr::unchecked(OWNER),
         active_pool_addr.clone(),
-        &set_addresses_msg,
+        &set_address_registry_msg,
+        &[],
+    )
+    .unwrap();
+
+    // Anyone can sync the addresses from the registry
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SyncAddresses {},
         &[],
     )
     .unwrap();
@@ -132,6 +335,33 @@ fn test_set_addresses() {
         )
         .unwrap();
     assert_eq!(dp_address, Addr::unchecked(DP));
+
+    // A redeployed contract is registered once and picked up on the next sync
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr,
+        &AddressRegistryExecuteMsg::SetAddresses {
+            addresses: vec![ContractAddress {
+                contract: ProtocolContract::TroveManager,
+                address: NEW_TM.to_string(),
+            }],
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SyncAddresses {},
+        &[],
+    )
+    .unwrap();
+
+    let tm_address: Addr = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr, &QueryMsg::GetTroveManagerAddress {})
+        .unwrap();
+    assert_eq!(tm_address, Addr::unchecked(NEW_TM));
 }
 
 #[test]
@@ -140,24 +370,12 @@ fn test_increase_decrease_ultra_debt() {
     let msg = InstantiateMsg {
         name: String::from("Active Pool"),
         owner: OWNER.to_string(),
+        collateral: native_asset_info(UJUNO_DENOM.to_string()),
     };
 
     let active_pool_addr = instantiate_active_pool(&mut app, msg);
 
-    let set_addresses_msg = ExecuteMsg::SetAddresses {
-        borrower_operations_address: BO.to_string(),
-        default_pool_address: DP.to_string(),
-        stability_pool_address: SP.to_string(),
-        trove_manager_address: TM.to_string(),
-    };
-
-    app.execute_contract(
-        Addr::unchecked(OWNER),
-        active_pool_addr.clone(),
-        &set_addresses_msg,
-        &[],
-    )
-    .unwrap();
+    set_addresses(&mut app, &active_pool_addr);
 
     let increase_ultra_debt_msg = ExecuteMsg::IncreaseULTRADebt {
         amount: Uint128::new(100u128),
@@ -209,3 +427,1560 @@ fn test_increase_decrease_ultra_debt() {
 
     assert_eq!(ultra_debt, Uint128::new(50u128));
 }
+
+/// Points the active pool to a new address registry and syncs its addresses
+fn set_addresses(app: &mut App, active_pool_addr: &Addr) {
+    let address_registry_addr = instantiate_address_registry(app, active_pool_addr);
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SetAddressRegistry {
+            address_registry: address_registry_addr.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SyncAddresses {},
+        &[],
+    )
+    .unwrap();
+}
+
+#[test]
+fn test_receive_juno() {
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(BO), coins(1000, UJUNO_DENOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(SOME), coins(1000, UJUNO_DENOM))
+            .unwrap();
+    });
+    let msg = InstantiateMsg {
+        name: String::from("Active Pool"),
+        owner: OWNER.to_string(),
+        collateral: native_asset_info(UJUNO_DENOM.to_string()),
+    };
+
+    let active_pool_addr = instantiate_active_pool(&mut app, msg);
+    set_addresses(&mut app, &active_pool_addr);
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            active_pool_addr.clone(),
+            &ExecuteMsg::ReceiveCollateral {},
+            &coins(100, UJUNO_DENOM),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNeitherBONorDP {});
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(BO),
+            active_pool_addr.clone(),
+            &ExecuteMsg::ReceiveCollateral {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Pool(PoolError::NoFundsReceived {
+            denom: UJUNO_DENOM.to_string()
+        })
+    );
+
+    app.execute_contract(
+        Addr::unchecked(BO),
+        active_pool_addr.clone(),
+        &ExecuteMsg::ReceiveCollateral {},
+        &coins(100, UJUNO_DENOM),
+    )
+    .unwrap();
+
+    let juno: Uint128 = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::GetCollateral {})
+        .unwrap();
+    assert_eq!(juno, Uint128::new(100u128));
+
+    let balance = app
+        .wrap()
+        .query_balance(active_pool_addr, UJUNO_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(100u128));
+}
+
+#[test]
+fn test_receive_cw20_collateral() {
+    let mut app = App::default();
+    let initial_balances = [BO, SOME]
+        .into_iter()
+        .map(|address| Cw20Coin {
+            address: address.to_string(),
+            amount: Uint128::new(1_000),
+        })
+        .collect();
+    let token_addr = instantiate_collateral_token(&mut app, initial_balances);
+    let other_token_addr = instantiate_collateral_token(
+        &mut app,
+        vec![Cw20Coin {
+            address: BO.to_string(),
+            amount: Uint128::new(1_000),
+        }],
+    );
+
+    let msg = InstantiateMsg {
+        name: String::from("Active Pool"),
+        owner: OWNER.to_string(),
+        collateral: AssetInfo::Cw20Token {
+            contract_addr: token_addr.clone(),
+        },
+    };
+    let active_pool_addr = instantiate_active_pool(&mut app, msg);
+    set_addresses(&mut app, &active_pool_addr);
+
+    let send = |amount: u128| ultra_token::msg::ExecuteMsg::Send {
+        contract: active_pool_addr.to_string(),
+        amount: Uint128::new(amount),
+        msg: to_binary(&Cw20HookMsg::ReceiveCollateral {}).unwrap(),
+    };
+
+    // Only borrower operations and the default pool can deposit collateral
+    let err: ContractError = app
+        .execute_contract(Addr::unchecked(SOME), token_addr.clone(), &send(100), &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNeitherBONorDP {});
+
+    // Only the collateral token is accepted
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(BO),
+            other_token_addr.clone(),
+            &send(100),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Pool(PoolError::InvalidCollateralToken {
+            token: other_token_addr.to_string()
+        })
+    );
+
+    // Native funds can't stand for a cw20 collateral
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(BO),
+            active_pool_addr.clone(),
+            &ExecuteMsg::ReceiveCollateral {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Pool(PoolError::InvalidFunds {
+            denom: token_addr.to_string()
+        })
+    );
+
+    app.execute_contract(Addr::unchecked(BO), token_addr.clone(), &send(100), &[])
+        .unwrap();
+
+    let coll: Uint128 = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::GetCollateral {})
+        .unwrap();
+    assert_eq!(coll, Uint128::new(100));
+    assert_eq!(
+        query_token_balance(&app, &token_addr, &active_pool_addr),
+        Uint128::new(100)
+    );
+
+    // The collateral is paid out with a cw20 transfer
+    app.execute_contract(
+        Addr::unchecked(TM),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SendCollateral {
+            recipient: Addr::unchecked(SOME),
+            amount: Uint128::new(40),
+        },
+        &[],
+    )
+    .unwrap();
+
+    let coll: Uint128 = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::GetCollateral {})
+        .unwrap();
+    assert_eq!(coll, Uint128::new(60));
+    assert_eq!(
+        query_token_balance(&app, &token_addr, &active_pool_addr),
+        Uint128::new(60)
+    );
+    assert_eq!(
+        query_token_balance(&app, &token_addr, &Addr::unchecked(SOME)),
+        Uint128::new(1_040)
+    );
+}
+
+#[test]
+fn test_reconcile_and_sweep_surplus() {
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(BO), coins(1000, UJUNO_DENOM))
+            .unwrap();
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(SOME), coins(1000, UJUNO_DENOM))
+            .unwrap();
+    });
+    let msg = InstantiateMsg {
+        name: String::from("Active Pool"),
+        owner: OWNER.to_string(),
+        collateral: native_asset_info(UJUNO_DENOM.to_string()),
+    };
+
+    let active_pool_addr = instantiate_active_pool(&mut app, msg);
+    set_addresses(&mut app, &active_pool_addr);
+
+    app.execute_contract(
+        Addr::unchecked(BO),
+        active_pool_addr.clone(),
+        &ExecuteMsg::ReceiveCollateral {},
+        &coins(100, UJUNO_DENOM),
+    )
+    .unwrap();
+
+    // A plain bank transfer is not recorded as collateral
+    app.send_tokens(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &coins(30, UJUNO_DENOM),
+    )
+    .unwrap();
+
+    let reconcile: ReconcileResponse = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::Reconcile {})
+        .unwrap();
+    assert_eq!(
+        reconcile,
+        ReconcileResponse {
+            recorded_coll: Uint128::new(100u128),
+            balance: Uint128::new(130u128),
+            surplus: Uint128::new(30u128),
+            deficit: Uint128::zero(),
+        }
+    );
+
+    let sweep_msg = ExecuteMsg::SweepSurplus {
+        recipient: SOME.to_string(),
+    };
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            active_pool_addr.clone(),
+            &sweep_msg,
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::UnauthorizedOwner {});
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &sweep_msg,
+        &[],
+    )
+    .unwrap();
+
+    let balance = app.wrap().query_balance(SOME, UJUNO_DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(1000u128));
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            active_pool_addr.clone(),
+            &sweep_msg,
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NoSurplusToSweep {});
+
+    let juno: Uint128 = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr, &QueryMsg::GetCollateral {})
+        .unwrap();
+    assert_eq!(juno, Uint128::new(100u128));
+}
+
+fn query_ownership(app: &App, active_pool_addr: &Addr) -> OwnershipResponse {
+    app.wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::GetOwnership {})
+        .unwrap()
+}
+
+#[test]
+fn test_two_step_ownership_transfer() {
+    let mut app = App::default();
+    let msg = InstantiateMsg {
+        name: String::from("Active Pool"),
+        owner: OWNER.to_string(),
+        collateral: native_asset_info(UJUNO_DENOM.to_string()),
+    };
+
+    let active_pool_addr = instantiate_active_pool(&mut app, msg);
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            active_pool_addr.clone(),
+            &ExecuteMsg::ProposeOwner {
+                owner: SOME.to_string(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::UnauthorizedOwner {});
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &ExecuteMsg::ProposeOwner {
+            owner: NEW_OWNER.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        query_ownership(&app, &active_pool_addr),
+        OwnershipResponse {
+            owner: Some(Addr::unchecked(OWNER)),
+            pending_owner: Some(Addr::unchecked(NEW_OWNER)),
+            addresses_locked: false,
+        }
+    );
+
+    // Only the pending owner can accept the ownership
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            active_pool_addr.clone(),
+            &ExecuteMsg::AcceptOwner {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Pool(PoolError::CallerIsNotPendingOwner {})
+    );
+
+    app.execute_contract(
+        Addr::unchecked(NEW_OWNER),
+        active_pool_addr.clone(),
+        &ExecuteMsg::AcceptOwner {},
+        &[],
+    )
+    .unwrap();
+
+    let response: ParamsResponse = app
+        .wrap()
+        .query_wasm_smart(&active_pool_addr, &QueryMsg::GetParams {})
+        .unwrap();
+    assert_eq!(response.owner, Addr::unchecked(NEW_OWNER));
+
+    // The previous owner lost its rights
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            active_pool_addr.clone(),
+            &ExecuteMsg::RenounceOwnership {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::UnauthorizedOwner {});
+
+    app.execute_contract(
+        Addr::unchecked(NEW_OWNER),
+        active_pool_addr.clone(),
+        &ExecuteMsg::RenounceOwnership {},
+        &[],
+    )
+    .unwrap();
+    assert_eq!(query_ownership(&app, &active_pool_addr).owner, None);
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(NEW_OWNER),
+            active_pool_addr.clone(),
+            &ExecuteMsg::SetAddressRegistry {
+                address_registry: SOME.to_string(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::UnauthorizedOwner {});
+
+    // Governance can still give the pool a new owner
+    app.wasm_sudo(
+        active_pool_addr.clone(),
+        &SudoMsg::UpdateParams {
+            name: None,
+            owner: Some(Addr::unchecked(OWNER)),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        query_ownership(&app, &active_pool_addr).owner,
+        Some(Addr::unchecked(OWNER))
+    );
+}
+
+#[test]
+fn test_lock_addresses() {
+    let mut app = App::default();
+    let msg = InstantiateMsg {
+        name: String::from("Active Pool"),
+        owner: OWNER.to_string(),
+        collateral: native_asset_info(UJUNO_DENOM.to_string()),
+    };
+
+    let active_pool_addr = instantiate_active_pool(&mut app, msg);
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            active_pool_addr.clone(),
+            &ExecuteMsg::LockAddresses {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::AddressesNotSet {});
+
+    // Addresses can be updated until they are locked
+    set_addresses(&mut app, &active_pool_addr);
+    set_addresses(&mut app, &active_pool_addr);
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &ExecuteMsg::LockAddresses {},
+        &[],
+    )
+    .unwrap();
+    assert!(query_ownership(&app, &active_pool_addr).addresses_locked);
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            active_pool_addr.clone(),
+            &ExecuteMsg::SetAddressRegistry {
+                address_registry: SOME.to_string(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Pool(PoolError::AddressesLocked {}));
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            active_pool_addr,
+            &ExecuteMsg::SyncAddresses {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Pool(PoolError::AddressesLocked {}));
+}
+
+#[test]
+fn test_migrate() {
+    let mut app = App::default();
+    let code_id = app.store_code(active_pool_contract());
+    let active_pool_addr = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(SOME),
+            &InstantiateMsg {
+                name: String::from("Active Pool"),
+                owner: OWNER.to_string(),
+                collateral: native_asset_info(UJUNO_DENOM.to_string()),
+            },
+            &[],
+            "active pool",
+            Some(OWNER.to_string()),
+        )
+        .unwrap();
+    set_addresses(&mut app, &active_pool_addr);
+
+    let new_code_id = app.store_code(active_pool_contract());
+    app.migrate_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &MigrateMsg {},
+        new_code_id,
+    )
+    .unwrap();
+
+    // The state is kept across the migration
+    let version = cw2::query_contract_info(&app.wrap(), active_pool_addr.clone()).unwrap();
+    assert_eq!(version.contract, "crates.io:active-pool");
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+    let tm_address: Addr = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr, &QueryMsg::GetTroveManagerAddress {})
+        .unwrap();
+    assert_eq!(tm_address, Addr::unchecked(TM));
+}
+
+#[test]
+fn test_pool_without_collateral_staking() {
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SOME, &[]),
+        InstantiateMsg {
+            name: String::from("Active Pool"),
+            owner: OWNER.to_string(),
+            collateral: native_asset_info(UJUNO_DENOM.to_string()),
+        },
+    )
+    .unwrap();
+
+    // Pools deployed before collateral staking existed have no staking state
+    deps.storage.remove(b"collateral_staking");
+    let staking: CollateralStaking =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetCollateralStaking {}).unwrap())
+            .unwrap();
+    assert_eq!(staking, CollateralStaking::default());
+}
+
+/// Builds an app bonding JUNO to two validators, with BO holding JUNO
+fn staking_app() -> App {
+    App::new(|router, api, storage| {
+        router
+            .staking
+            .setup(
+                storage,
+                StakingInfo {
+                    bonded_denom: UJUNO_DENOM.to_string(),
+                    unbonding_time: UNBONDING_PERIOD,
+                    apr: Decimal::percent(YEARLY_REWARD_PERCENT),
+                },
+            )
+            .unwrap();
+        for validator in [VALIDATOR1, VALIDATOR2] {
+            router
+                .staking
+                .add_validator(
+                    api,
+                    storage,
+                    &mock_env().block,
+                    Validator {
+                        address: validator.to_string(),
+                        commission: Decimal::zero(),
+                        max_commission: Decimal::one(),
+                        max_change_rate: Decimal::one(),
+                    },
+                )
+                .unwrap();
+        }
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(BO), coins(1_000_000, UJUNO_DENOM))
+            .unwrap();
+    })
+}
+
+/// Rewards accrued by `amount` of delegated JUNO over `elapsed` seconds
+fn staking_rewards(amount: Uint128, elapsed: u64) -> Uint128 {
+    amount.multiply_ratio(elapsed * YEARLY_REWARD_PERCENT, 100 * SECONDS_PER_YEAR)
+}
+
+fn staking_config(staked_ratio: Decimal256) -> StakingConfig {
+    StakingConfig {
+        validators: vec![VALIDATOR1.to_string(), VALIDATOR2.to_string()],
+        staked_ratio,
+        unbonding_period: UNBONDING_PERIOD,
+        min_rebalance: Uint128::new(100),
+    }
+}
+
+fn query_collateral_staking(app: &App, active_pool_addr: &Addr) -> CollateralStaking {
+    app.wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::GetCollateralStaking {})
+        .unwrap()
+}
+
+fn query_delegations(app: &App, active_pool_addr: &Addr) -> Vec<ValidatorDelegation> {
+    app.wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::GetDelegations {})
+        .unwrap()
+}
+
+/// Moves the chain past the unbonding period and releases the completed unbondings
+fn complete_unbondings(app: &mut App) {
+    app.update_block(|block| block.time = block.time.plus_seconds(UNBONDING_PERIOD));
+    app.sudo(AppSudoMsg::Staking(StakingSudo::ProcessQueue {}))
+        .unwrap();
+}
+
+#[test]
+fn test_set_staking_config() {
+    let mut app = staking_app();
+    let active_pool_addr = instantiate_active_pool(
+        &mut app,
+        InstantiateMsg {
+            name: String::from("Active Pool"),
+            owner: OWNER.to_string(),
+            collateral: native_asset_info(UJUNO_DENOM.to_string()),
+        },
+    );
+    set_addresses(&mut app, &active_pool_addr);
+
+    let set_config = |config: StakingConfig| ExecuteMsg::SetStakingConfig {
+        config: Some(config),
+    };
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            active_pool_addr.clone(),
+            &set_config(staking_config(Decimal256::percent(80))),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::UnauthorizedOwner {});
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            active_pool_addr.clone(),
+            &set_config(staking_config(Decimal256::percent(120))),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::CollateralStaking(CollateralStakingError::InvalidStakedRatio {})
+    );
+
+    let mut config = staking_config(Decimal256::percent(80));
+    config.validators.push(String::from("unknown"));
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            active_pool_addr.clone(),
+            &set_config(config),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::ValidatorNotFound {
+            validator: String::from("unknown")
+        }
+    );
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &set_config(staking_config(Decimal256::percent(80))),
+        &[],
+    )
+    .unwrap();
+    let config: Option<StakingConfig> = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::GetStakingConfig {})
+        .unwrap();
+    assert_eq!(config, Some(staking_config(Decimal256::percent(80))));
+
+    // Sweeping would take the staking rewards
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            active_pool_addr,
+            &ExecuteMsg::SweepSurplus {
+                recipient: SOME.to_string(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::StakingEnabled {});
+}
+
+#[test]
+fn test_rebalance_and_staking_rewards() {
+    let mut app = staking_app();
+    let active_pool_addr = instantiate_active_pool(
+        &mut app,
+        InstantiateMsg {
+            name: String::from("Active Pool"),
+            owner: OWNER.to_string(),
+            collateral: native_asset_info(UJUNO_DENOM.to_string()),
+        },
+    );
+    set_addresses(&mut app, &active_pool_addr);
+
+    app.execute_contract(
+        Addr::unchecked(BO),
+        active_pool_addr.clone(),
+        &ExecuteMsg::ReceiveCollateral {},
+        &coins(1_000_000, UJUNO_DENOM),
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SetStakingConfig {
+            config: Some(staking_config(Decimal256::percent(80))),
+        },
+        &[],
+    )
+    .unwrap();
+
+    let rebalance: Rebalance = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::GetRebalance {})
+        .unwrap();
+    assert_eq!(rebalance, Rebalance::Delegate(Uint128::new(800_000)));
+
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::Rebalance {},
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        query_delegations(&app, &active_pool_addr),
+        vec![
+            ValidatorDelegation {
+                validator: VALIDATOR1.to_string(),
+                amount: Uint128::new(400_000),
+            },
+            ValidatorDelegation {
+                validator: VALIDATOR2.to_string(),
+                amount: Uint128::new(400_000),
+            },
+        ]
+    );
+    let delegations = app
+        .wrap()
+        .query_all_delegations(active_pool_addr.clone())
+        .unwrap();
+    assert_eq!(delegations.len(), 2);
+    assert!(delegations
+        .iter()
+        .all(|delegation| delegation.amount.amount == Uint128::new(400_000)));
+
+    // The delegated JUNO is still accounted for
+    let reconcile: ReconcileResponse = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::Reconcile {})
+        .unwrap();
+    assert_eq!(
+        reconcile,
+        ReconcileResponse {
+            recorded_coll: Uint128::new(1_000_000),
+            balance: Uint128::new(200_000),
+            surplus: Uint128::zero(),
+            deficit: Uint128::zero(),
+        }
+    );
+
+    // Only the liquid buffer can be sent to the protocol contracts, which can't wait for the
+    // collateral they record
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(TM),
+            active_pool_addr.clone(),
+            &ExecuteMsg::SendCollateral {
+                recipient: Addr::unchecked(SP),
+                amount: Uint128::new(300_000),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::InsufficientLiquidJUNO {
+            liquid: Uint128::new(200_000)
+        }
+    );
+    app.execute_contract(
+        Addr::unchecked(TM),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SendCollateral {
+            recipient: Addr::unchecked(SOME),
+            amount: Uint128::new(150_000),
+        },
+        &[],
+    )
+    .unwrap();
+
+    // 80% of the remaining 850_000 stays staked, the chain paying out the rewards of the
+    // undelegated validator along the way
+    app.update_block(|block| block.time = block.time.plus_seconds(SECONDS_PER_WEEK));
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::Rebalance {},
+        &[],
+    )
+    .unwrap();
+    let staking = query_collateral_staking(&app, &active_pool_addr);
+    assert_eq!(staking.delegated, Uint128::new(680_000));
+    assert_eq!(staking.total_unbonding().unwrap(), Uint128::new(120_000));
+    let rebalance_rewards = staking_rewards(Uint128::new(400_000), SECONDS_PER_WEEK);
+    assert_eq!(staking.rewards_held, rebalance_rewards);
+
+    complete_unbondings(&mut app);
+    let staking = query_collateral_staking(&app, &active_pool_addr);
+    assert!(staking.unbonding.is_empty());
+
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::ClaimStakingRewards {},
+        &[],
+    )
+    .unwrap();
+
+    // The rewards are held for the troves on top of the liquid collateral
+    let claimed_rewards = staking_rewards(Uint128::new(280_000), UNBONDING_PERIOD)
+        + staking_rewards(Uint128::new(400_000), SECONDS_PER_WEEK + UNBONDING_PERIOD);
+    let staking = query_collateral_staking(&app, &active_pool_addr);
+    assert_eq!(staking.rewards_held, rebalance_rewards + claimed_rewards);
+    assert_eq!(
+        staking.reward_per_unit_coll,
+        Decimal256::from_ratio(rebalance_rewards, 850_000u128)
+            + Decimal256::from_ratio(claimed_rewards, 850_000u128)
+    );
+    let balance = app
+        .wrap()
+        .query_balance(active_pool_addr.clone(), UJUNO_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(170_000) + staking.rewards_held);
+    let reconcile: ReconcileResponse = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::Reconcile {})
+        .unwrap();
+    assert_eq!(reconcile.surplus, Uint128::zero());
+    assert_eq!(reconcile.deficit, Uint128::zero());
+
+    let send_reward = ExecuteMsg::SendStakingReward {
+        recipient: Addr::unchecked(SOME),
+        amount: staking.rewards_held,
+    };
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            active_pool_addr.clone(),
+            &send_reward,
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNeitherBONorTM {});
+
+    app.execute_contract(
+        Addr::unchecked(TM),
+        active_pool_addr.clone(),
+        &send_reward,
+        &[],
+    )
+    .unwrap();
+    let balance = app.wrap().query_balance(SOME, UJUNO_DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(150_000) + staking.rewards_held);
+    let staking = query_collateral_staking(&app, &active_pool_addr);
+    assert_eq!(staking.rewards_held, Uint128::zero());
+}
+
+#[test]
+fn test_claim_staking_rewards() {
+    let mut app = staking_app();
+    let active_pool_addr = instantiate_active_pool(
+        &mut app,
+        InstantiateMsg {
+            name: String::from("Active Pool"),
+            owner: OWNER.to_string(),
+            collateral: native_asset_info(UJUNO_DENOM.to_string()),
+        },
+    );
+    set_addresses(&mut app, &active_pool_addr);
+
+    app.execute_contract(
+        Addr::unchecked(BO),
+        active_pool_addr.clone(),
+        &ExecuteMsg::ReceiveCollateral {},
+        &coins(1_000_000, UJUNO_DENOM),
+    )
+    .unwrap();
+
+    // Nothing is delegated without staking config
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            active_pool_addr.clone(),
+            &ExecuteMsg::ClaimStakingRewards {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::StakingDisabled {});
+
+    // The configured unbonding period is shorter than the chain's
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SetStakingConfig {
+            config: Some(StakingConfig {
+                unbonding_period: UNBONDING_PERIOD / 3,
+                ..staking_config(Decimal256::percent(80))
+            }),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::Rebalance {},
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(TM),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SendCollateral {
+            recipient: Addr::unchecked(SOME),
+            amount: Uint128::new(150_000),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::Rebalance {},
+        &[],
+    )
+    .unwrap();
+
+    // The pool deems the 120_000 undelegated JUNO liquid while the chain still holds them,
+    // which leaves the rewards claimed untouched
+    app.update_block(|block| block.time = block.time.plus_seconds(UNBONDING_PERIOD / 2));
+    let staking = query_collateral_staking(&app, &active_pool_addr);
+    assert!(staking.unbonding.is_empty());
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::ClaimStakingRewards {},
+        &[],
+    )
+    .unwrap();
+    let staking = query_collateral_staking(&app, &active_pool_addr);
+    assert_eq!(
+        staking.rewards_held,
+        staking_rewards(Uint128::new(280_000), UNBONDING_PERIOD / 2)
+            + staking_rewards(Uint128::new(400_000), UNBONDING_PERIOD / 2)
+    );
+    let balance = app
+        .wrap()
+        .query_balance(active_pool_addr, UJUNO_DENOM)
+        .unwrap();
+    assert_eq!(balance.amount, Uint128::new(50_000) + staking.rewards_held);
+}
+
+#[test]
+fn test_slashing() {
+    let mut app = staking_app();
+    let active_pool_addr = instantiate_active_pool(
+        &mut app,
+        InstantiateMsg {
+            name: String::from("Active Pool"),
+            owner: OWNER.to_string(),
+            collateral: native_asset_info(UJUNO_DENOM.to_string()),
+        },
+    );
+    set_addresses(&mut app, &active_pool_addr);
+
+    app.execute_contract(
+        Addr::unchecked(BO),
+        active_pool_addr.clone(),
+        &ExecuteMsg::ReceiveCollateral {},
+        &coins(1_000_000, UJUNO_DENOM),
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SetStakingConfig {
+            config: Some(staking_config(Decimal256::percent(80))),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::Rebalance {},
+        &[],
+    )
+    .unwrap();
+
+    // The chain slashes 10% of the 400_000 JUNO delegated to the first validator
+    app.sudo(AppSudoMsg::Staking(StakingSudo::Slash {
+        validator: VALIDATOR1.to_string(),
+        percentage: Decimal::percent(10),
+    }))
+    .unwrap();
+    let delegation = app
+        .wrap()
+        .query_delegation(active_pool_addr.clone(), VALIDATOR1)
+        .unwrap()
+        .unwrap();
+    assert_eq!(delegation.amount.amount, Uint128::new(360_000));
+
+    // The rebalance writes the 40_000 slashed JUNO off the troves, then delegates 80% of the
+    // 960_000 left
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::Rebalance {},
+        &[],
+    )
+    .unwrap();
+
+    let staking = query_collateral_staking(&app, &active_pool_addr);
+    assert_eq!(staking.delegated, Uint128::new(768_000));
+    assert_eq!(
+        staking.slash_per_unit_coll,
+        Decimal256::from_ratio(40_000u128, 1_000_000u128)
+    );
+    assert_eq!(
+        staking
+            .pending_slash(Uint128::new(500_000), Decimal256::zero())
+            .unwrap(),
+        Uint128::new(20_000)
+    );
+    assert_eq!(
+        query_delegations(&app, &active_pool_addr),
+        vec![
+            ValidatorDelegation {
+                validator: VALIDATOR1.to_string(),
+                amount: Uint128::new(364_000),
+            },
+            ValidatorDelegation {
+                validator: VALIDATOR2.to_string(),
+                amount: Uint128::new(404_000),
+            },
+        ]
+    );
+    let delegation = app
+        .wrap()
+        .query_delegation(active_pool_addr.clone(), VALIDATOR1)
+        .unwrap()
+        .unwrap();
+    assert_eq!(delegation.amount.amount, Uint128::new(364_000));
+
+    // The recorded collateral is backed by the pool again
+    let reconcile: ReconcileResponse = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::Reconcile {})
+        .unwrap();
+    assert_eq!(
+        reconcile,
+        ReconcileResponse {
+            recorded_coll: Uint128::new(960_000),
+            balance: Uint128::new(192_000),
+            surplus: Uint128::zero(),
+            deficit: Uint128::zero(),
+        }
+    );
+
+    // Nothing more is written off without a new slashing
+    app.update_block(|block| block.time = block.time.plus_seconds(SECONDS_PER_WEEK));
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::Rebalance {},
+        &[],
+    )
+    .unwrap();
+    let staking = query_collateral_staking(&app, &active_pool_addr);
+    assert_eq!(
+        staking.slash_per_unit_coll,
+        Decimal256::from_ratio(40_000u128, 1_000_000u128)
+    );
+}
+
+#[test]
+fn test_queued_payouts() {
+    let mut app = staking_app();
+    let active_pool_addr = instantiate_active_pool(
+        &mut app,
+        InstantiateMsg {
+            name: String::from("Active Pool"),
+            owner: OWNER.to_string(),
+            collateral: native_asset_info(UJUNO_DENOM.to_string()),
+        },
+    );
+    set_addresses(&mut app, &active_pool_addr);
+
+    app.execute_contract(
+        Addr::unchecked(BO),
+        active_pool_addr.clone(),
+        &ExecuteMsg::ReceiveCollateral {},
+        &coins(1_000_000, UJUNO_DENOM),
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SetStakingConfig {
+            config: Some(staking_config(Decimal256::percent(80))),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::Rebalance {},
+        &[],
+    )
+    .unwrap();
+
+    // The coll surplus pool records the collateral it receives
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(TM),
+            active_pool_addr.clone(),
+            &ExecuteMsg::SendCollateral {
+                recipient: Addr::unchecked(COLL_SURPLUS_POOL),
+                amount: Uint128::new(300_000),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::InsufficientLiquidJUNO {
+            liquid: Uint128::new(200_000)
+        }
+    );
+    // So does the gas pool, which pays the gas compensation out to the liquidator
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(TM),
+            active_pool_addr.clone(),
+            &ExecuteMsg::SendCollateral {
+                recipient: Addr::unchecked(GAS_POOL),
+                amount: Uint128::new(300_000),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::InsufficientLiquidJUNO {
+            liquid: Uint128::new(200_000)
+        }
+    );
+
+    // A withdrawal above the liquid buffer is sent in part, the rest being queued
+    app.execute_contract(
+        Addr::unchecked(BO),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SendCollateral {
+            recipient: Addr::unchecked(SOME),
+            amount: Uint128::new(500_000),
+        },
+        &[],
+    )
+    .unwrap();
+    let balance = app.wrap().query_balance(SOME, UJUNO_DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(200_000));
+    let get_payout = QueryMsg::GetPayout {
+        address: SOME.to_string(),
+    };
+    let payout: Uint128 = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &get_payout)
+        .unwrap();
+    assert_eq!(payout, Uint128::new(300_000));
+    let staking = query_collateral_staking(&app, &active_pool_addr);
+    assert_eq!(staking.queued_payouts, Uint128::new(300_000));
+    let reconcile: ReconcileResponse = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::Reconcile {})
+        .unwrap();
+    assert_eq!(
+        reconcile,
+        ReconcileResponse {
+            recorded_coll: Uint128::new(500_000),
+            balance: Uint128::zero(),
+            surplus: Uint128::zero(),
+            deficit: Uint128::zero(),
+        }
+    );
+
+    // The queued collateral is still delegated
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            active_pool_addr.clone(),
+            &ExecuteMsg::ClaimPayout {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::InsufficientLiquidJUNO {
+            liquid: Uint128::zero()
+        }
+    );
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            active_pool_addr.clone(),
+            &ExecuteMsg::ClaimPayout {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NoPayout {});
+
+    // 80% of the remaining 500_000 stays staked, the rest being undelegated for the payout
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::Rebalance {},
+        &[],
+    )
+    .unwrap();
+    let staking = query_collateral_staking(&app, &active_pool_addr);
+    assert_eq!(staking.delegated, Uint128::new(400_000));
+    assert_eq!(staking.total_unbonding().unwrap(), Uint128::new(400_000));
+    let rebalance: Rebalance = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::GetRebalance {})
+        .unwrap();
+    assert_eq!(rebalance, Rebalance::Balanced);
+
+    complete_unbondings(&mut app);
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::ClaimPayout {},
+        &[],
+    )
+    .unwrap();
+    let balance = app.wrap().query_balance(SOME, UJUNO_DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(500_000));
+    let payout: Uint128 = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &get_payout)
+        .unwrap();
+    assert_eq!(payout, Uint128::zero());
+    let staking = query_collateral_staking(&app, &active_pool_addr);
+    assert_eq!(staking.queued_payouts, Uint128::zero());
+    let reconcile: ReconcileResponse = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::Reconcile {})
+        .unwrap();
+    assert_eq!(
+        reconcile,
+        ReconcileResponse {
+            recorded_coll: Uint128::new(500_000),
+            balance: Uint128::new(100_000),
+            surplus: Uint128::zero(),
+            deficit: Uint128::zero(),
+        }
+    );
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            active_pool_addr,
+            &ExecuteMsg::ClaimPayout {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NoPayout {});
+}
+
+#[test]
+fn test_rebalance_limits() {
+    let mut app = staking_app();
+    let active_pool_addr = instantiate_active_pool(
+        &mut app,
+        InstantiateMsg {
+            name: String::from("Active Pool"),
+            owner: OWNER.to_string(),
+            collateral: native_asset_info(UJUNO_DENOM.to_string()),
+        },
+    );
+    set_addresses(&mut app, &active_pool_addr);
+
+    app.execute_contract(
+        Addr::unchecked(BO),
+        active_pool_addr.clone(),
+        &ExecuteMsg::ReceiveCollateral {},
+        &coins(1_000_000, UJUNO_DENOM),
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SetStakingConfig {
+            config: Some(StakingConfig {
+                min_rebalance: Uint128::new(10_000),
+                ..staking_config(Decimal256::percent(80))
+            }),
+        },
+        &[],
+    )
+    .unwrap();
+    let rebalance = ExecuteMsg::Rebalance {};
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &rebalance,
+        &[],
+    )
+    .unwrap();
+    let send = |amount: u128| ExecuteMsg::SendCollateral {
+        recipient: Addr::unchecked(SOME),
+        amount: Uint128::new(amount),
+    };
+
+    // Changes below the minimum rebalance are left alone
+    app.execute_contract(
+        Addr::unchecked(TM),
+        active_pool_addr.clone(),
+        &send(10_000),
+        &[],
+    )
+    .unwrap();
+    let next_rebalance: Rebalance = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::GetRebalance {})
+        .unwrap();
+    assert_eq!(next_rebalance, Rebalance::Balanced);
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &rebalance,
+        &[],
+    )
+    .unwrap();
+    let staking = query_collateral_staking(&app, &active_pool_addr);
+    assert_eq!(staking.delegated, Uint128::new(800_000));
+    assert!(staking.unbonding.is_empty());
+
+    // The undelegations wait once the chain's unbonding entries are used up
+    for _ in 0..MAX_UNBONDINGS {
+        app.execute_contract(
+            Addr::unchecked(TM),
+            active_pool_addr.clone(),
+            &send(20_000),
+            &[],
+        )
+        .unwrap();
+        app.execute_contract(
+            Addr::unchecked(SOME),
+            active_pool_addr.clone(),
+            &rebalance,
+            &[],
+        )
+        .unwrap();
+    }
+    let staking = query_collateral_staking(&app, &active_pool_addr);
+    assert_eq!(staking.delegated, Uint128::new(680_000));
+    assert_eq!(staking.unbonding.len(), MAX_UNBONDINGS);
+
+    app.execute_contract(
+        Addr::unchecked(TM),
+        active_pool_addr.clone(),
+        &send(20_000),
+        &[],
+    )
+    .unwrap();
+    let next_rebalance: Rebalance = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::GetRebalance {})
+        .unwrap();
+    assert_eq!(next_rebalance, Rebalance::Balanced);
+
+    complete_unbondings(&mut app);
+    let next_rebalance: Rebalance = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr, &QueryMsg::GetRebalance {})
+        .unwrap();
+    assert_eq!(next_rebalance, Rebalance::Undelegate(Uint128::new(16_000)));
+}
+
+#[test]
+fn test_disable_staking() {
+    let mut app = staking_app();
+    let active_pool_addr = instantiate_active_pool(
+        &mut app,
+        InstantiateMsg {
+            name: String::from("Active Pool"),
+            owner: OWNER.to_string(),
+            collateral: native_asset_info(UJUNO_DENOM.to_string()),
+        },
+    );
+    set_addresses(&mut app, &active_pool_addr);
+
+    app.execute_contract(
+        Addr::unchecked(BO),
+        active_pool_addr.clone(),
+        &ExecuteMsg::ReceiveCollateral {},
+        &coins(1_000, UJUNO_DENOM),
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SetStakingConfig {
+            config: Some(staking_config(Decimal256::percent(50))),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::Rebalance {},
+        &[],
+    )
+    .unwrap();
+
+    let disable = ExecuteMsg::SetStakingConfig { config: None };
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            active_pool_addr.clone(),
+            &disable,
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::StakingNotUnwound {});
+
+    // A zero staked ratio undelegates everything
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &ExecuteMsg::SetStakingConfig {
+            config: Some(staking_config(Decimal256::zero())),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(SOME),
+        active_pool_addr.clone(),
+        &ExecuteMsg::Rebalance {},
+        &[],
+    )
+    .unwrap();
+    assert_eq!(query_delegations(&app, &active_pool_addr), vec![]);
+
+    // The unbonding JUNO must be back first
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            active_pool_addr.clone(),
+            &disable,
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::StakingNotUnwound {});
+
+    complete_unbondings(&mut app);
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &disable,
+        &[],
+    )
+    .unwrap();
+    let config: Option<StakingConfig> = app
+        .wrap()
+        .query_wasm_smart(active_pool_addr.clone(), &QueryMsg::GetStakingConfig {})
+        .unwrap();
+    assert_eq!(config, None);
+
+    let balance = app
+        .wrap()
+        .query_balance(active_pool_addr, UJUNO_DENOM)
+        .unwrap();
+    assert!(balance.amount >= Uint128::new(1_000));
+}
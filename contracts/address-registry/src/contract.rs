@@ -0,0 +1,299 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_binary, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response, StdError, StdResult,
+    Storage,
+};
+use cw_storage_plus::Bound;
+
+use cw2::set_contract_version;
+
+use crate::error::ContractError;
+use crate::state::{load_risk_params, ADDRESSES, COLLATERALS, RISK_PARAMS};
+use ultra_base::address_registry::{
+    AddressResponse, AddressesResponse, CollateralsResponse, ContractAddress, ExecuteMsg,
+    InstantiateMsg, MigrateMsg, ParamsResponse, ProtocolContract, QueryMsg,
+};
+use ultra_base::asset::AssetInfo;
+use ultra_base::collateral::{Collateral, CollateralContracts};
+use ultra_base::migration::{migrate_contract, Migration};
+use ultra_base::risk_params::RiskParams;
+use ultra_pool::access;
+use ultra_pool::ownership;
+use ultra_pool::state::{SudoParams, SUDO_PARAMS};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:address-registry";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// State migrations, ordered by the version introducing them
+const MIGRATIONS: &[Migration] = &[];
+
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // store sudo params
+    let sudo_params = SudoParams {
+        name: msg.name,
+        owner: deps.api.addr_validate(&msg.owner)?,
+    };
+    SUDO_PARAMS.save(deps.storage, &sudo_params)?;
+
+    // governance adjusts the risk parameters through sudo
+    RISK_PARAMS.save(deps.storage, &RiskParams::default())?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    Ok(migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+    )?)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::SetAddresses { addresses } => execute_set_addresses(deps, env, info, addresses),
+        ExecuteMsg::RemoveAddress { contract } => execute_remove_address(deps, env, info, contract),
+        ExecuteMsg::RegisterCollateral { collateral } => {
+            execute_register_collateral(deps, env, info, collateral)
+        }
+        ExecuteMsg::RemoveCollateral { asset } => execute_remove_collateral(deps, env, info, asset),
+        ExecuteMsg::ProposeOwner { owner } => execute_propose_owner(deps, env, info, owner),
+        ExecuteMsg::AcceptOwner {} => execute_accept_owner(deps, env, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, env, info),
+        ExecuteMsg::LockAddresses {} => execute_lock_addresses(deps, env, info),
+    }
+}
+
+pub fn execute_set_addresses(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    addresses: Vec<ContractAddress>,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+    ownership::ensure_addresses_unlocked(deps.storage)?;
+
+    let mut res = Response::new().add_attribute("action", "set_addresses");
+    for ContractAddress { contract, address } in addresses {
+        let address = deps.api.addr_validate(&address)?;
+        ADDRESSES.save(deps.storage, contract.key(), &address)?;
+        res = res.add_attribute(contract.key(), address);
+    }
+    Ok(res)
+}
+
+pub fn execute_remove_address(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    contract: ProtocolContract,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+    ownership::ensure_addresses_unlocked(deps.storage)?;
+
+    if !ADDRESSES.has(deps.storage, contract.key()) {
+        return Err(ContractError::AddressNotFound {
+            contract: contract.key().to_string(),
+        });
+    }
+    ADDRESSES.remove(deps.storage, contract.key());
+
+    let res = Response::new()
+        .add_attribute("action", "remove_address")
+        .add_attribute("contract", contract.key());
+    Ok(res)
+}
+
+pub fn execute_register_collateral(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collateral: Collateral,
+) -> Result<Response, ContractError> {
+    // Collateral types are added after the deployment, the addresses lock doesn't apply
+    only_owner(deps.storage, &info)?;
+
+    // The params must hold on their own and along the protocol risk params
+    collateral.params.validate()?;
+    collateral.risk_params(&load_risk_params(deps.storage)?)?;
+    let validate = |address: &str| deps.api.addr_validate(address);
+    let asset = match collateral.asset {
+        AssetInfo::NativeToken { denom } => AssetInfo::NativeToken { denom },
+        AssetInfo::Cw20Token { contract_addr } => AssetInfo::Cw20Token {
+            contract_addr: validate(contract_addr.as_str())?,
+        },
+    };
+    let contracts = collateral.contracts;
+    let collateral = Collateral {
+        asset,
+        contracts: CollateralContracts {
+            active_pool: validate(contracts.active_pool.as_str())?,
+            default_pool: validate(contracts.default_pool.as_str())?,
+            coll_surplus_pool: validate(contracts.coll_surplus_pool.as_str())?,
+            sorted_troves: validate(contracts.sorted_troves.as_str())?,
+            price_feed: validate(contracts.price_feed.as_str())?,
+        },
+        params: collateral.params,
+    };
+    COLLATERALS.save(deps.storage, &collateral.key(), &collateral)?;
+
+    let res = Response::new()
+        .add_attribute("action", "register_collateral")
+        .add_attribute("collateral", collateral.key())
+        .add_attribute("active_pool", collateral.contracts.active_pool)
+        .add_attribute("mcr", collateral.params.mcr.to_string())
+        .add_attribute("debt_ceiling", collateral.params.debt_ceiling);
+    Ok(res)
+}
+
+pub fn execute_remove_collateral(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    asset: AssetInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    let key = asset.to_string();
+    if !COLLATERALS.has(deps.storage, &key) {
+        return Err(ContractError::CollateralNotFound { asset: key });
+    }
+    COLLATERALS.remove(deps.storage, &key);
+
+    let res = Response::new()
+        .add_attribute("action", "remove_collateral")
+        .add_attribute("collateral", key);
+    Ok(res)
+}
+
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    owner: String,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    let owner = deps.api.addr_validate(&owner)?;
+    Ok(ownership::propose_owner(deps.storage, owner)?)
+}
+
+pub fn execute_accept_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    Ok(ownership::accept_owner(deps.storage, &info)?)
+}
+
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    Ok(ownership::renounce_ownership(deps.storage)?)
+}
+
+pub fn execute_lock_addresses(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    Ok(ownership::lock_addresses(deps.storage)?)
+}
+
+/// Checks to enforce only owner can call
+fn only_owner(store: &dyn Storage, info: &MessageInfo) -> Result<(), ContractError> {
+    access::only_owner(store, info, ContractError::UnauthorizedOwner {})?;
+    Ok(())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetParams {} => to_binary(&query_params(deps)?),
+        QueryMsg::GetOwnership {} => to_binary(&ownership::query_ownership(deps.storage)?),
+        QueryMsg::GetAddress { contract } => to_binary(&query_address(deps, contract)?),
+        QueryMsg::ListAddresses {} => to_binary(&query_addresses(deps)?),
+        QueryMsg::GetRiskParams {} => to_binary(&load_risk_params(deps.storage)?),
+        QueryMsg::GetCollateral { asset } => to_binary(&query_collateral(deps, asset)?),
+        QueryMsg::ListCollaterals { start_after, limit } => {
+            to_binary(&query_collaterals(deps, start_after, limit)?)
+        }
+    }
+}
+
+pub fn query_params(deps: Deps) -> StdResult<ParamsResponse> {
+    let info = SUDO_PARAMS.load(deps.storage)?;
+    let res = ParamsResponse {
+        name: info.name,
+        owner: info.owner,
+    };
+    Ok(res)
+}
+
+pub fn query_address(deps: Deps, contract: ProtocolContract) -> StdResult<AddressResponse> {
+    let address = ADDRESSES
+        .may_load(deps.storage, contract.key())?
+        .ok_or_else(|| StdError::not_found(contract.key()))?;
+    Ok(AddressResponse { contract, address })
+}
+
+pub fn query_addresses(deps: Deps) -> StdResult<AddressesResponse> {
+    let mut addresses = vec![];
+    for contract in ProtocolContract::ALL {
+        if let Some(address) = ADDRESSES.may_load(deps.storage, contract.key())? {
+            addresses.push(AddressResponse { contract, address });
+        }
+    }
+    Ok(AddressesResponse { addresses })
+}
+
+pub fn query_collateral(deps: Deps, asset: AssetInfo) -> StdResult<Collateral> {
+    let key = asset.to_string();
+    COLLATERALS
+        .may_load(deps.storage, &key)?
+        .ok_or_else(|| StdError::not_found(format!("collateral {}", key)))
+}
+
+pub fn query_collaterals(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<CollateralsResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.as_deref().map(Bound::exclusive);
+
+    let collaterals = COLLATERALS
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(_, collateral)| collateral))
+        .collect::<StdResult<_>>()?;
+    Ok(CollateralsResponse { collaterals })
+}
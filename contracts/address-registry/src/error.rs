@@ -0,0 +1,33 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+use ultra_base::collateral::CollateralError;
+use ultra_base::migration::MigrationError;
+use ultra_base::risk_params::RiskParamsError;
+use ultra_pool::PoolError;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Pool(#[from] PoolError),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
+    #[error("{0}")]
+    RiskParams(#[from] RiskParamsError),
+
+    #[error("{0}")]
+    Collateral(#[from] CollateralError),
+
+    #[error("UnauthorizedOwner")]
+    UnauthorizedOwner {},
+
+    #[error("AddressRegistry: No address registered for {contract}")]
+    AddressNotFound { contract: String },
+
+    #[error("AddressRegistry: No collateral registered for {asset}")]
+    CollateralNotFound { asset: String },
+}
@@ -0,0 +1,17 @@
+use cosmwasm_std::{Addr, StdResult, Storage};
+use cw_storage_plus::{Item, Map};
+use ultra_base::collateral::Collateral;
+use ultra_base::risk_params::RiskParams;
+
+/// Registered addresses, keyed by `ProtocolContract::key`
+pub const ADDRESSES: Map<&str, Addr> = Map::new("addresses");
+
+/// Registered collateral types, keyed by `Collateral::key`
+pub const COLLATERALS: Map<&str, Collateral> = Map::new("collaterals");
+
+pub const RISK_PARAMS: Item<RiskParams> = Item::new("risk-params");
+
+/// Loads the risk parameters, registries instantiated before they existed use the defaults
+pub fn load_risk_params(store: &dyn Storage) -> StdResult<RiskParams> {
+    Ok(RISK_PARAMS.may_load(store)?.unwrap_or_default())
+}
@@ -0,0 +1,88 @@
+use crate::error::ContractError;
+use crate::state::{load_risk_params, COLLATERALS, RISK_PARAMS};
+use cosmwasm_std::{entry_point, DepsMut, Env, Order, Response, StdResult};
+use ultra_base::address_registry::SudoMsg;
+use ultra_base::asset::AssetInfo;
+use ultra_base::collateral::{Collateral, CollateralParamsUpdate};
+use ultra_base::risk_params::RiskParamsUpdate;
+use ultra_pool::sudo::sudo_update_params;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::UpdateParams { name, owner } => Ok(sudo_update_params(deps.storage, name, owner)?),
+        SudoMsg::UpdateRiskParams { params } => sudo_update_risk_params(deps, *params),
+        SudoMsg::UpdateCollateralParams { asset, params } => {
+            sudo_update_collateral_params(deps, asset, params)
+        }
+    }
+}
+
+/// Only governance can update the risk parameters of a collateral, the resulting set has to
+/// be valid along the protocol risk parameters
+pub fn sudo_update_collateral_params(
+    deps: DepsMut,
+    asset: AssetInfo,
+    update: CollateralParamsUpdate,
+) -> Result<Response, ContractError> {
+    let key = asset.to_string();
+    let mut collateral = COLLATERALS
+        .may_load(deps.storage, &key)?
+        .ok_or(ContractError::CollateralNotFound { asset: key.clone() })?;
+    collateral.params = collateral.params.apply(update)?;
+    collateral.risk_params(&load_risk_params(deps.storage)?)?;
+    COLLATERALS.save(deps.storage, &key, &collateral)?;
+
+    let res = Response::new()
+        .add_attribute("action", "update_collateral_params")
+        .add_attribute("collateral", key)
+        .add_attribute("mcr", collateral.params.mcr.to_string())
+        .add_attribute("debt_ceiling", collateral.params.debt_ceiling);
+    Ok(res)
+}
+
+/// Only governance can update the risk parameters, the resulting set has to be valid along
+/// the params of every collateral
+pub fn sudo_update_risk_params(
+    deps: DepsMut,
+    update: RiskParamsUpdate,
+) -> Result<Response, ContractError> {
+    let params = load_risk_params(deps.storage)?.apply(update)?;
+    let collaterals = COLLATERALS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, collateral)| collateral))
+        .collect::<StdResult<Vec<Collateral>>>()?;
+    for collateral in collaterals {
+        collateral.risk_params(&params)?;
+    }
+    RISK_PARAMS.save(deps.storage, &params)?;
+
+    let res = Response::new()
+        .add_attribute("action", "update_risk_params")
+        .add_attribute("mcr", params.mcr.to_string())
+        .add_attribute("ccr", params.ccr.to_string())
+        .add_attribute("min_net_debt", params.min_net_debt)
+        .add_attribute(
+            "borrowing_fee_floor",
+            params.borrowing_fee_floor.to_string(),
+        )
+        .add_attribute(
+            "redemption_fee_floor",
+            params.redemption_fee_floor.to_string(),
+        )
+        .add_attribute("max_borrowing_fee", params.max_borrowing_fee.to_string())
+        .add_attribute("gas_compensation", params.gas_compensation)
+        .add_attribute(
+            "coll_gas_compensation",
+            params.coll_gas_compensation.to_string(),
+        )
+        .add_attribute(
+            "base_rate_half_life",
+            params.base_rate_half_life.to_string(),
+        )
+        .add_attribute(
+            "base_rate_minute_decay_factor",
+            params.base_rate_minute_decay_factor.to_string(),
+        );
+    Ok(res)
+}
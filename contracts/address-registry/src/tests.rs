@@ -0,0 +1,603 @@
+use crate::ContractError;
+
+use ultra_base::address_registry::{
+    AddressResponse, AddressesResponse, CollateralsResponse, ContractAddress, ExecuteMsg,
+    InstantiateMsg, ParamsResponse, ProtocolContract, QueryMsg, SudoMsg,
+};
+use ultra_base::asset::{native_asset_info, AssetInfo, UJUNO_DENOM};
+use ultra_base::collateral::{
+    Collateral, CollateralContracts, CollateralError, CollateralParams, CollateralParamsUpdate,
+};
+use ultra_base::risk_params::{RiskParams, RiskParamsError, RiskParamsUpdate};
+use ultra_pool::PoolError;
+
+use cosmwasm_std::{Addr, Decimal256, Empty, StdResult, Uint128};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+const SOME: &str = "someone";
+const OWNER: &str = "owner";
+const AP: &str = "active-pool";
+const TM: &str = "trove-manager";
+const NEW_TM: &str = "new-trove-manager";
+
+fn address_registry_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_sudo(crate::sudo::sudo)
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+fn instantiate_address_registry(app: &mut App) -> Addr {
+    let code_id = app.store_code(address_registry_contract());
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(SOME),
+        &InstantiateMsg {
+            name: String::from("Address Registry"),
+            owner: OWNER.to_string(),
+        },
+        &[],
+        "address registry",
+        None,
+    )
+    .unwrap()
+}
+
+fn set_address_msg(contract: ProtocolContract, address: &str) -> ExecuteMsg {
+    ExecuteMsg::SetAddresses {
+        addresses: vec![ContractAddress {
+            contract,
+            address: address.to_string(),
+        }],
+    }
+}
+
+fn query_addresses(app: &App, address_registry_addr: &Addr) -> Vec<AddressResponse> {
+    let res: AddressesResponse = app
+        .wrap()
+        .query_wasm_smart(address_registry_addr.clone(), &QueryMsg::ListAddresses {})
+        .unwrap();
+    res.addresses
+}
+
+#[test]
+fn test_instantiate() {
+    let mut app = App::default();
+    let address_registry_addr = instantiate_address_registry(&mut app);
+
+    let response: ParamsResponse = app
+        .wrap()
+        .query_wasm_smart(&address_registry_addr, &QueryMsg::GetParams {})
+        .unwrap();
+    assert_eq!(response.owner, Addr::unchecked(OWNER));
+    assert_eq!(response.name, "Address Registry");
+
+    assert_eq!(query_addresses(&app, &address_registry_addr), vec![]);
+}
+
+#[test]
+fn test_set_and_remove_addresses() {
+    let mut app = App::default();
+    let address_registry_addr = instantiate_address_registry(&mut app);
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            address_registry_addr.clone(),
+            &set_address_msg(ProtocolContract::TroveManager, TM),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::UnauthorizedOwner {});
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &ExecuteMsg::SetAddresses {
+            addresses: vec![
+                ContractAddress {
+                    contract: ProtocolContract::TroveManager,
+                    address: TM.to_string(),
+                },
+                ContractAddress {
+                    contract: ProtocolContract::ActivePool,
+                    address: AP.to_string(),
+                },
+            ],
+        },
+        &[],
+    )
+    .unwrap();
+
+    let res: AddressResponse = app
+        .wrap()
+        .query_wasm_smart(
+            address_registry_addr.clone(),
+            &QueryMsg::GetAddress {
+                contract: ProtocolContract::TroveManager,
+            },
+        )
+        .unwrap();
+    assert_eq!(res.address, Addr::unchecked(TM));
+
+    // Unregistered contracts can't be resolved
+    app.wrap()
+        .query_wasm_smart::<AddressResponse>(
+            address_registry_addr.clone(),
+            &QueryMsg::GetAddress {
+                contract: ProtocolContract::StabilityPool,
+            },
+        )
+        .unwrap_err();
+
+    // A redeployed contract replaces the previous address
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &set_address_msg(ProtocolContract::TroveManager, NEW_TM),
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        query_addresses(&app, &address_registry_addr),
+        vec![
+            AddressResponse {
+                contract: ProtocolContract::ActivePool,
+                address: Addr::unchecked(AP),
+            },
+            AddressResponse {
+                contract: ProtocolContract::TroveManager,
+                address: Addr::unchecked(NEW_TM),
+            },
+        ]
+    );
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &ExecuteMsg::RemoveAddress {
+            contract: ProtocolContract::ActivePool,
+        },
+        &[],
+    )
+    .unwrap();
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            address_registry_addr.clone(),
+            &ExecuteMsg::RemoveAddress {
+                contract: ProtocolContract::ActivePool,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::AddressNotFound {
+            contract: "active_pool".to_string()
+        }
+    );
+    assert_eq!(query_addresses(&app, &address_registry_addr).len(), 1);
+}
+
+#[test]
+fn test_lock_addresses() {
+    let mut app = App::default();
+    let address_registry_addr = instantiate_address_registry(&mut app);
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &set_address_msg(ProtocolContract::TroveManager, TM),
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &ExecuteMsg::LockAddresses {},
+        &[],
+    )
+    .unwrap();
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            address_registry_addr.clone(),
+            &set_address_msg(ProtocolContract::TroveManager, NEW_TM),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Pool(PoolError::AddressesLocked {}));
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            address_registry_addr.clone(),
+            &ExecuteMsg::RemoveAddress {
+                contract: ProtocolContract::TroveManager,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Pool(PoolError::AddressesLocked {}));
+
+    // Collateral types can still be added to and removed from the locked registry
+    let juno = collateral(native_asset_info(UJUNO_DENOM.to_string()), "juno");
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &ExecuteMsg::RegisterCollateral {
+            collateral: juno.clone(),
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        query_collaterals(&app, &address_registry_addr),
+        vec![juno.clone()]
+    );
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &ExecuteMsg::RemoveCollateral { asset: juno.asset },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(query_collaterals(&app, &address_registry_addr), vec![]);
+}
+
+#[test]
+fn test_update_risk_params() {
+    let mut app = App::default();
+    let address_registry_addr = instantiate_address_registry(&mut app);
+
+    let query_risk_params = |app: &App| -> RiskParams {
+        app.wrap()
+            .query_wasm_smart(address_registry_addr.clone(), &QueryMsg::GetRiskParams {})
+            .unwrap()
+    };
+    assert_eq!(query_risk_params(&app), RiskParams::default());
+
+    app.wasm_sudo(
+        address_registry_addr.clone(),
+        &SudoMsg::UpdateRiskParams {
+            params: Box::new(RiskParamsUpdate {
+                mcr: Some(Decimal256::percent(120)),
+                min_net_debt: Some(Uint128::new(1800u128)),
+                ..RiskParamsUpdate::default()
+            }),
+        },
+    )
+    .unwrap();
+    assert_eq!(
+        query_risk_params(&app),
+        RiskParams {
+            mcr: Decimal256::percent(120),
+            min_net_debt: Uint128::new(1800u128),
+            ..RiskParams::default()
+        }
+    );
+
+    // Updates leaving the parameters inconsistent are rejected
+    let err: ContractError = app
+        .wasm_sudo(
+            address_registry_addr.clone(),
+            &SudoMsg::UpdateRiskParams {
+                params: Box::new(RiskParamsUpdate {
+                    ccr: Some(Decimal256::percent(115)),
+                    ..RiskParamsUpdate::default()
+                }),
+            },
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::RiskParams(RiskParamsError::CcrNotAboveMcr {})
+    );
+    assert_eq!(query_risk_params(&app).ccr, Decimal256::percent(150));
+}
+
+/// Collateral served by pools prefixed with `prefix`
+fn collateral(asset: AssetInfo, prefix: &str) -> Collateral {
+    let address = |contract: &str| Addr::unchecked(format!("{}-{}", prefix, contract));
+    Collateral {
+        asset,
+        contracts: CollateralContracts {
+            active_pool: address("active-pool"),
+            default_pool: address("default-pool"),
+            coll_surplus_pool: address("coll-surplus-pool"),
+            sorted_troves: address("sorted-troves"),
+            price_feed: address("price-feed"),
+        },
+        params: CollateralParams {
+            mcr: Decimal256::percent(110),
+            debt_ceiling: Uint128::new(1_000_000u128),
+        },
+    }
+}
+
+fn query_collaterals(app: &App, address_registry_addr: &Addr) -> Vec<Collateral> {
+    let res: CollateralsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            address_registry_addr.clone(),
+            &QueryMsg::ListCollaterals {
+                start_after: None,
+                limit: None,
+            },
+        )
+        .unwrap();
+    res.collaterals
+}
+
+#[test]
+fn test_register_and_remove_collaterals() {
+    let mut app = App::default();
+    let address_registry_addr = instantiate_address_registry(&mut app);
+
+    let juno = collateral(native_asset_info(UJUNO_DENOM.to_string()), "juno");
+    let token = collateral(
+        AssetInfo::Cw20Token {
+            contract_addr: Addr::unchecked("collateral-token"),
+        },
+        "token",
+    );
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            address_registry_addr.clone(),
+            &ExecuteMsg::RegisterCollateral {
+                collateral: juno.clone(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::UnauthorizedOwner {});
+
+    let mut invalid = juno.clone();
+    invalid.params.mcr = Decimal256::one();
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            address_registry_addr.clone(),
+            &ExecuteMsg::RegisterCollateral {
+                collateral: invalid,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Collateral(CollateralError::McrTooLow {})
+    );
+
+    // The MCR must stay below the protocol CCR
+    let mut invalid = juno.clone();
+    invalid.params.mcr = Decimal256::percent(150);
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            address_registry_addr.clone(),
+            &ExecuteMsg::RegisterCollateral {
+                collateral: invalid,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Collateral(CollateralError::RiskParams(
+            RiskParamsError::CcrNotAboveMcr {}
+        ))
+    );
+
+    for collateral in [&juno, &token] {
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            address_registry_addr.clone(),
+            &ExecuteMsg::RegisterCollateral {
+                collateral: collateral.clone(),
+            },
+            &[],
+        )
+        .unwrap();
+    }
+
+    let res: Collateral = app
+        .wrap()
+        .query_wasm_smart(
+            address_registry_addr.clone(),
+            &QueryMsg::GetCollateral {
+                asset: juno.asset.clone(),
+            },
+        )
+        .unwrap();
+    assert_eq!(res, juno);
+
+    // Collaterals are listed by key
+    assert_eq!(
+        query_collaterals(&app, &address_registry_addr),
+        vec![token.clone(), juno.clone()]
+    );
+    let res: CollateralsResponse = app
+        .wrap()
+        .query_wasm_smart(
+            address_registry_addr.clone(),
+            &QueryMsg::ListCollaterals {
+                start_after: Some(token.key()),
+                limit: Some(1),
+            },
+        )
+        .unwrap();
+    assert_eq!(res.collaterals, vec![juno.clone()]);
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &ExecuteMsg::RemoveCollateral {
+            asset: token.asset.clone(),
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(query_collaterals(&app, &address_registry_addr), vec![juno]);
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            address_registry_addr.clone(),
+            &ExecuteMsg::RemoveCollateral {
+                asset: token.asset.clone(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::CollateralNotFound {
+            asset: "collateral-token".to_string()
+        }
+    );
+
+    let res: StdResult<Collateral> = app.wrap().query_wasm_smart(
+        address_registry_addr,
+        &QueryMsg::GetCollateral { asset: token.asset },
+    );
+    res.unwrap_err();
+}
+
+#[test]
+fn test_update_collateral_params() {
+    let mut app = App::default();
+    let address_registry_addr = instantiate_address_registry(&mut app);
+    let juno = collateral(native_asset_info(UJUNO_DENOM.to_string()), "juno");
+
+    let update_msg = SudoMsg::UpdateCollateralParams {
+        asset: juno.asset.clone(),
+        params: CollateralParamsUpdate {
+            debt_ceiling: Some(Uint128::zero()),
+            ..CollateralParamsUpdate::default()
+        },
+    };
+    let err: ContractError = app
+        .wasm_sudo(address_registry_addr.clone(), &update_msg)
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::CollateralNotFound {
+            asset: UJUNO_DENOM.to_string()
+        }
+    );
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &ExecuteMsg::RegisterCollateral {
+            collateral: juno.clone(),
+        },
+        &[],
+    )
+    .unwrap();
+    app.wasm_sudo(address_registry_addr.clone(), &update_msg)
+        .unwrap();
+    assert_eq!(
+        query_collaterals(&app, &address_registry_addr)[0].params,
+        CollateralParams {
+            mcr: Decimal256::percent(110),
+            debt_ceiling: Uint128::zero(),
+        }
+    );
+
+    let err: ContractError = app
+        .wasm_sudo(
+            address_registry_addr.clone(),
+            &SudoMsg::UpdateCollateralParams {
+                asset: juno.asset.clone(),
+                params: CollateralParamsUpdate {
+                    mcr: Some(Decimal256::percent(90)),
+                    ..CollateralParamsUpdate::default()
+                },
+            },
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Collateral(CollateralError::McrTooLow {})
+    );
+
+    // The MCR of the collateral and the protocol CCR must stay consistent either way
+    let err: ContractError = app
+        .wasm_sudo(
+            address_registry_addr.clone(),
+            &SudoMsg::UpdateCollateralParams {
+                asset: juno.asset,
+                params: CollateralParamsUpdate {
+                    mcr: Some(Decimal256::percent(150)),
+                    ..CollateralParamsUpdate::default()
+                },
+            },
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Collateral(CollateralError::RiskParams(
+            RiskParamsError::CcrNotAboveMcr {}
+        ))
+    );
+    let err: ContractError = app
+        .wasm_sudo(
+            address_registry_addr.clone(),
+            &SudoMsg::UpdateRiskParams {
+                params: Box::new(RiskParamsUpdate {
+                    mcr: Some(Decimal256::percent(105)),
+                    ccr: Some(Decimal256::percent(108)),
+                    ..RiskParamsUpdate::default()
+                }),
+            },
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Collateral(CollateralError::RiskParams(
+            RiskParamsError::CcrNotAboveMcr {}
+        ))
+    );
+    assert_eq!(
+        query_collaterals(&app, &address_registry_addr)[0]
+            .params
+            .mcr,
+        Decimal256::percent(110)
+    );
+}
@@ -5,7 +5,8 @@ use cosmwasm_std::{
     Response, StdResult,
 };
 
-use cw2::{get_contract_version, set_contract_version};
+use cw2::set_contract_version;
+use ultra_base::migration::{migrate_contract, Migration};
 
 use crate::error::ContractError;
 use crate::ibc::OracleRequestPacket;
@@ -19,6 +20,9 @@ use crate::state::{Config, CHANNEL_INFO, CONFIG};
 const CONTRACT_NAME: &str = "band-protocol";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// state migrations, ordered by the version introducing them
+const MIGRATIONS: &[Migration] = &[];
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -94,13 +98,12 @@ pub fn execute_oracle(deps: DepsMut, env: Env, msg: OracleMsg) -> Result<Respons
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
-    let version = get_contract_version(deps.storage)?;
-    if version.contract != CONTRACT_NAME {
-        return Err(ContractError::CannotMigrate {
-            previous_contract: version.contract,
-        });
-    }
-    Ok(Response::default())
+    Ok(migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+    )?)
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
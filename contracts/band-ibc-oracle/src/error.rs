@@ -1,5 +1,6 @@
 use std::string::FromUtf8Error;
 use thiserror::Error;
+use ultra_base::migration::MigrationError;
 
 use cosmwasm_std::StdError;
 
@@ -30,8 +31,8 @@ pub enum ContractError {
     #[error("Parsed channel from denom ({channel}) doesn't match packet")]
     FromOtherChannel { channel: String },
 
-    #[error("Cannot migrate from different contract type: {previous_contract}")]
-    CannotMigrate { previous_contract: String },
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
 
     #[error("Got a submessage reply with unknown id: {id}")]
     UnknownReplyId { id: u64 },
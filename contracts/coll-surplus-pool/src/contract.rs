@@ -1,26 +1,39 @@
-use std::vec;
-
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coin, to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError,
-    StdResult, Storage, Uint128,
+    from_binary, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Order, Response,
+    StdError, StdResult, Storage, Uint128,
 };
+use cw20::Cw20ReceiveMsg;
+use cw_storage_plus::Bound;
 
-use cw2::set_contract_version;
+use cw2::{get_contract_version, set_contract_version};
 
 use crate::error::ContractError;
-use crate::state::{
-    AddressesSet, SudoParams, TotalCollsInPool, ADDRESSES_SET, COLL_OF_ACCOUNT, SUDO_PARAMS,
-    TOTAL_COLLS_IN_POOL,
+use crate::state::{AddressesSet, ADDRESSES_SET, COLL_OF_ACCOUNT, TOTAL_COLLS_IN_POOL};
+use ultra_base::address_registry::ProtocolContract;
+use ultra_base::asset::Asset;
+use ultra_base::coll_surplus_pool::{
+    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, ParamsResponse, QueryMsg, SurplusResponse,
+    SurplusesResponse,
 };
-use ultra_base::coll_surplus_pool::{ExecuteMsg, InstantiateMsg, ParamsResponse, QueryMsg};
+use ultra_base::migration::{migrate_contract, Migration};
+use ultra_base::querier::{query_collateral, query_protocol_address};
+use ultra_pool::access::{self, Role};
+use ultra_pool::events::PoolEvent;
+use ultra_pool::funds::{must_receive_collateral, validate_collateral};
+use ultra_pool::ledger::TotalCollsInPool;
+use ultra_pool::ownership;
+use ultra_pool::registry;
+use ultra_pool::state::{load_collateral, SudoParams, COLLATERAL, SUDO_PARAMS};
 
 // version info for migration info
-const CONTRACT_NAME: &str = "crates.io:active-pool";
+const CONTRACT_NAME: &str = "crates.io:coll-surplus-pool";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub const NATIVE_JUNO_DENOM: &str = "ujuno";
+// settings for pagination
+const MAX_LIMIT: u32 = 30;
+const DEFAULT_LIMIT: u32 = 10;
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
@@ -38,16 +51,44 @@ pub fn instantiate(
     };
 
     // initial assets in pool
-    let assets_in_pool = TotalCollsInPool {
-        juno: Uint128::zero(),
-    };
+    let assets_in_pool = TotalCollsInPool::default();
+
+    // native or cw20 collateral held by the pool, sent along by the active pool before the
+    // trove manager accounts it
+    let collateral = validate_collateral(deps.api, msg.collateral)?;
 
     SUDO_PARAMS.save(deps.storage, &sudo_params)?;
+    COLLATERAL.save(deps.storage, &collateral)?;
     TOTAL_COLLS_IN_POOL.save(deps.storage, &assets_in_pool)?;
 
     Ok(Response::default())
 }
 
+// Name stored by the pools instantiated before the contract name was fixed
+const LEGACY_CONTRACT_NAME: &str = "crates.io:active-pool";
+
+// State migrations, ordered by the version introducing them
+const MIGRATIONS: &[Migration] = &[];
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    // pools stored under the legacy name are recognized by their surplus ledger,
+    // which the active pool doesn't have
+    let stored = get_contract_version(deps.storage)?;
+    if stored.contract == LEGACY_CONTRACT_NAME
+        && TOTAL_COLLS_IN_POOL.may_load(deps.storage)?.is_some()
+    {
+        set_contract_version(deps.storage, CONTRACT_NAME, stored.version)?;
+    }
+
+    Ok(migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+    )?)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -59,20 +100,19 @@ pub fn execute(
         ExecuteMsg::AccountSurplus { account, amount } => {
             execute_account_surplus(deps, env, info, account, amount)
         }
-        ExecuteMsg::ClaimColl { account } => execute_claim_coll(deps, env, info, account),
-
-        ExecuteMsg::SetAddresses {
-            borrower_operations_address,
-            trove_manager_address,
-            active_pool_address,
-        } => execute_set_addresses(
-            deps,
-            env,
-            info,
-            borrower_operations_address,
-            trove_manager_address,
-            active_pool_address,
-        ),
+        ExecuteMsg::ClaimColl { account, recipient } => {
+            execute_claim_coll(deps, env, info, account, recipient)
+        }
+        ExecuteMsg::Receive(cw20_msg) => execute_receive(deps, env, info, cw20_msg),
+
+        ExecuteMsg::SetAddressRegistry { address_registry } => {
+            execute_set_address_registry(deps, env, info, address_registry)
+        }
+        ExecuteMsg::SyncAddresses {} => execute_sync_addresses(deps, env, info),
+        ExecuteMsg::ProposeOwner { owner } => execute_propose_owner(deps, env, info, owner),
+        ExecuteMsg::AcceptOwner {} => execute_accept_owner(deps, env, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, env, info),
+        ExecuteMsg::LockAddresses {} => execute_lock_addresses(deps, env, info),
     }
 }
 
@@ -85,13 +125,21 @@ pub fn execute_account_surplus(
 ) -> Result<Response, ContractError> {
     only_tm(deps.storage, &info)?;
 
-    let mut coll_of_account = COLL_OF_ACCOUNT.load(deps.storage, account.clone())?;
-    coll_of_account += amount;
+    let coll_of_account = COLL_OF_ACCOUNT
+        .may_load(deps.storage, account.clone())?
+        .unwrap_or_default()
+        .checked_add(amount)
+        .map_err(StdError::overflow)?;
     COLL_OF_ACCOUNT.save(deps.storage, account.clone(), &coll_of_account)?;
 
+    let mut total_colls_in_pool = TOTAL_COLLS_IN_POOL.load(deps.storage)?;
+    total_colls_in_pool.increase_coll(amount)?;
+    TOTAL_COLLS_IN_POOL.save(deps.storage, &total_colls_in_pool)?;
+
     let res = Response::new()
         .add_attribute("action", "account_surplus")
-        .add_attribute("account", account);
+        .add_attribute("account", account)
+        .add_attribute("amount", amount);
     Ok(res)
 }
 
@@ -100,100 +148,208 @@ pub fn execute_claim_coll(
     _env: Env,
     info: MessageInfo,
     account: Addr,
+    recipient: Option<Addr>,
 ) -> Result<Response, ContractError> {
     only_bo(deps.storage, &info)?;
 
-    let mut coll_of_account = COLL_OF_ACCOUNT.load(deps.storage, account.clone())?;
-    let mut total_colls_in_pool = TOTAL_COLLS_IN_POOL.load(deps.storage)?;
+    let recipient = match recipient {
+        Some(recipient) => deps.api.addr_validate(recipient.as_str())?,
+        None => account.clone(),
+    };
 
+    let coll_of_account = COLL_OF_ACCOUNT
+        .may_load(deps.storage, account.clone())?
+        .unwrap_or_default();
     if coll_of_account.is_zero() {
         return Err(ContractError::NoCollAvailableToClaim {});
     }
-    let send_msg = BankMsg::Send {
-        to_address: account.to_string(),
-        amount: vec![coin(coll_of_account.u128(), NATIVE_JUNO_DENOM.to_string())],
-    };
 
-    total_colls_in_pool.juno = total_colls_in_pool
-        .juno
-        .checked_sub(coll_of_account)
-        .map_err(StdError::overflow)?;
-    coll_of_account = Uint128::zero();
+    let mut total_colls_in_pool = TOTAL_COLLS_IN_POOL.load(deps.storage)?;
+    total_colls_in_pool.decrease_coll(coll_of_account)?;
 
-    COLL_OF_ACCOUNT.save(deps.storage, account.clone(), &coll_of_account)?;
+    COLL_OF_ACCOUNT.remove(deps.storage, account.clone());
     TOTAL_COLLS_IN_POOL.save(deps.storage, &total_colls_in_pool)?;
 
+    let send_msg = Asset {
+        info: load_collateral(deps.storage)?,
+        amount: coll_of_account,
+    }
+    .into_msg(&deps.querier, &recipient)?;
     let res = Response::new()
         .add_message(send_msg)
         .add_attribute("action", "claim_coll")
-        .add_attribute("account", account);
+        .add_attribute("account", account)
+        .add_attribute("recipient", recipient)
+        .add_attribute("amount", coll_of_account);
     Ok(res)
 }
 
-pub fn execute_set_addresses(
+/// Takes the cw20 collateral sent through the cw20 hook, the sender of the tokens standing for
+/// the caller
+pub fn execute_receive(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    borrower_operations_address: String,
-    trove_manager_address: String,
-    active_pool_address: String,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let collateral = load_collateral(deps.storage)?;
+    let amount = must_receive_collateral(&info, &collateral, cw20_msg.amount)?;
+
+    let sender_info = MessageInfo {
+        sender: deps.api.addr_validate(&cw20_msg.sender)?,
+        funds: vec![],
+    };
+    match from_binary(&cw20_msg.msg)? {
+        Cw20HookMsg::ReceiveCollateral {} => {
+            let sender = only_ap(deps.storage, &sender_info)?;
+            Ok(PoolEvent::ReceiveCollateral { sender, amount }.into_response())
+        }
+    }
+}
+
+pub fn execute_set_address_registry(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address_registry: String,
 ) -> Result<Response, ContractError> {
     only_owner(deps.storage, &info)?;
+    ownership::ensure_addresses_unlocked(deps.storage)?;
+
+    let address_registry = deps.api.addr_validate(&address_registry)?;
+    Ok(registry::set_address_registry(
+        deps.storage,
+        address_registry,
+    )?)
+}
 
+/// Resolves the peers from the address registry and caches them in the addresses set,
+/// the active pool being the one registered along with this pool for its collateral
+pub fn execute_sync_addresses(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ownership::ensure_addresses_unlocked(deps.storage)?;
+
+    let address_registry = registry::load_address_registry(deps.storage)?;
+    let resolve = |contract| query_protocol_address(&deps.querier, &address_registry, contract);
+    let collateral = query_collateral(
+        &deps.querier,
+        &address_registry,
+        &load_collateral(deps.storage)?,
+    )?;
     let new_addresses_set = AddressesSet {
-        borrower_operations_address: deps.api.addr_validate(&borrower_operations_address)?,
-        trove_manager_address: deps.api.addr_validate(&trove_manager_address)?,
-        active_pool_address: deps.api.addr_validate(&active_pool_address)?,
+        borrower_operations_address: resolve(ProtocolContract::BorrowerOperations)?,
+        trove_manager_address: resolve(ProtocolContract::TroveManager)?,
+        active_pool_address: collateral.contracts.active_pool,
     };
 
     ADDRESSES_SET.save(deps.storage, &new_addresses_set)?;
     let res = Response::new()
-        .add_attribute("action", "set_addresses")
-        .add_attribute("borrower_operations_address", borrower_operations_address)
-        .add_attribute("trove_manager_address", trove_manager_address)
-        .add_attribute("stability_pool_address", active_pool_address);
+        .add_attribute("action", "sync_addresses")
+        .add_attribute(
+            "borrower_operations_address",
+            new_addresses_set.borrower_operations_address,
+        )
+        .add_attribute(
+            "trove_manager_address",
+            new_addresses_set.trove_manager_address,
+        )
+        .add_attribute("active_pool_address", new_addresses_set.active_pool_address);
     Ok(res)
 }
 
-/// Checks to enfore only borrower operations can call
-fn only_bo(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
-    let addresses_set = ADDRESSES_SET.load(store)?;
-    if addresses_set.borrower_operations_address != info.sender.as_ref() {
-        return Err(ContractError::CallerIsNotBO {});
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    owner: String,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    let owner = deps.api.addr_validate(&owner)?;
+    Ok(ownership::propose_owner(deps.storage, owner)?)
+}
+
+pub fn execute_accept_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    Ok(ownership::accept_owner(deps.storage, &info)?)
+}
+
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    Ok(ownership::renounce_ownership(deps.storage)?)
+}
+
+pub fn execute_lock_addresses(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    // the addresses must be configured before they are locked for good
+    if ADDRESSES_SET.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::AddressesNotSet {});
     }
-    Ok(info.sender.clone())
+    Ok(ownership::lock_addresses(deps.storage)?)
 }
-/// Checks to enfore only trove manager can call
-fn only_tm(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
+
+/// Checks to enfore only borrower operations can call
+fn only_bo(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
     let addresses_set = ADDRESSES_SET.load(store)?;
-    if addresses_set.trove_manager_address != info.sender.as_ref() {
-        return Err(ContractError::CallerIsNotTM {});
-    }
-    Ok(info.sender.clone())
+    access::only_roles(
+        &addresses_set,
+        info,
+        &[Role::BorrowerOperations],
+        ContractError::CallerIsNotBO {},
+    )
 }
 /// Checks to enfore only active pool can call
 fn only_ap(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
     let addresses_set = ADDRESSES_SET.load(store)?;
-    if addresses_set.trove_manager_address != info.sender.as_ref() {
-        return Err(ContractError::CallerIsNotTM {});
-    }
-    Ok(info.sender.clone())
+    access::only_roles(
+        &addresses_set,
+        info,
+        &[Role::ActivePool],
+        ContractError::CallerIsNotAP {},
+    )
+}
+/// Checks to enfore only trove manager can call
+fn only_tm(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
+    let addresses_set = ADDRESSES_SET.load(store)?;
+    access::only_roles(
+        &addresses_set,
+        info,
+        &[Role::TroveManager],
+        ContractError::CallerIsNotTM {},
+    )
 }
 /// Checks to enfore only owner can call
 fn only_owner(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
-    let params = SUDO_PARAMS.load(store)?;
-    if params.owner != info.sender.as_ref() {
-        return Err(ContractError::UnauthorizedOwner {});
-    }
-    Ok(info.sender.clone())
+    access::only_owner(store, info, ContractError::UnauthorizedOwner {})
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetParams {} => to_binary(&query_params(deps)?),
-        QueryMsg::GetJUNO {} => to_binary(&query_juno_state(deps)?),
+        QueryMsg::GetOwnership {} => to_binary(&ownership::query_ownership(deps.storage)?),
+        QueryMsg::GetTotalCollateral {} => to_binary(&query_coll_state(deps)?),
+        QueryMsg::GetCollateralInfo {} => to_binary(&load_collateral(deps.storage)?),
         QueryMsg::GetCollateral { account } => to_binary(&query_coll_of_account(deps, account)?),
+        QueryMsg::ListSurpluses { start_after, limit } => {
+            to_binary(&query_surpluses(deps, start_after, limit)?)
+        }
         QueryMsg::GetBorrowerOperationsAddress {} => {
             to_binary(&query_borrower_operations_address(deps)?)
         }
@@ -202,17 +358,35 @@ pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     }
 }
 
-pub fn query_juno_state(deps: Deps) -> StdResult<Uint128> {
+pub fn query_coll_state(deps: Deps) -> StdResult<Uint128> {
     let info = TOTAL_COLLS_IN_POOL.load(deps.storage)?;
-    let res = info.juno;
+    let res = info.coll;
     Ok(res)
 }
 
 pub fn query_coll_of_account(deps: Deps, account: Addr) -> StdResult<Uint128> {
-    let info = COLL_OF_ACCOUNT.load(deps.storage, account)?;
+    let info = COLL_OF_ACCOUNT
+        .may_load(deps.storage, account)?
+        .unwrap_or_default();
     Ok(info)
 }
 
+pub fn query_surpluses(
+    deps: Deps,
+    start_after: Option<String>,
+    limit: Option<u32>,
+) -> StdResult<SurplusesResponse> {
+    let limit = limit.unwrap_or(DEFAULT_LIMIT).min(MAX_LIMIT) as usize;
+    let start = start_after.map(|s| Bound::ExclusiveRaw(s.into_bytes()));
+
+    let surpluses = COLL_OF_ACCOUNT
+        .range(deps.storage, start, None, Order::Ascending)
+        .take(limit)
+        .map(|item| item.map(|(account, amount)| SurplusResponse { account, amount }))
+        .collect::<StdResult<_>>()?;
+    Ok(SurplusesResponse { surpluses })
+}
+
 pub fn query_params(deps: Deps) -> StdResult<ParamsResponse> {
     let info = SUDO_PARAMS.load(deps.storage)?;
     let res = ParamsResponse {
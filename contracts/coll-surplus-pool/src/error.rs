@@ -1,11 +1,19 @@
 use cosmwasm_std::StdError;
 use thiserror::Error;
+use ultra_base::migration::MigrationError;
+use ultra_pool::PoolError;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Pool(#[from] PoolError),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
     #[error("UnauthorizedOwner")]
     UnauthorizedOwner {},
 
@@ -20,4 +28,7 @@ pub enum ContractError {
 
     #[error("CollSurplusPool: No collateral available to claim")]
     NoCollAvailableToClaim {},
+
+    #[error("CollSurplusPool: Addresses must be set before they are locked")]
+    AddressesNotSet {},
 }
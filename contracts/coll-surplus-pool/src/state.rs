@@ -2,6 +2,8 @@ use cosmwasm_std::{Addr, Uint128};
 use cw_storage_plus::{Item, Map};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use ultra_pool::access::{Role, RoleAddresses};
+use ultra_pool::ledger::TotalCollsInPool;
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct AddressesSet {
@@ -10,18 +12,17 @@ pub struct AddressesSet {
     pub active_pool_address: Addr,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct TotalCollsInPool {
-    pub juno: Uint128,
-}
-
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct SudoParams {
-    pub name: String,
-    pub owner: Addr,
+impl RoleAddresses for AddressesSet {
+    fn address_of(&self, role: Role) -> Option<&Addr> {
+        match role {
+            Role::BorrowerOperations => Some(&self.borrower_operations_address),
+            Role::TroveManager => Some(&self.trove_manager_address),
+            Role::ActivePool => Some(&self.active_pool_address),
+            _ => None,
+        }
+    }
 }
 
-pub const SUDO_PARAMS: Item<SudoParams> = Item::new("sudo-params");
 pub const ADDRESSES_SET: Item<AddressesSet> = Item::new("addresses_set");
 pub const TOTAL_COLLS_IN_POOL: Item<TotalCollsInPool> = Item::new("total_colls_in_pool");
 pub const COLL_OF_ACCOUNT: Map<Addr, Uint128> = Map::new("coll-of-account");
@@ -0,0 +1,640 @@
+use crate::contract::{instantiate, migrate};
+use crate::ContractError;
+
+use ultra_base::address_registry::{
+    ContractAddress, ExecuteMsg as AddressRegistryExecuteMsg,
+    InstantiateMsg as AddressRegistryInstantiateMsg, ProtocolContract,
+};
+use ultra_base::asset::{native_asset_info, AssetInfo, UJUNO_DENOM};
+use ultra_base::coll_surplus_pool::{
+    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg, SurplusResponse,
+    SurplusesResponse,
+};
+use ultra_base::collateral::{Collateral, CollateralContracts, CollateralParams};
+use ultra_base::migration::MigrationError;
+use ultra_pool::PoolError;
+
+use cosmwasm_std::testing::{mock_dependencies, mock_env, mock_info};
+use cosmwasm_std::{coins, to_binary, Addr, Decimal256, Empty, Uint128};
+use cw2::{get_contract_version, set_contract_version};
+use cw20::{BalanceResponse, Cw20Coin, Cw20QueryMsg, MinterResponse};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+const SOME: &str = "someone";
+const OWNER: &str = "owner";
+const BO: &str = "borrower-operations";
+const TM: &str = "trove-manager";
+const AP: &str = "active-pool";
+const DP: &str = "default-pool";
+const SORTED_TROVES: &str = "sorted-troves";
+const PRICE_FEED: &str = "price-feed";
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+const CAROL: &str = "carol";
+
+fn coll_surplus_pool_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_sudo(crate::sudo::sudo)
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+fn address_registry_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        address_registry::contract::execute,
+        address_registry::contract::instantiate,
+        address_registry::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn ultra_token_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        ultra_token::contract::execute,
+        ultra_token::contract::instantiate,
+        ultra_token::contract::query,
+    );
+    Box::new(contract)
+}
+
+/// Instantiates a cw20 collateral token held by the active pool
+fn instantiate_collateral_token(app: &mut App) -> Addr {
+    let code_id = app.store_code(ultra_token_contract());
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(SOME),
+        &ultra_token::msg::InstantiateMsg {
+            name: String::from("Collateral"),
+            symbol: String::from("COLL"),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: AP.to_string(),
+                amount: Uint128::new(1_000),
+            }],
+            mint: Some(MinterResponse {
+                minter: OWNER.to_string(),
+                cap: None,
+            }),
+            marketing: None,
+        },
+        &[],
+        "collateral token",
+        None,
+    )
+    .unwrap()
+}
+
+fn query_token_balance(app: &App, token_addr: &Addr, address: &str) -> Uint128 {
+    let res: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            token_addr,
+            &Cw20QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    res.balance
+}
+
+/// Instantiates a coll surplus pool of `collateral` resolving its peers from an address registry
+fn instantiate_coll_surplus_pool(app: &mut App, collateral: AssetInfo) -> Addr {
+    let code_id = app.store_code(coll_surplus_pool_contract());
+    let coll_surplus_pool_addr = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(SOME),
+            &InstantiateMsg {
+                name: String::from("Coll Surplus Pool"),
+                owner: OWNER.to_string(),
+                collateral: collateral.clone(),
+            },
+            &[],
+            "coll surplus pool",
+            None,
+        )
+        .unwrap();
+
+    let address_registry_code_id = app.store_code(address_registry_contract());
+    let address_registry_addr = app
+        .instantiate_contract(
+            address_registry_code_id,
+            Addr::unchecked(SOME),
+            &AddressRegistryInstantiateMsg {
+                name: String::from("Address Registry"),
+                owner: OWNER.to_string(),
+            },
+            &[],
+            "address registry",
+            None,
+        )
+        .unwrap();
+
+    let addresses = [
+        (ProtocolContract::BorrowerOperations, BO),
+        (ProtocolContract::TroveManager, TM),
+        (ProtocolContract::ActivePool, AP),
+    ];
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &AddressRegistryExecuteMsg::SetAddresses {
+            addresses: addresses
+                .into_iter()
+                .map(|(contract, address)| ContractAddress {
+                    contract,
+                    address: address.to_string(),
+                })
+                .collect(),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &AddressRegistryExecuteMsg::RegisterCollateral {
+            collateral: Collateral {
+                asset: collateral,
+                contracts: CollateralContracts {
+                    active_pool: Addr::unchecked(AP),
+                    default_pool: Addr::unchecked(DP),
+                    coll_surplus_pool: coll_surplus_pool_addr.clone(),
+                    sorted_troves: Addr::unchecked(SORTED_TROVES),
+                    price_feed: Addr::unchecked(PRICE_FEED),
+                },
+                params: CollateralParams {
+                    mcr: Decimal256::percent(110),
+                    debt_ceiling: Uint128::new(1_000_000),
+                },
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        coll_surplus_pool_addr.clone(),
+        &ExecuteMsg::SetAddressRegistry {
+            address_registry: address_registry_addr.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        coll_surplus_pool_addr.clone(),
+        &ExecuteMsg::SyncAddresses {},
+        &[],
+    )
+    .unwrap();
+
+    coll_surplus_pool_addr
+}
+
+/// Moves `amount` JUNO from the active pool and records it as surplus of `account`
+fn account_surplus(app: &mut App, coll_surplus_pool_addr: &Addr, account: &str, amount: u128) {
+    app.send_tokens(
+        Addr::unchecked(AP),
+        coll_surplus_pool_addr.clone(),
+        &coins(amount, UJUNO_DENOM),
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(TM),
+        coll_surplus_pool_addr.clone(),
+        &ExecuteMsg::AccountSurplus {
+            account: Addr::unchecked(account),
+            amount: Uint128::new(amount),
+        },
+        &[],
+    )
+    .unwrap();
+}
+
+fn query_collateral(app: &App, coll_surplus_pool_addr: &Addr, account: &str) -> Uint128 {
+    app.wrap()
+        .query_wasm_smart(
+            coll_surplus_pool_addr.clone(),
+            &QueryMsg::GetCollateral {
+                account: Addr::unchecked(account),
+            },
+        )
+        .unwrap()
+}
+
+#[test]
+fn test_account_surplus() {
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(AP), coins(1000, UJUNO_DENOM))
+            .unwrap();
+    });
+    let coll_surplus_pool_addr =
+        instantiate_coll_surplus_pool(&mut app, native_asset_info(UJUNO_DENOM.to_string()));
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            coll_surplus_pool_addr.clone(),
+            &ExecuteMsg::AccountSurplus {
+                account: Addr::unchecked(ALICE),
+                amount: Uint128::new(100u128),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNotTM {});
+
+    // Unknown accounts have no surplus
+    assert_eq!(
+        query_collateral(&app, &coll_surplus_pool_addr, ALICE),
+        Uint128::zero()
+    );
+
+    // The first surplus of an account is recorded, later ones accumulate
+    account_surplus(&mut app, &coll_surplus_pool_addr, ALICE, 100);
+    assert_eq!(
+        query_collateral(&app, &coll_surplus_pool_addr, ALICE),
+        Uint128::new(100u128)
+    );
+    account_surplus(&mut app, &coll_surplus_pool_addr, ALICE, 50);
+    assert_eq!(
+        query_collateral(&app, &coll_surplus_pool_addr, ALICE),
+        Uint128::new(150u128)
+    );
+
+    let juno: Uint128 = app
+        .wrap()
+        .query_wasm_smart(coll_surplus_pool_addr, &QueryMsg::GetTotalCollateral {})
+        .unwrap();
+    assert_eq!(juno, Uint128::new(150u128));
+}
+
+#[test]
+fn test_list_surpluses() {
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(AP), coins(1000, UJUNO_DENOM))
+            .unwrap();
+    });
+    let coll_surplus_pool_addr =
+        instantiate_coll_surplus_pool(&mut app, native_asset_info(UJUNO_DENOM.to_string()));
+
+    account_surplus(&mut app, &coll_surplus_pool_addr, CAROL, 30);
+    account_surplus(&mut app, &coll_surplus_pool_addr, ALICE, 10);
+    account_surplus(&mut app, &coll_surplus_pool_addr, BOB, 20);
+
+    let res: SurplusesResponse = app
+        .wrap()
+        .query_wasm_smart(
+            coll_surplus_pool_addr.clone(),
+            &QueryMsg::ListSurpluses {
+                start_after: None,
+                limit: Some(2),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res.surpluses,
+        vec![
+            SurplusResponse {
+                account: Addr::unchecked(ALICE),
+                amount: Uint128::new(10u128),
+            },
+            SurplusResponse {
+                account: Addr::unchecked(BOB),
+                amount: Uint128::new(20u128),
+            },
+        ]
+    );
+
+    let res: SurplusesResponse = app
+        .wrap()
+        .query_wasm_smart(
+            coll_surplus_pool_addr,
+            &QueryMsg::ListSurpluses {
+                start_after: Some(BOB.to_string()),
+                limit: None,
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res.surpluses,
+        vec![SurplusResponse {
+            account: Addr::unchecked(CAROL),
+            amount: Uint128::new(30u128),
+        }]
+    );
+}
+
+#[test]
+fn test_claim_coll() {
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(AP), coins(1000, UJUNO_DENOM))
+            .unwrap();
+    });
+    let coll_surplus_pool_addr =
+        instantiate_coll_surplus_pool(&mut app, native_asset_info(UJUNO_DENOM.to_string()));
+
+    account_surplus(&mut app, &coll_surplus_pool_addr, ALICE, 100);
+    account_surplus(&mut app, &coll_surplus_pool_addr, BOB, 40);
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            coll_surplus_pool_addr.clone(),
+            &ExecuteMsg::ClaimColl {
+                account: Addr::unchecked(ALICE),
+                recipient: None,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNotBO {});
+
+    // Without a recipient the surplus goes to the account itself
+    app.execute_contract(
+        Addr::unchecked(BO),
+        coll_surplus_pool_addr.clone(),
+        &ExecuteMsg::ClaimColl {
+            account: Addr::unchecked(ALICE),
+            recipient: None,
+        },
+        &[],
+    )
+    .unwrap();
+    let balance = app.wrap().query_balance(ALICE, UJUNO_DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(100u128));
+
+    // The surplus can be paid out to a different recipient
+    app.execute_contract(
+        Addr::unchecked(BO),
+        coll_surplus_pool_addr.clone(),
+        &ExecuteMsg::ClaimColl {
+            account: Addr::unchecked(BOB),
+            recipient: Some(Addr::unchecked(CAROL)),
+        },
+        &[],
+    )
+    .unwrap();
+    let balance = app.wrap().query_balance(CAROL, UJUNO_DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(40u128));
+    let balance = app.wrap().query_balance(BOB, UJUNO_DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::zero());
+
+    assert_eq!(
+        query_collateral(&app, &coll_surplus_pool_addr, ALICE),
+        Uint128::zero()
+    );
+    let juno: Uint128 = app
+        .wrap()
+        .query_wasm_smart(
+            coll_surplus_pool_addr.clone(),
+            &QueryMsg::GetTotalCollateral {},
+        )
+        .unwrap();
+    assert_eq!(juno, Uint128::zero());
+
+    // Claimed surpluses cannot be claimed twice
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(BO),
+            coll_surplus_pool_addr,
+            &ExecuteMsg::ClaimColl {
+                account: Addr::unchecked(ALICE),
+                recipient: None,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NoCollAvailableToClaim {});
+}
+
+#[test]
+fn test_claim_cw20_coll() {
+    let mut app = App::default();
+    let token_addr = instantiate_collateral_token(&mut app);
+    let coll_surplus_pool_addr = instantiate_coll_surplus_pool(
+        &mut app,
+        AssetInfo::Cw20Token {
+            contract_addr: token_addr.clone(),
+        },
+    );
+
+    // The active pool sends the surplus before the trove manager accounts it
+    app.execute_contract(
+        Addr::unchecked(AP),
+        token_addr.clone(),
+        &ultra_token::msg::ExecuteMsg::Send {
+            contract: coll_surplus_pool_addr.to_string(),
+            amount: Uint128::new(100u128),
+            msg: to_binary(&Cw20HookMsg::ReceiveCollateral {}).unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(TM),
+        coll_surplus_pool_addr.clone(),
+        &ExecuteMsg::AccountSurplus {
+            account: Addr::unchecked(ALICE),
+            amount: Uint128::new(100u128),
+        },
+        &[],
+    )
+    .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(BO),
+        coll_surplus_pool_addr.clone(),
+        &ExecuteMsg::ClaimColl {
+            account: Addr::unchecked(ALICE),
+            recipient: None,
+        },
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        query_token_balance(&app, &token_addr, ALICE),
+        Uint128::new(100u128)
+    );
+    assert_eq!(
+        query_token_balance(&app, &token_addr, coll_surplus_pool_addr.as_str()),
+        Uint128::zero()
+    );
+    assert_eq!(
+        query_collateral(&app, &coll_surplus_pool_addr, ALICE),
+        Uint128::zero()
+    );
+}
+
+#[test]
+fn test_receive_cw20_coll() {
+    let mut app = App::default();
+    let token_addr = instantiate_collateral_token(&mut app);
+    let other_token_addr = instantiate_collateral_token(&mut app);
+    let coll_surplus_pool_addr = instantiate_coll_surplus_pool(
+        &mut app,
+        AssetInfo::Cw20Token {
+            contract_addr: token_addr.clone(),
+        },
+    );
+    app.execute_contract(
+        Addr::unchecked(AP),
+        token_addr.clone(),
+        &ultra_token::msg::ExecuteMsg::Transfer {
+            recipient: SOME.to_string(),
+            amount: Uint128::new(100u128),
+        },
+        &[],
+    )
+    .unwrap();
+
+    let send = |amount: u128| ultra_token::msg::ExecuteMsg::Send {
+        contract: coll_surplus_pool_addr.to_string(),
+        amount: Uint128::new(amount),
+        msg: to_binary(&Cw20HookMsg::ReceiveCollateral {}).unwrap(),
+    };
+
+    // Only the active pool can send the surplus
+    let err: ContractError = app
+        .execute_contract(Addr::unchecked(SOME), token_addr.clone(), &send(100), &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNotAP {});
+
+    // Only the collateral token is accepted
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(AP),
+            other_token_addr.clone(),
+            &send(100),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Pool(PoolError::InvalidCollateralToken {
+            token: other_token_addr.to_string()
+        })
+    );
+
+    app.execute_contract(Addr::unchecked(AP), token_addr.clone(), &send(100), &[])
+        .unwrap();
+    assert_eq!(
+        query_token_balance(&app, &token_addr, coll_surplus_pool_addr.as_str()),
+        Uint128::new(100u128)
+    );
+
+    // The surplus is only claimable once the trove manager accounts it
+    assert_eq!(
+        query_collateral(&app, &coll_surplus_pool_addr, ALICE),
+        Uint128::zero()
+    );
+    let total: Uint128 = app
+        .wrap()
+        .query_wasm_smart(&coll_surplus_pool_addr, &QueryMsg::GetTotalCollateral {})
+        .unwrap();
+    assert_eq!(total, Uint128::zero());
+
+    // A pool of native collateral takes no cw20 tokens
+    let native_pool_addr =
+        instantiate_coll_surplus_pool(&mut app, native_asset_info(UJUNO_DENOM.to_string()));
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(AP),
+            token_addr.clone(),
+            &ultra_token::msg::ExecuteMsg::Send {
+                contract: native_pool_addr.to_string(),
+                amount: Uint128::new(100u128),
+                msg: to_binary(&Cw20HookMsg::ReceiveCollateral {}).unwrap(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(
+        err,
+        ContractError::Pool(PoolError::InvalidCollateralToken {
+            token: token_addr.to_string()
+        })
+    );
+}
+
+#[test]
+fn test_collateral() {
+    // A cw20 token can be held by the pool as well
+    let collateral = AssetInfo::Cw20Token {
+        contract_addr: Addr::unchecked("collateral-token"),
+    };
+    let mut app = App::default();
+    let coll_surplus_pool_addr = instantiate_coll_surplus_pool(&mut app, collateral.clone());
+    let response: AssetInfo = app
+        .wrap()
+        .query_wasm_smart(coll_surplus_pool_addr, &QueryMsg::GetCollateralInfo {})
+        .unwrap();
+    assert_eq!(response, collateral);
+
+    let mut app = App::default();
+    let coll_surplus_pool_addr =
+        instantiate_coll_surplus_pool(&mut app, native_asset_info(UJUNO_DENOM.to_string()));
+    let collateral: AssetInfo = app
+        .wrap()
+        .query_wasm_smart(coll_surplus_pool_addr, &QueryMsg::GetCollateralInfo {})
+        .unwrap();
+    assert_eq!(collateral, native_asset_info(UJUNO_DENOM.to_string()));
+}
+
+#[test]
+fn test_migrate_from_legacy_contract_name() {
+    let mut deps = mock_dependencies();
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SOME, &[]),
+        InstantiateMsg {
+            name: String::from("Coll Surplus Pool"),
+            owner: OWNER.to_string(),
+            collateral: native_asset_info(UJUNO_DENOM.to_string()),
+        },
+    )
+    .unwrap();
+
+    // Pools instantiated before the name fix stored the active pool name
+    set_contract_version(deps.as_mut().storage, "crates.io:active-pool", "0.1.0").unwrap();
+    migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap();
+
+    let version = get_contract_version(deps.as_ref().storage).unwrap();
+    assert_eq!(version.contract, "crates.io:coll-surplus-pool");
+    assert_eq!(version.version, env!("CARGO_PKG_VERSION"));
+
+    // Any other contract is still rejected
+    set_contract_version(deps.as_mut().storage, "crates.io:default-pool", "0.1.0").unwrap();
+    let err = migrate(deps.as_mut(), mock_env(), MigrateMsg {}).unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::Migration(MigrationError::CannotMigrate {
+            previous_contract: "crates.io:default-pool".to_string()
+        })
+    );
+}
@@ -0,0 +1,242 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage, Uint128,
+};
+
+use cw2::set_contract_version;
+
+use crate::error::ContractError;
+use crate::state::{AddressesSet, ADDRESSES_SET, ISSUANCE_CURVE, REWARD_TOKEN, TOTAL_ISSUED};
+use ultra_base::address_registry::ProtocolContract;
+use ultra_base::asset::token_asset;
+use ultra_base::community_issuance::{
+    ExecuteMsg, InstantiateMsg, IssuanceCurve, MigrateMsg, ParamsResponse, QueryMsg,
+};
+use ultra_base::migration::{migrate_contract, Migration};
+use ultra_base::querier::query_protocol_address;
+use ultra_pool::access::{self, Role};
+use ultra_pool::ownership;
+use ultra_pool::registry;
+use ultra_pool::state::{SudoParams, SUDO_PARAMS};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:community-issuance";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// State migrations, ordered by the version introducing them
+const MIGRATIONS: &[Migration] = &[];
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // store sudo params
+    let sudo_params = SudoParams {
+        name: msg.name,
+        owner: deps.api.addr_validate(&msg.owner)?,
+    };
+
+    SUDO_PARAMS.save(deps.storage, &sudo_params)?;
+    REWARD_TOKEN.save(deps.storage, &deps.api.addr_validate(&msg.reward_token)?)?;
+    ISSUANCE_CURVE.save(
+        deps.storage,
+        &IssuanceCurve::new(msg.issuance_cap, msg.issuance_half_life, env.block.time)?,
+    )?;
+    TOTAL_ISSUED.save(deps.storage, &Uint128::zero())?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    Ok(migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+    )?)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::IssueRewards {} => execute_issue_rewards(deps, env, info),
+
+        ExecuteMsg::SetAddressRegistry { address_registry } => {
+            execute_set_address_registry(deps, env, info, address_registry)
+        }
+        ExecuteMsg::SyncAddresses {} => execute_sync_addresses(deps, env, info),
+        ExecuteMsg::ProposeOwner { owner } => execute_propose_owner(deps, env, info, owner),
+        ExecuteMsg::AcceptOwner {} => execute_accept_owner(deps, env, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, env, info),
+        ExecuteMsg::LockAddresses {} => execute_lock_addresses(deps, env, info),
+    }
+}
+
+pub fn execute_issue_rewards(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let stability_pool = only_sp(deps.storage, &info)?;
+
+    let issuance = issuable_rewards(deps.storage, &env)?;
+    let total_issued = TOTAL_ISSUED.update(deps.storage, |total| -> StdResult<_> {
+        Ok(total.checked_add(issuance)?)
+    })?;
+
+    let mut res = Response::new()
+        .add_attribute("action", "issue_rewards")
+        .add_attribute("issuance", issuance)
+        .add_attribute("total_issued", total_issued);
+    if !issuance.is_zero() {
+        let reward_token = REWARD_TOKEN.load(deps.storage)?;
+        res = res.add_message(
+            token_asset(reward_token, issuance).into_msg(&deps.querier, stability_pool)?,
+        );
+    }
+    Ok(res)
+}
+
+/// Reward tokens issued since the previous issuance
+fn issuable_rewards(store: &dyn Storage, env: &Env) -> StdResult<Uint128> {
+    let curve = ISSUANCE_CURVE.load(store)?;
+    curve.issuance(env.block.time, TOTAL_ISSUED.load(store)?)
+}
+
+pub fn execute_set_address_registry(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address_registry: String,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+    ownership::ensure_addresses_unlocked(deps.storage)?;
+
+    let address_registry = deps.api.addr_validate(&address_registry)?;
+    Ok(registry::set_address_registry(
+        deps.storage,
+        address_registry,
+    )?)
+}
+
+/// Resolves the peers from the address registry and caches them in the addresses set
+pub fn execute_sync_addresses(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ownership::ensure_addresses_unlocked(deps.storage)?;
+
+    let address_registry = registry::load_address_registry(deps.storage)?;
+    let new_addresses_set = AddressesSet {
+        stability_pool_address: query_protocol_address(
+            &deps.querier,
+            &address_registry,
+            ProtocolContract::StabilityPool,
+        )?,
+    };
+
+    ADDRESSES_SET.save(deps.storage, &new_addresses_set)?;
+    let res = Response::new()
+        .add_attribute("action", "sync_addresses")
+        .add_attribute(
+            "stability_pool_address",
+            new_addresses_set.stability_pool_address,
+        );
+    Ok(res)
+}
+
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    owner: String,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    let owner = deps.api.addr_validate(&owner)?;
+    Ok(ownership::propose_owner(deps.storage, owner)?)
+}
+
+pub fn execute_accept_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    Ok(ownership::accept_owner(deps.storage, &info)?)
+}
+
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    Ok(ownership::renounce_ownership(deps.storage)?)
+}
+
+pub fn execute_lock_addresses(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    // the addresses must be configured before they are locked for good
+    if ADDRESSES_SET.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::AddressesNotSet {});
+    }
+    Ok(ownership::lock_addresses(deps.storage)?)
+}
+
+/// Checks to enfore only stability pool can call
+fn only_sp(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
+    let addresses_set = ADDRESSES_SET.load(store)?;
+    access::only_roles(
+        &addresses_set,
+        info,
+        &[Role::StabilityPool],
+        ContractError::CallerIsNotSP {},
+    )
+}
+/// Checks to enfore only owner can call
+fn only_owner(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
+    access::only_owner(store, info, ContractError::UnauthorizedOwner {})
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetParams {} => to_binary(&query_params(deps)?),
+        QueryMsg::GetOwnership {} => to_binary(&ownership::query_ownership(deps.storage)?),
+        QueryMsg::GetIssuanceCurve {} => to_binary(&ISSUANCE_CURVE.load(deps.storage)?),
+        QueryMsg::GetTotalIssued {} => to_binary(&TOTAL_ISSUED.load(deps.storage)?),
+        QueryMsg::GetIssuableRewards {} => to_binary(&issuable_rewards(deps.storage, &env)?),
+        QueryMsg::GetStabilityPoolAddress {} => {
+            to_binary(&ADDRESSES_SET.load(deps.storage)?.stability_pool_address)
+        }
+        QueryMsg::GetRewardTokenAddress {} => to_binary(&REWARD_TOKEN.load(deps.storage)?),
+    }
+}
+
+pub fn query_params(deps: Deps) -> StdResult<ParamsResponse> {
+    let info = SUDO_PARAMS.load(deps.storage)?;
+    let res = ParamsResponse {
+        name: info.name,
+        owner: info.owner,
+    };
+    Ok(res)
+}
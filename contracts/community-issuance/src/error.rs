@@ -0,0 +1,25 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+use ultra_base::migration::MigrationError;
+use ultra_pool::PoolError;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Pool(#[from] PoolError),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
+    #[error("UnauthorizedOwner")]
+    UnauthorizedOwner {},
+
+    #[error("CommunityIssuance: Caller is not the StabilityPool")]
+    CallerIsNotSP {},
+
+    #[error("CommunityIssuance: Addresses must be set before they are locked")]
+    AddressesNotSet {},
+}
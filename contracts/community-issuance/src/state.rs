@@ -0,0 +1,25 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ultra_base::community_issuance::IssuanceCurve;
+use ultra_pool::access::{Role, RoleAddresses};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AddressesSet {
+    pub stability_pool_address: Addr,
+}
+
+impl RoleAddresses for AddressesSet {
+    fn address_of(&self, role: Role) -> Option<&Addr> {
+        match role {
+            Role::StabilityPool => Some(&self.stability_pool_address),
+            _ => None,
+        }
+    }
+}
+
+pub const ADDRESSES_SET: Item<AddressesSet> = Item::new("addresses_set");
+pub const REWARD_TOKEN: Item<Addr> = Item::new("reward_token");
+pub const ISSUANCE_CURVE: Item<IssuanceCurve> = Item::new("issuance_curve");
+pub const TOTAL_ISSUED: Item<Uint128> = Item::new("total_issued");
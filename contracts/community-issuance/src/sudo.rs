@@ -0,0 +1,11 @@
+use crate::error::ContractError;
+use cosmwasm_std::{entry_point, DepsMut, Env, Response};
+use ultra_base::community_issuance::SudoMsg;
+use ultra_pool::sudo::sudo_update_params;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn sudo(deps: DepsMut, _env: Env, msg: SudoMsg) -> Result<Response, ContractError> {
+    match msg {
+        SudoMsg::UpdateParams { name, owner } => Ok(sudo_update_params(deps.storage, name, owner)?),
+    }
+}
@@ -0,0 +1,270 @@
+use crate::ContractError;
+
+use ultra_base::address_registry::{
+    ContractAddress, ExecuteMsg as AddressRegistryExecuteMsg,
+    InstantiateMsg as AddressRegistryInstantiateMsg, ProtocolContract,
+};
+use ultra_base::community_issuance::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+use cosmwasm_std::{Addr, Empty, Uint128};
+use cw20::{BalanceResponse, Cw20Coin, MinterResponse};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+const SOME: &str = "someone";
+const OWNER: &str = "owner";
+const SP: &str = "stability-pool";
+
+const ISSUANCE_CAP: u128 = 1_000_000;
+
+fn community_issuance_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_sudo(crate::sudo::sudo)
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+fn address_registry_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        address_registry::contract::execute,
+        address_registry::contract::instantiate,
+        address_registry::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn ultra_token_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        ultra_token::contract::execute,
+        ultra_token::contract::instantiate,
+        ultra_token::contract::query,
+    );
+    Box::new(contract)
+}
+
+/// Instantiates a community issuance halving every minute, funded with the whole issuance
+/// cap, and resolving the stability pool from an address registry. Returns the community
+/// issuance and reward token addresses
+fn instantiate_community_issuance(app: &mut App) -> (Addr, Addr) {
+    let reward_token_code_id = app.store_code(ultra_token_contract());
+    let reward_token_addr = app
+        .instantiate_contract(
+            reward_token_code_id,
+            Addr::unchecked(SOME),
+            &ultra_token::msg::InstantiateMsg {
+                name: String::from("Reward Token"),
+                symbol: String::from("REWARD"),
+                decimals: 6,
+                initial_balances: vec![Cw20Coin {
+                    address: OWNER.to_string(),
+                    amount: Uint128::new(ISSUANCE_CAP),
+                }],
+                mint: Some(MinterResponse {
+                    minter: OWNER.to_string(),
+                    cap: None,
+                }),
+                marketing: None,
+            },
+            &[],
+            "reward token",
+            None,
+        )
+        .unwrap();
+
+    let code_id = app.store_code(community_issuance_contract());
+    let community_issuance_addr = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(SOME),
+            &InstantiateMsg {
+                name: String::from("Community Issuance"),
+                owner: OWNER.to_string(),
+                reward_token: reward_token_addr.to_string(),
+                issuance_cap: Uint128::new(ISSUANCE_CAP),
+                issuance_half_life: 60,
+            },
+            &[],
+            "community issuance",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        reward_token_addr.clone(),
+        &ultra_token::msg::ExecuteMsg::Transfer {
+            recipient: community_issuance_addr.to_string(),
+            amount: Uint128::new(ISSUANCE_CAP),
+        },
+        &[],
+    )
+    .unwrap();
+
+    let address_registry_code_id = app.store_code(address_registry_contract());
+    let address_registry_addr = app
+        .instantiate_contract(
+            address_registry_code_id,
+            Addr::unchecked(SOME),
+            &AddressRegistryInstantiateMsg {
+                name: String::from("Address Registry"),
+                owner: OWNER.to_string(),
+            },
+            &[],
+            "address registry",
+            None,
+        )
+        .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &AddressRegistryExecuteMsg::SetAddresses {
+            addresses: vec![ContractAddress {
+                contract: ProtocolContract::StabilityPool,
+                address: SP.to_string(),
+            }],
+        },
+        &[],
+    )
+    .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        community_issuance_addr.clone(),
+        &ExecuteMsg::SetAddressRegistry {
+            address_registry: address_registry_addr.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        community_issuance_addr.clone(),
+        &ExecuteMsg::SyncAddresses {},
+        &[],
+    )
+    .unwrap();
+
+    (community_issuance_addr, reward_token_addr)
+}
+
+fn advance_seconds(app: &mut App, seconds: u64) {
+    app.update_block(|block| {
+        block.time = block.time.plus_seconds(seconds);
+        block.height += seconds / 5;
+    });
+}
+
+fn issue_rewards(app: &mut App, community_issuance_addr: &Addr) {
+    app.execute_contract(
+        Addr::unchecked(SP),
+        community_issuance_addr.clone(),
+        &ExecuteMsg::IssueRewards {},
+        &[],
+    )
+    .unwrap();
+}
+
+fn query_issuable_rewards(app: &App, community_issuance_addr: &Addr) -> Uint128 {
+    app.wrap()
+        .query_wasm_smart(
+            community_issuance_addr.clone(),
+            &QueryMsg::GetIssuableRewards {},
+        )
+        .unwrap()
+}
+
+fn query_reward_balance(app: &App, reward_token_addr: &Addr, address: &str) -> Uint128 {
+    let res: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            reward_token_addr.clone(),
+            &ultra_token::msg::QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    res.balance
+}
+
+#[test]
+fn test_issue_rewards() {
+    let mut app = App::default();
+    let (community_issuance_addr, reward_token_addr) = instantiate_community_issuance(&mut app);
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            community_issuance_addr.clone(),
+            &ExecuteMsg::IssueRewards {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNotSP {});
+
+    // Nothing is issued at deployment
+    issue_rewards(&mut app, &community_issuance_addr);
+    assert_eq!(
+        query_reward_balance(&app, &reward_token_addr, SP),
+        Uint128::zero()
+    );
+
+    // Half of the cap is issued after a half-life
+    advance_seconds(&mut app, 60);
+    assert_eq!(
+        query_issuable_rewards(&app, &community_issuance_addr),
+        Uint128::new(ISSUANCE_CAP / 2)
+    );
+    issue_rewards(&mut app, &community_issuance_addr);
+    assert_eq!(
+        query_reward_balance(&app, &reward_token_addr, SP),
+        Uint128::new(ISSUANCE_CAP / 2)
+    );
+    assert_eq!(
+        query_issuable_rewards(&app, &community_issuance_addr),
+        Uint128::zero()
+    );
+
+    // Then half of what is left after the next one
+    advance_seconds(&mut app, 60);
+    issue_rewards(&mut app, &community_issuance_addr);
+    assert_eq!(
+        query_reward_balance(&app, &reward_token_addr, SP),
+        Uint128::new(ISSUANCE_CAP / 4 * 3)
+    );
+    let total_issued: Uint128 = app
+        .wrap()
+        .query_wasm_smart(
+            community_issuance_addr.clone(),
+            &QueryMsg::GetTotalIssued {},
+        )
+        .unwrap();
+    assert_eq!(total_issued, Uint128::new(ISSUANCE_CAP / 4 * 3));
+    assert_eq!(
+        query_reward_balance(&app, &reward_token_addr, community_issuance_addr.as_str()),
+        Uint128::new(ISSUANCE_CAP / 4)
+    );
+}
+
+#[test]
+fn test_issuance_never_exceeds_cap() {
+    let mut app = App::default();
+    let (community_issuance_addr, reward_token_addr) = instantiate_community_issuance(&mut app);
+
+    advance_seconds(&mut app, 60 * 60 * 24 * 365);
+    issue_rewards(&mut app, &community_issuance_addr);
+    assert_eq!(
+        query_reward_balance(&app, &reward_token_addr, SP),
+        Uint128::new(ISSUANCE_CAP)
+    );
+
+    advance_seconds(&mut app, 60);
+    assert_eq!(
+        query_issuable_rewards(&app, &community_issuance_addr),
+        Uint128::zero()
+    );
+    issue_rewards(&mut app, &community_issuance_addr);
+}
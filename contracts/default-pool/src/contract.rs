@@ -1,26 +1,37 @@
-use std::vec;
-
 #[cfg(not(feature = "library"))]
 use cosmwasm_std::entry_point;
 use cosmwasm_std::{
-    coin, to_binary, Addr, BankMsg, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdError,
-    StdResult, Storage, Uint128,
+    from_binary, to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult,
+    Storage, Uint128,
 };
 
 use cw2::set_contract_version;
+use cw20::Cw20ReceiveMsg;
 
 use crate::error::ContractError;
-use crate::state::{
-    AddressesSet, AssetsInPool, SudoParams, ADDRESSES_SET, ASSETS_IN_POOL, SUDO_PARAMS,
+use crate::state::{AddressesSet, ADDRESSES_SET, ASSETS_IN_POOL};
+use ultra_base::active_pool::{
+    Cw20HookMsg as ActivePoolCw20HookMsg, ExecuteMsg as ActivePoolExecuteMsg,
+};
+use ultra_base::address_registry::ProtocolContract;
+use ultra_base::asset::Asset;
+use ultra_base::default_pool::{
+    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, ParamsResponse, QueryMsg,
 };
-use ultra_base::default_pool::{ExecuteMsg, InstantiateMsg, ParamsResponse, QueryMsg};
+use ultra_base::migration::{migrate_contract, Migration};
+use ultra_base::querier::{query_collateral, query_protocol_address};
+use ultra_pool::access::{self, Role};
+use ultra_pool::events::PoolEvent;
+use ultra_pool::funds::{must_pay_collateral, must_receive_collateral, validate_collateral};
+use ultra_pool::ledger::AssetsInPool;
+use ultra_pool::ownership;
+use ultra_pool::registry;
+use ultra_pool::state::{load_collateral, SudoParams, COLLATERAL, SUDO_PARAMS};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:default-pool";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
-pub const NATIVE_JUNO_DENOM: &str = "ujuno";
-
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn instantiate(
     deps: DepsMut,
@@ -37,17 +48,31 @@ pub fn instantiate(
     };
 
     // initial assets in pool
-    let assets_in_pool = AssetsInPool {
-        juno: Uint128::zero(),
-        ultra_debt: Uint128::zero(),
-    };
+    let assets_in_pool = AssetsInPool::default();
+
+    // native or cw20 collateral held by the pool
+    let collateral = validate_collateral(deps.api, msg.collateral)?;
 
     SUDO_PARAMS.save(deps.storage, &data)?;
+    COLLATERAL.save(deps.storage, &collateral)?;
     ASSETS_IN_POOL.save(deps.storage, &assets_in_pool)?;
 
     Ok(Response::default())
 }
 
+// State migrations, ordered by the version introducing them
+const MIGRATIONS: &[Migration] = &[];
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    Ok(migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+    )?)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
@@ -62,13 +87,19 @@ pub fn execute(
         ExecuteMsg::DecreaseULTRADebt { amount } => {
             execute_decrease_ultra_debt(deps, env, info, amount)
         }
-        ExecuteMsg::SendJUNOToActivePool { amount } => {
-            execute_send_juno_to_active_pool(deps, env, info, amount)
+        ExecuteMsg::SendCollateralToActivePool { amount } => {
+            execute_send_collateral_to_active_pool(deps, env, info, amount)
         }
-        ExecuteMsg::SetAddresses {
-            trove_manager_address,
-            active_pool_address,
-        } => execute_set_addresses(deps, env, info, trove_manager_address, active_pool_address),
+        ExecuteMsg::ReceiveCollateral {} => execute_receive_collateral(deps, env, info),
+        ExecuteMsg::Receive(cw20_msg) => execute_receive(deps, env, info, cw20_msg),
+        ExecuteMsg::SetAddressRegistry { address_registry } => {
+            execute_set_address_registry(deps, env, info, address_registry)
+        }
+        ExecuteMsg::SyncAddresses {} => execute_sync_addresses(deps, env, info),
+        ExecuteMsg::ProposeOwner { owner } => execute_propose_owner(deps, env, info, owner),
+        ExecuteMsg::AcceptOwner {} => execute_accept_owner(deps, env, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, env, info),
+        ExecuteMsg::LockAddresses {} => execute_lock_addresses(deps, env, info),
     }
 }
 
@@ -81,12 +112,9 @@ pub fn execute_increase_ultra_debt(
     only_tm(deps.storage, &info)?;
 
     let mut assets_in_pool = ASSETS_IN_POOL.load(deps.storage)?;
-    assets_in_pool.ultra_debt += amount;
+    assets_in_pool.increase_debt(amount)?;
     ASSETS_IN_POOL.save(deps.storage, &assets_in_pool)?;
-    let res = Response::new()
-        .add_attribute("action", "increase_ultra_debt")
-        .add_attribute("amount", amount);
-    Ok(res)
+    Ok(PoolEvent::IncreaseULTRADebt { amount }.into_response())
 }
 
 pub fn execute_decrease_ultra_debt(
@@ -98,18 +126,12 @@ pub fn execute_decrease_ultra_debt(
     only_tm(deps.storage, &info)?;
 
     let mut assets_in_pool = ASSETS_IN_POOL.load(deps.storage)?;
-    assets_in_pool.ultra_debt = assets_in_pool
-        .ultra_debt
-        .checked_sub(amount)
-        .map_err(StdError::overflow)?;
+    assets_in_pool.decrease_debt(amount)?;
     ASSETS_IN_POOL.save(deps.storage, &assets_in_pool)?;
-    let res = Response::new()
-        .add_attribute("action", "decrease_ultra_debt")
-        .add_attribute("amount", amount);
-    Ok(res)
+    Ok(PoolEvent::DecreaseULTRADebt { amount }.into_response())
 }
 
-pub fn execute_send_juno_to_active_pool(
+pub fn execute_send_collateral_to_active_pool(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
@@ -118,87 +140,216 @@ pub fn execute_send_juno_to_active_pool(
     only_tm(deps.storage, &info)?;
 
     let mut assets_in_pool = ASSETS_IN_POOL.load(deps.storage)?;
-    assets_in_pool.juno = assets_in_pool
-        .juno
-        .checked_sub(amount)
-        .map_err(StdError::overflow)?;
+    assets_in_pool.decrease_coll(amount)?;
     ASSETS_IN_POOL.save(deps.storage, &assets_in_pool)?;
 
+    // Transfer the collateral and let the active pool record it in a single message
+    let collateral = load_collateral(deps.storage)?;
     let addresses_set = ADDRESSES_SET.load(deps.storage)?;
     let active_pool_address = addresses_set.active_pool_address;
-    let send_msg = BankMsg::Send {
-        to_address: active_pool_address.to_string(),
-        amount: vec![coin(amount.u128(), NATIVE_JUNO_DENOM.to_string())],
+    let receive_msg = if collateral.is_native_token() {
+        to_binary(&ActivePoolExecuteMsg::ReceiveCollateral {})?
+    } else {
+        to_binary(&ActivePoolCw20HookMsg::ReceiveCollateral {})?
     };
-    let res = Response::new()
-        .add_message(send_msg)
-        .add_attribute("action", "send_juno")
-        .add_attribute("recipient", active_pool_address.to_string())
-        .add_attribute("amount", amount);
+    let send_msg = Asset {
+        info: collateral,
+        amount,
+    }
+    .into_execute_msg(&active_pool_address, receive_msg)?;
+    let res = PoolEvent::SendCollateral {
+        recipient: active_pool_address,
+        amount,
+    }
+    .into_response()
+    .add_message(send_msg);
     Ok(res)
 }
 
-pub fn execute_set_addresses(
+pub fn execute_receive_collateral(
     deps: DepsMut,
     _env: Env,
     info: MessageInfo,
-    trove_manager_address: String,
-    active_pool_address: String,
+) -> Result<Response, ContractError> {
+    only_ap(deps.storage, &info)?;
+
+    let collateral = load_collateral(deps.storage)?;
+    let amount = must_pay_collateral(&info, &collateral)?;
+
+    record_collateral(deps.storage, info.sender, amount)
+}
+
+/// Records the cw20 collateral sent through the cw20 hook, the sender of the tokens standing
+/// for the caller
+pub fn execute_receive(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let collateral = load_collateral(deps.storage)?;
+    let amount = must_receive_collateral(&info, &collateral, cw20_msg.amount)?;
+
+    let sender_info = MessageInfo {
+        sender: deps.api.addr_validate(&cw20_msg.sender)?,
+        funds: vec![],
+    };
+    match from_binary(&cw20_msg.msg)? {
+        Cw20HookMsg::ReceiveCollateral {} => {
+            only_ap(deps.storage, &sender_info)?;
+            record_collateral(deps.storage, sender_info.sender, amount)
+        }
+    }
+}
+
+fn record_collateral(
+    store: &mut dyn Storage,
+    sender: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let mut assets_in_pool = ASSETS_IN_POOL.load(store)?;
+    assets_in_pool.increase_coll(amount)?;
+    ASSETS_IN_POOL.save(store, &assets_in_pool)?;
+
+    Ok(PoolEvent::ReceiveCollateral { sender, amount }.into_response())
+}
+
+pub fn execute_set_address_registry(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address_registry: String,
 ) -> Result<Response, ContractError> {
     only_owner(deps.storage, &info)?;
+    ownership::ensure_addresses_unlocked(deps.storage)?;
 
+    let address_registry = deps.api.addr_validate(&address_registry)?;
+    Ok(registry::set_address_registry(
+        deps.storage,
+        address_registry,
+    )?)
+}
+
+/// Resolves the peers from the address registry and caches them in the addresses set,
+/// the active pool being the one registered along with this pool for its collateral
+pub fn execute_sync_addresses(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ownership::ensure_addresses_unlocked(deps.storage)?;
+
+    let address_registry = registry::load_address_registry(deps.storage)?;
+    let collateral = query_collateral(
+        &deps.querier,
+        &address_registry,
+        &load_collateral(deps.storage)?,
+    )?;
     let new_addresses_set = AddressesSet {
-        trove_manager_address: deps.api.addr_validate(&trove_manager_address)?,
-        active_pool_address: deps.api.addr_validate(&active_pool_address)?,
+        trove_manager_address: query_protocol_address(
+            &deps.querier,
+            &address_registry,
+            ProtocolContract::TroveManager,
+        )?,
+        active_pool_address: collateral.contracts.active_pool,
     };
 
     ADDRESSES_SET.save(deps.storage, &new_addresses_set)?;
     let res = Response::new()
-        .add_attribute("action", "set_addresses")
-        .add_attribute("trove_manager_address", trove_manager_address)
-        .add_attribute("active_pool_address", active_pool_address);
+        .add_attribute("action", "sync_addresses")
+        .add_attribute(
+            "trove_manager_address",
+            new_addresses_set.trove_manager_address,
+        )
+        .add_attribute("active_pool_address", new_addresses_set.active_pool_address);
     Ok(res)
 }
 
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    owner: String,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    let owner = deps.api.addr_validate(&owner)?;
+    Ok(ownership::propose_owner(deps.storage, owner)?)
+}
+
+pub fn execute_accept_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    Ok(ownership::accept_owner(deps.storage, &info)?)
+}
+
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    Ok(ownership::renounce_ownership(deps.storage)?)
+}
+
+pub fn execute_lock_addresses(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    // the addresses must be configured before they are locked for good
+    if ADDRESSES_SET.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::AddressesNotSet {});
+    }
+    Ok(ownership::lock_addresses(deps.storage)?)
+}
+
 /// Checks to enfore only active pool can call
 fn only_ap(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
     let addresses_set = ADDRESSES_SET.load(store)?;
-    if addresses_set.active_pool_address != info.sender.as_ref() {
-        return Err(ContractError::CallerIsNotAP {});
-    }
-    Ok(info.sender.clone())
+    access::only_roles(
+        &addresses_set,
+        info,
+        &[Role::ActivePool],
+        ContractError::CallerIsNotAP {},
+    )
 }
 /// Checks to enfore only trove manager can call
 fn only_tm(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
     let addresses_set = ADDRESSES_SET.load(store)?;
-    if addresses_set.trove_manager_address != info.sender.as_ref() {
-        return Err(ContractError::CallerIsNotTM {});
-    }
-    Ok(info.sender.clone())
+    access::only_roles(
+        &addresses_set,
+        info,
+        &[Role::TroveManager],
+        ContractError::CallerIsNotTM {},
+    )
 }
 /// Checks to enfore only owner can call
 fn only_owner(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
-    let params = SUDO_PARAMS.load(store)?;
-    if params.owner != info.sender.as_ref() {
-        return Err(ContractError::UnauthorizedOwner {});
-    }
-    Ok(info.sender.clone())
+    access::only_owner(store, info, ContractError::UnauthorizedOwner {})
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
     match msg {
         QueryMsg::GetParams {} => to_binary(&query_params(deps)?),
-        QueryMsg::GetJUNO {} => to_binary(&query_juno_state(deps)?),
+        QueryMsg::GetOwnership {} => to_binary(&ownership::query_ownership(deps.storage)?),
+        QueryMsg::GetCollateral {} => to_binary(&query_coll_state(deps)?),
+        QueryMsg::GetCollateralInfo {} => to_binary(&load_collateral(deps.storage)?),
         QueryMsg::GetULTRADebt {} => to_binary(&query_ultra_debt_state(deps)?),
         QueryMsg::GetActivePoolAddress {} => to_binary(&query_active_pool_address(deps)?),
         QueryMsg::GetTroveManagerAddress {} => to_binary(&query_trove_manager_address(deps)?),
     }
 }
 
-pub fn query_juno_state(deps: Deps) -> StdResult<Uint128> {
+pub fn query_coll_state(deps: Deps) -> StdResult<Uint128> {
     let info = ASSETS_IN_POOL.load(deps.storage)?;
-    let res = info.juno;
+    let res = info.coll;
     Ok(res)
 }
 
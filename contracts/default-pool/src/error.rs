@@ -1,11 +1,19 @@
 use cosmwasm_std::StdError;
 use thiserror::Error;
+use ultra_base::migration::MigrationError;
+use ultra_pool::PoolError;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Pool(#[from] PoolError),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
     #[error("UnauthorizedOwner")]
     UnauthorizedOwner {},
 
@@ -14,4 +22,7 @@ pub enum ContractError {
 
     #[error("DefaultPool: Caller is not the TroveManager")]
     CallerIsNotTM {},
+
+    #[error("DefaultPool: Addresses must be set before they are locked")]
+    AddressesNotSet {},
 }
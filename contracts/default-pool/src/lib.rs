@@ -2,5 +2,7 @@ pub mod contract;
 mod error;
 pub mod state;
 pub mod sudo;
+#[cfg(test)]
+mod tests;
 
 pub use crate::error::ContractError;
@@ -1,7 +1,9 @@
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::Addr;
 use cw_storage_plus::Item;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
+use ultra_pool::access::{Role, RoleAddresses};
+use ultra_pool::ledger::AssetsInPool;
 
 #[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
 pub struct AddressesSet {
@@ -9,18 +11,15 @@ pub struct AddressesSet {
     pub active_pool_address: Addr,
 }
 
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct AssetsInPool {
-    pub juno: Uint128,
-    pub ultra_debt: Uint128,
-}
-
-#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
-pub struct SudoParams {
-    pub name: String,
-    pub owner: Addr,
+impl RoleAddresses for AddressesSet {
+    fn address_of(&self, role: Role) -> Option<&Addr> {
+        match role {
+            Role::TroveManager => Some(&self.trove_manager_address),
+            Role::ActivePool => Some(&self.active_pool_address),
+            _ => None,
+        }
+    }
 }
 
-pub const SUDO_PARAMS: Item<SudoParams> = Item::new("sudo-params");
 pub const ADDRESSES_SET: Item<AddressesSet> = Item::new("addresses_set");
 pub const ASSETS_IN_POOL: Item<AssetsInPool> = Item::new("assets_in_pool");
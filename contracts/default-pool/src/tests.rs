@@ -0,0 +1,449 @@
+use crate::ContractError;
+
+use ultra_base::active_pool::{
+    Cw20HookMsg as ActivePoolCw20HookMsg, ExecuteMsg as ActivePoolExecuteMsg,
+    InstantiateMsg as ActivePoolInstantiateMsg,
+};
+use ultra_base::address_registry::{
+    ContractAddress, ExecuteMsg as AddressRegistryExecuteMsg,
+    InstantiateMsg as AddressRegistryInstantiateMsg, ProtocolContract,
+};
+use ultra_base::asset::{native_asset_info, AssetInfo, UJUNO_DENOM};
+use ultra_base::collateral::{Collateral, CollateralContracts, CollateralParams};
+use ultra_base::default_pool::{Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg};
+
+use cosmwasm_std::{coins, to_binary, Addr, Decimal256, Empty, Uint128};
+use cw20::{BalanceResponse, Cw20Coin, Cw20QueryMsg, MinterResponse};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+const SOME: &str = "someone";
+const OWNER: &str = "owner";
+const BO: &str = "borrower-operations";
+const TM: &str = "trove-manager";
+const SP: &str = "stability-pool";
+const COLL_SURPLUS_POOL: &str = "coll-surplus-pool";
+const SORTED_TROVES: &str = "sorted-troves";
+const PRICE_FEED: &str = "price-feed";
+
+fn default_pool_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_sudo(crate::sudo::sudo);
+    Box::new(contract)
+}
+
+fn address_registry_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        address_registry::contract::execute,
+        address_registry::contract::instantiate,
+        address_registry::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn active_pool_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        active_pool::contract::execute,
+        active_pool::contract::instantiate,
+        active_pool::contract::query,
+    )
+    .with_sudo(active_pool::sudo::sudo);
+    Box::new(contract)
+}
+
+fn ultra_token_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        ultra_token::contract::execute,
+        ultra_token::contract::instantiate,
+        ultra_token::contract::query,
+    );
+    Box::new(contract)
+}
+
+/// Instantiates a cw20 collateral token held by borrower operations
+fn instantiate_collateral_token(app: &mut App) -> Addr {
+    let code_id = app.store_code(ultra_token_contract());
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(SOME),
+        &ultra_token::msg::InstantiateMsg {
+            name: String::from("Collateral"),
+            symbol: String::from("COLL"),
+            decimals: 6,
+            initial_balances: vec![Cw20Coin {
+                address: BO.to_string(),
+                amount: Uint128::new(1_000),
+            }],
+            mint: Some(MinterResponse {
+                minter: OWNER.to_string(),
+                cap: None,
+            }),
+            marketing: None,
+        },
+        &[],
+        "collateral token",
+        None,
+    )
+    .unwrap()
+}
+
+/// Instantiates an active pool and a default pool of `collateral` wired to each other through an
+/// address registry
+fn instantiate_pools(app: &mut App, collateral: AssetInfo) -> (Addr, Addr) {
+    let active_pool_code_id = app.store_code(active_pool_contract());
+    let active_pool_addr = app
+        .instantiate_contract(
+            active_pool_code_id,
+            Addr::unchecked(SOME),
+            &ActivePoolInstantiateMsg {
+                name: String::from("Active Pool"),
+                owner: OWNER.to_string(),
+                collateral: collateral.clone(),
+            },
+            &[],
+            "active pool",
+            None,
+        )
+        .unwrap();
+
+    let default_pool_code_id = app.store_code(default_pool_contract());
+    let default_pool_addr = app
+        .instantiate_contract(
+            default_pool_code_id,
+            Addr::unchecked(SOME),
+            &InstantiateMsg {
+                name: String::from("Default Pool"),
+                owner: OWNER.to_string(),
+                collateral: collateral.clone(),
+            },
+            &[],
+            "default pool",
+            None,
+        )
+        .unwrap();
+
+    let address_registry_code_id = app.store_code(address_registry_contract());
+    let address_registry_addr = app
+        .instantiate_contract(
+            address_registry_code_id,
+            Addr::unchecked(SOME),
+            &AddressRegistryInstantiateMsg {
+                name: String::from("Address Registry"),
+                owner: OWNER.to_string(),
+            },
+            &[],
+            "address registry",
+            None,
+        )
+        .unwrap();
+
+    let addresses = [
+        (ProtocolContract::BorrowerOperations, BO),
+        (ProtocolContract::TroveManager, TM),
+        (ProtocolContract::StabilityPool, SP),
+        (ProtocolContract::ActivePool, active_pool_addr.as_str()),
+        (ProtocolContract::DefaultPool, default_pool_addr.as_str()),
+    ];
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &AddressRegistryExecuteMsg::SetAddresses {
+            addresses: addresses
+                .into_iter()
+                .map(|(contract, address)| ContractAddress {
+                    contract,
+                    address: address.to_string(),
+                })
+                .collect(),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &AddressRegistryExecuteMsg::RegisterCollateral {
+            collateral: Collateral {
+                asset: collateral,
+                contracts: CollateralContracts {
+                    active_pool: active_pool_addr.clone(),
+                    default_pool: default_pool_addr.clone(),
+                    coll_surplus_pool: Addr::unchecked(COLL_SURPLUS_POOL),
+                    sorted_troves: Addr::unchecked(SORTED_TROVES),
+                    price_feed: Addr::unchecked(PRICE_FEED),
+                },
+                params: CollateralParams {
+                    mcr: Decimal256::percent(110),
+                    debt_ceiling: Uint128::new(1_000_000),
+                },
+            },
+        },
+        &[],
+    )
+    .unwrap();
+
+    // Both pools resolve each other from the registry
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &ActivePoolExecuteMsg::SetAddressRegistry {
+            address_registry: address_registry_addr.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        active_pool_addr.clone(),
+        &ActivePoolExecuteMsg::SyncAddresses {},
+        &[],
+    )
+    .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        default_pool_addr.clone(),
+        &ExecuteMsg::SetAddressRegistry {
+            address_registry: address_registry_addr.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        default_pool_addr.clone(),
+        &ExecuteMsg::SyncAddresses {},
+        &[],
+    )
+    .unwrap();
+
+    (active_pool_addr, default_pool_addr)
+}
+
+/// Returns the JUNO recorded by a pool along with its actual bank balance
+fn query_juno(app: &App, pool_addr: &Addr) -> (Uint128, Uint128) {
+    let recorded: Uint128 = app
+        .wrap()
+        .query_wasm_smart(pool_addr.clone(), &QueryMsg::GetCollateral {})
+        .unwrap();
+    let balance = app
+        .wrap()
+        .query_balance(pool_addr.clone(), UJUNO_DENOM)
+        .unwrap();
+    (recorded, balance.amount)
+}
+
+/// Returns the cw20 collateral recorded by a pool along with its actual token balance
+fn query_cw20_collateral(app: &App, token_addr: &Addr, pool_addr: &Addr) -> (Uint128, Uint128) {
+    let recorded: Uint128 = app
+        .wrap()
+        .query_wasm_smart(pool_addr.clone(), &QueryMsg::GetCollateral {})
+        .unwrap();
+    let balance: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            token_addr.clone(),
+            &Cw20QueryMsg::Balance {
+                address: pool_addr.to_string(),
+            },
+        )
+        .unwrap();
+    (recorded, balance.balance)
+}
+
+#[test]
+fn test_receive_juno_only_from_active_pool() {
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(SOME), coins(1000, UJUNO_DENOM))
+            .unwrap();
+    });
+    let (_, default_pool_addr) =
+        instantiate_pools(&mut app, native_asset_info(UJUNO_DENOM.to_string()));
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            default_pool_addr.clone(),
+            &ExecuteMsg::ReceiveCollateral {},
+            &coins(100, UJUNO_DENOM),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNotAP {});
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            default_pool_addr,
+            &ExecuteMsg::SendCollateralToActivePool {
+                amount: Uint128::new(100u128),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNotTM {});
+}
+
+#[test]
+fn test_juno_round_trip_between_pools() {
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(BO), coins(1000, UJUNO_DENOM))
+            .unwrap();
+    });
+    let (active_pool_addr, default_pool_addr) =
+        instantiate_pools(&mut app, native_asset_info(UJUNO_DENOM.to_string()));
+
+    // Borrower operations deposits collateral into the active pool
+    app.execute_contract(
+        Addr::unchecked(BO),
+        active_pool_addr.clone(),
+        &ActivePoolExecuteMsg::ReceiveCollateral {},
+        &coins(100, UJUNO_DENOM),
+    )
+    .unwrap();
+
+    // A redistribution moves part of it to the default pool
+    app.execute_contract(
+        Addr::unchecked(TM),
+        active_pool_addr.clone(),
+        &ActivePoolExecuteMsg::SendCollateral {
+            recipient: default_pool_addr.clone(),
+            amount: Uint128::new(40u128),
+        },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(
+        query_juno(&app, &active_pool_addr),
+        (Uint128::new(60u128), Uint128::new(60u128))
+    );
+    assert_eq!(
+        query_juno(&app, &default_pool_addr),
+        (Uint128::new(40u128), Uint128::new(40u128))
+    );
+
+    // Pending rewards are applied and moved back to the active pool
+    app.execute_contract(
+        Addr::unchecked(TM),
+        default_pool_addr.clone(),
+        &ExecuteMsg::SendCollateralToActivePool {
+            amount: Uint128::new(25u128),
+        },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(
+        query_juno(&app, &active_pool_addr),
+        (Uint128::new(85u128), Uint128::new(85u128))
+    );
+    assert_eq!(
+        query_juno(&app, &default_pool_addr),
+        (Uint128::new(15u128), Uint128::new(15u128))
+    );
+
+    // The default pool cannot send more than it holds
+    app.execute_contract(
+        Addr::unchecked(TM),
+        default_pool_addr,
+        &ExecuteMsg::SendCollateralToActivePool {
+            amount: Uint128::new(16u128),
+        },
+        &[],
+    )
+    .unwrap_err();
+}
+
+#[test]
+fn test_cw20_round_trip_between_pools() {
+    let mut app = App::default();
+    let token_addr = instantiate_collateral_token(&mut app);
+    let (active_pool_addr, default_pool_addr) = instantiate_pools(
+        &mut app,
+        AssetInfo::Cw20Token {
+            contract_addr: token_addr.clone(),
+        },
+    );
+
+    // Only the active pool can move collateral into the default pool
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(BO),
+            token_addr.clone(),
+            &ultra_token::msg::ExecuteMsg::Send {
+                contract: default_pool_addr.to_string(),
+                amount: Uint128::new(100u128),
+                msg: to_binary(&Cw20HookMsg::ReceiveCollateral {}).unwrap(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNotAP {});
+
+    // Borrower operations deposits collateral into the active pool
+    app.execute_contract(
+        Addr::unchecked(BO),
+        token_addr.clone(),
+        &ultra_token::msg::ExecuteMsg::Send {
+            contract: active_pool_addr.to_string(),
+            amount: Uint128::new(100u128),
+            msg: to_binary(&ActivePoolCw20HookMsg::ReceiveCollateral {}).unwrap(),
+        },
+        &[],
+    )
+    .unwrap();
+
+    // A redistribution moves part of it to the default pool through its cw20 hook
+    app.execute_contract(
+        Addr::unchecked(TM),
+        active_pool_addr.clone(),
+        &ActivePoolExecuteMsg::SendCollateral {
+            recipient: default_pool_addr.clone(),
+            amount: Uint128::new(40u128),
+        },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(
+        query_cw20_collateral(&app, &token_addr, &active_pool_addr),
+        (Uint128::new(60u128), Uint128::new(60u128))
+    );
+    assert_eq!(
+        query_cw20_collateral(&app, &token_addr, &default_pool_addr),
+        (Uint128::new(40u128), Uint128::new(40u128))
+    );
+
+    // Pending rewards are moved back to the active pool through its cw20 hook
+    app.execute_contract(
+        Addr::unchecked(TM),
+        default_pool_addr.clone(),
+        &ExecuteMsg::SendCollateralToActivePool {
+            amount: Uint128::new(25u128),
+        },
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(
+        query_cw20_collateral(&app, &token_addr, &active_pool_addr),
+        (Uint128::new(85u128), Uint128::new(85u128))
+    );
+    assert_eq!(
+        query_cw20_collateral(&app, &token_addr, &default_pool_addr),
+        (Uint128::new(15u128), Uint128::new(15u128))
+    );
+}
@@ -0,0 +1,409 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Reply, Response, StdError, StdResult,
+    SubMsg, WasmMsg,
+};
+use serde::Serialize;
+
+use cw2::set_contract_version;
+use cw20::MinterResponse;
+use cw_utils::parse_reply_instantiate_data;
+
+use crate::error::ContractError;
+use crate::state::{Config, ADDRESS_REGISTRY, CONFIG, DEPLOYED};
+use ultra_base::address_registry::{
+    ContractAddress, ExecuteMsg as AddressRegistryExecuteMsg,
+    InstantiateMsg as AddressRegistryInstantiateMsg, ProtocolContract,
+};
+use ultra_base::asset::{native_asset_info, UJUNO_DENOM};
+use ultra_base::collateral::{Collateral, CollateralContracts};
+use ultra_base::factory::{DeploymentResponse, InstantiateMsg, MigrateMsg, QueryMsg};
+use ultra_base::migration::{migrate_contract, Migration};
+use ultra_base::{
+    active_pool, borrower_operations, coll_surplus_pool, default_pool, gas_pool, hint_helpers,
+    oracle, sorted_troves, stability_pool, trove_manager,
+};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:factory";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// State migrations, ordered by the version introducing them
+const MIGRATIONS: &[Migration] = &[];
+
+const INSTANTIATE_ADDRESS_REGISTRY_REPLY_ID: u64 = 0;
+
+/// Protocol contracts in instantiation order, each one is instantiated from the reply of the
+/// previous one with its position plus one as reply id.
+/// The ultra token comes last as the borrower operations is its minter
+const STEPS: [ProtocolContract; 11] = [
+    ProtocolContract::ActivePool,
+    ProtocolContract::DefaultPool,
+    ProtocolContract::CollSurplusPool,
+    ProtocolContract::StabilityPool,
+    ProtocolContract::TroveManager,
+    ProtocolContract::BorrowerOperations,
+    ProtocolContract::SortedTroves,
+    ProtocolContract::HintHelpers,
+    ProtocolContract::PriceFeed,
+    ProtocolContract::GasPool,
+    ProtocolContract::UltraToken,
+];
+
+/// Protocol contracts resolving their peers from the address registry
+const WIRED: [ProtocolContract; 9] = [
+    ProtocolContract::ActivePool,
+    ProtocolContract::DefaultPool,
+    ProtocolContract::CollSurplusPool,
+    ProtocolContract::StabilityPool,
+    ProtocolContract::TroveManager,
+    ProtocolContract::BorrowerOperations,
+    ProtocolContract::SortedTroves,
+    ProtocolContract::HintHelpers,
+    ProtocolContract::GasPool,
+];
+
+/// Configuration messages accepted by every wired contract
+#[derive(Serialize)]
+#[serde(rename_all = "snake_case")]
+enum ProtocolExecuteMsg {
+    SetAddressRegistry { address_registry: String },
+    SyncAddresses {},
+    LockAddresses {},
+    ProposeOwner { owner: String },
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    msg.juno_collateral.validate()?;
+    let config = Config {
+        owner: deps.api.addr_validate(&msg.owner)?,
+        code_ids: msg.code_ids,
+        ultra_token: msg.ultra_token,
+        sorted_troves_size: msg.sorted_troves_size,
+        price_feed_pool_address: msg.price_feed_pool_address,
+        juno_collateral: msg.juno_collateral,
+        reward_pool: deps.api.addr_validate(&msg.reward_pool)?,
+        community_issuance: deps.api.addr_validate(&msg.community_issuance)?,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    // The factory owns every contract until the deployment is wired and locked
+    let instantiate_address_registry = WasmMsg::Instantiate {
+        admin: Some(config.owner.to_string()),
+        code_id: config.code_ids.address_registry,
+        msg: to_binary(&AddressRegistryInstantiateMsg {
+            name: String::from("Address Registry"),
+            owner: env.contract.address.to_string(),
+        })?,
+        funds: vec![],
+        label: String::from("address_registry"),
+    };
+
+    let res = Response::new()
+        .add_attribute("action", "instantiate")
+        .add_attribute("owner", config.owner)
+        .add_submessage(SubMsg::reply_on_success(
+            instantiate_address_registry,
+            INSTANTIATE_ADDRESS_REGISTRY_REPLY_ID,
+        ));
+    Ok(res)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    Ok(migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+    )?)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, env: Env, msg: Reply) -> Result<Response, ContractError> {
+    let id = msg.id;
+    let address = deps
+        .api
+        .addr_validate(&parse_reply_instantiate_data(msg)?.contract_address)?;
+
+    // Position in `STEPS` of the contract to instantiate next
+    let (key, next) = match id {
+        INSTANTIATE_ADDRESS_REGISTRY_REPLY_ID => {
+            ADDRESS_REGISTRY.save(deps.storage, &address)?;
+            ("address_registry", 0)
+        }
+        id if id as usize <= STEPS.len() => {
+            let key = STEPS[id as usize - 1].key();
+            DEPLOYED.save(deps.storage, key, &address)?;
+            (key, id as usize)
+        }
+        id => return Err(ContractError::UnknownReplyId { id }),
+    };
+
+    let res = Response::new()
+        .add_attribute("action", "deploy")
+        .add_attribute(key, address);
+    match STEPS.get(next) {
+        Some(contract) => {
+            let instantiate_msg = instantiate_contract(deps.as_ref(), &env, *contract)?;
+            Ok(res.add_submessage(SubMsg::reply_on_success(instantiate_msg, next as u64 + 1)))
+        }
+        None => Ok(res.add_messages(wire_deployment(deps.as_ref())?)),
+    }
+}
+
+/// Builds the message instantiating `contract` from its stored code
+fn instantiate_contract(deps: Deps, env: &Env, contract: ProtocolContract) -> StdResult<WasmMsg> {
+    let config = CONFIG.load(deps.storage)?;
+    let code_ids = config.code_ids;
+    let owner = env.contract.address.to_string();
+
+    let (code_id, msg) = match contract {
+        ProtocolContract::ActivePool => (
+            code_ids.active_pool,
+            to_binary(&active_pool::InstantiateMsg {
+                name: String::from("Active Pool"),
+                owner,
+                collateral: native_asset_info(UJUNO_DENOM.to_string()),
+            })?,
+        ),
+        ProtocolContract::DefaultPool => (
+            code_ids.default_pool,
+            to_binary(&default_pool::InstantiateMsg {
+                name: String::from("Default Pool"),
+                owner,
+                collateral: native_asset_info(UJUNO_DENOM.to_string()),
+            })?,
+        ),
+        ProtocolContract::CollSurplusPool => (
+            code_ids.coll_surplus_pool,
+            to_binary(&coll_surplus_pool::InstantiateMsg {
+                name: String::from("Coll Surplus Pool"),
+                owner,
+                collateral: native_asset_info(UJUNO_DENOM.to_string()),
+            })?,
+        ),
+        ProtocolContract::StabilityPool => (
+            code_ids.stability_pool,
+            to_binary(&stability_pool::InstantiateMsg {
+                name: String::from("Stability Pool"),
+                owner,
+            })?,
+        ),
+        ProtocolContract::TroveManager => (
+            code_ids.trove_manager,
+            to_binary(&trove_manager::InstantiateMsg {
+                name: String::from("Trove Manager"),
+                owner,
+            })?,
+        ),
+        ProtocolContract::BorrowerOperations => (
+            code_ids.borrower_operations,
+            to_binary(&borrower_operations::InstantiateMsg {
+                name: String::from("Borrower Operations"),
+                owner,
+            })?,
+        ),
+        ProtocolContract::SortedTroves => (
+            code_ids.sorted_troves,
+            to_binary(&sorted_troves::InstantiateMsg {
+                name: String::from("Sorted Troves"),
+                owner,
+            })?,
+        ),
+        ProtocolContract::HintHelpers => (
+            code_ids.hint_helpers,
+            to_binary(&hint_helpers::InstantiateMsg {
+                name: String::from("Hint Helpers"),
+                owner,
+            })?,
+        ),
+        ProtocolContract::PriceFeed => (
+            code_ids.price_feed,
+            to_binary(&oracle::InstantiateMsg {
+                pool_contract_address: config.price_feed_pool_address,
+            })?,
+        ),
+        ProtocolContract::GasPool => (
+            code_ids.gas_pool,
+            to_binary(&gas_pool::InstantiateMsg {
+                name: String::from("Gas Pool"),
+                owner,
+            })?,
+        ),
+        ProtocolContract::UltraToken => {
+            let borrower_operations =
+                DEPLOYED.load(deps.storage, ProtocolContract::BorrowerOperations.key())?;
+            (
+                code_ids.ultra_token,
+                to_binary(&ultra_token::msg::InstantiateMsg {
+                    name: config.ultra_token.name,
+                    symbol: config.ultra_token.symbol,
+                    decimals: config.ultra_token.decimals,
+                    initial_balances: vec![],
+                    mint: Some(MinterResponse {
+                        minter: borrower_operations.to_string(),
+                        cap: None,
+                    }),
+                    marketing: None,
+                })?,
+            )
+        }
+        ProtocolContract::RewardPool => {
+            return Err(StdError::generic_err(
+                "Factory: The reward pool is not deployed by the factory",
+            ))
+        }
+        ProtocolContract::CommunityIssuance => {
+            return Err(StdError::generic_err(
+                "Factory: The community issuance is not deployed by the factory",
+            ))
+        }
+    };
+
+    Ok(WasmMsg::Instantiate {
+        admin: Some(config.owner.to_string()),
+        code_id,
+        msg,
+        funds: vec![],
+        label: contract.key().to_string(),
+    })
+}
+
+/// Registers the deployed contracts, the contracts deployed beforehand and the JUNO collateral
+/// served by the deployed pools, lets each of them sync its peers, locks their addresses and
+/// proposes the configured owner as their new owner
+fn wire_deployment(deps: Deps) -> StdResult<Vec<WasmMsg>> {
+    let config = CONFIG.load(deps.storage)?;
+    let address_registry = ADDRESS_REGISTRY.load(deps.storage)?;
+    let owner = config.owner.to_string();
+
+    let mut addresses = STEPS
+        .iter()
+        .map(|contract| {
+            Ok(ContractAddress {
+                contract: *contract,
+                address: DEPLOYED.load(deps.storage, contract.key())?.to_string(),
+            })
+        })
+        .collect::<StdResult<Vec<_>>>()?;
+    // The registry can't be changed once locked, so the contracts the factory doesn't deploy
+    // are registered along the deployed ones
+    addresses.extend([
+        ContractAddress {
+            contract: ProtocolContract::RewardPool,
+            address: config.reward_pool.to_string(),
+        },
+        ContractAddress {
+            contract: ProtocolContract::CommunityIssuance,
+            address: config.community_issuance.to_string(),
+        },
+    ]);
+    let deployed = |contract: ProtocolContract| DEPLOYED.load(deps.storage, contract.key());
+    let sorted_troves = deployed(ProtocolContract::SortedTroves)?;
+    let juno = Collateral {
+        asset: native_asset_info(UJUNO_DENOM.to_string()),
+        contracts: CollateralContracts {
+            active_pool: deployed(ProtocolContract::ActivePool)?,
+            default_pool: deployed(ProtocolContract::DefaultPool)?,
+            coll_surplus_pool: deployed(ProtocolContract::CollSurplusPool)?,
+            sorted_troves: sorted_troves.clone(),
+            price_feed: deployed(ProtocolContract::PriceFeed)?,
+        },
+        params: config.juno_collateral,
+    };
+
+    let mut msgs = vec![
+        execute_msg(
+            &address_registry,
+            &AddressRegistryExecuteMsg::SetAddresses { addresses },
+        )?,
+        execute_msg(
+            &address_registry,
+            &AddressRegistryExecuteMsg::RegisterCollateral { collateral: juno },
+        )?,
+        execute_msg(
+            &sorted_troves,
+            &sorted_troves::ExecuteMsg::SetParams {
+                size: config.sorted_troves_size,
+            },
+        )?,
+    ];
+    for contract in WIRED {
+        let contract_addr = DEPLOYED.load(deps.storage, contract.key())?;
+        msgs.extend([
+            execute_msg(
+                &contract_addr,
+                &ProtocolExecuteMsg::SetAddressRegistry {
+                    address_registry: address_registry.to_string(),
+                },
+            )?,
+            execute_msg(&contract_addr, &ProtocolExecuteMsg::SyncAddresses {})?,
+            execute_msg(&contract_addr, &ProtocolExecuteMsg::LockAddresses {})?,
+            execute_msg(
+                &contract_addr,
+                &ProtocolExecuteMsg::ProposeOwner {
+                    owner: owner.clone(),
+                },
+            )?,
+        ]);
+    }
+    msgs.extend([
+        execute_msg(
+            &address_registry,
+            &AddressRegistryExecuteMsg::LockAddresses {},
+        )?,
+        execute_msg(
+            &address_registry,
+            &AddressRegistryExecuteMsg::ProposeOwner { owner },
+        )?,
+    ]);
+    Ok(msgs)
+}
+
+fn execute_msg<T: Serialize>(contract_addr: &Addr, msg: &T) -> StdResult<WasmMsg> {
+    Ok(WasmMsg::Execute {
+        contract_addr: contract_addr.to_string(),
+        msg: to_binary(msg)?,
+        funds: vec![],
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetDeployment {} => to_binary(&query_deployment(deps)?),
+    }
+}
+
+pub fn query_deployment(deps: Deps) -> StdResult<DeploymentResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let deployed = |contract: ProtocolContract| DEPLOYED.load(deps.storage, contract.key());
+    let res = DeploymentResponse {
+        owner: config.owner,
+        address_registry: ADDRESS_REGISTRY.load(deps.storage)?,
+        ultra_token: deployed(ProtocolContract::UltraToken)?,
+        active_pool: deployed(ProtocolContract::ActivePool)?,
+        default_pool: deployed(ProtocolContract::DefaultPool)?,
+        coll_surplus_pool: deployed(ProtocolContract::CollSurplusPool)?,
+        trove_manager: deployed(ProtocolContract::TroveManager)?,
+        borrower_operations: deployed(ProtocolContract::BorrowerOperations)?,
+        stability_pool: deployed(ProtocolContract::StabilityPool)?,
+        sorted_troves: deployed(ProtocolContract::SortedTroves)?,
+        hint_helpers: deployed(ProtocolContract::HintHelpers)?,
+        price_feed: deployed(ProtocolContract::PriceFeed)?,
+        reward_pool: config.reward_pool,
+        gas_pool: deployed(ProtocolContract::GasPool)?,
+        community_issuance: config.community_issuance,
+    };
+    Ok(res)
+}
@@ -0,0 +1,23 @@
+use cosmwasm_std::StdError;
+use cw_utils::ParseReplyError;
+use thiserror::Error;
+use ultra_base::collateral::CollateralError;
+use ultra_base::migration::MigrationError;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    ParseReply(#[from] ParseReplyError),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
+    #[error("{0}")]
+    Collateral(#[from] CollateralError),
+
+    #[error("Factory: Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
+}
@@ -0,0 +1,7 @@
+pub mod contract;
+mod error;
+pub mod state;
+#[cfg(test)]
+mod tests;
+
+pub use crate::error::ContractError;
@@ -0,0 +1,25 @@
+use cosmwasm_std::{Addr, Uint256};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ultra_base::collateral::CollateralParams;
+use ultra_base::factory::{CodeIds, UltraTokenParams};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub owner: Addr,
+    pub code_ids: CodeIds,
+    pub ultra_token: UltraTokenParams,
+    pub sorted_troves_size: Uint256,
+    pub price_feed_pool_address: String,
+    pub juno_collateral: CollateralParams,
+    pub reward_pool: Addr,
+    pub community_issuance: Addr,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+
+pub const ADDRESS_REGISTRY: Item<Addr> = Item::new("address-registry");
+
+/// Deployed contracts, keyed by `ProtocolContract::key`
+pub const DEPLOYED: Map<&str, Addr> = Map::new("deployed");
@@ -0,0 +1,314 @@
+use crate::ContractError;
+
+use ultra_base::active_pool::{ExecuteMsg as ActivePoolExecuteMsg, QueryMsg as ActivePoolQueryMsg};
+use ultra_base::address_registry::{
+    AddressResponse, AddressesResponse, ProtocolContract, QueryMsg as AddressRegistryQueryMsg,
+};
+use ultra_base::asset::{native_asset_info, UJUNO_DENOM};
+use ultra_base::collateral::{Collateral, CollateralContracts, CollateralParams};
+use ultra_base::factory::{
+    CodeIds, DeploymentResponse, InstantiateMsg, QueryMsg, UltraTokenParams,
+};
+use ultra_base::ownership::OwnershipResponse;
+
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Decimal256, Deps, DepsMut, Empty, Env, MessageInfo, Response,
+    StdResult, Uint128, Uint256,
+};
+use cw20::MinterResponse;
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+const SOME: &str = "someone";
+const OWNER: &str = "owner";
+const JUNOSWAP_POOL: &str = "junoswap-pool";
+const REWARD_POOL: &str = "reward-pool";
+const COMMUNITY_ISSUANCE: &str = "community-issuance";
+
+// The factory has no execute messages, `ContractWrapper` requires one anyway
+fn factory_execute(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> Result<Response, ContractError> {
+    Ok(Response::default())
+}
+
+fn factory_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        factory_execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_reply(crate::contract::reply)
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+fn address_registry_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        address_registry::contract::execute,
+        address_registry::contract::instantiate,
+        address_registry::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn active_pool_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        active_pool::contract::execute,
+        active_pool::contract::instantiate,
+        active_pool::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn default_pool_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        default_pool::contract::execute,
+        default_pool::contract::instantiate,
+        default_pool::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn coll_surplus_pool_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        coll_surplus_pool::contract::execute,
+        coll_surplus_pool::contract::instantiate,
+        coll_surplus_pool::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn ultra_token_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        ultra_token::contract::execute,
+        ultra_token::contract::instantiate,
+        ultra_token::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn gas_pool_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        gas_pool::contract::execute,
+        gas_pool::contract::instantiate,
+        gas_pool::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn stub_execute(_deps: DepsMut, _env: Env, _info: MessageInfo, _msg: Empty) -> StdResult<Response> {
+    Ok(Response::default())
+}
+
+fn stub_instantiate(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: Empty,
+) -> StdResult<Response> {
+    Ok(Response::default())
+}
+
+fn stub_query(_deps: Deps, _env: Env, _msg: Empty) -> StdResult<Binary> {
+    to_binary(&Empty {})
+}
+
+/// Stands in for the protocol contracts that are not implemented yet, accepting any message
+fn stub_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(stub_execute, stub_instantiate, stub_query);
+    Box::new(contract)
+}
+
+fn juno_collateral_params() -> CollateralParams {
+    CollateralParams {
+        mcr: Decimal256::percent(110),
+        debt_ceiling: Uint128::new(10_000_000_000_000),
+    }
+}
+
+fn instantiate_factory(app: &mut App) -> Addr {
+    let stub_code_id = app.store_code(stub_contract());
+    let code_ids = CodeIds {
+        address_registry: app.store_code(address_registry_contract()),
+        ultra_token: app.store_code(ultra_token_contract()),
+        active_pool: app.store_code(active_pool_contract()),
+        default_pool: app.store_code(default_pool_contract()),
+        coll_surplus_pool: app.store_code(coll_surplus_pool_contract()),
+        trove_manager: stub_code_id,
+        borrower_operations: stub_code_id,
+        stability_pool: stub_code_id,
+        sorted_troves: stub_code_id,
+        hint_helpers: stub_code_id,
+        price_feed: stub_code_id,
+        gas_pool: app.store_code(gas_pool_contract()),
+    };
+
+    let factory_code_id = app.store_code(factory_contract());
+    app.instantiate_contract(
+        factory_code_id,
+        Addr::unchecked(SOME),
+        &InstantiateMsg {
+            owner: OWNER.to_string(),
+            code_ids,
+            ultra_token: UltraTokenParams {
+                name: String::from("Ultra Stable Juno"),
+                symbol: String::from("ULTRA"),
+                decimals: 6,
+            },
+            sorted_troves_size: Uint256::from(1_000u64),
+            price_feed_pool_address: JUNOSWAP_POOL.to_string(),
+            juno_collateral: juno_collateral_params(),
+            reward_pool: REWARD_POOL.to_string(),
+            community_issuance: COMMUNITY_ISSUANCE.to_string(),
+        },
+        &[],
+        "factory",
+        None,
+    )
+    .unwrap()
+}
+
+fn query_ownership(app: &App, contract_addr: &Addr) -> OwnershipResponse {
+    app.wrap()
+        .query_wasm_smart(contract_addr.clone(), &ActivePoolQueryMsg::GetOwnership {})
+        .unwrap()
+}
+
+#[test]
+fn test_deploy_protocol() {
+    let mut app = App::default();
+    let factory_addr = instantiate_factory(&mut app);
+
+    let deployment: DeploymentResponse = app
+        .wrap()
+        .query_wasm_smart(&factory_addr, &QueryMsg::GetDeployment {})
+        .unwrap();
+    assert_eq!(deployment.owner, Addr::unchecked(OWNER));
+    assert_eq!(deployment.reward_pool, Addr::unchecked(REWARD_POOL));
+    assert_eq!(
+        deployment.community_issuance,
+        Addr::unchecked(COMMUNITY_ISSUANCE)
+    );
+
+    // Every deployed contract is registered under its key
+    let res: AddressesResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &deployment.address_registry,
+            &AddressRegistryQueryMsg::ListAddresses {},
+        )
+        .unwrap();
+    let expected = [
+        (ProtocolContract::UltraToken, &deployment.ultra_token),
+        (ProtocolContract::ActivePool, &deployment.active_pool),
+        (ProtocolContract::DefaultPool, &deployment.default_pool),
+        (
+            ProtocolContract::CollSurplusPool,
+            &deployment.coll_surplus_pool,
+        ),
+        (ProtocolContract::TroveManager, &deployment.trove_manager),
+        (
+            ProtocolContract::BorrowerOperations,
+            &deployment.borrower_operations,
+        ),
+        (ProtocolContract::StabilityPool, &deployment.stability_pool),
+        (ProtocolContract::SortedTroves, &deployment.sorted_troves),
+        (ProtocolContract::HintHelpers, &deployment.hint_helpers),
+        (ProtocolContract::PriceFeed, &deployment.price_feed),
+        (ProtocolContract::RewardPool, &deployment.reward_pool),
+        (ProtocolContract::GasPool, &deployment.gas_pool),
+        (
+            ProtocolContract::CommunityIssuance,
+            &deployment.community_issuance,
+        ),
+    ]
+    .into_iter()
+    .map(|(contract, address)| AddressResponse {
+        contract,
+        address: address.clone(),
+    })
+    .collect::<Vec<_>>();
+    assert_eq!(res.addresses, expected);
+
+    // The deployed pools serve the JUNO collateral
+    let res: Collateral = app
+        .wrap()
+        .query_wasm_smart(
+            &deployment.address_registry,
+            &AddressRegistryQueryMsg::GetCollateral {
+                asset: native_asset_info(UJUNO_DENOM.to_string()),
+            },
+        )
+        .unwrap();
+    assert_eq!(
+        res,
+        Collateral {
+            asset: native_asset_info(UJUNO_DENOM.to_string()),
+            contracts: CollateralContracts {
+                active_pool: deployment.active_pool.clone(),
+                default_pool: deployment.default_pool.clone(),
+                coll_surplus_pool: deployment.coll_surplus_pool.clone(),
+                sorted_troves: deployment.sorted_troves.clone(),
+                price_feed: deployment.price_feed.clone(),
+            },
+            params: juno_collateral_params(),
+        }
+    );
+
+    // The pools synced their peers from the registry
+    let res: Addr = app
+        .wrap()
+        .query_wasm_smart(
+            &deployment.active_pool,
+            &ActivePoolQueryMsg::GetTroveManagerAddress {},
+        )
+        .unwrap();
+    assert_eq!(res, deployment.trove_manager);
+
+    // Only the borrower operations can mint ULTRA
+    let res: MinterResponse = app
+        .wrap()
+        .query_wasm_smart(
+            &deployment.ultra_token,
+            &ultra_token::msg::QueryMsg::Minter {},
+        )
+        .unwrap();
+    assert_eq!(res.minter, deployment.borrower_operations.to_string());
+
+    // Addresses are locked and the ownership is handed over to the owner
+    for contract_addr in [
+        &deployment.address_registry,
+        &deployment.active_pool,
+        &deployment.default_pool,
+        &deployment.coll_surplus_pool,
+        &deployment.gas_pool,
+    ] {
+        assert_eq!(
+            query_ownership(&app, contract_addr),
+            OwnershipResponse {
+                owner: Some(factory_addr.clone()),
+                pending_owner: Some(Addr::unchecked(OWNER)),
+                addresses_locked: true,
+            }
+        );
+        assert_eq!(
+            app.contract_data(contract_addr).unwrap().admin,
+            Some(Addr::unchecked(OWNER))
+        );
+    }
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        deployment.active_pool.clone(),
+        &ActivePoolExecuteMsg::AcceptOwner {},
+        &[],
+    )
+    .unwrap();
+    assert_eq!(
+        query_ownership(&app, &deployment.active_pool).owner,
+        Some(Addr::unchecked(OWNER))
+    );
+}
@@ -0,0 +1,391 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_binary, Addr, Binary, Deps, DepsMut, Env, MessageInfo, Response, StdResult, Storage,
+    Uint128, WasmMsg,
+};
+
+use cw2::set_contract_version;
+use cw20::Cw20ExecuteMsg;
+
+use crate::error::ContractError;
+use crate::state::{AddressesSet, ADDRESSES_SET, RESERVES, TOTAL_RESERVE};
+use ultra_base::address_registry::ProtocolContract;
+use ultra_base::asset::{Asset, AssetInfo};
+use ultra_base::gas_pool::{ExecuteMsg, InstantiateMsg, MigrateMsg, ParamsResponse, QueryMsg};
+use ultra_base::migration::{migrate_contract, Migration};
+use ultra_base::querier::query_protocol_address;
+use ultra_pool::access::{self, Role};
+use ultra_pool::ownership;
+use ultra_pool::registry;
+use ultra_pool::state::{SudoParams, SUDO_PARAMS};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:gas-pool";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// State migrations, ordered by the version introducing them
+const MIGRATIONS: &[Migration] = &[];
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // store sudo params
+    let sudo_params = SudoParams {
+        name: msg.name,
+        owner: deps.api.addr_validate(&msg.owner)?,
+    };
+
+    SUDO_PARAMS.save(deps.storage, &sudo_params)?;
+    TOTAL_RESERVE.save(deps.storage, &Uint128::zero())?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    Ok(migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+    )?)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::AccountReserve {
+            collateral,
+            borrower,
+            amount,
+        } => execute_account_reserve(deps, env, info, collateral, borrower, amount),
+        ExecuteMsg::CompensateLiquidator {
+            collateral,
+            liquidator,
+            borrowers,
+            coll_amount,
+        } => execute_compensate_liquidator(
+            deps,
+            env,
+            info,
+            collateral,
+            liquidator,
+            borrowers,
+            coll_amount,
+        ),
+        ExecuteMsg::RefundReserve {
+            collateral,
+            borrower,
+        } => execute_refund_reserve(deps, env, info, collateral, borrower),
+
+        ExecuteMsg::SetAddressRegistry { address_registry } => {
+            execute_set_address_registry(deps, env, info, address_registry)
+        }
+        ExecuteMsg::SyncAddresses {} => execute_sync_addresses(deps, env, info),
+        ExecuteMsg::ProposeOwner { owner } => execute_propose_owner(deps, env, info, owner),
+        ExecuteMsg::AcceptOwner {} => execute_accept_owner(deps, env, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, env, info),
+        ExecuteMsg::LockAddresses {} => execute_lock_addresses(deps, env, info),
+    }
+}
+
+pub fn execute_account_reserve(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collateral: AssetInfo,
+    borrower: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    only_bo(deps.storage, &info)?;
+
+    if load_reserve(deps.storage, &collateral, &borrower)?.is_some() {
+        return Err(ContractError::ReserveAlreadyAccounted {});
+    }
+    RESERVES.save(deps.storage, (&collateral.to_string(), &borrower), &amount)?;
+    TOTAL_RESERVE.update(deps.storage, |total| -> StdResult<_> {
+        Ok(total.checked_add(amount)?)
+    })?;
+
+    let res = Response::new()
+        .add_attribute("action", "account_reserve")
+        .add_attribute("collateral", collateral.to_string())
+        .add_attribute("borrower", borrower)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+pub fn execute_compensate_liquidator(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collateral: AssetInfo,
+    liquidator: Addr,
+    borrowers: Vec<Addr>,
+    coll_amount: Uint128,
+) -> Result<Response, ContractError> {
+    only_tm(deps.storage, &info)?;
+
+    let liquidator = deps.api.addr_validate(liquidator.as_str())?;
+    let ultra_amount = borrowers
+        .into_iter()
+        .map(|borrower| take_reserve(deps.storage, &collateral, borrower))
+        .sum::<Result<Uint128, ContractError>>()?;
+
+    let mut res = Response::new();
+    if !ultra_amount.is_zero() {
+        let ultra_token_address = ADDRESSES_SET.load(deps.storage)?.ultra_token_address;
+        res = res.add_message(WasmMsg::Execute {
+            contract_addr: ultra_token_address.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: liquidator.to_string(),
+                amount: ultra_amount,
+            })?,
+            funds: vec![],
+        });
+    }
+    if !coll_amount.is_zero() {
+        let coll = Asset {
+            info: collateral.clone(),
+            amount: coll_amount,
+        };
+        res = res.add_message(coll.into_msg(&deps.querier, &liquidator)?);
+    }
+    Ok(res
+        .add_attribute("action", "compensate_liquidator")
+        .add_attribute("collateral", collateral.to_string())
+        .add_attribute("liquidator", liquidator)
+        .add_attribute("ultra_amount", ultra_amount)
+        .add_attribute("coll_amount", coll_amount))
+}
+
+pub fn execute_refund_reserve(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collateral: AssetInfo,
+    borrower: Addr,
+) -> Result<Response, ContractError> {
+    let borrower_operations = only_bo(deps.storage, &info)?;
+
+    let amount = take_reserve(deps.storage, &collateral, borrower.clone())?;
+    if amount.is_zero() {
+        return Err(ContractError::NoReserve {});
+    }
+    let ultra_token_address = ADDRESSES_SET.load(deps.storage)?.ultra_token_address;
+    let transfer_msg = WasmMsg::Execute {
+        contract_addr: ultra_token_address.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: borrower_operations.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    };
+
+    let res = Response::new()
+        .add_message(transfer_msg)
+        .add_attribute("action", "refund_reserve")
+        .add_attribute("collateral", collateral.to_string())
+        .add_attribute("borrower", borrower)
+        .add_attribute("amount", amount);
+    Ok(res)
+}
+
+/// Returns the reserve of the trove of `borrower`
+fn load_reserve(
+    store: &dyn Storage,
+    collateral: &AssetInfo,
+    borrower: &Addr,
+) -> StdResult<Option<Uint128>> {
+    RESERVES.may_load(store, (&collateral.to_string(), borrower))
+}
+
+/// Removes the reserve of the trove of `borrower` from the pool and returns it, zero if it has
+/// none
+fn take_reserve(
+    store: &mut dyn Storage,
+    collateral: &AssetInfo,
+    borrower: Addr,
+) -> Result<Uint128, ContractError> {
+    let amount = load_reserve(store, collateral, &borrower)?.unwrap_or_default();
+    RESERVES.remove(store, (&collateral.to_string(), &borrower));
+    TOTAL_RESERVE.update(store, |total| -> StdResult<_> {
+        Ok(total.checked_sub(amount)?)
+    })?;
+    Ok(amount)
+}
+
+pub fn execute_set_address_registry(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address_registry: String,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+    ownership::ensure_addresses_unlocked(deps.storage)?;
+
+    let address_registry = deps.api.addr_validate(&address_registry)?;
+    Ok(registry::set_address_registry(
+        deps.storage,
+        address_registry,
+    )?)
+}
+
+/// Resolves the peers from the address registry and caches them in the addresses set
+pub fn execute_sync_addresses(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ownership::ensure_addresses_unlocked(deps.storage)?;
+
+    let address_registry = registry::load_address_registry(deps.storage)?;
+    let resolve = |contract| query_protocol_address(&deps.querier, &address_registry, contract);
+    let new_addresses_set = AddressesSet {
+        borrower_operations_address: resolve(ProtocolContract::BorrowerOperations)?,
+        trove_manager_address: resolve(ProtocolContract::TroveManager)?,
+        ultra_token_address: resolve(ProtocolContract::UltraToken)?,
+    };
+
+    ADDRESSES_SET.save(deps.storage, &new_addresses_set)?;
+    let res = Response::new()
+        .add_attribute("action", "sync_addresses")
+        .add_attribute(
+            "borrower_operations_address",
+            new_addresses_set.borrower_operations_address,
+        )
+        .add_attribute(
+            "trove_manager_address",
+            new_addresses_set.trove_manager_address,
+        )
+        .add_attribute("ultra_token_address", new_addresses_set.ultra_token_address);
+    Ok(res)
+}
+
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    owner: String,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    let owner = deps.api.addr_validate(&owner)?;
+    Ok(ownership::propose_owner(deps.storage, owner)?)
+}
+
+pub fn execute_accept_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    Ok(ownership::accept_owner(deps.storage, &info)?)
+}
+
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    Ok(ownership::renounce_ownership(deps.storage)?)
+}
+
+pub fn execute_lock_addresses(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    // the addresses must be configured before they are locked for good
+    if ADDRESSES_SET.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::AddressesNotSet {});
+    }
+    Ok(ownership::lock_addresses(deps.storage)?)
+}
+
+/// Checks to enfore only borrower operations can call
+fn only_bo(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
+    let addresses_set = ADDRESSES_SET.load(store)?;
+    access::only_roles(
+        &addresses_set,
+        info,
+        &[Role::BorrowerOperations],
+        ContractError::CallerIsNotBO {},
+    )
+}
+/// Checks to enfore only trove manager can call
+fn only_tm(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
+    let addresses_set = ADDRESSES_SET.load(store)?;
+    access::only_roles(
+        &addresses_set,
+        info,
+        &[Role::TroveManager],
+        ContractError::CallerIsNotTM {},
+    )
+}
+/// Checks to enfore only owner can call
+fn only_owner(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
+    access::only_owner(store, info, ContractError::UnauthorizedOwner {})
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetParams {} => to_binary(&query_params(deps)?),
+        QueryMsg::GetOwnership {} => to_binary(&ownership::query_ownership(deps.storage)?),
+        QueryMsg::GetReserve {
+            collateral,
+            borrower,
+        } => to_binary(&query_reserve(deps, collateral, borrower)?),
+        QueryMsg::GetTotalReserve {} => to_binary(&TOTAL_RESERVE.load(deps.storage)?),
+        QueryMsg::GetBorrowerOperationsAddress {} => {
+            to_binary(&query_borrower_operations_address(deps)?)
+        }
+        QueryMsg::GetTroveManagerAddress {} => to_binary(&query_trove_manager_address(deps)?),
+        QueryMsg::GetULTRATokenAddress {} => to_binary(&query_ultra_token_address(deps)?),
+    }
+}
+
+pub fn query_reserve(deps: Deps, collateral: AssetInfo, borrower: Addr) -> StdResult<Uint128> {
+    let reserve = load_reserve(deps.storage, &collateral, &borrower)?.unwrap_or_default();
+    Ok(reserve)
+}
+
+pub fn query_params(deps: Deps) -> StdResult<ParamsResponse> {
+    let info = SUDO_PARAMS.load(deps.storage)?;
+    let res = ParamsResponse {
+        name: info.name,
+        owner: info.owner,
+    };
+    Ok(res)
+}
+
+pub fn query_borrower_operations_address(deps: Deps) -> StdResult<Addr> {
+    let addresses_set = ADDRESSES_SET.load(deps.storage)?;
+    Ok(addresses_set.borrower_operations_address)
+}
+
+pub fn query_trove_manager_address(deps: Deps) -> StdResult<Addr> {
+    let addresses_set = ADDRESSES_SET.load(deps.storage)?;
+    Ok(addresses_set.trove_manager_address)
+}
+
+pub fn query_ultra_token_address(deps: Deps) -> StdResult<Addr> {
+    let addresses_set = ADDRESSES_SET.load(deps.storage)?;
+    Ok(addresses_set.ultra_token_address)
+}
@@ -0,0 +1,34 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+use ultra_base::migration::MigrationError;
+use ultra_pool::PoolError;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Pool(#[from] PoolError),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
+    #[error("UnauthorizedOwner")]
+    UnauthorizedOwner {},
+
+    #[error("GasPool: Caller is not Borrower Operations")]
+    CallerIsNotBO {},
+
+    #[error("GasPool: Caller is not TroveManager")]
+    CallerIsNotTM {},
+
+    #[error("GasPool: Trove already has a reserve")]
+    ReserveAlreadyAccounted {},
+
+    #[error("GasPool: Trove has no reserve")]
+    NoReserve {},
+
+    #[error("GasPool: Addresses must be set before they are locked")]
+    AddressesNotSet {},
+}
@@ -0,0 +1,27 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ultra_pool::access::{Role, RoleAddresses};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AddressesSet {
+    pub borrower_operations_address: Addr,
+    pub trove_manager_address: Addr,
+    pub ultra_token_address: Addr,
+}
+
+impl RoleAddresses for AddressesSet {
+    fn address_of(&self, role: Role) -> Option<&Addr> {
+        match role {
+            Role::BorrowerOperations => Some(&self.borrower_operations_address),
+            Role::TroveManager => Some(&self.trove_manager_address),
+            _ => None,
+        }
+    }
+}
+
+pub const ADDRESSES_SET: Item<AddressesSet> = Item::new("addresses_set");
+pub const TOTAL_RESERVE: Item<Uint128> = Item::new("total_reserve");
+/// ULTRA reserved for each trove, keyed by collateral and borrower
+pub const RESERVES: Map<(&str, &Addr), Uint128> = Map::new("reserves");
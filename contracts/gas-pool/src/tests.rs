@@ -0,0 +1,371 @@
+use crate::ContractError;
+
+use ultra_base::address_registry::{
+    ContractAddress, ExecuteMsg as AddressRegistryExecuteMsg,
+    InstantiateMsg as AddressRegistryInstantiateMsg, ProtocolContract,
+};
+use ultra_base::asset::{native_asset_info, AssetInfo, UJUNO_DENOM};
+use ultra_base::gas_pool::{ExecuteMsg, InstantiateMsg, QueryMsg};
+
+use cosmwasm_std::{coins, Addr, Empty, Uint128};
+use cw20::{BalanceResponse, MinterResponse};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+const SOME: &str = "someone";
+const OWNER: &str = "owner";
+const BO: &str = "borrower-operations";
+const TM: &str = "trove-manager";
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+const LIQUIDATOR: &str = "liquidator";
+const COLLATERAL_TOKEN: &str = "collateral-token";
+
+fn juno() -> AssetInfo {
+    native_asset_info(UJUNO_DENOM.to_string())
+}
+
+fn gas_pool_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_sudo(crate::sudo::sudo)
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+fn address_registry_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        address_registry::contract::execute,
+        address_registry::contract::instantiate,
+        address_registry::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn ultra_token_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        ultra_token::contract::execute,
+        ultra_token::contract::instantiate,
+        ultra_token::contract::query,
+    );
+    Box::new(contract)
+}
+
+/// Instantiates a gas pool and the ULTRA token, minted by borrower operations, resolving
+/// them from an address registry. Returns the gas pool and token addresses
+fn instantiate_gas_pool(app: &mut App) -> (Addr, Addr) {
+    let code_id = app.store_code(gas_pool_contract());
+    let gas_pool_addr = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(SOME),
+            &InstantiateMsg {
+                name: String::from("Gas Pool"),
+                owner: OWNER.to_string(),
+            },
+            &[],
+            "gas pool",
+            None,
+        )
+        .unwrap();
+
+    let ultra_token_code_id = app.store_code(ultra_token_contract());
+    let ultra_token_addr = app
+        .instantiate_contract(
+            ultra_token_code_id,
+            Addr::unchecked(SOME),
+            &ultra_token::msg::InstantiateMsg {
+                name: String::from("Ultra Stable Juno"),
+                symbol: String::from("ULTRA"),
+                decimals: 6,
+                initial_balances: vec![],
+                mint: Some(MinterResponse {
+                    minter: BO.to_string(),
+                    cap: None,
+                }),
+                marketing: None,
+            },
+            &[],
+            "ultra token",
+            None,
+        )
+        .unwrap();
+
+    let address_registry_code_id = app.store_code(address_registry_contract());
+    let address_registry_addr = app
+        .instantiate_contract(
+            address_registry_code_id,
+            Addr::unchecked(SOME),
+            &AddressRegistryInstantiateMsg {
+                name: String::from("Address Registry"),
+                owner: OWNER.to_string(),
+            },
+            &[],
+            "address registry",
+            None,
+        )
+        .unwrap();
+
+    let addresses = [
+        (ProtocolContract::BorrowerOperations, BO.to_string()),
+        (ProtocolContract::TroveManager, TM.to_string()),
+        (ProtocolContract::UltraToken, ultra_token_addr.to_string()),
+    ];
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &AddressRegistryExecuteMsg::SetAddresses {
+            addresses: addresses
+                .into_iter()
+                .map(|(contract, address)| ContractAddress { contract, address })
+                .collect(),
+        },
+        &[],
+    )
+    .unwrap();
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        gas_pool_addr.clone(),
+        &ExecuteMsg::SetAddressRegistry {
+            address_registry: address_registry_addr.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        gas_pool_addr.clone(),
+        &ExecuteMsg::SyncAddresses {},
+        &[],
+    )
+    .unwrap();
+
+    (gas_pool_addr, ultra_token_addr)
+}
+
+/// Mints the liquidation reserve of the JUNO trove of `borrower` to the gas pool and records it
+fn account_reserve(app: &mut App, gas_pool_addr: &Addr, ultra_token_addr: &Addr, borrower: &str) {
+    account_collateral_reserve(app, gas_pool_addr, ultra_token_addr, juno(), borrower)
+}
+
+fn account_collateral_reserve(
+    app: &mut App,
+    gas_pool_addr: &Addr,
+    ultra_token_addr: &Addr,
+    collateral: AssetInfo,
+    borrower: &str,
+) {
+    app.execute_contract(
+        Addr::unchecked(BO),
+        ultra_token_addr.clone(),
+        &ultra_token::msg::ExecuteMsg::Mint {
+            recipient: gas_pool_addr.to_string(),
+            amount: Uint128::new(200u128),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(BO),
+        gas_pool_addr.clone(),
+        &ExecuteMsg::AccountReserve {
+            collateral,
+            borrower: Addr::unchecked(borrower),
+            amount: Uint128::new(200u128),
+        },
+        &[],
+    )
+    .unwrap();
+}
+
+fn query_reserve(app: &App, gas_pool_addr: &Addr, borrower: &str) -> Uint128 {
+    app.wrap()
+        .query_wasm_smart(
+            gas_pool_addr.clone(),
+            &QueryMsg::GetReserve {
+                collateral: juno(),
+                borrower: Addr::unchecked(borrower),
+            },
+        )
+        .unwrap()
+}
+
+fn query_total_reserve(app: &App, gas_pool_addr: &Addr) -> Uint128 {
+    app.wrap()
+        .query_wasm_smart(gas_pool_addr.clone(), &QueryMsg::GetTotalReserve {})
+        .unwrap()
+}
+
+fn query_ultra_balance(app: &App, ultra_token_addr: &Addr, address: &str) -> Uint128 {
+    let res: BalanceResponse = app
+        .wrap()
+        .query_wasm_smart(
+            ultra_token_addr.clone(),
+            &ultra_token::msg::QueryMsg::Balance {
+                address: address.to_string(),
+            },
+        )
+        .unwrap();
+    res.balance
+}
+
+#[test]
+fn test_account_reserve() {
+    let mut app = App::default();
+    let (gas_pool_addr, ultra_token_addr) = instantiate_gas_pool(&mut app);
+
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            gas_pool_addr.clone(),
+            &ExecuteMsg::AccountReserve {
+                collateral: juno(),
+                borrower: Addr::unchecked(ALICE),
+                amount: Uint128::new(200u128),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNotBO {});
+
+    account_reserve(&mut app, &gas_pool_addr, &ultra_token_addr, ALICE);
+    account_reserve(&mut app, &gas_pool_addr, &ultra_token_addr, BOB);
+    assert_eq!(
+        query_reserve(&app, &gas_pool_addr, ALICE),
+        Uint128::new(200u128)
+    );
+    assert_eq!(
+        query_total_reserve(&app, &gas_pool_addr),
+        Uint128::new(400u128)
+    );
+    assert_eq!(
+        query_ultra_balance(&app, &ultra_token_addr, gas_pool_addr.as_str()),
+        Uint128::new(400u128)
+    );
+
+    // A trove only has one reserve
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(BO),
+            gas_pool_addr.clone(),
+            &ExecuteMsg::AccountReserve {
+                collateral: juno(),
+                borrower: Addr::unchecked(ALICE),
+                amount: Uint128::new(200u128),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::ReserveAlreadyAccounted {});
+
+    // Troves against other collaterals have their own reserve
+    let collateral_token = AssetInfo::Cw20Token {
+        contract_addr: Addr::unchecked(COLLATERAL_TOKEN),
+    };
+    account_collateral_reserve(
+        &mut app,
+        &gas_pool_addr,
+        &ultra_token_addr,
+        collateral_token,
+        ALICE,
+    );
+    assert_eq!(
+        query_reserve(&app, &gas_pool_addr, ALICE),
+        Uint128::new(200u128)
+    );
+    assert_eq!(
+        query_total_reserve(&app, &gas_pool_addr),
+        Uint128::new(600u128)
+    );
+}
+
+#[test]
+fn test_compensate_liquidator() {
+    let mut app = App::default();
+    let (gas_pool_addr, ultra_token_addr) = instantiate_gas_pool(&mut app);
+    account_reserve(&mut app, &gas_pool_addr, &ultra_token_addr, ALICE);
+    account_reserve(&mut app, &gas_pool_addr, &ultra_token_addr, BOB);
+
+    let compensate_msg = ExecuteMsg::CompensateLiquidator {
+        collateral: juno(),
+        liquidator: Addr::unchecked(LIQUIDATOR),
+        borrowers: vec![Addr::unchecked(ALICE), Addr::unchecked(BOB)],
+        coll_amount: Uint128::new(50u128),
+    };
+    let err: ContractError = app
+        .execute_contract(
+            Addr::unchecked(BO),
+            gas_pool_addr.clone(),
+            &compensate_msg,
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNotTM {});
+
+    // The collateral compensation is sent along by the active pool
+    app.init_modules(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &gas_pool_addr, coins(50, UJUNO_DENOM))
+            .unwrap();
+    });
+    app.execute_contract(
+        Addr::unchecked(TM),
+        gas_pool_addr.clone(),
+        &compensate_msg,
+        &[],
+    )
+    .unwrap();
+
+    assert_eq!(
+        query_ultra_balance(&app, &ultra_token_addr, LIQUIDATOR),
+        Uint128::new(400u128)
+    );
+    let balance = app.wrap().query_balance(LIQUIDATOR, UJUNO_DENOM).unwrap();
+    assert_eq!(balance.amount, Uint128::new(50u128));
+    assert_eq!(query_reserve(&app, &gas_pool_addr, ALICE), Uint128::zero());
+    assert_eq!(query_total_reserve(&app, &gas_pool_addr), Uint128::zero());
+}
+
+#[test]
+fn test_refund_reserve() {
+    let mut app = App::default();
+    let (gas_pool_addr, ultra_token_addr) = instantiate_gas_pool(&mut app);
+    account_reserve(&mut app, &gas_pool_addr, &ultra_token_addr, ALICE);
+
+    let refund_msg = ExecuteMsg::RefundReserve {
+        collateral: juno(),
+        borrower: Addr::unchecked(ALICE),
+    };
+    let err: ContractError = app
+        .execute_contract(Addr::unchecked(TM), gas_pool_addr.clone(), &refund_msg, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNotBO {});
+
+    // The reserve goes back to borrower operations, which burns it
+    app.execute_contract(Addr::unchecked(BO), gas_pool_addr.clone(), &refund_msg, &[])
+        .unwrap();
+    assert_eq!(
+        query_ultra_balance(&app, &ultra_token_addr, BO),
+        Uint128::new(200u128)
+    );
+    assert_eq!(query_total_reserve(&app, &gas_pool_addr), Uint128::zero());
+
+    let err: ContractError = app
+        .execute_contract(Addr::unchecked(BO), gas_pool_addr, &refund_msg, &[])
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NoReserve {});
+}
@@ -0,0 +1,170 @@
+use crate::error::ContractError;
+use crate::state::{Config, CONFIG};
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    to_binary, Addr, Api, Binary, Deps, DepsMut, Env, MessageInfo, QuerierWrapper, Response,
+    StdError, StdResult, Uint128, Uint256,
+};
+use cw2::set_contract_version;
+use ultra_base::asset::{token_asset_info, AssetInfo};
+use ultra_base::lp_oracle::{
+    fair_reserves_value, lp_token_value, ConfigResponse, InstantiateMsg, LpPriceResponse,
+    MigrateMsg, QueryMsg,
+};
+use ultra_base::migration::{migrate_contract, Migration};
+use ultra_base::oracle::QueryMsg as OracleQueryMsg;
+use ultra_base::querier::query_pool_info;
+
+const CONTRACT_NAME: &str = "junoswap-lp-oracle";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+/// State migrations, ordered by the version introducing them
+const MIGRATIONS: &[Migration] = &[];
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    let pool_contract_addr = deps.api.addr_validate(&msg.pool_contract_address)?;
+    let pool_info = query_pool_info(&deps.querier, pool_contract_addr.clone())?;
+
+    let config = Config {
+        pool_contract_addr,
+        lp_token: token_asset_info(deps.api.addr_validate(&pool_info.lp_token_address)?),
+        token1_oracle: validate_oracle(
+            deps.api,
+            msg.token1_oracle,
+            &pool_info.token1_denom,
+            &msg.quote,
+        )?,
+        token2_oracle: validate_oracle(
+            deps.api,
+            msg.token2_oracle,
+            &pool_info.token2_denom,
+            &msg.quote,
+        )?,
+        quote: msg.quote,
+    };
+    CONFIG.save(deps.storage, &config)?;
+
+    Ok(Response::new()
+        .add_attribute("action", "instantiate")
+        .add_attribute("lp_token", config.lp_token.to_string()))
+}
+
+/// Only the quote asset can do without an oracle, being worth its own amount
+fn validate_oracle(
+    api: &dyn Api,
+    oracle: Option<String>,
+    token: &AssetInfo,
+    quote: &AssetInfo,
+) -> Result<Option<Addr>, ContractError> {
+    match oracle {
+        Some(oracle) => Ok(Some(api.addr_validate(&oracle)?)),
+        None if token.equal(quote) => Ok(None),
+        None => Err(ContractError::MissingOracle {
+            token: token.to_string(),
+        }),
+    }
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::Consult { token, amount } => to_binary(&consult(deps, token, amount)?),
+        QueryMsg::GetLpPrice {} => to_binary(&query_lp_price(deps)?),
+        QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
+    }
+}
+
+/// Returns the value of `amount` LP tokens at the fair reserves of the pool
+fn consult(deps: Deps, token: AssetInfo, amount: Uint128) -> StdResult<Uint256> {
+    let config = CONFIG.load(deps.storage)?;
+    if !config.lp_token.equal(&token) {
+        return Err(StdError::generic_err("Invalid Token"));
+    }
+
+    let price = query_lp_price(deps)?;
+    lp_token_value(price.fair_value, amount, price.lp_token_supply)
+}
+
+/// Values the current reserves of the pool at the TWAP prices of its tokens
+fn query_lp_price(deps: Deps) -> StdResult<LpPriceResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let pool_info = query_pool_info(&deps.querier, config.pool_contract_addr)?;
+
+    let token1_value = query_reserve_value(
+        &deps.querier,
+        config.token1_oracle,
+        pool_info.token1_denom,
+        pool_info.token1_reserve,
+    )?;
+    let token2_value = query_reserve_value(
+        &deps.querier,
+        config.token2_oracle,
+        pool_info.token2_denom,
+        pool_info.token2_reserve,
+    )?;
+
+    Ok(LpPriceResponse {
+        token1_value,
+        token2_value,
+        fair_value: fair_reserves_value(token1_value, token2_value)?,
+        lp_token_supply: pool_info.lp_token_supply,
+    })
+}
+
+/// Returns the value of `reserve` in the quote asset at the TWAP price of `token`.
+/// Fails if the oracle holds no price yet, which would value the LP token at zero
+fn query_reserve_value(
+    querier: &QuerierWrapper,
+    oracle: Option<Addr>,
+    token: AssetInfo,
+    reserve: Uint128,
+) -> StdResult<Uint256> {
+    let oracle = match oracle {
+        Some(oracle) => oracle,
+        None => return Ok(Uint256::from(reserve)),
+    };
+    let value: Uint256 = querier.query_wasm_smart(
+        oracle,
+        &OracleQueryMsg::Consult {
+            token: token.clone(),
+            amount: reserve,
+        },
+    )?;
+    if value.is_zero() && !reserve.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "No TWAP price of {} yet",
+            token
+        )));
+    }
+    Ok(value)
+}
+
+fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    Ok(ConfigResponse {
+        pool_contract_addr: config.pool_contract_addr,
+        lp_token: config.lp_token,
+        quote: config.quote,
+        token1_oracle: config.token1_oracle,
+        token2_oracle: config.token2_oracle,
+    })
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    Ok(migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+    )?)
+}
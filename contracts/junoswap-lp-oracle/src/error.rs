@@ -0,0 +1,15 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+use ultra_base::migration::MigrationError;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
+    #[error("LpOracle: {token} is not the quote asset and needs an oracle")]
+    MissingOracle { token: String },
+}
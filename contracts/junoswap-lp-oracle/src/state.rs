@@ -0,0 +1,19 @@
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use cosmwasm_std::Addr;
+use cw_storage_plus::Item;
+use ultra_base::asset::AssetInfo;
+
+/// Global configuration for the contract
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Config {
+    pub pool_contract_addr: Addr,
+    pub lp_token: AssetInfo,
+    pub quote: AssetInfo,
+    /// TWAP oracles of the pool tokens, none for the quote asset
+    pub token1_oracle: Option<Addr>,
+    pub token2_oracle: Option<Addr>,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
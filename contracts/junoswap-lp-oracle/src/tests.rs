@@ -0,0 +1,176 @@
+use crate::contract::{instantiate, query};
+use crate::ContractError;
+
+use ultra_base::asset::{native_asset_info, token_asset_info, AssetInfo, UJUNO_DENOM};
+use ultra_base::lp_oracle::{ConfigResponse, InstantiateMsg, LpPriceResponse, QueryMsg};
+use ultra_base::oracle::QueryMsg as OracleQueryMsg;
+
+use cosmwasm_std::testing::{
+    mock_dependencies, mock_env, mock_info, MockApi, MockQuerier, MockStorage,
+};
+use cosmwasm_std::{
+    from_binary, to_binary, Addr, Binary, ContractResult, Empty, OwnedDeps, StdError, StdResult,
+    SystemError, SystemResult, Uint128, Uint256, WasmQuery,
+};
+
+const SOME: &str = "someone";
+const POOL: &str = "juno-ultra-pool";
+const LP_TOKEN: &str = "lp-token";
+const ULTRA: &str = "ultra-token";
+const JUNO_ORACLE: &str = "juno-oracle";
+
+type Deps = OwnedDeps<MockStorage, MockApi, MockQuerier<Empty>>;
+
+fn ultra() -> AssetInfo {
+    token_asset_info(Addr::unchecked(ULTRA))
+}
+
+/// Mocks a JUNO/ULTRA pool holding the given reserves, and a JUNO oracle with a TWAP price of
+/// `juno_price` ULTRA
+fn mock_pool(deps: &mut Deps, reserves: (u128, u128), lp_token_supply: u128, juno_price: u128) {
+    deps.querier.update_wasm(move |query| {
+        let (contract_addr, msg) = match query {
+            WasmQuery::Smart { contract_addr, msg } => (contract_addr.as_str(), msg),
+            _ => panic!("Unexpected query {:?}", query),
+        };
+        let res = match contract_addr {
+            POOL => Binary::from(
+                format!(
+                    r#"{{"token1_reserve":"{}","token1_denom":{{"native":"{}"}},"token2_reserve":"{}","token2_denom":{{"cw20":"{}"}},"lp_token_supply":"{}","lp_token_address":"{}"}}"#,
+                    reserves.0, UJUNO_DENOM, reserves.1, ULTRA, lp_token_supply, LP_TOKEN
+                )
+                .as_bytes(),
+            ),
+            JUNO_ORACLE => match from_binary(msg).unwrap() {
+                OracleQueryMsg::Consult { token, amount } => {
+                    assert_eq!(token, native_asset_info(UJUNO_DENOM.to_string()));
+                    to_binary(&(Uint256::from(amount) * Uint256::from(juno_price))).unwrap()
+                }
+            },
+            _ => {
+                return SystemResult::Err(SystemError::NoSuchContract {
+                    addr: contract_addr.to_string(),
+                })
+            }
+        };
+        SystemResult::Ok(ContractResult::Ok(res))
+    });
+}
+
+fn instantiate_oracle(deps: &mut Deps) {
+    instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SOME, &[]),
+        InstantiateMsg {
+            pool_contract_address: POOL.to_string(),
+            quote: ultra(),
+            token1_oracle: Some(JUNO_ORACLE.to_string()),
+            token2_oracle: None,
+        },
+    )
+    .unwrap();
+}
+
+fn consult(deps: &Deps, token: AssetInfo, amount: u128) -> StdResult<Uint256> {
+    let msg = QueryMsg::Consult {
+        token,
+        amount: Uint128::new(amount),
+    };
+    query(deps.as_ref(), mock_env(), msg).map(|res| from_binary(&res).unwrap())
+}
+
+#[test]
+fn test_instantiate() {
+    let mut deps = mock_dependencies();
+    mock_pool(&mut deps, (1_000, 2_000), 1_000, 2);
+    instantiate_oracle(&mut deps);
+
+    let config: ConfigResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetConfig {}).unwrap()).unwrap();
+    assert_eq!(
+        config,
+        ConfigResponse {
+            pool_contract_addr: Addr::unchecked(POOL),
+            lp_token: token_asset_info(Addr::unchecked(LP_TOKEN)),
+            quote: ultra(),
+            token1_oracle: Some(Addr::unchecked(JUNO_ORACLE)),
+            token2_oracle: None,
+        }
+    );
+
+    // Only the quote asset can be priced without oracle
+    let err = instantiate(
+        deps.as_mut(),
+        mock_env(),
+        mock_info(SOME, &[]),
+        InstantiateMsg {
+            pool_contract_address: POOL.to_string(),
+            quote: ultra(),
+            token1_oracle: None,
+            token2_oracle: None,
+        },
+    )
+    .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::MissingOracle {
+            token: UJUNO_DENOM.to_string()
+        }
+    );
+}
+
+#[test]
+fn test_consult() {
+    let mut deps = mock_dependencies();
+    mock_pool(&mut deps, (1_000, 2_000), 1_000, 2);
+    instantiate_oracle(&mut deps);
+
+    // The balanced pool is worth its reserves
+    let price: LpPriceResponse =
+        from_binary(&query(deps.as_ref(), mock_env(), QueryMsg::GetLpPrice {}).unwrap()).unwrap();
+    assert_eq!(
+        price,
+        LpPriceResponse {
+            token1_value: Uint256::from(2_000u128),
+            token2_value: Uint256::from(2_000u128),
+            fair_value: Uint256::from(4_000u128),
+            lp_token_supply: Uint128::new(1_000),
+        }
+    );
+    assert_eq!(
+        consult(&deps, token_asset_info(Addr::unchecked(LP_TOKEN)), 250).unwrap(),
+        Uint256::from(1_000u128)
+    );
+
+    // Only the LP token is priced
+    consult(&deps, ultra(), 250).unwrap_err();
+}
+
+#[test]
+fn test_skewed_pool_does_not_inflate_the_price() {
+    let mut deps = mock_dependencies();
+    mock_pool(&mut deps, (1_000, 2_000), 1_000, 2);
+    instantiate_oracle(&mut deps);
+
+    // 1_000 ULTRA swapped into the pool within the block: the reserves are worth 4_332 ULTRA
+    // at spot but the TWAP price of JUNO is unchanged
+    mock_pool(&mut deps, (666, 3_000), 1_000, 2);
+    assert_eq!(
+        consult(&deps, token_asset_info(Addr::unchecked(LP_TOKEN)), 1_000).unwrap(),
+        Uint256::from(3_996u128)
+    );
+}
+
+#[test]
+fn test_missing_twap_price() {
+    let mut deps = mock_dependencies();
+    mock_pool(&mut deps, (1_000, 2_000), 1_000, 0);
+    instantiate_oracle(&mut deps);
+
+    let err = consult(&deps, token_asset_info(Addr::unchecked(LP_TOKEN)), 250).unwrap_err();
+    assert_eq!(
+        err,
+        StdError::generic_err(format!("No TWAP price of {} yet", UJUNO_DENOM))
+    );
+}
@@ -6,12 +6,16 @@ use cosmwasm_std::{
 };
 use cw2::set_contract_version;
 use ultra_base::asset::{AssetInfo, PoolInfo};
+use ultra_base::migration::{migrate_contract, Migration};
 use ultra_base::oracle::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 use ultra_base::querier::query_pool_info;
 
 const CONTRACT_NAME: &str = "junoswap-oracle";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+/// State migrations, ordered by the version introducing them
+const MIGRATIONS: &[Migration] = &[];
+
 /// Time between two consecutive TWAP updates.
 pub const PERIOD: Uint128 = Uint128::new(1200u128);
 
@@ -140,6 +144,11 @@ fn consult(deps: Deps, token: AssetInfo, amount: Uint128) -> Result<Uint256, Std
 }
 
 #[cfg_attr(not(feature = "library"), entry_point)]
-pub fn migrate(_deps: DepsMut, _env: Env, _msg: MigrateMsg) -> StdResult<Response> {
-    Ok(Response::default())
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    Ok(migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+    )?)
 }
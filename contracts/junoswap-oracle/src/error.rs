@@ -1,11 +1,15 @@
 use cosmwasm_std::StdError;
 use thiserror::Error;
+use ultra_base::migration::MigrationError;
 
 #[derive(Error, Debug, PartialEq)]
 pub enum ContractError {
     #[error("{0}")]
     Std(#[from] StdError),
 
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
     #[error("Unauthorized")]
     Unauthorized {},
 
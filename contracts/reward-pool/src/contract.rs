@@ -0,0 +1,516 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    from_binary, to_binary, Addr, Binary, CosmosMsg, Decimal256, Deps, DepsMut, Env, MessageInfo,
+    QuerierWrapper, Response, StdError, StdResult, Storage, Uint128, Uint256,
+};
+
+use cw2::set_contract_version;
+use cw20::Cw20ReceiveMsg;
+
+use crate::error::ContractError;
+use crate::state::{
+    AddressesSet, ADDRESSES_SET, FEES_PER_UNIT_STAKED, REWARD_TOKEN, SNAPSHOTS, STAKES,
+    TOTAL_STAKED,
+};
+use ultra_base::address_registry::ProtocolContract;
+use ultra_base::asset::{token_asset, token_asset_info, Asset, AssetInfo};
+use ultra_base::migration::{migrate_contract, Migration};
+use ultra_base::querier::{query_collateral, query_protocol_address};
+use ultra_base::reward_pool::{
+    Cw20HookMsg, ExecuteMsg, FeesPerUnitStaked, InstantiateMsg, MigrateMsg, ParamsResponse,
+    QueryMsg,
+};
+use ultra_base::ultra_math::min;
+use ultra_pool::access::{self, Role};
+use ultra_pool::funds::{must_pay_collateral, must_receive_collateral};
+use ultra_pool::ownership;
+use ultra_pool::registry;
+use ultra_pool::state::{SudoParams, SUDO_PARAMS};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:reward-pool";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// State migrations, ordered by the version introducing them
+const MIGRATIONS: &[Migration] = &[];
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // store sudo params
+    let sudo_params = SudoParams {
+        name: msg.name,
+        owner: deps.api.addr_validate(&msg.owner)?,
+    };
+
+    SUDO_PARAMS.save(deps.storage, &sudo_params)?;
+    REWARD_TOKEN.save(deps.storage, &deps.api.addr_validate(&msg.reward_token)?)?;
+    TOTAL_STAKED.save(deps.storage, &Uint128::zero())?;
+    FEES_PER_UNIT_STAKED.save(deps.storage, &FeesPerUnitStaked::default())?;
+
+    Ok(Response::default())
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    Ok(migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+    )?)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+        ExecuteMsg::Unstake { amount } => execute_unstake(deps, env, info, amount),
+        ExecuteMsg::Claim {} => execute_claim(deps, env, info),
+        ExecuteMsg::IncreaseCollateralFee { collateral } => {
+            execute_increase_collateral_fee(deps, env, info, collateral)
+        }
+        ExecuteMsg::IncreaseULTRAFee { ultra_fee } => {
+            execute_increase_ultra_fee(deps, env, info, ultra_fee)
+        }
+
+        ExecuteMsg::SetAddressRegistry { address_registry } => {
+            execute_set_address_registry(deps, env, info, address_registry)
+        }
+        ExecuteMsg::SyncAddresses {} => execute_sync_addresses(deps, env, info),
+        ExecuteMsg::ProposeOwner { owner } => execute_propose_owner(deps, env, info, owner),
+        ExecuteMsg::AcceptOwner {} => execute_accept_owner(deps, env, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, env, info),
+        ExecuteMsg::LockAddresses {} => execute_lock_addresses(deps, env, info),
+    }
+}
+
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+    match from_binary(&cw20_msg.msg)? {
+        Cw20HookMsg::Stake {} => {
+            if info.sender != REWARD_TOKEN.load(deps.storage)? {
+                return Err(ContractError::InvalidRewardToken {});
+            }
+            execute_stake(deps, env, sender, cw20_msg.amount)
+        }
+        Cw20HookMsg::IncreaseCollateralFee {} => {
+            if sender != ADDRESSES_SET.load(deps.storage)?.trove_manager_address {
+                return Err(ContractError::CallerIsNotTM {});
+            }
+            let collateral = token_asset_info(info.sender.clone());
+            let coll_fee = must_receive_collateral(&info, &collateral, cw20_msg.amount)?;
+            increase_collateral_fee(deps, collateral, coll_fee)
+        }
+    }
+}
+
+pub fn execute_stake(
+    deps: DepsMut,
+    _env: Env,
+    staker: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    // the gains earned by the current stake are paid out before it changes
+    let (gain_msgs, coll_gains, ultra_gain) = take_gains(deps.storage, &deps.querier, &staker)?;
+
+    let stake = STAKES
+        .may_load(deps.storage, staker.clone())?
+        .unwrap_or_default()
+        .checked_add(amount)
+        .map_err(StdError::overflow)?;
+    STAKES.save(deps.storage, staker.clone(), &stake)?;
+    TOTAL_STAKED.update(deps.storage, |total| -> StdResult<_> {
+        Ok(total.checked_add(amount)?)
+    })?;
+
+    let res = Response::new()
+        .add_messages(gain_msgs)
+        .add_attribute("action", "stake")
+        .add_attribute("staker", staker)
+        .add_attribute("amount", amount)
+        .add_attribute("coll_gains", assets_attribute(&coll_gains))
+        .add_attribute("ultra_gain", ultra_gain);
+    Ok(res)
+}
+
+pub fn execute_unstake(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    if amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    let staker = info.sender;
+    let stake = require_stake(deps.storage, &staker)?;
+
+    let (mut msgs, coll_gains, ultra_gain) = take_gains(deps.storage, &deps.querier, &staker)?;
+
+    let withdrawn = min(amount, stake);
+    let new_stake = stake - withdrawn;
+    if new_stake.is_zero() {
+        STAKES.remove(deps.storage, staker.clone());
+        SNAPSHOTS.remove(deps.storage, staker.clone());
+    } else {
+        STAKES.save(deps.storage, staker.clone(), &new_stake)?;
+    }
+    TOTAL_STAKED.update(deps.storage, |total| -> StdResult<_> {
+        Ok(total.checked_sub(withdrawn)?)
+    })?;
+
+    let reward_token = REWARD_TOKEN.load(deps.storage)?;
+    msgs.push(token_asset(reward_token, withdrawn).into_msg(&deps.querier, &staker)?);
+
+    let res = Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "unstake")
+        .add_attribute("staker", staker)
+        .add_attribute("amount", withdrawn)
+        .add_attribute("coll_gains", assets_attribute(&coll_gains))
+        .add_attribute("ultra_gain", ultra_gain);
+    Ok(res)
+}
+
+pub fn execute_claim(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let staker = info.sender;
+    require_stake(deps.storage, &staker)?;
+
+    let (msgs, coll_gains, ultra_gain) = take_gains(deps.storage, &deps.querier, &staker)?;
+
+    let res = Response::new()
+        .add_messages(msgs)
+        .add_attribute("action", "claim")
+        .add_attribute("staker", staker)
+        .add_attribute("coll_gains", assets_attribute(&coll_gains))
+        .add_attribute("ultra_gain", ultra_gain);
+    Ok(res)
+}
+
+pub fn execute_increase_collateral_fee(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    collateral: AssetInfo,
+) -> Result<Response, ContractError> {
+    only_tm(deps.storage, &info)?;
+    let coll_fee = must_pay_collateral(&info, &collateral)?;
+
+    increase_collateral_fee(deps, collateral, coll_fee)
+}
+
+/// Shares the `coll_fee` redemption fee received in `collateral` among the stakers
+fn increase_collateral_fee(
+    deps: DepsMut,
+    collateral: AssetInfo,
+    coll_fee: Uint128,
+) -> Result<Response, ContractError> {
+    // Only the registered collateral types can be redeemed
+    let address_registry = registry::load_address_registry(deps.storage)?;
+    query_collateral(&deps.querier, &address_registry, &collateral)?;
+
+    let mut fees = FEES_PER_UNIT_STAKED.load(deps.storage)?;
+    fees.add_coll_fee(&collateral, fee_per_unit_staked(deps.storage, coll_fee)?);
+    FEES_PER_UNIT_STAKED.save(deps.storage, &fees)?;
+
+    let res = Response::new()
+        .add_attribute("action", "increase_collateral_fee")
+        .add_attribute("collateral", collateral.to_string())
+        .add_attribute("coll_fee", coll_fee)
+        .add_attribute("f_coll", fees.f_coll(&collateral).to_string());
+    Ok(res)
+}
+
+pub fn execute_increase_ultra_fee(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    ultra_fee: Uint128,
+) -> Result<Response, ContractError> {
+    only_bo(deps.storage, &info)?;
+
+    let mut fees = FEES_PER_UNIT_STAKED.load(deps.storage)?;
+    fees.f_ultra += fee_per_unit_staked(deps.storage, ultra_fee)?;
+    FEES_PER_UNIT_STAKED.save(deps.storage, &fees)?;
+
+    let res = Response::new()
+        .add_attribute("action", "increase_ultra_fee")
+        .add_attribute("ultra_fee", ultra_fee)
+        .add_attribute("f_ultra", fees.f_ultra.to_string());
+    Ok(res)
+}
+
+/// Share of `fee` of each reward token staked. Fees received while nothing is staked are kept
+/// by the pool without being shared
+fn fee_per_unit_staked(store: &dyn Storage, fee: Uint128) -> StdResult<Decimal256> {
+    let total_staked = TOTAL_STAKED.load(store)?;
+    if total_staked.is_zero() {
+        return Ok(Decimal256::zero());
+    }
+    Ok(Decimal256::from_ratio(fee, total_staked))
+}
+
+fn require_stake(store: &dyn Storage, staker: &Addr) -> Result<Uint128, ContractError> {
+    let stake = STAKES.may_load(store, staker.clone())?.unwrap_or_default();
+    if stake.is_zero() {
+        return Err(ContractError::NoStake {});
+    }
+    Ok(stake)
+}
+
+/// Snapshots the current fees for `staker` and returns the messages sending it the gains it
+/// earned since its previous snapshot, along with the collateral and ULTRA gains
+fn take_gains(
+    store: &mut dyn Storage,
+    querier: &QuerierWrapper,
+    staker: &Addr,
+) -> Result<(Vec<CosmosMsg>, Vec<Asset>, Uint128), ContractError> {
+    let fees = FEES_PER_UNIT_STAKED.load(store)?;
+    let (coll_gains, ultra_gain) = pending_gains(store, staker)?;
+    SNAPSHOTS.save(store, staker.clone(), &fees)?;
+
+    let mut msgs = vec![];
+    for coll_gain in &coll_gains {
+        msgs.push(coll_gain.clone().into_msg(querier, staker)?);
+    }
+    if !ultra_gain.is_zero() {
+        let ultra_token = ADDRESSES_SET.load(store)?.ultra_token_address;
+        msgs.push(token_asset(ultra_token, ultra_gain).into_msg(querier, staker)?);
+    }
+    Ok((msgs, coll_gains, ultra_gain))
+}
+
+/// Non-zero gain of each collateral type and ULTRA earned by the stake of `staker` since its
+/// last snapshot
+fn pending_gains(store: &dyn Storage, staker: &Addr) -> StdResult<(Vec<Asset>, Uint128)> {
+    let stake = STAKES.may_load(store, staker.clone())?.unwrap_or_default();
+    if stake.is_zero() {
+        return Ok((vec![], Uint128::zero()));
+    }
+    let fees = FEES_PER_UNIT_STAKED.load(store)?;
+    let snapshot = SNAPSHOTS
+        .may_load(store, staker.clone())?
+        .unwrap_or_default();
+    let gain = |fee: Decimal256, snapshot: Decimal256| -> StdResult<Uint128> {
+        Ok(Uint128::try_from(Uint256::from(stake) * (fee - snapshot))?)
+    };
+
+    let mut coll_gains = vec![];
+    for f_coll in fees.f_colls {
+        let amount = gain(f_coll.fee, snapshot.f_coll(&f_coll.collateral))?;
+        if !amount.is_zero() {
+            coll_gains.push(Asset {
+                info: f_coll.collateral,
+                amount,
+            });
+        }
+    }
+    Ok((coll_gains, gain(fees.f_ultra, snapshot.f_ultra)?))
+}
+
+fn assets_attribute(assets: &[Asset]) -> String {
+    assets
+        .iter()
+        .map(|asset| asset.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+pub fn execute_set_address_registry(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    address_registry: String,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+    ownership::ensure_addresses_unlocked(deps.storage)?;
+
+    let address_registry = deps.api.addr_validate(&address_registry)?;
+    Ok(registry::set_address_registry(
+        deps.storage,
+        address_registry,
+    )?)
+}
+
+/// Resolves the peers from the address registry and caches them in the addresses set
+pub fn execute_sync_addresses(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    ownership::ensure_addresses_unlocked(deps.storage)?;
+
+    let address_registry = registry::load_address_registry(deps.storage)?;
+    let resolve = |contract| query_protocol_address(&deps.querier, &address_registry, contract);
+    let new_addresses_set = AddressesSet {
+        borrower_operations_address: resolve(ProtocolContract::BorrowerOperations)?,
+        trove_manager_address: resolve(ProtocolContract::TroveManager)?,
+        ultra_token_address: resolve(ProtocolContract::UltraToken)?,
+    };
+
+    ADDRESSES_SET.save(deps.storage, &new_addresses_set)?;
+    let res = Response::new()
+        .add_attribute("action", "sync_addresses")
+        .add_attribute(
+            "borrower_operations_address",
+            new_addresses_set.borrower_operations_address,
+        )
+        .add_attribute(
+            "trove_manager_address",
+            new_addresses_set.trove_manager_address,
+        )
+        .add_attribute("ultra_token_address", new_addresses_set.ultra_token_address);
+    Ok(res)
+}
+
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    owner: String,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    let owner = deps.api.addr_validate(&owner)?;
+    Ok(ownership::propose_owner(deps.storage, owner)?)
+}
+
+pub fn execute_accept_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    Ok(ownership::accept_owner(deps.storage, &info)?)
+}
+
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    Ok(ownership::renounce_ownership(deps.storage)?)
+}
+
+pub fn execute_lock_addresses(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    // the addresses must be configured before they are locked for good
+    if ADDRESSES_SET.may_load(deps.storage)?.is_none() {
+        return Err(ContractError::AddressesNotSet {});
+    }
+    Ok(ownership::lock_addresses(deps.storage)?)
+}
+
+/// Checks to enfore only borrower operations can call
+fn only_bo(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
+    let addresses_set = ADDRESSES_SET.load(store)?;
+    access::only_roles(
+        &addresses_set,
+        info,
+        &[Role::BorrowerOperations],
+        ContractError::CallerIsNotBO {},
+    )
+}
+/// Checks to enfore only trove manager can call
+fn only_tm(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
+    let addresses_set = ADDRESSES_SET.load(store)?;
+    access::only_roles(
+        &addresses_set,
+        info,
+        &[Role::TroveManager],
+        ContractError::CallerIsNotTM {},
+    )
+}
+/// Checks to enfore only owner can call
+fn only_owner(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
+    access::only_owner(store, info, ContractError::UnauthorizedOwner {})
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, _env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetParams {} => to_binary(&query_params(deps)?),
+        QueryMsg::GetOwnership {} => to_binary(&ownership::query_ownership(deps.storage)?),
+        QueryMsg::GetStake { staker } => to_binary(&query_stake(deps, staker)?),
+        QueryMsg::GetTotalStaked {} => to_binary(&TOTAL_STAKED.load(deps.storage)?),
+        QueryMsg::GetFeesPerUnitStaked {} => to_binary(&FEES_PER_UNIT_STAKED.load(deps.storage)?),
+        QueryMsg::GetPendingCollGain { collateral, staker } => {
+            to_binary(&query_pending_coll_gain(deps, collateral, staker)?)
+        }
+        QueryMsg::GetPendingULTRAGain { staker } => {
+            to_binary(&pending_gains(deps.storage, &staker)?.1)
+        }
+        QueryMsg::GetBorrowerOperationsAddress {} => to_binary(
+            &ADDRESSES_SET
+                .load(deps.storage)?
+                .borrower_operations_address,
+        ),
+        QueryMsg::GetTroveManagerAddress {} => {
+            to_binary(&ADDRESSES_SET.load(deps.storage)?.trove_manager_address)
+        }
+        QueryMsg::GetULTRATokenAddress {} => {
+            to_binary(&ADDRESSES_SET.load(deps.storage)?.ultra_token_address)
+        }
+        QueryMsg::GetRewardTokenAddress {} => to_binary(&REWARD_TOKEN.load(deps.storage)?),
+    }
+}
+
+pub fn query_pending_coll_gain(
+    deps: Deps,
+    collateral: AssetInfo,
+    staker: Addr,
+) -> StdResult<Uint128> {
+    let (coll_gains, _) = pending_gains(deps.storage, &staker)?;
+    let gain = coll_gains
+        .into_iter()
+        .find(|gain| gain.info == collateral)
+        .map(|gain| gain.amount)
+        .unwrap_or_default();
+    Ok(gain)
+}
+
+pub fn query_stake(deps: Deps, staker: Addr) -> StdResult<Uint128> {
+    let stake = STAKES.may_load(deps.storage, staker)?.unwrap_or_default();
+    Ok(stake)
+}
+
+pub fn query_params(deps: Deps) -> StdResult<ParamsResponse> {
+    let info = SUDO_PARAMS.load(deps.storage)?;
+    let res = ParamsResponse {
+        name: info.name,
+        owner: info.owner,
+    };
+    Ok(res)
+}
@@ -0,0 +1,37 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+use ultra_base::migration::MigrationError;
+use ultra_pool::PoolError;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Pool(#[from] PoolError),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
+    #[error("UnauthorizedOwner")]
+    UnauthorizedOwner {},
+
+    #[error("RewardPool: Caller is not Borrower Operations")]
+    CallerIsNotBO {},
+
+    #[error("RewardPool: Caller is not TroveManager")]
+    CallerIsNotTM {},
+
+    #[error("RewardPool: Only the reward token can be staked")]
+    InvalidRewardToken {},
+
+    #[error("RewardPool: Amount must be non-zero")]
+    ZeroAmount {},
+
+    #[error("RewardPool: User must have a non-zero stake")]
+    NoStake {},
+
+    #[error("RewardPool: Addresses must be set before they are locked")]
+    AddressesNotSet {},
+}
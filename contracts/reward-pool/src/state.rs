@@ -0,0 +1,31 @@
+use cosmwasm_std::{Addr, Uint128};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ultra_base::reward_pool::FeesPerUnitStaked;
+use ultra_pool::access::{Role, RoleAddresses};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct AddressesSet {
+    pub borrower_operations_address: Addr,
+    pub trove_manager_address: Addr,
+    pub ultra_token_address: Addr,
+}
+
+impl RoleAddresses for AddressesSet {
+    fn address_of(&self, role: Role) -> Option<&Addr> {
+        match role {
+            Role::BorrowerOperations => Some(&self.borrower_operations_address),
+            Role::TroveManager => Some(&self.trove_manager_address),
+            _ => None,
+        }
+    }
+}
+
+pub const ADDRESSES_SET: Item<AddressesSet> = Item::new("addresses_set");
+pub const REWARD_TOKEN: Item<Addr> = Item::new("reward_token");
+pub const TOTAL_STAKED: Item<Uint128> = Item::new("total_staked");
+pub const FEES_PER_UNIT_STAKED: Item<FeesPerUnitStaked> = Item::new("fees_per_unit_staked");
+pub const STAKES: Map<Addr, Uint128> = Map::new("stakes");
+/// Fees per unit staked when the gains of each staker were last paid out
+pub const SNAPSHOTS: Map<Addr, FeesPerUnitStaked> = Map::new("snapshots");
@@ -0,0 +1,673 @@
+use crate::ContractError;
+
+use ultra_base::address_registry::{
+    ContractAddress, ExecuteMsg as AddressRegistryExecuteMsg,
+    InstantiateMsg as AddressRegistryInstantiateMsg, ProtocolContract,
+};
+use ultra_base::asset::{native_asset_info, token_asset_info, AssetInfo, UJUNO_DENOM};
+use ultra_base::collateral::{Collateral, CollateralContracts, CollateralParams};
+use ultra_base::reward_pool::{
+    CollateralFee, Cw20HookMsg, ExecuteMsg, FeesPerUnitStaked, InstantiateMsg, QueryMsg,
+};
+use ultra_pool::PoolError;
+
+use cosmwasm_std::{coins, to_binary, Addr, Decimal256, Empty, Uint128};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ReceiveMsg, MinterResponse};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+
+const SOME: &str = "someone";
+const OWNER: &str = "owner";
+const BO: &str = "borrower-operations";
+const TM: &str = "trove-manager";
+const AP: &str = "active-pool";
+const DP: &str = "default-pool";
+const CSP: &str = "coll-surplus-pool";
+const SORTED_TROVES: &str = "sorted-troves";
+const PRICE_FEED: &str = "price-feed";
+const ALICE: &str = "alice";
+const BOB: &str = "bob";
+
+struct Suite {
+    app: App,
+    reward_pool: Addr,
+    ultra_token: Addr,
+    reward_token: Addr,
+    coll_token: Addr,
+}
+
+fn reward_pool_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_sudo(crate::sudo::sudo)
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+fn address_registry_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        address_registry::contract::execute,
+        address_registry::contract::instantiate,
+        address_registry::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn ultra_token_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        ultra_token::contract::execute,
+        ultra_token::contract::instantiate,
+        ultra_token::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn instantiate_token(
+    app: &mut App,
+    code_id: u64,
+    symbol: &str,
+    initial_balances: Vec<Cw20Coin>,
+    minter: &str,
+) -> Addr {
+    app.instantiate_contract(
+        code_id,
+        Addr::unchecked(SOME),
+        &ultra_token::msg::InstantiateMsg {
+            name: symbol.to_string(),
+            symbol: symbol.to_string(),
+            decimals: 6,
+            initial_balances,
+            mint: Some(MinterResponse {
+                minter: minter.to_string(),
+                cap: None,
+            }),
+            marketing: None,
+        },
+        &[],
+        symbol,
+        None,
+    )
+    .unwrap()
+}
+
+/// Instantiates a reward pool staking a token held by alice and bob, and resolving the ULTRA
+/// token minted by borrower operations and the JUNO and cw20 collateral types redeemed by
+/// trove manager from an address registry
+fn instantiate_reward_pool() -> Suite {
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(storage, &Addr::unchecked(TM), coins(10_000, UJUNO_DENOM))
+            .unwrap();
+    });
+
+    let token_code_id = app.store_code(ultra_token_contract());
+    let ultra_token = instantiate_token(&mut app, token_code_id, "ULTRA", vec![], BO);
+    let reward_token = instantiate_token(
+        &mut app,
+        token_code_id,
+        "REWARD",
+        vec![
+            Cw20Coin {
+                address: ALICE.to_string(),
+                amount: Uint128::new(1_000u128),
+            },
+            Cw20Coin {
+                address: BOB.to_string(),
+                amount: Uint128::new(1_000u128),
+            },
+        ],
+        OWNER,
+    );
+    let coll_token = instantiate_token(
+        &mut app,
+        token_code_id,
+        "COLL",
+        vec![Cw20Coin {
+            address: TM.to_string(),
+            amount: Uint128::new(10_000u128),
+        }],
+        OWNER,
+    );
+
+    let code_id = app.store_code(reward_pool_contract());
+    let reward_pool = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(SOME),
+            &InstantiateMsg {
+                name: String::from("Reward Pool"),
+                owner: OWNER.to_string(),
+                reward_token: reward_token.to_string(),
+            },
+            &[],
+            "reward pool",
+            None,
+        )
+        .unwrap();
+
+    let address_registry_code_id = app.store_code(address_registry_contract());
+    let address_registry_addr = app
+        .instantiate_contract(
+            address_registry_code_id,
+            Addr::unchecked(SOME),
+            &AddressRegistryInstantiateMsg {
+                name: String::from("Address Registry"),
+                owner: OWNER.to_string(),
+            },
+            &[],
+            "address registry",
+            None,
+        )
+        .unwrap();
+
+    let addresses = [
+        (ProtocolContract::BorrowerOperations, BO.to_string()),
+        (ProtocolContract::TroveManager, TM.to_string()),
+        (ProtocolContract::UltraToken, ultra_token.to_string()),
+    ];
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        address_registry_addr.clone(),
+        &AddressRegistryExecuteMsg::SetAddresses {
+            addresses: addresses
+                .into_iter()
+                .map(|(contract, address)| ContractAddress { contract, address })
+                .collect(),
+        },
+        &[],
+    )
+    .unwrap();
+    for asset in [
+        native_asset_info(UJUNO_DENOM.to_string()),
+        token_asset_info(coll_token.clone()),
+    ] {
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            address_registry_addr.clone(),
+            &AddressRegistryExecuteMsg::RegisterCollateral {
+                collateral: Collateral {
+                    asset,
+                    contracts: CollateralContracts {
+                        active_pool: Addr::unchecked(AP),
+                        default_pool: Addr::unchecked(DP),
+                        coll_surplus_pool: Addr::unchecked(CSP),
+                        sorted_troves: Addr::unchecked(SORTED_TROVES),
+                        price_feed: Addr::unchecked(PRICE_FEED),
+                    },
+                    params: CollateralParams {
+                        mcr: Decimal256::percent(110),
+                        debt_ceiling: Uint128::new(1_000_000),
+                    },
+                },
+            },
+            &[],
+        )
+        .unwrap();
+    }
+
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        reward_pool.clone(),
+        &ExecuteMsg::SetAddressRegistry {
+            address_registry: address_registry_addr.to_string(),
+        },
+        &[],
+    )
+    .unwrap();
+    app.execute_contract(
+        Addr::unchecked(OWNER),
+        reward_pool.clone(),
+        &ExecuteMsg::SyncAddresses {},
+        &[],
+    )
+    .unwrap();
+
+    Suite {
+        app,
+        reward_pool,
+        ultra_token,
+        reward_token,
+        coll_token,
+    }
+}
+
+impl Suite {
+    fn stake(&mut self, staker: &str, amount: u128) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(staker),
+                self.reward_token.clone(),
+                &ultra_token::msg::ExecuteMsg::Send {
+                    contract: self.reward_pool.to_string(),
+                    amount: Uint128::new(amount),
+                    msg: to_binary(&Cw20HookMsg::Stake {}).unwrap(),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    /// Mints the borrowing fee to the pool and shares it
+    fn increase_ultra_fee(&mut self, amount: u128) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(BO),
+                self.ultra_token.clone(),
+                &ultra_token::msg::ExecuteMsg::Mint {
+                    recipient: self.reward_pool.to_string(),
+                    amount: Uint128::new(amount),
+                },
+                &[],
+            )
+            .unwrap();
+        self.app
+            .execute_contract(
+                Addr::unchecked(BO),
+                self.reward_pool.clone(),
+                &ExecuteMsg::IncreaseULTRAFee {
+                    ultra_fee: Uint128::new(amount),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    fn increase_juno_fee(&mut self, amount: u128) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(TM),
+                self.reward_pool.clone(),
+                &ExecuteMsg::IncreaseCollateralFee {
+                    collateral: native_asset_info(UJUNO_DENOM.to_string()),
+                },
+                &coins(amount, UJUNO_DENOM),
+            )
+            .unwrap();
+    }
+
+    /// Sends the redemption fee in the cw20 collateral through the hook
+    fn increase_coll_token_fee(&mut self, amount: u128) {
+        self.app
+            .execute_contract(
+                Addr::unchecked(TM),
+                self.coll_token.clone(),
+                &ultra_token::msg::ExecuteMsg::Send {
+                    contract: self.reward_pool.to_string(),
+                    amount: Uint128::new(amount),
+                    msg: to_binary(&Cw20HookMsg::IncreaseCollateralFee {}).unwrap(),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    fn query<T: serde::de::DeserializeOwned>(&self, msg: &QueryMsg) -> T {
+        self.app
+            .wrap()
+            .query_wasm_smart(self.reward_pool.clone(), msg)
+            .unwrap()
+    }
+
+    /// Pending JUNO and ULTRA gains of `staker`
+    fn pending_gains(&self, staker: &str) -> (Uint128, Uint128) {
+        (
+            self.pending_coll_gain(&native_asset_info(UJUNO_DENOM.to_string()), staker),
+            self.query(&QueryMsg::GetPendingULTRAGain {
+                staker: Addr::unchecked(staker),
+            }),
+        )
+    }
+
+    fn pending_coll_gain(&self, collateral: &AssetInfo, staker: &str) -> Uint128 {
+        self.query(&QueryMsg::GetPendingCollGain {
+            collateral: collateral.clone(),
+            staker: Addr::unchecked(staker),
+        })
+    }
+
+    fn token_balance(&self, token: &Addr, address: &str) -> Uint128 {
+        let res: BalanceResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                token.clone(),
+                &ultra_token::msg::QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        res.balance
+    }
+
+    fn juno_balance(&self, address: &str) -> Uint128 {
+        self.app
+            .wrap()
+            .query_balance(address, UJUNO_DENOM)
+            .unwrap()
+            .amount
+    }
+}
+
+#[test]
+fn test_stake() {
+    let mut suite = instantiate_reward_pool();
+
+    // Only the reward token can be staked
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            suite.reward_pool.clone(),
+            &ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: ALICE.to_string(),
+                amount: Uint128::new(100u128),
+                msg: to_binary(&Cw20HookMsg::Stake {}).unwrap(),
+            }),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidRewardToken {});
+
+    suite.stake(ALICE, 100);
+    suite.stake(BOB, 300);
+    suite.stake(ALICE, 100);
+
+    let stake: Uint128 = suite.query(&QueryMsg::GetStake {
+        staker: Addr::unchecked(ALICE),
+    });
+    assert_eq!(stake, Uint128::new(200u128));
+    let total_staked: Uint128 = suite.query(&QueryMsg::GetTotalStaked {});
+    assert_eq!(total_staked, Uint128::new(500u128));
+    assert_eq!(
+        suite.token_balance(&suite.reward_token, ALICE),
+        Uint128::new(800u128)
+    );
+}
+
+#[test]
+fn test_fees_are_shared_by_stake() {
+    let mut suite = instantiate_reward_pool();
+
+    // Fees collected before anything is staked are not shared
+    suite.increase_ultra_fee(1_000);
+
+    suite.stake(ALICE, 100);
+    suite.stake(BOB, 300);
+
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(TM),
+            suite.reward_pool.clone(),
+            &ExecuteMsg::IncreaseULTRAFee {
+                ultra_fee: Uint128::new(400u128),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNotBO {});
+
+    suite.increase_ultra_fee(400);
+    suite.increase_juno_fee(800);
+
+    let fees: FeesPerUnitStaked = suite.query(&QueryMsg::GetFeesPerUnitStaked {});
+    assert_eq!(
+        fees,
+        FeesPerUnitStaked {
+            f_colls: vec![CollateralFee {
+                collateral: native_asset_info(UJUNO_DENOM.to_string()),
+                fee: Decimal256::from_ratio(2u128, 1u128),
+            }],
+            f_ultra: Decimal256::one(),
+        }
+    );
+    assert_eq!(
+        suite.pending_gains(ALICE),
+        (Uint128::new(200u128), Uint128::new(100u128))
+    );
+    assert_eq!(
+        suite.pending_gains(BOB),
+        (Uint128::new(600u128), Uint128::new(300u128))
+    );
+
+    // Staking more pays out the gains of the current stake first
+    suite.stake(ALICE, 100);
+    assert_eq!(suite.juno_balance(ALICE), Uint128::new(200u128));
+    assert_eq!(
+        suite.token_balance(&suite.ultra_token, ALICE),
+        Uint128::new(100u128)
+    );
+    assert_eq!(
+        suite.pending_gains(ALICE),
+        (Uint128::zero(), Uint128::zero())
+    );
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BOB),
+            suite.reward_pool.clone(),
+            &ExecuteMsg::Claim {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.juno_balance(BOB), Uint128::new(600u128));
+    assert_eq!(
+        suite.token_balance(&suite.ultra_token, BOB),
+        Uint128::new(300u128)
+    );
+
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            suite.reward_pool.clone(),
+            &ExecuteMsg::Claim {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NoStake {});
+}
+
+#[test]
+fn test_unstake() {
+    let mut suite = instantiate_reward_pool();
+    suite.stake(ALICE, 100);
+    suite.stake(BOB, 100);
+    suite.increase_juno_fee(100);
+
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(ALICE),
+            suite.reward_pool.clone(),
+            &ExecuteMsg::Unstake {
+                amount: Uint128::new(40u128),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.juno_balance(ALICE), Uint128::new(50u128));
+    assert_eq!(
+        suite.token_balance(&suite.reward_token, ALICE),
+        Uint128::new(940u128)
+    );
+
+    // Unstaking more than the stake withdraws all of it
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BOB),
+            suite.reward_pool.clone(),
+            &ExecuteMsg::Unstake {
+                amount: Uint128::new(1_000u128),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(suite.juno_balance(BOB), Uint128::new(50u128));
+    assert_eq!(
+        suite.token_balance(&suite.reward_token, BOB),
+        Uint128::new(1_000u128)
+    );
+    let total_staked: Uint128 = suite.query(&QueryMsg::GetTotalStaked {});
+    assert_eq!(total_staked, Uint128::new(60u128));
+
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BOB),
+            suite.reward_pool.clone(),
+            &ExecuteMsg::Unstake {
+                amount: Uint128::new(1u128),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::NoStake {});
+}
+
+#[test]
+fn test_collateral_fees() {
+    let mut suite = instantiate_reward_pool();
+    suite.stake(ALICE, 100);
+    suite.stake(BOB, 300);
+
+    // Only trove manager sends redemption fees
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            suite.reward_pool.clone(),
+            &ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: SOME.to_string(),
+                amount: Uint128::new(400u128),
+                msg: to_binary(&Cw20HookMsg::IncreaseCollateralFee {}).unwrap(),
+            }),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::CallerIsNotTM {});
+
+    // A cw20 fee has to come from a registered collateral token
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            suite.reward_pool.clone(),
+            &ExecuteMsg::Receive(Cw20ReceiveMsg {
+                sender: TM.to_string(),
+                amount: Uint128::new(400u128),
+                msg: to_binary(&Cw20HookMsg::IncreaseCollateralFee {}).unwrap(),
+            }),
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert!(matches!(err, ContractError::Std(_)));
+
+    // Native funds must match the collateral type
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(TM),
+            suite.reward_pool.clone(),
+            &ExecuteMsg::IncreaseCollateralFee {
+                collateral: token_asset_info(suite.coll_token.clone()),
+            },
+            &coins(100, UJUNO_DENOM),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert!(matches!(
+        err,
+        ContractError::Pool(PoolError::InvalidFunds { .. })
+    ));
+
+    suite.increase_coll_token_fee(400);
+    suite.increase_juno_fee(800);
+
+    let coll = token_asset_info(suite.coll_token.clone());
+    let fees: FeesPerUnitStaked = suite.query(&QueryMsg::GetFeesPerUnitStaked {});
+    assert_eq!(
+        fees.f_colls,
+        vec![
+            CollateralFee {
+                collateral: coll.clone(),
+                fee: Decimal256::one(),
+            },
+            CollateralFee {
+                collateral: native_asset_info(UJUNO_DENOM.to_string()),
+                fee: Decimal256::from_ratio(2u128, 1u128),
+            },
+        ]
+    );
+    assert_eq!(suite.pending_coll_gain(&coll, ALICE), Uint128::new(100u128));
+    assert_eq!(suite.pending_coll_gain(&coll, BOB), Uint128::new(300u128));
+
+    // Claiming pays out the gain in every collateral type
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(BOB),
+            suite.reward_pool.clone(),
+            &ExecuteMsg::Claim {},
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        suite.token_balance(&suite.coll_token, BOB),
+        Uint128::new(300u128)
+    );
+    assert_eq!(suite.juno_balance(BOB), Uint128::new(600u128));
+    assert_eq!(suite.pending_coll_gain(&coll, BOB), Uint128::zero());
+
+    // Later fees only count from the snapshot taken by the claim
+    suite.increase_coll_token_fee(800);
+    assert_eq!(suite.pending_coll_gain(&coll, ALICE), Uint128::new(300u128));
+    assert_eq!(suite.pending_coll_gain(&coll, BOB), Uint128::new(600u128));
+}
+
+#[test]
+fn test_unregistered_collateral_fee() {
+    let mut suite = instantiate_reward_pool();
+    suite.stake(ALICE, 100);
+
+    suite
+        .app
+        .init_modules(|router, _, storage| {
+            router
+                .bank
+                .init_balance(storage, &Addr::unchecked(TM), coins(100, "uatom"))
+        })
+        .unwrap();
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(TM),
+            suite.reward_pool.clone(),
+            &ExecuteMsg::IncreaseCollateralFee {
+                collateral: native_asset_info("uatom".to_string()),
+            },
+            &coins(100, "uatom"),
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert!(matches!(err, ContractError::Std(_)));
+}
@@ -0,0 +1,816 @@
+#[cfg(not(feature = "library"))]
+use cosmwasm_std::entry_point;
+use cosmwasm_std::{
+    coin, from_binary, to_binary, Addr, Binary, CosmosMsg, Decimal256, Deps, DepsMut, Env,
+    MessageInfo, Order, QuerierWrapper, Reply, Response, StdError, StdResult, Storage, SubMsg,
+    Uint128, Uint256, WasmMsg,
+};
+
+use cw2::set_contract_version;
+use cw20::{Cw20ExecuteMsg, Cw20ReceiveMsg, MinterResponse};
+use cw_utils::parse_reply_instantiate_data;
+use wasmswap::msg::{
+    ExecuteMsg as WasmSwapExecuteMsg, QueryMsg as WasmSwapQueryMsg, Token1ForToken2PriceResponse,
+    Token2ForToken1PriceResponse, TokenSelect,
+};
+
+use crate::error::ContractError;
+use crate::state::{load_collateral_swap, Config, COLLATERAL_SWAPS, CONFIG, SHARE_TOKEN};
+use ultra_base::asset::{token_asset, token_asset_info, Asset, AssetInfo};
+use ultra_base::migration::{migrate_contract, Migration};
+use ultra_base::oracle::QueryMsg as OracleQueryMsg;
+use ultra_base::querier::{
+    query_collateral, query_collaterals, query_pool_info, query_supply, query_token_balance,
+};
+use ultra_base::stability_pool::{
+    Cw20HookMsg as StabilityPoolHookMsg, ExecuteMsg as StabilityPoolExecuteMsg,
+    QueryMsg as StabilityPoolQueryMsg,
+};
+use ultra_base::stability_vault::{
+    convert_to_assets, convert_to_shares, min_output, price_impact, CollateralSwap, ConfigResponse,
+    Cw20HookMsg, ExecuteMsg, InstantiateMsg, MigrateMsg, ParamsResponse, QueryMsg, MINIMUM_SHARES,
+};
+use ultra_base::ultra_math::uint256_to_uint128;
+use ultra_pool::access;
+use ultra_pool::ownership;
+use ultra_pool::state::{SudoParams, SUDO_PARAMS};
+
+// version info for migration info
+const CONTRACT_NAME: &str = "crates.io:stability-vault";
+const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
+
+// State migrations, ordered by the version introducing them
+const MIGRATIONS: &[Migration] = &[];
+
+const INSTANTIATE_SHARE_TOKEN_REPLY_ID: u64 = 1;
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn instantiate(
+    mut deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+    msg: InstantiateMsg,
+) -> Result<Response, ContractError> {
+    set_contract_version(deps.storage, CONTRACT_NAME, CONTRACT_VERSION)?;
+
+    // store sudo params
+    let sudo_params = SudoParams {
+        name: msg.name,
+        owner: deps.api.addr_validate(&msg.owner)?,
+    };
+    SUDO_PARAMS.save(deps.storage, &sudo_params)?;
+
+    validate_swap_limit(msg.max_slippage)?;
+    validate_swap_limit(msg.max_price_impact)?;
+    let config = Config {
+        stability_pool: deps.api.addr_validate(&msg.stability_pool)?,
+        ultra_token: deps.api.addr_validate(&msg.ultra_token)?,
+        address_registry: deps.api.addr_validate(&msg.address_registry)?,
+        reward_token: deps.api.addr_validate(&msg.reward_token)?,
+        reward_swap_pool: deps.api.addr_validate(&msg.reward_swap_pool)?,
+        reward_oracle: deps.api.addr_validate(&msg.reward_oracle)?,
+        front_end: msg
+            .front_end
+            .map(|front_end| deps.api.addr_validate(&front_end))
+            .transpose()?,
+        max_slippage: msg.max_slippage,
+        max_price_impact: msg.max_price_impact,
+    };
+    CONFIG.save(deps.storage, &config)?;
+    for swap in msg.collateral_swaps {
+        save_collateral_swap(deps.branch(), &config, swap)?;
+    }
+
+    // The vault is the only minter of its shares
+    let instantiate_share_token = WasmMsg::Instantiate {
+        admin: Some(sudo_params.owner.to_string()),
+        code_id: msg.share_token.code_id,
+        msg: to_binary(&ultra_token::msg::InstantiateMsg {
+            name: msg.share_token.name,
+            symbol: msg.share_token.symbol,
+            decimals: msg.share_token.decimals,
+            initial_balances: vec![],
+            mint: Some(MinterResponse {
+                minter: env.contract.address.to_string(),
+                cap: None,
+            }),
+            marketing: None,
+        })?,
+        funds: vec![],
+        label: String::from("stability_vault_share_token"),
+    };
+
+    let res = Response::new()
+        .add_attribute("action", "instantiate")
+        .add_submessage(SubMsg::reply_on_success(
+            instantiate_share_token,
+            INSTANTIATE_SHARE_TOKEN_REPLY_ID,
+        ));
+    Ok(res)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    Ok(migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+    )?)
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn reply(deps: DepsMut, _env: Env, msg: Reply) -> Result<Response, ContractError> {
+    if msg.id != INSTANTIATE_SHARE_TOKEN_REPLY_ID {
+        return Err(ContractError::UnknownReplyId { id: msg.id });
+    }
+    let share_token = deps
+        .api
+        .addr_validate(&parse_reply_instantiate_data(msg)?.contract_address)?;
+    SHARE_TOKEN.save(deps.storage, &share_token)?;
+
+    Ok(Response::new().add_attribute("share_token", share_token))
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: ExecuteMsg,
+) -> Result<Response, ContractError> {
+    match msg {
+        ExecuteMsg::Receive(msg) => execute_receive(deps, env, info, msg),
+        ExecuteMsg::Harvest {} => execute_harvest(deps, env, info),
+        ExecuteMsg::Compound {} => execute_compound(deps, env, info),
+        ExecuteMsg::UpdateConfig {
+            max_slippage,
+            max_price_impact,
+        } => execute_update_config(deps, env, info, max_slippage, max_price_impact),
+        ExecuteMsg::SetCollateralSwap { swap } => {
+            execute_set_collateral_swap(deps, env, info, swap)
+        }
+        ExecuteMsg::ProposeOwner { owner } => execute_propose_owner(deps, env, info, owner),
+        ExecuteMsg::AcceptOwner {} => execute_accept_owner(deps, env, info),
+        ExecuteMsg::RenounceOwnership {} => execute_renounce_ownership(deps, env, info),
+    }
+}
+
+pub fn execute_receive(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    cw20_msg: Cw20ReceiveMsg,
+) -> Result<Response, ContractError> {
+    if cw20_msg.amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+    let sender = deps.api.addr_validate(&cw20_msg.sender)?;
+    match from_binary(&cw20_msg.msg)? {
+        Cw20HookMsg::Deposit {} => {
+            if info.sender != CONFIG.load(deps.storage)?.ultra_token {
+                return Err(ContractError::InvalidToken {});
+            }
+            execute_deposit(deps, env, sender, cw20_msg.amount)
+        }
+        Cw20HookMsg::Redeem {} => {
+            if info.sender != SHARE_TOKEN.load(deps.storage)? {
+                return Err(ContractError::InvalidToken {});
+            }
+            execute_redeem(deps, env, sender, cw20_msg.amount)
+        }
+    }
+}
+
+/// Mints shares for the received ULTRA and adds it to the deposit of the vault
+pub fn execute_deposit(
+    deps: DepsMut,
+    env: Env,
+    depositor: Addr,
+    amount: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let share_token = SHARE_TOKEN.load(deps.storage)?;
+
+    // The received ULTRA is already in the balance of the vault
+    let total_assets = total_assets(deps.as_ref(), &env, &config)?
+        .checked_sub(amount)
+        .map_err(StdError::overflow)?;
+    let total_shares = query_supply(&deps.querier, &share_token)?;
+    let mut shares = convert_to_shares(amount, total_assets, total_shares)?;
+
+    let mut res = Response::new();
+    if total_shares.is_zero() {
+        // The minimum shares are locked in the vault, which never redeems them
+        shares = shares.checked_sub(MINIMUM_SHARES).map_err(|_| {
+            ContractError::FirstDepositTooSmall {
+                minimum: MINIMUM_SHARES,
+            }
+        })?;
+        res = res.add_message(mint_shares_msg(
+            &share_token,
+            &env.contract.address,
+            MINIMUM_SHARES,
+        )?);
+    }
+    if shares.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    let res = res
+        .add_attribute("action", "deposit")
+        .add_attribute("depositor", depositor.to_string())
+        .add_attribute("assets", amount)
+        .add_attribute("shares", shares)
+        .add_message(mint_shares_msg(&share_token, &depositor, shares)?)
+        .add_message(provide_to_sp_msg(&config, amount)?);
+    Ok(res)
+}
+
+/// Burns the received shares and sends their part of the ULTRA of the vault, withdrawn from its
+/// deposit, and of its collateral and reward token gains
+pub fn execute_redeem(
+    deps: DepsMut,
+    env: Env,
+    redeemer: Addr,
+    shares: Uint128,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+    let share_token = SHARE_TOKEN.load(deps.storage)?;
+
+    // The gains are paid as they are rather than at their TWAP value, which the ULTRA of the
+    // vault may not cover
+    let holdings = query_holdings(deps.as_ref(), &env, &config)?;
+    let total_shares = query_supply(&deps.querier, &share_token)?;
+    let assets = convert_to_assets(shares, holdings.ultra()?, total_shares)?;
+    let mut coll_amounts = vec![];
+    // Withdrawing also claims the pending gains
+    let mut claim_gains = false;
+    for coll in &holdings.colls {
+        let amount = convert_to_assets(shares, coll.amount()?, total_shares)?;
+        if !amount.is_zero() {
+            claim_gains |= amount > coll.balance;
+            coll_amounts.push(Asset {
+                info: coll.collateral.clone(),
+                amount,
+            });
+        }
+    }
+    let reward_amount = convert_to_assets(shares, holdings.reward()?, total_shares)?;
+    claim_gains |= reward_amount > holdings.reward_balance;
+    if assets.is_zero() && coll_amounts.is_empty() && reward_amount.is_zero() {
+        return Err(ContractError::ZeroAmount {});
+    }
+
+    // ULTRA held by the vault is paid out before the deposit is withdrawn
+    let withdrawn = assets.saturating_sub(holdings.idle);
+
+    let mut res = Response::new()
+        .add_attribute("action", "redeem")
+        .add_attribute("redeemer", redeemer.to_string())
+        .add_attribute("shares", shares)
+        .add_attribute("assets", assets)
+        .add_attribute("coll_amounts", assets_attribute(&coll_amounts))
+        .add_attribute("reward_amount", reward_amount)
+        .add_message(WasmMsg::Execute {
+            contract_addr: share_token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Burn { amount: shares })?,
+            funds: vec![],
+        });
+    if !withdrawn.is_zero() || claim_gains {
+        res = res.add_message(WasmMsg::Execute {
+            contract_addr: config.stability_pool.to_string(),
+            msg: to_binary(&StabilityPoolExecuteMsg::WithdrawFromSP { amount: withdrawn })?,
+            funds: vec![],
+        });
+    }
+    if !assets.is_zero() {
+        res = res.add_message(
+            token_asset(config.ultra_token, assets).into_msg(&deps.querier, redeemer.clone())?,
+        );
+    }
+    for coll_amount in coll_amounts {
+        res = res.add_message(coll_amount.into_msg(&deps.querier, redeemer.clone())?);
+    }
+    if !reward_amount.is_zero() {
+        res = res.add_message(
+            token_asset(config.reward_token, reward_amount).into_msg(&deps.querier, redeemer)?,
+        );
+    }
+    Ok(res)
+}
+
+/// Claims the collateral and reward token gains of the vault and swaps them to ULTRA, which is
+/// then compounded. Fails if a collateral gain has no route or if a swap moves the pool price by
+/// more than the maximum price impact, and the swaps fail if they return less than the TWAP value
+/// of the gains minus the maximum slippage
+pub fn execute_harvest(
+    deps: DepsMut,
+    env: Env,
+    _info: MessageInfo,
+) -> Result<Response, ContractError> {
+    let config = CONFIG.load(deps.storage)?;
+
+    // Gains paid out by past deposits and withdrawals are swapped along the pending gains
+    let holdings = query_holdings(deps.as_ref(), &env, &config)?;
+    let mut coll_gains = vec![];
+    for coll in &holdings.colls {
+        let amount = coll.amount()?;
+        if !amount.is_zero() {
+            coll_gains.push(Asset {
+                info: coll.collateral.clone(),
+                amount,
+            });
+        }
+    }
+    let reward_amount = holdings.reward()?;
+    if coll_gains.is_empty() && reward_amount.is_zero() {
+        return Err(ContractError::NothingToHarvest {});
+    }
+
+    let mut res = Response::new().add_attribute("action", "harvest");
+    if holdings.colls.iter().any(|coll| !coll.pending.is_zero())
+        || !holdings.pending_reward.is_zero()
+    {
+        res = res.add_message(WasmMsg::Execute {
+            contract_addr: config.stability_pool.to_string(),
+            msg: to_binary(&StabilityPoolExecuteMsg::WithdrawFromSP {
+                amount: Uint128::zero(),
+            })?,
+            funds: vec![],
+        });
+    }
+    for coll_gain in coll_gains {
+        let swap = load_collateral_swap(deps.storage, &coll_gain.info)?;
+        res = add_swap_to_ultra(
+            deps.as_ref(),
+            &config,
+            res,
+            coll_gain,
+            &swap.swap_pool,
+            &swap.oracle,
+        )?;
+    }
+    if !reward_amount.is_zero() {
+        res = add_swap_to_ultra(
+            deps.as_ref(),
+            &config,
+            res,
+            token_asset(config.reward_token.clone(), reward_amount),
+            &config.reward_swap_pool,
+            &config.reward_oracle,
+        )?;
+    }
+    let res = res.add_message(WasmMsg::Execute {
+        contract_addr: env.contract.address.to_string(),
+        msg: to_binary(&ExecuteMsg::Compound {})?,
+        funds: vec![],
+    });
+    Ok(res)
+}
+
+/// Adds the swap of `input` to ULTRA through `pool` to `res`, floored at the value of `input` at
+/// the TWAP price of `oracle`
+fn add_swap_to_ultra(
+    deps: Deps,
+    config: &Config,
+    res: Response,
+    input: Asset,
+    pool: &Addr,
+    oracle: &Addr,
+) -> Result<Response, ContractError> {
+    let pool_info = query_pool_info(&deps.querier, pool.clone())?;
+    let ultra = token_asset_info(config.ultra_token.clone());
+    let (input_token, reserve_in, reserve_out, expected_output) =
+        if pool_info.token1_denom == input.info && pool_info.token2_denom == ultra {
+            let res: Token1ForToken2PriceResponse = deps.querier.query_wasm_smart(
+                pool,
+                &WasmSwapQueryMsg::Token1ForToken2Price {
+                    token1_amount: input.amount,
+                },
+            )?;
+            (
+                TokenSelect::Token1,
+                pool_info.token1_reserve,
+                pool_info.token2_reserve,
+                res.token2_amount,
+            )
+        } else if pool_info.token1_denom == ultra && pool_info.token2_denom == input.info {
+            let res: Token2ForToken1PriceResponse = deps.querier.query_wasm_smart(
+                pool,
+                &WasmSwapQueryMsg::Token2ForToken1Price {
+                    token2_amount: input.amount,
+                },
+            )?;
+            (
+                TokenSelect::Token2,
+                pool_info.token2_reserve,
+                pool_info.token1_reserve,
+                res.token1_amount,
+            )
+        } else {
+            return Err(ContractError::InvalidSwapPool {});
+        };
+
+    let price_impact = price_impact(input.amount, expected_output, reserve_in, reserve_out)?;
+    if price_impact > config.max_price_impact {
+        return Err(ContractError::PriceImpactTooHigh {
+            price_impact: price_impact.to_string(),
+            max_price_impact: config.max_price_impact.to_string(),
+        });
+    }
+    // The quote moves with the spot price, which can be pushed within the block, unlike the
+    // TWAP price
+    let twap_output = query_twap_value(&deps.querier, oracle, input.info.clone(), input.amount)?;
+    let min_output = min_output(twap_output, config.max_slippage)?;
+
+    let swap = to_binary(&WasmSwapExecuteMsg::Swap {
+        input_token,
+        input_amount: input.amount,
+        min_output,
+        expiration: None,
+    })?;
+    let res = res
+        .add_attribute("input", input.to_string())
+        .add_attribute("expected_ultra_amount", expected_output)
+        .add_attribute("twap_ultra_amount", twap_output)
+        .add_attribute("min_ultra_amount", min_output);
+    let res = match input.info {
+        // The pool pulls cw20 inputs from the allowance of the vault
+        AssetInfo::Cw20Token { contract_addr } => res
+            .add_message(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::IncreaseAllowance {
+                    spender: pool.to_string(),
+                    amount: input.amount,
+                    expires: None,
+                })?,
+                funds: vec![],
+            })
+            .add_message(WasmMsg::Execute {
+                contract_addr: pool.to_string(),
+                msg: swap,
+                funds: vec![],
+            }),
+        AssetInfo::NativeToken { denom } => res.add_message(WasmMsg::Execute {
+            contract_addr: pool.to_string(),
+            msg: swap,
+            funds: vec![coin(input.amount.u128(), denom)],
+        }),
+    };
+    Ok(res)
+}
+
+/// Deposits the ULTRA held by the vault, bought by the swap of a harvest
+pub fn execute_compound(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    if info.sender != env.contract.address {
+        return Err(ContractError::Unauthorized {});
+    }
+    let config = CONFIG.load(deps.storage)?;
+
+    let amount = query_token_balance(&deps.querier, &config.ultra_token, &env.contract.address)?;
+    let mut res = Response::new()
+        .add_attribute("action", "compound")
+        .add_attribute("amount", amount);
+    if !amount.is_zero() {
+        res = res.add_message(provide_to_sp_msg(&config, amount)?);
+    }
+    Ok(res)
+}
+
+pub fn execute_update_config(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    max_slippage: Option<Decimal256>,
+    max_price_impact: Option<Decimal256>,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    let mut config = CONFIG.load(deps.storage)?;
+    if let Some(max_slippage) = max_slippage {
+        validate_swap_limit(max_slippage)?;
+        config.max_slippage = max_slippage;
+    }
+    if let Some(max_price_impact) = max_price_impact {
+        validate_swap_limit(max_price_impact)?;
+        config.max_price_impact = max_price_impact;
+    }
+    CONFIG.save(deps.storage, &config)?;
+
+    let res = Response::new()
+        .add_attribute("action", "update_config")
+        .add_attribute("max_slippage", config.max_slippage.to_string())
+        .add_attribute("max_price_impact", config.max_price_impact.to_string());
+    Ok(res)
+}
+
+pub fn execute_set_collateral_swap(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    swap: CollateralSwap,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    let config = CONFIG.load(deps.storage)?;
+    let swap = save_collateral_swap(deps, &config, swap)?;
+
+    let res = Response::new()
+        .add_attribute("action", "set_collateral_swap")
+        .add_attribute("collateral", swap.collateral.to_string())
+        .add_attribute("swap_pool", swap.swap_pool)
+        .add_attribute("oracle", swap.oracle);
+    Ok(res)
+}
+
+/// Validates and saves the route of a collateral type registered in the address registry
+fn save_collateral_swap(
+    deps: DepsMut,
+    config: &Config,
+    swap: CollateralSwap,
+) -> Result<CollateralSwap, ContractError> {
+    query_collateral(&deps.querier, &config.address_registry, &swap.collateral)?;
+    let swap = CollateralSwap {
+        collateral: swap.collateral,
+        swap_pool: deps.api.addr_validate(swap.swap_pool.as_str())?,
+        oracle: deps.api.addr_validate(swap.oracle.as_str())?,
+    };
+    COLLATERAL_SWAPS.save(deps.storage, &swap.collateral.to_string(), &swap)?;
+    Ok(swap)
+}
+
+pub fn execute_propose_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    owner: String,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    let owner = deps.api.addr_validate(&owner)?;
+    Ok(ownership::propose_owner(deps.storage, owner)?)
+}
+
+pub fn execute_accept_owner(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    Ok(ownership::accept_owner(deps.storage, &info)?)
+}
+
+pub fn execute_renounce_ownership(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+) -> Result<Response, ContractError> {
+    only_owner(deps.storage, &info)?;
+
+    Ok(ownership::renounce_ownership(deps.storage)?)
+}
+
+fn mint_shares_msg(share_token: &Addr, recipient: &Addr, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: share_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Mint {
+            recipient: recipient.to_string(),
+            amount,
+        })?,
+        funds: vec![],
+    }))
+}
+
+/// Sends `amount` ULTRA held by the vault to its stability pool deposit
+fn provide_to_sp_msg(config: &Config, amount: Uint128) -> StdResult<CosmosMsg> {
+    Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+        contract_addr: config.ultra_token.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Send {
+            contract: config.stability_pool.to_string(),
+            amount,
+            msg: to_binary(&StabilityPoolHookMsg::ProvideToSP {
+                front_end: config.front_end.as_ref().map(Addr::to_string),
+            })?,
+        })?,
+        funds: vec![],
+    }))
+}
+
+fn assets_attribute(assets: &[Asset]) -> String {
+    assets
+        .iter()
+        .map(|asset| asset.to_string())
+        .collect::<Vec<_>>()
+        .join(",")
+}
+
+/// Gain of the vault in a collateral type
+struct CollateralHolding {
+    collateral: AssetInfo,
+    /// Collateral held by the vault
+    balance: Uint128,
+    /// Collateral gain of the deposit, not claimed yet
+    pending: Uint128,
+}
+
+impl CollateralHolding {
+    fn amount(&self) -> StdResult<Uint128> {
+        Ok(self.balance.checked_add(self.pending)?)
+    }
+}
+
+/// Assets of the vault, in the stability pool or held by the vault
+struct Holdings {
+    /// Compounded deposit of the vault
+    deposit: Uint128,
+    /// ULTRA held by the vault
+    idle: Uint128,
+    /// Gains in each collateral type registered in the address registry
+    colls: Vec<CollateralHolding>,
+    /// Reward tokens held by the vault
+    reward_balance: Uint128,
+    /// Reward token gain of the deposit, not claimed yet
+    pending_reward: Uint128,
+}
+
+impl Holdings {
+    fn ultra(&self) -> StdResult<Uint128> {
+        Ok(self.deposit.checked_add(self.idle)?)
+    }
+
+    fn reward(&self) -> StdResult<Uint128> {
+        Ok(self.reward_balance.checked_add(self.pending_reward)?)
+    }
+}
+
+fn query_holdings(deps: Deps, env: &Env, config: &Config) -> StdResult<Holdings> {
+    let depositor = env.contract.address.to_string();
+    let mut colls = vec![];
+    for collateral in query_collaterals(&deps.querier, &config.address_registry)? {
+        colls.push(CollateralHolding {
+            balance: collateral
+                .asset
+                .query_pool(&deps.querier, &env.contract.address)?,
+            pending: deps.querier.query_wasm_smart(
+                &config.stability_pool,
+                &StabilityPoolQueryMsg::GetDepositorCollGain {
+                    collateral: collateral.asset.clone(),
+                    depositor: depositor.clone(),
+                },
+            )?,
+            collateral: collateral.asset,
+        });
+    }
+    Ok(Holdings {
+        deposit: deps.querier.query_wasm_smart(
+            &config.stability_pool,
+            &StabilityPoolQueryMsg::GetCompoundedUltraDeposit {
+                depositor: depositor.clone(),
+            },
+        )?,
+        idle: query_token_balance(&deps.querier, &config.ultra_token, &env.contract.address)?,
+        colls,
+        reward_balance: query_token_balance(
+            &deps.querier,
+            &config.reward_token,
+            &env.contract.address,
+        )?,
+        pending_reward: deps.querier.query_wasm_smart(
+            &config.stability_pool,
+            &StabilityPoolQueryMsg::GetDepositorRewardGain { depositor },
+        )?,
+    })
+}
+
+/// Value in ULTRA of the assets of the vault, its gains valued at their TWAP price so that they
+/// are not captured by the deposits made before a harvest. Fails while a collateral gain has no
+/// route, rather than leaving it out
+fn total_assets(deps: Deps, env: &Env, config: &Config) -> StdResult<Uint128> {
+    let holdings = query_holdings(deps, env, config)?;
+    let mut total_assets = holdings.ultra()?;
+    for coll in &holdings.colls {
+        let amount = coll.amount()?;
+        if amount.is_zero() {
+            continue;
+        }
+        let swap = load_collateral_swap(deps.storage, &coll.collateral)?;
+        let coll_value =
+            query_twap_value(&deps.querier, &swap.oracle, coll.collateral.clone(), amount)?;
+        total_assets = total_assets.checked_add(coll_value)?;
+    }
+    let reward_value = query_twap_value(
+        &deps.querier,
+        &config.reward_oracle,
+        token_asset_info(config.reward_token.clone()),
+        holdings.reward()?,
+    )?;
+    Ok(total_assets.checked_add(reward_value)?)
+}
+
+/// Value in ULTRA of `amount` of `token` at the TWAP price of `oracle`.
+/// Fails if the oracle holds no price yet, which would value the tokens at zero
+fn query_twap_value(
+    querier: &QuerierWrapper,
+    oracle: &Addr,
+    token: AssetInfo,
+    amount: Uint128,
+) -> StdResult<Uint128> {
+    if amount.is_zero() {
+        return Ok(Uint128::zero());
+    }
+    let value: Uint256 = querier.query_wasm_smart(
+        oracle,
+        &OracleQueryMsg::Consult {
+            token: token.clone(),
+            amount,
+        },
+    )?;
+    if value.is_zero() {
+        return Err(StdError::generic_err(format!(
+            "No TWAP price of {} yet",
+            token
+        )));
+    }
+    uint256_to_uint128(value)
+}
+
+fn validate_swap_limit(limit: Decimal256) -> Result<(), ContractError> {
+    if limit > Decimal256::one() {
+        return Err(ContractError::InvalidSwapLimit {});
+    }
+    Ok(())
+}
+
+/// Checks to enfore only owner can call
+fn only_owner(store: &dyn Storage, info: &MessageInfo) -> Result<Addr, ContractError> {
+    access::only_owner(store, info, ContractError::UnauthorizedOwner {})
+}
+
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn query(deps: Deps, env: Env, msg: QueryMsg) -> StdResult<Binary> {
+    match msg {
+        QueryMsg::GetParams {} => to_binary(&query_params(deps)?),
+        QueryMsg::GetOwnership {} => to_binary(&ownership::query_ownership(deps.storage)?),
+        QueryMsg::GetConfig {} => to_binary(&query_config(deps)?),
+        QueryMsg::GetShareTokenAddress {} => to_binary(&SHARE_TOKEN.load(deps.storage)?),
+        QueryMsg::ListCollateralSwaps {} => to_binary(&query_collateral_swaps(deps)?),
+        QueryMsg::GetTotalAssets {} => {
+            to_binary(&total_assets(deps, &env, &CONFIG.load(deps.storage)?)?)
+        }
+        QueryMsg::ConvertToShares { assets } => {
+            let (total_assets, total_shares) = totals(deps, &env)?;
+            let shares = convert_to_shares(assets, total_assets, total_shares)?;
+            if total_shares.is_zero() {
+                return to_binary(&shares.saturating_sub(MINIMUM_SHARES));
+            }
+            to_binary(&shares)
+        }
+        QueryMsg::ConvertToAssets { shares } => {
+            let (total_assets, total_shares) = totals(deps, &env)?;
+            to_binary(&convert_to_assets(shares, total_assets, total_shares)?)
+        }
+    }
+}
+
+/// Total assets and shares of the vault
+fn totals(deps: Deps, env: &Env) -> StdResult<(Uint128, Uint128)> {
+    let config = CONFIG.load(deps.storage)?;
+    let total_shares = query_supply(&deps.querier, SHARE_TOKEN.load(deps.storage)?)?;
+    Ok((total_assets(deps, env, &config)?, total_shares))
+}
+
+pub fn query_params(deps: Deps) -> StdResult<ParamsResponse> {
+    let info = SUDO_PARAMS.load(deps.storage)?;
+    let res = ParamsResponse {
+        name: info.name,
+        owner: info.owner,
+    };
+    Ok(res)
+}
+
+pub fn query_config(deps: Deps) -> StdResult<ConfigResponse> {
+    let config = CONFIG.load(deps.storage)?;
+    let res = ConfigResponse {
+        stability_pool: config.stability_pool,
+        ultra_token: config.ultra_token,
+        address_registry: config.address_registry,
+        reward_token: config.reward_token,
+        reward_swap_pool: config.reward_swap_pool,
+        reward_oracle: config.reward_oracle,
+        front_end: config.front_end,
+        max_slippage: config.max_slippage,
+        max_price_impact: config.max_price_impact,
+    };
+    Ok(res)
+}
+
+pub fn query_collateral_swaps(deps: Deps) -> StdResult<Vec<CollateralSwap>> {
+    COLLATERAL_SWAPS
+        .range(deps.storage, None, None, Order::Ascending)
+        .map(|item| item.map(|(_, swap)| swap))
+        .collect()
+}
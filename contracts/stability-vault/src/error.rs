@@ -0,0 +1,53 @@
+use cosmwasm_std::{StdError, Uint128};
+use cw_utils::ParseReplyError;
+use thiserror::Error;
+use ultra_base::migration::MigrationError;
+use ultra_pool::PoolError;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum ContractError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    Pool(#[from] PoolError),
+
+    #[error("{0}")]
+    Migration(#[from] MigrationError),
+
+    #[error("{0}")]
+    ParseReply(#[from] ParseReplyError),
+
+    #[error("Unauthorized")]
+    Unauthorized {},
+
+    #[error("UnauthorizedOwner")]
+    UnauthorizedOwner {},
+
+    #[error("StabilityVault: Unknown reply id {id}")]
+    UnknownReplyId { id: u64 },
+
+    #[error("StabilityVault: Only ULTRA can be deposited and only shares can be redeemed")]
+    InvalidToken {},
+
+    #[error("StabilityVault: Amount must be non-zero")]
+    ZeroAmount {},
+
+    #[error("StabilityVault: The first deposit must exceed the {minimum} locked shares")]
+    FirstDepositTooSmall { minimum: Uint128 },
+
+    #[error("StabilityVault: There are no gains to harvest")]
+    NothingToHarvest {},
+
+    #[error("StabilityVault: Price impact {price_impact} exceeds the maximum {max_price_impact}")]
+    PriceImpactTooHigh {
+        price_impact: String,
+        max_price_impact: String,
+    },
+
+    #[error("StabilityVault: Swap pools must trade the gains against ULTRA")]
+    InvalidSwapPool {},
+
+    #[error("StabilityVault: Slippage and price impact limits must be in range [0,1]")]
+    InvalidSwapLimit {},
+}
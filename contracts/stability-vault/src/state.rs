@@ -0,0 +1,36 @@
+use cosmwasm_std::{Addr, Decimal256, StdError, StdResult, Storage};
+use cw_storage_plus::{Item, Map};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ultra_base::asset::AssetInfo;
+use ultra_base::stability_vault::CollateralSwap;
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct Config {
+    pub stability_pool: Addr,
+    pub ultra_token: Addr,
+    pub address_registry: Addr,
+    pub reward_token: Addr,
+    pub reward_swap_pool: Addr,
+    pub reward_oracle: Addr,
+    pub front_end: Option<Addr>,
+    pub max_slippage: Decimal256,
+    pub max_price_impact: Decimal256,
+}
+
+pub const CONFIG: Item<Config> = Item::new("config");
+pub const SHARE_TOKEN: Item<Addr> = Item::new("share_token");
+
+/// Routes the collateral gains are sold through, keyed by collateral
+pub const COLLATERAL_SWAPS: Map<&str, CollateralSwap> = Map::new("collateral_swaps");
+
+/// Loads the route of `collateral`, fails if the owner hasn't set one
+pub fn load_collateral_swap(
+    store: &dyn Storage,
+    collateral: &AssetInfo,
+) -> StdResult<CollateralSwap> {
+    let key = collateral.to_string();
+    COLLATERAL_SWAPS
+        .may_load(store, &key)?
+        .ok_or_else(|| StdError::not_found(format!("swap of collateral {}", key)))
+}
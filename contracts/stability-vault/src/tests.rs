@@ -0,0 +1,1164 @@
+use crate::ContractError;
+
+use ultra_base::address_registry::{
+    ExecuteMsg as AddressRegistryExecuteMsg, InstantiateMsg as AddressRegistryInstantiateMsg,
+};
+use ultra_base::asset::{native_asset_info, AssetInfo, UJUNO_DENOM};
+use ultra_base::collateral::{Collateral, CollateralContracts, CollateralParams};
+use ultra_base::oracle::{ExecuteMsg as OracleExecuteMsg, QueryMsg as OracleQueryMsg};
+use ultra_base::stability_pool::{
+    Cw20HookMsg as StabilityPoolHookMsg, ExecuteMsg as StabilityPoolExecuteMsg,
+    QueryMsg as StabilityPoolQueryMsg,
+};
+use ultra_base::stability_vault::{
+    CollateralSwap, Cw20HookMsg, ExecuteMsg, InstantiateMsg, QueryMsg, ShareTokenParams,
+    MINIMUM_SHARES,
+};
+
+use cosmwasm_std::{
+    coin, coins, from_binary, to_binary, Addr, BankMsg, Binary, Coin, Decimal256, Deps, DepsMut,
+    Empty, Env, MessageInfo, Order, Response, StdError, StdResult, Uint128, Uint256, WasmMsg,
+};
+use cw20::{BalanceResponse, Cw20Coin, Cw20ExecuteMsg, Denom, MinterResponse};
+use cw_multi_test::{App, Contract, ContractWrapper, Executor};
+use cw_storage_plus::{Item, Map};
+use serde::{Deserialize, Serialize};
+use wasmswap::msg::{
+    ExecuteMsg as WasmSwapExecuteMsg, InfoResponse, QueryMsg as WasmSwapQueryMsg,
+    Token1ForToken2PriceResponse, TokenSelect,
+};
+
+const SOME: &str = "someone";
+const OWNER: &str = "owner";
+const USER: &str = "user";
+const LIQUIDATOR: &str = "liquidator";
+/// Second collateral type of the protocol
+const UATOM: &str = "uatom";
+
+const JUNO_RESERVE: u128 = 10_000;
+const ULTRA_RESERVE: u128 = 20_000;
+/// TWAP price of JUNO in ULTRA, the price of the initial reserves
+const JUNO_PRICE: u128 = ULTRA_RESERVE / JUNO_RESERVE;
+const REWARD_RESERVE: u128 = 50_000;
+const REWARD_ULTRA_RESERVE: u128 = 150_000;
+/// TWAP price of the reward token in ULTRA, the price of the initial reserves
+const REWARD_PRICE: u128 = REWARD_ULTRA_RESERVE / REWARD_RESERVE;
+const ATOM_RESERVE: u128 = 10_000;
+const ATOM_ULTRA_RESERVE: u128 = 50_000;
+/// TWAP price of ATOM in ULTRA, the price of the initial reserves
+const ATOM_PRICE: u128 = ATOM_ULTRA_RESERVE / ATOM_RESERVE;
+
+/// Deposit of the mock stability pool
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq)]
+struct MockDeposit {
+    deposit: Uint128,
+    /// Gains in each native collateral type
+    coll_gains: Vec<Coin>,
+    reward_gain: Uint128,
+}
+
+/// Tokens of the mocks: the ULTRA token and either the reward token of the stability pool, or
+/// the token traded against ULTRA by the swap pool
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq)]
+struct MockInstantiateMsg {
+    ultra: Addr,
+    token: Denom,
+    /// Reserves of the token and of ULTRA of the swap pool
+    reserves: (Uint128, Uint128),
+}
+
+const MOCK_DEPOSITS: Map<&Addr, MockDeposit> = Map::new("deposits");
+const MOCK_ULTRA: Item<Addr> = Item::new("ultra");
+const MOCK_TOKEN: Item<Denom> = Item::new("token");
+const MOCK_RESERVES: Item<(Uint128, Uint128)> = Item::new("reserves");
+/// Price of the mock oracle
+const MOCK_PRICE: Item<Uint128> = Item::new("price");
+
+fn mock_instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    msg: MockInstantiateMsg,
+) -> StdResult<Response> {
+    MOCK_ULTRA.save(deps.storage, &msg.ultra)?;
+    MOCK_TOKEN.save(deps.storage, &msg.token)?;
+    MOCK_RESERVES.save(deps.storage, &msg.reserves)?;
+    Ok(Response::default())
+}
+
+/// Stability pool keeping deposits at their face value, and sharing the native collateral sent
+/// with an `Offset` and the reward tokens sent to it between the depositors
+fn mock_sp_execute(
+    deps: DepsMut,
+    _env: Env,
+    info: MessageInfo,
+    msg: StabilityPoolExecuteMsg,
+) -> StdResult<Response> {
+    match msg {
+        StabilityPoolExecuteMsg::Receive(cw20_msg)
+            if info.sender == MOCK_ULTRA.load(deps.storage)? =>
+        {
+            let StabilityPoolHookMsg::ProvideToSP { .. } =
+                from_binary::<StabilityPoolHookMsg>(&cw20_msg.msg)?;
+            let depositor = Addr::unchecked(cw20_msg.sender);
+            let deposit = MOCK_DEPOSITS
+                .may_load(deps.storage, &depositor)?
+                .unwrap_or_default();
+            MOCK_DEPOSITS.save(
+                deps.storage,
+                &depositor,
+                &MockDeposit {
+                    deposit: deposit.deposit + cw20_msg.amount,
+                    ..MockDeposit::default()
+                },
+            )?;
+            pay_gains(deps.as_ref(), Response::new(), &depositor, &deposit)
+        }
+        // Issuance of reward tokens
+        StabilityPoolExecuteMsg::Receive(cw20_msg) => {
+            assert_eq!(Denom::Cw20(info.sender), MOCK_TOKEN.load(deps.storage)?);
+            share_gains(deps, None, cw20_msg.amount)?;
+            Ok(Response::default())
+        }
+        StabilityPoolExecuteMsg::WithdrawFromSP { amount } => {
+            let deposit = MOCK_DEPOSITS.load(deps.storage, &info.sender)?;
+            let withdrawn = std::cmp::min(amount, deposit.deposit);
+            MOCK_DEPOSITS.save(
+                deps.storage,
+                &info.sender,
+                &MockDeposit {
+                    deposit: deposit.deposit - withdrawn,
+                    ..MockDeposit::default()
+                },
+            )?;
+            let mut res = pay_gains(deps.as_ref(), Response::new(), &info.sender, &deposit)?;
+            if !withdrawn.is_zero() {
+                res = res.add_message(WasmMsg::Execute {
+                    contract_addr: MOCK_ULTRA.load(deps.storage)?.to_string(),
+                    msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                        recipient: info.sender.to_string(),
+                        amount: withdrawn,
+                    })?,
+                    funds: vec![],
+                });
+            }
+            Ok(res)
+        }
+        StabilityPoolExecuteMsg::Offset {
+            collateral: AssetInfo::NativeToken { denom },
+        } => {
+            let amount = cw_utils::must_pay(&info, &denom)
+                .map_err(|err| StdError::generic_err(err.to_string()))?;
+            share_gains(deps, Some(coin(amount.u128(), denom)), Uint128::zero())?;
+            Ok(Response::default())
+        }
+        _ => Err(StdError::generic_err("not mocked")),
+    }
+}
+
+fn share_gains(deps: DepsMut, coll: Option<Coin>, reward: Uint128) -> StdResult<()> {
+    let deposits = MOCK_DEPOSITS
+        .range(deps.storage, None, None, Order::Ascending)
+        .collect::<StdResult<Vec<_>>>()?;
+    let total: Uint128 = deposits.iter().map(|(_, deposit)| deposit.deposit).sum();
+    for (depositor, mut deposit) in deposits {
+        if let Some(coll) = &coll {
+            let gain = coll.amount.multiply_ratio(deposit.deposit, total);
+            match deposit
+                .coll_gains
+                .iter_mut()
+                .find(|coll_gain| coll_gain.denom == coll.denom)
+            {
+                Some(coll_gain) => coll_gain.amount += gain,
+                None => deposit.coll_gains.push(coin(gain.u128(), &coll.denom)),
+            }
+        }
+        deposit.reward_gain += reward.multiply_ratio(deposit.deposit, total);
+        MOCK_DEPOSITS.save(deps.storage, &depositor, &deposit)?;
+    }
+    Ok(())
+}
+
+fn pay_gains(
+    deps: Deps,
+    mut res: Response,
+    depositor: &Addr,
+    deposit: &MockDeposit,
+) -> StdResult<Response> {
+    let coll_gains: Vec<Coin> = deposit
+        .coll_gains
+        .iter()
+        .filter(|coll_gain| !coll_gain.amount.is_zero())
+        .cloned()
+        .collect();
+    if !coll_gains.is_empty() {
+        res = res.add_message(BankMsg::Send {
+            to_address: depositor.to_string(),
+            amount: coll_gains,
+        });
+    }
+    if !deposit.reward_gain.is_zero() {
+        let reward_token = match MOCK_TOKEN.load(deps.storage)? {
+            Denom::Cw20(reward_token) => reward_token,
+            Denom::Native(_) => unreachable!(),
+        };
+        res = res.add_message(WasmMsg::Execute {
+            contract_addr: reward_token.to_string(),
+            msg: to_binary(&Cw20ExecuteMsg::Transfer {
+                recipient: depositor.to_string(),
+                amount: deposit.reward_gain,
+            })?,
+            funds: vec![],
+        });
+    }
+    Ok(res)
+}
+
+fn mock_sp_query(deps: Deps, _env: Env, msg: StabilityPoolQueryMsg) -> StdResult<Binary> {
+    let load = |depositor: String| -> StdResult<MockDeposit> {
+        Ok(MOCK_DEPOSITS
+            .may_load(deps.storage, &Addr::unchecked(depositor))?
+            .unwrap_or_default())
+    };
+    match msg {
+        StabilityPoolQueryMsg::GetCompoundedUltraDeposit { depositor } => {
+            to_binary(&load(depositor)?.deposit)
+        }
+        StabilityPoolQueryMsg::GetDepositorCollGain {
+            collateral: AssetInfo::NativeToken { denom },
+            depositor,
+        } => {
+            let gain = load(depositor)?
+                .coll_gains
+                .into_iter()
+                .find(|coll_gain| coll_gain.denom == denom)
+                .map(|coll_gain| coll_gain.amount)
+                .unwrap_or_default();
+            to_binary(&gain)
+        }
+        StabilityPoolQueryMsg::GetDepositorRewardGain { depositor } => {
+            to_binary(&load(depositor)?.reward_gain)
+        }
+        _ => Err(StdError::generic_err("not mocked")),
+    }
+}
+
+/// Constant product pool without fees, trading its token as token1 against ULTRA
+fn mock_swap_execute(
+    deps: DepsMut,
+    env: Env,
+    info: MessageInfo,
+    msg: WasmSwapExecuteMsg,
+) -> StdResult<Response> {
+    let (input_token, input_amount, min_output) = match msg {
+        WasmSwapExecuteMsg::Swap {
+            input_token,
+            input_amount,
+            min_output,
+            ..
+        } => (input_token, input_amount, min_output),
+        _ => return Err(StdError::generic_err("not mocked")),
+    };
+    assert_eq!(input_token, TokenSelect::Token1);
+
+    let mut res = Response::new();
+    match MOCK_TOKEN.load(deps.storage)? {
+        Denom::Native(denom) => assert_eq!(info.funds, coins(input_amount.u128(), denom)),
+        Denom::Cw20(token) => {
+            res = res.add_message(WasmMsg::Execute {
+                contract_addr: token.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::TransferFrom {
+                    owner: info.sender.to_string(),
+                    recipient: env.contract.address.to_string(),
+                    amount: input_amount,
+                })?,
+                funds: vec![],
+            })
+        }
+    }
+
+    let (token_reserve, ultra_reserve) = MOCK_RESERVES.load(deps.storage)?;
+    let output = swap_output(input_amount, token_reserve, ultra_reserve);
+    if output < min_output {
+        return Err(StdError::generic_err("Swap: Min output not met"));
+    }
+    MOCK_RESERVES.save(
+        deps.storage,
+        &(token_reserve + input_amount, ultra_reserve - output),
+    )?;
+    Ok(res.add_message(WasmMsg::Execute {
+        contract_addr: MOCK_ULTRA.load(deps.storage)?.to_string(),
+        msg: to_binary(&Cw20ExecuteMsg::Transfer {
+            recipient: info.sender.to_string(),
+            amount: output,
+        })?,
+        funds: vec![],
+    }))
+}
+
+fn swap_output(input: Uint128, reserve_in: Uint128, reserve_out: Uint128) -> Uint128 {
+    input.multiply_ratio(reserve_out, reserve_in + input)
+}
+
+fn mock_swap_query(deps: Deps, _env: Env, msg: WasmSwapQueryMsg) -> StdResult<Binary> {
+    let (token_reserve, ultra_reserve) = MOCK_RESERVES.load(deps.storage)?;
+    match msg {
+        WasmSwapQueryMsg::Info {} => to_binary(&InfoResponse {
+            token1_reserve: token_reserve,
+            token1_denom: MOCK_TOKEN.load(deps.storage)?,
+            token2_reserve: ultra_reserve,
+            token2_denom: Denom::Cw20(MOCK_ULTRA.load(deps.storage)?),
+            lp_token_supply: Uint128::zero(),
+            lp_token_address: String::from("lp-token"),
+        }),
+        WasmSwapQueryMsg::Token1ForToken2Price { token1_amount } => {
+            to_binary(&Token1ForToken2PriceResponse {
+                token2_amount: swap_output(token1_amount, token_reserve, ultra_reserve),
+            })
+        }
+        _ => Err(StdError::generic_err("not mocked")),
+    }
+}
+
+fn mock_oracle_instantiate(
+    deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    price: Uint128,
+) -> StdResult<Response> {
+    MOCK_PRICE.save(deps.storage, &price)?;
+    Ok(Response::default())
+}
+
+fn mock_oracle_execute(
+    _deps: DepsMut,
+    _env: Env,
+    _info: MessageInfo,
+    _msg: OracleExecuteMsg,
+) -> StdResult<Response> {
+    Err(StdError::generic_err("not mocked"))
+}
+
+/// Oracle with a fixed TWAP price
+fn mock_oracle_query(deps: Deps, _env: Env, msg: OracleQueryMsg) -> StdResult<Binary> {
+    match msg {
+        OracleQueryMsg::Consult { amount, .. } => {
+            to_binary(&Uint256::from(amount * MOCK_PRICE.load(deps.storage)?))
+        }
+    }
+}
+
+fn mock_sp_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(mock_sp_execute, mock_instantiate, mock_sp_query);
+    Box::new(contract)
+}
+
+fn mock_swap_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(mock_swap_execute, mock_instantiate, mock_swap_query);
+    Box::new(contract)
+}
+
+fn mock_oracle_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        mock_oracle_execute,
+        mock_oracle_instantiate,
+        mock_oracle_query,
+    );
+    Box::new(contract)
+}
+
+fn stability_vault_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        crate::contract::execute,
+        crate::contract::instantiate,
+        crate::contract::query,
+    )
+    .with_reply(crate::contract::reply)
+    .with_sudo(crate::sudo::sudo)
+    .with_migrate(crate::contract::migrate);
+    Box::new(contract)
+}
+
+fn address_registry_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        address_registry::contract::execute,
+        address_registry::contract::instantiate,
+        address_registry::contract::query,
+    );
+    Box::new(contract)
+}
+
+fn ultra_token_contract() -> Box<dyn Contract<Empty>> {
+    let contract = ContractWrapper::new(
+        ultra_token::contract::execute,
+        ultra_token::contract::instantiate,
+        ultra_token::contract::query,
+    );
+    Box::new(contract)
+}
+
+struct Suite {
+    app: App,
+    vault: Addr,
+    ultra: Addr,
+    reward: Addr,
+    shares: Addr,
+    /// Route of the ATOM gains, left for the tests to set
+    atom_swap: CollateralSwap,
+}
+
+/// Instantiates a vault on top of a mock stability pool, with JUNO and ATOM registered as
+/// collateral types. Its JUNO gains are swapped through a mock pool holding `JUNO_RESERVE` JUNO
+/// and `ULTRA_RESERVE` ULTRA, with a TWAP price of `JUNO_PRICE`, and its reward tokens through a
+/// mock pool holding `REWARD_RESERVE` reward tokens and `REWARD_ULTRA_RESERVE` ULTRA, with a TWAP
+/// price of `REWARD_PRICE`. The ATOM pool, holding `ATOM_RESERVE` ATOM and `ATOM_ULTRA_RESERVE`
+/// ULTRA with a TWAP price of `ATOM_PRICE`, is not set as a route. The user holds 10_000 ULTRA
+fn instantiate_vault() -> Suite {
+    let mut app = App::new(|router, _, storage| {
+        router
+            .bank
+            .init_balance(
+                storage,
+                &Addr::unchecked(LIQUIDATOR),
+                vec![coin(1_000_000, UATOM), coin(1_000_000, UJUNO_DENOM)],
+            )
+            .unwrap();
+    });
+
+    let address_registry_code_id = app.store_code(address_registry_contract());
+    let address_registry = app
+        .instantiate_contract(
+            address_registry_code_id,
+            Addr::unchecked(SOME),
+            &AddressRegistryInstantiateMsg {
+                name: String::from("Address Registry"),
+                owner: OWNER.to_string(),
+            },
+            &[],
+            "address registry",
+            None,
+        )
+        .unwrap();
+    for denom in [UJUNO_DENOM, UATOM] {
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            address_registry.clone(),
+            &AddressRegistryExecuteMsg::RegisterCollateral {
+                collateral: Collateral {
+                    asset: native_asset_info(denom.to_string()),
+                    contracts: CollateralContracts {
+                        active_pool: Addr::unchecked("active-pool"),
+                        default_pool: Addr::unchecked("default-pool"),
+                        coll_surplus_pool: Addr::unchecked("coll-surplus-pool"),
+                        sorted_troves: Addr::unchecked("sorted-troves"),
+                        price_feed: Addr::unchecked("price-feed"),
+                    },
+                    params: CollateralParams {
+                        mcr: Decimal256::percent(110),
+                        debt_ceiling: Uint128::new(1_000_000),
+                    },
+                },
+            },
+            &[],
+        )
+        .unwrap();
+    }
+
+    let ultra_token_code_id = app.store_code(ultra_token_contract());
+    let ultra = app
+        .instantiate_contract(
+            ultra_token_code_id,
+            Addr::unchecked(SOME),
+            &ultra_token::msg::InstantiateMsg {
+                name: String::from("Ultra Stable Juno"),
+                symbol: String::from("ULTRA"),
+                decimals: 6,
+                initial_balances: vec![Cw20Coin {
+                    address: USER.to_string(),
+                    amount: Uint128::new(10_000),
+                }],
+                mint: Some(MinterResponse {
+                    minter: OWNER.to_string(),
+                    cap: None,
+                }),
+                marketing: None,
+            },
+            &[],
+            "ultra token",
+            None,
+        )
+        .unwrap();
+
+    // The owner issues the reward tokens
+    let reward = app
+        .instantiate_contract(
+            ultra_token_code_id,
+            Addr::unchecked(SOME),
+            &ultra_token::msg::InstantiateMsg {
+                name: String::from("Reward"),
+                symbol: String::from("REWARD"),
+                decimals: 6,
+                initial_balances: vec![Cw20Coin {
+                    address: OWNER.to_string(),
+                    amount: Uint128::new(1_000_000),
+                }],
+                mint: None,
+                marketing: None,
+            },
+            &[],
+            "reward token",
+            None,
+        )
+        .unwrap();
+
+    let sp_code_id = app.store_code(mock_sp_contract());
+    let stability_pool = app
+        .instantiate_contract(
+            sp_code_id,
+            Addr::unchecked(SOME),
+            &MockInstantiateMsg {
+                ultra: ultra.clone(),
+                token: Denom::Cw20(reward.clone()),
+                reserves: (Uint128::zero(), Uint128::zero()),
+            },
+            &[],
+            "sp",
+            None,
+        )
+        .unwrap();
+
+    let swap_code_id = app.store_code(mock_swap_contract());
+    let oracle_code_id = app.store_code(mock_oracle_contract());
+    let mut swap_pools = vec![];
+    for (token, token_reserve, ultra_reserve, price) in [
+        (
+            Denom::Native(UJUNO_DENOM.to_string()),
+            JUNO_RESERVE,
+            ULTRA_RESERVE,
+            JUNO_PRICE,
+        ),
+        (
+            Denom::Native(UATOM.to_string()),
+            ATOM_RESERVE,
+            ATOM_ULTRA_RESERVE,
+            ATOM_PRICE,
+        ),
+        (
+            Denom::Cw20(reward.clone()),
+            REWARD_RESERVE,
+            REWARD_ULTRA_RESERVE,
+            REWARD_PRICE,
+        ),
+    ] {
+        let swap_pool = app
+            .instantiate_contract(
+                swap_code_id,
+                Addr::unchecked(SOME),
+                &MockInstantiateMsg {
+                    ultra: ultra.clone(),
+                    token,
+                    reserves: (Uint128::new(token_reserve), Uint128::new(ultra_reserve)),
+                },
+                &[],
+                "swap",
+                None,
+            )
+            .unwrap();
+        app.execute_contract(
+            Addr::unchecked(OWNER),
+            ultra.clone(),
+            &Cw20ExecuteMsg::Mint {
+                recipient: swap_pool.to_string(),
+                amount: Uint128::new(ultra_reserve),
+            },
+            &[],
+        )
+        .unwrap();
+        let oracle = app
+            .instantiate_contract(
+                oracle_code_id,
+                Addr::unchecked(SOME),
+                &Uint128::new(price),
+                &[],
+                "oracle",
+                None,
+            )
+            .unwrap();
+        swap_pools.push((swap_pool, oracle));
+    }
+    let (reward_swap_pool, reward_oracle) = swap_pools.pop().unwrap();
+    let (atom_swap_pool, atom_oracle) = swap_pools.pop().unwrap();
+    let (juno_swap_pool, juno_oracle) = swap_pools.pop().unwrap();
+
+    let code_id = app.store_code(stability_vault_contract());
+    let vault = app
+        .instantiate_contract(
+            code_id,
+            Addr::unchecked(SOME),
+            &InstantiateMsg {
+                name: String::from("Stability Vault"),
+                owner: OWNER.to_string(),
+                share_token: ShareTokenParams {
+                    code_id: ultra_token_code_id,
+                    name: String::from("Stability Vault Share"),
+                    symbol: String::from("svULTRA"),
+                    decimals: 6,
+                },
+                stability_pool: stability_pool.to_string(),
+                ultra_token: ultra.to_string(),
+                address_registry: address_registry.to_string(),
+                collateral_swaps: vec![CollateralSwap {
+                    collateral: native_asset_info(UJUNO_DENOM.to_string()),
+                    swap_pool: juno_swap_pool,
+                    oracle: juno_oracle,
+                }],
+                reward_token: reward.to_string(),
+                reward_swap_pool: reward_swap_pool.to_string(),
+                reward_oracle: reward_oracle.to_string(),
+                front_end: None,
+                max_slippage: Decimal256::percent(1),
+                max_price_impact: Decimal256::percent(5),
+            },
+            &[],
+            "stability vault",
+            None,
+        )
+        .unwrap();
+    let shares = app
+        .wrap()
+        .query_wasm_smart(vault.clone(), &QueryMsg::GetShareTokenAddress {})
+        .unwrap();
+
+    Suite {
+        app,
+        vault,
+        ultra,
+        reward,
+        shares,
+        atom_swap: CollateralSwap {
+            collateral: native_asset_info(UATOM.to_string()),
+            swap_pool: atom_swap_pool,
+            oracle: atom_oracle,
+        },
+    }
+}
+
+impl Suite {
+    fn send(&mut self, token: &Addr, amount: u128, msg: Cw20HookMsg) -> Result<(), ContractError> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(USER),
+                token.clone(),
+                &Cw20ExecuteMsg::Send {
+                    contract: self.vault.to_string(),
+                    amount: Uint128::new(amount),
+                    msg: to_binary(&msg).unwrap(),
+                },
+                &[],
+            )
+            .map(|_| ())
+            .map_err(|err| err.downcast().unwrap())
+    }
+
+    fn deposit(&mut self, amount: u128) {
+        let ultra = self.ultra.clone();
+        self.send(&ultra, amount, Cw20HookMsg::Deposit {}).unwrap();
+    }
+
+    fn redeem(&mut self, shares: u128) {
+        let share_token = self.shares.clone();
+        self.send(&share_token, shares, Cw20HookMsg::Redeem {})
+            .unwrap();
+    }
+
+    /// Liquidation sending `juno` to the depositors of the stability pool
+    fn offset(&mut self, juno: u128) {
+        self.offset_coll(UJUNO_DENOM, juno);
+    }
+
+    /// Liquidation sending `amount` of the `denom` collateral to the depositors of the stability
+    /// pool
+    fn offset_coll(&mut self, denom: &str, amount: u128) {
+        let config: ultra_base::stability_vault::ConfigResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(self.vault.clone(), &QueryMsg::GetConfig {})
+            .unwrap();
+        self.app
+            .execute_contract(
+                Addr::unchecked(LIQUIDATOR),
+                config.stability_pool,
+                &StabilityPoolExecuteMsg::Offset {
+                    collateral: native_asset_info(denom.to_string()),
+                },
+                &coins(amount, denom),
+            )
+            .unwrap();
+    }
+
+    fn juno_balance(&self, address: &str) -> Uint128 {
+        self.balance(address, UJUNO_DENOM)
+    }
+
+    fn balance(&self, address: &str, denom: &str) -> Uint128 {
+        self.app
+            .wrap()
+            .query_balance(address, denom)
+            .unwrap()
+            .amount
+    }
+
+    /// Community issuance sending `reward` tokens to the depositors of the stability pool
+    fn issue_rewards(&mut self, reward: u128) {
+        let config: ultra_base::stability_vault::ConfigResponse =
+            self.query(&QueryMsg::GetConfig {});
+        self.app
+            .execute_contract(
+                Addr::unchecked(OWNER),
+                self.reward.clone(),
+                &Cw20ExecuteMsg::Send {
+                    contract: config.stability_pool.to_string(),
+                    amount: Uint128::new(reward),
+                    msg: Binary::default(),
+                },
+                &[],
+            )
+            .unwrap();
+    }
+
+    fn harvest(&mut self) -> Result<(), ContractError> {
+        self.app
+            .execute_contract(
+                Addr::unchecked(SOME),
+                self.vault.clone(),
+                &ExecuteMsg::Harvest {},
+                &[],
+            )
+            .map(|_| ())
+            .map_err(|err| err.downcast().unwrap())
+    }
+
+    fn query<T: serde::de::DeserializeOwned>(&self, msg: &QueryMsg) -> T {
+        self.app
+            .wrap()
+            .query_wasm_smart(self.vault.clone(), msg)
+            .unwrap()
+    }
+
+    fn token_balance(&self, token: &Addr, address: &str) -> Uint128 {
+        let res: BalanceResponse = self
+            .app
+            .wrap()
+            .query_wasm_smart(
+                token.clone(),
+                &ultra_token::msg::QueryMsg::Balance {
+                    address: address.to_string(),
+                },
+            )
+            .unwrap();
+        res.balance
+    }
+}
+
+#[test]
+fn test_deposit_and_redeem() {
+    let mut suite = instantiate_vault();
+
+    // Only ULTRA can be deposited
+    let shares = suite.shares.clone();
+    let err = suite.send(&shares, 1, Cw20HookMsg::Deposit {}).unwrap_err();
+    assert_eq!(err, ContractError::InvalidToken {});
+
+    // The first deposit mints one share per ULTRA, the minimum shares being locked in the vault
+    let ultra = suite.ultra.clone();
+    let err = suite
+        .send(&ultra, 1_000, Cw20HookMsg::Deposit {})
+        .unwrap_err();
+    assert_eq!(
+        err,
+        ContractError::FirstDepositTooSmall {
+            minimum: MINIMUM_SHARES
+        }
+    );
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::ConvertToShares {
+            assets: Uint128::new(2_000)
+        }),
+        Uint128::new(1_000)
+    );
+    suite.deposit(2_000);
+    assert_eq!(
+        suite.token_balance(&suite.shares, USER),
+        Uint128::new(1_000)
+    );
+    assert_eq!(
+        suite.token_balance(&suite.shares, suite.vault.as_str()),
+        MINIMUM_SHARES
+    );
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::GetTotalAssets {}),
+        Uint128::new(2_000)
+    );
+    assert_eq!(
+        suite.token_balance(&suite.ultra, suite.vault.as_str()),
+        Uint128::zero()
+    );
+
+    // Only shares can be redeemed
+    let err = suite.send(&ultra, 1, Cw20HookMsg::Redeem {}).unwrap_err();
+    assert_eq!(err, ContractError::InvalidToken {});
+
+    suite.redeem(400);
+    assert_eq!(suite.token_balance(&suite.shares, USER), Uint128::new(600));
+    assert_eq!(suite.token_balance(&suite.ultra, USER), Uint128::new(8_400));
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::GetTotalAssets {}),
+        Uint128::new(1_600)
+    );
+}
+
+#[test]
+fn test_donations_benefit_the_locked_shares() {
+    let mut suite = instantiate_vault();
+    suite.deposit(1_001);
+    assert_eq!(suite.token_balance(&suite.shares, USER), Uint128::new(1));
+
+    // Donating ULTRA to inflate the share price
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(USER),
+            suite.ultra.clone(),
+            &Cw20ExecuteMsg::Transfer {
+                recipient: suite.vault.to_string(),
+                amount: Uint128::new(8_999),
+            },
+            &[],
+        )
+        .unwrap();
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::GetTotalAssets {}),
+        Uint128::new(10_000)
+    );
+
+    // The donor only gets back its share of the donation
+    suite.redeem(1);
+    assert_eq!(suite.token_balance(&suite.ultra, USER), Uint128::new(9));
+}
+
+#[test]
+fn test_harvest_compounds_juno_gains() {
+    let mut suite = instantiate_vault();
+    suite.deposit(2_000);
+
+    let err = suite.harvest().unwrap_err();
+    assert_eq!(err, ContractError::NothingToHarvest {});
+
+    // The 100 JUNO gained are swapped to 198 ULTRA, a 1% price impact, their TWAP value being
+    // 200 ULTRA
+    suite.offset(100);
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::GetTotalAssets {}),
+        Uint128::new(2_200)
+    );
+    suite.harvest().unwrap();
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::GetTotalAssets {}),
+        Uint128::new(2_198)
+    );
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::ConvertToAssets {
+            shares: Uint128::new(1_000)
+        }),
+        Uint128::new(1_099)
+    );
+    assert_eq!(suite.juno_balance(suite.vault.as_str()), Uint128::zero());
+
+    // Later deposits get fewer shares per ULTRA
+    suite.deposit(1_099);
+    assert_eq!(
+        suite.token_balance(&suite.shares, USER),
+        Uint128::new(2_000)
+    );
+    suite.redeem(2_000);
+    assert_eq!(
+        suite.token_balance(&suite.ultra, USER),
+        Uint128::new(10_000 - 2_000 + 1_099)
+    );
+}
+
+#[test]
+fn test_harvest_limits() {
+    let mut suite = instantiate_vault();
+    suite.deposit(2_000);
+
+    // Swapping 5_000 JUNO moves the price by a third, and returns two thirds of their TWAP value
+    suite.offset(5_000);
+    let err = suite.harvest().unwrap_err();
+    assert!(matches!(err, ContractError::PriceImpactTooHigh { .. }));
+
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            suite.vault.clone(),
+            &ExecuteMsg::UpdateConfig {
+                max_slippage: None,
+                max_price_impact: Some(Decimal256::percent(50)),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::UnauthorizedOwner {});
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.vault.clone(),
+            &ExecuteMsg::UpdateConfig {
+                max_slippage: Some(Decimal256::percent(101)),
+                max_price_impact: None,
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::InvalidSwapLimit {});
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.vault.clone(),
+            &ExecuteMsg::UpdateConfig {
+                max_slippage: None,
+                max_price_impact: Some(Decimal256::percent(50)),
+            },
+            &[],
+        )
+        .unwrap();
+    // The swap fails below the floor
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            suite.vault.clone(),
+            &ExecuteMsg::Harvest {},
+            &[],
+        )
+        .unwrap_err();
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.vault.clone(),
+            &ExecuteMsg::UpdateConfig {
+                max_slippage: Some(Decimal256::percent(34)),
+                max_price_impact: None,
+            },
+            &[],
+        )
+        .unwrap();
+    suite.harvest().unwrap();
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::GetTotalAssets {}),
+        Uint128::new(2_000 + 6_666)
+    );
+
+    // Only the vault compounds, at the end of a harvest
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            suite.vault.clone(),
+            &ExecuteMsg::Compound {},
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::Unauthorized {});
+}
+
+#[test]
+fn test_unharvested_gains() {
+    let mut suite = instantiate_vault();
+    suite.deposit(2_000);
+    suite.offset(100);
+
+    // Deposits don't capture the gains made before them
+    suite.deposit(1_100);
+    assert_eq!(
+        suite.token_balance(&suite.shares, USER),
+        Uint128::new(2_000)
+    );
+    assert_eq!(suite.juno_balance(suite.vault.as_str()), Uint128::new(100));
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::GetTotalAssets {}),
+        Uint128::new(3_300)
+    );
+
+    // Redemptions are paid their part of the gains in JUNO
+    suite.redeem(1_500);
+    assert_eq!(
+        suite.token_balance(&suite.ultra, USER),
+        Uint128::new(10_000 - 2_000 - 1_100 + 1_550)
+    );
+    assert_eq!(suite.juno_balance(USER), Uint128::new(50));
+    assert_eq!(suite.juno_balance(suite.vault.as_str()), Uint128::new(50));
+}
+
+#[test]
+fn test_harvest_floor_ignores_the_spot_price() {
+    let mut suite = instantiate_vault();
+    suite.deposit(2_000);
+    suite.offset(100);
+
+    // Swapping JUNO into the pool in the same block lowers the quote of the harvest, but not its
+    // floor
+    let swaps: Vec<CollateralSwap> = suite.query(&QueryMsg::ListCollateralSwaps {});
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(LIQUIDATOR),
+            swaps[0].swap_pool.clone(),
+            &WasmSwapExecuteMsg::Swap {
+                input_token: TokenSelect::Token1,
+                input_amount: Uint128::new(1_000),
+                min_output: Uint128::zero(),
+                expiration: None,
+            },
+            &coins(1_000, UJUNO_DENOM),
+        )
+        .unwrap();
+    // The swap fails below the floor
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            suite.vault.clone(),
+            &ExecuteMsg::Harvest {},
+            &[],
+        )
+        .unwrap_err();
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::GetTotalAssets {}),
+        Uint128::new(2_200)
+    );
+}
+
+#[test]
+fn test_harvest_compounds_reward_tokens() {
+    let mut suite = instantiate_vault();
+    suite.deposit(2_000);
+
+    // The 100 reward tokens are worth 300 ULTRA at the TWAP price, and are swapped to 299 ULTRA
+    suite.issue_rewards(100);
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::GetTotalAssets {}),
+        Uint128::new(2_300)
+    );
+    suite.harvest().unwrap();
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::GetTotalAssets {}),
+        Uint128::new(2_299)
+    );
+    assert_eq!(
+        suite.token_balance(&suite.reward, suite.vault.as_str()),
+        Uint128::zero()
+    );
+
+    // Redemptions are paid their part of the reward tokens not harvested yet
+    suite.issue_rewards(100);
+    suite.redeem(1_000);
+    assert_eq!(
+        suite.token_balance(&suite.ultra, USER),
+        Uint128::new(10_000 - 2_000 + 1_149)
+    );
+    assert_eq!(suite.token_balance(&suite.reward, USER), Uint128::new(50));
+    assert_eq!(
+        suite.token_balance(&suite.reward, suite.vault.as_str()),
+        Uint128::new(50)
+    );
+}
+
+#[test]
+fn test_gains_in_every_collateral() {
+    let mut suite = instantiate_vault();
+    suite.deposit(2_000);
+
+    // Gains in a collateral type without a route can't be valued nor harvested
+    suite.offset_coll(UATOM, 100);
+    suite
+        .app
+        .wrap()
+        .query_wasm_smart::<Uint128>(suite.vault.clone(), &QueryMsg::GetTotalAssets {})
+        .unwrap_err();
+    let err = suite.harvest().unwrap_err();
+    assert!(matches!(err, ContractError::Std(_)));
+
+    // Redemptions are still paid their part of them
+    suite.redeem(500);
+    assert_eq!(suite.token_balance(&suite.ultra, USER), Uint128::new(8_500));
+    assert_eq!(suite.balance(USER, UATOM), Uint128::new(25));
+    assert_eq!(suite.balance(suite.vault.as_str(), UATOM), Uint128::new(75));
+
+    // Only the owner sets routes, and only for registered collateral types
+    let atom_swap = suite.atom_swap.clone();
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(SOME),
+            suite.vault.clone(),
+            &ExecuteMsg::SetCollateralSwap {
+                swap: atom_swap.clone(),
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert_eq!(err, ContractError::UnauthorizedOwner {});
+    let err: ContractError = suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.vault.clone(),
+            &ExecuteMsg::SetCollateralSwap {
+                swap: CollateralSwap {
+                    collateral: native_asset_info("uosmo".to_string()),
+                    ..atom_swap.clone()
+                },
+            },
+            &[],
+        )
+        .unwrap_err()
+        .downcast()
+        .unwrap();
+    assert!(matches!(err, ContractError::Std(_)));
+    suite
+        .app
+        .execute_contract(
+            Addr::unchecked(OWNER),
+            suite.vault.clone(),
+            &ExecuteMsg::SetCollateralSwap { swap: atom_swap },
+            &[],
+        )
+        .unwrap();
+    let swaps: Vec<CollateralSwap> = suite.query(&QueryMsg::ListCollateralSwaps {});
+    assert_eq!(swaps.len(), 2);
+
+    // The 75 ATOM left are worth 375 ULTRA at the TWAP price, and are swapped to 372 ULTRA
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::GetTotalAssets {}),
+        Uint128::new(1_875)
+    );
+    suite.harvest().unwrap();
+    assert_eq!(
+        suite.query::<Uint128>(&QueryMsg::GetTotalAssets {}),
+        Uint128::new(1_872)
+    );
+    assert_eq!(suite.balance(suite.vault.as_str(), UATOM), Uint128::zero());
+}
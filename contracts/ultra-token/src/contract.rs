@@ -16,13 +16,17 @@ use crate::allowances::{
 };
 use crate::enumerable::{query_all_accounts, query_all_allowances};
 use crate::error::ContractError;
-use crate::msg::{ExecuteMsg, InstantiateMsg, QueryMsg};
+use crate::msg::{ExecuteMsg, InstantiateMsg, MigrateMsg, QueryMsg};
 use crate::state::{MinterData, TokenInfo, BALANCES, LOGO, MARKETING_INFO, TOKEN_INFO};
+use ultra_base::migration::{migrate_contract, Migration};
 
 // version info for migration info
 const CONTRACT_NAME: &str = "crates.io:ultra-token";
 const CONTRACT_VERSION: &str = env!("CARGO_PKG_VERSION");
 
+// state migrations, ordered by the version introducing them
+const MIGRATIONS: &[Migration] = &[];
+
 const LOGO_SIZE_CAP: usize = 5 * 1024;
 
 /// Checks if data starts with XML preamble
@@ -179,6 +183,16 @@ pub fn validate_accounts(accounts: &[Cw20Coin]) -> Result<(), ContractError> {
     }
 }
 
+#[cfg_attr(not(feature = "library"), entry_point)]
+pub fn migrate(deps: DepsMut, _env: Env, _msg: MigrateMsg) -> Result<Response, ContractError> {
+    Ok(migrate_contract(
+        deps.storage,
+        CONTRACT_NAME,
+        CONTRACT_VERSION,
+        MIGRATIONS,
+    )?)
+}
+
 #[cfg_attr(not(feature = "library"), entry_point)]
 pub fn execute(
     deps: DepsMut,
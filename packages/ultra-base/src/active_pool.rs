@@ -1,11 +1,17 @@
 use cosmwasm_std::{Addr, Uint128};
+use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::asset::AssetInfo;
+use crate::collateral_staking::StakingConfig;
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMsg {
     pub name: String,
     pub owner: String,
+    /// Collateral type held by the pool
+    pub collateral: AssetInfo,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -17,30 +23,107 @@ pub enum ExecuteMsg {
     IncreaseULTRADebt {
         amount: Uint128,
     },
-    SendJUNO {
+    /// Send collateral to `recipient`. In staking mode, what exceeds the liquid collateral is
+    /// queued for `ClaimPayout`, unless the recipient is a protocol contract.
+    /// Can only be called by borrower operations, the trove manager or the stability pool
+    SendCollateral {
         recipient: Addr,
         amount: Uint128,
     },
-    SetAddresses {
-        borrower_operations_address: String,
-        trove_manager_address: String,
-        stability_pool_address: String,
-        default_pool_address: String,
+    /// Record the native collateral sent along with this message.
+    /// Can only be called by borrower operations or the default pool
+    ReceiveCollateral {},
+    /// Record the cw20 collateral sent with a `Cw20HookMsg::ReceiveCollateral`
+    Receive(Cw20ReceiveMsg),
+    /// Send the collateral held by the pool but not recorded to `recipient`.
+    /// Can only be called by the owner while staking is disabled, as the surplus is
+    /// distributed with the staking rewards otherwise
+    SweepSurplus {
+        recipient: String,
+    },
+    /// Enable the staking mode with `config`, or disable it. Existing delegations are
+    /// moved by the following `Rebalance`. Can only be called by the owner
+    SetStakingConfig {
+        config: Option<StakingConfig>,
+    },
+    /// Delegate or undelegate JUNO so that the staked ratio of the collateral is met.
+    /// Can be called by anyone, typically a keeper
+    Rebalance {},
+    /// Claim the staking rewards from the validators and distribute them to the troves.
+    /// Can be called by anyone, typically a keeper, while staking is enabled
+    ClaimStakingRewards {},
+    /// Send the collateral queued for the caller, once the JUNO is back from unbonding.
+    /// Can be called by anyone
+    ClaimPayout {},
+    /// Send the staking rewards of a trove to its owner.
+    /// Can only be called by borrower operations or the trove manager
+    SendStakingReward {
+        recipient: Addr,
+        amount: Uint128,
     },
+    /// Point the pool to the address registry its peers are resolved from.
+    /// Can only be called by the owner while the addresses are not locked
+    SetAddressRegistry {
+        address_registry: String,
+    },
+    /// Refresh the cached peer addresses from the address registry.
+    /// Can be called by anyone while the addresses are not locked
+    SyncAddresses {},
+    /// Propose a new owner, who becomes owner once it accepts.
+    /// Can only be called by the owner
+    ProposeOwner {
+        owner: String,
+    },
+    /// Become the owner. Can only be called by the pending owner
+    AcceptOwner {},
+    /// Leave the pool without owner. Can only be called by the owner
+    RenounceOwnership {},
+    /// Permanently prevent the addresses from being changed again.
+    /// Can only be called by the owner once the addresses are synced
+    LockAddresses {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Record the received collateral.
+    /// Can only be sent by borrower operations or the default pool
+    ReceiveCollateral {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetParams {},
-    GetJUNO {},
+    /// Returns the owner, pending owner and addresses lock status
+    GetOwnership {},
+    GetCollateral {},
+    /// Returns the collateral type held by the pool
+    GetCollateralInfo {},
     GetULTRADebt {},
     GetBorrowerOperationsAddress {},
     GetStabilityPoolAddress {},
     GetDefaultPoolAddress {},
     GetTroveManagerAddress {},
+    /// Compare the recorded collateral with the pool's actual balance
+    Reconcile {},
+    /// Returns the staking config, if the staking mode is enabled
+    GetStakingConfig {},
+    /// Returns the delegated and unbonding JUNO, and the staking rewards
+    GetCollateralStaking {},
+    /// Returns the JUNO delegated to each validator
+    GetDelegations {},
+    /// Returns the delegation or undelegation the next `Rebalance` would make
+    GetRebalance {},
+    /// Returns the collateral queued for `address`
+    GetPayout {
+        address: String,
+    },
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SudoMsg {
@@ -57,3 +140,16 @@ pub struct ParamsResponse {
     pub name: String,
     pub owner: Addr,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ReconcileResponse {
+    /// Collateral recorded in the pool
+    pub recorded_coll: Uint128,
+    /// Actual collateral balance of the pool
+    pub balance: Uint128,
+    /// Collateral held, delegated or unbonding by the pool but neither recorded nor held as
+    /// staking rewards
+    pub surplus: Uint128,
+    /// Recorded collateral and staking rewards not backed by the collateral of the pool
+    pub deficit: Uint128,
+}
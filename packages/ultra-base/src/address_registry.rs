@@ -0,0 +1,171 @@
+use crate::asset::AssetInfo;
+use crate::collateral::{Collateral, CollateralParamsUpdate};
+use crate::risk_params::RiskParamsUpdate;
+use cosmwasm_std::Addr;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Protocol contracts whose address is kept in the registry
+#[derive(Serialize, Deserialize, Clone, Copy, Debug, PartialEq, Eq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ProtocolContract {
+    UltraToken,
+    ActivePool,
+    DefaultPool,
+    CollSurplusPool,
+    TroveManager,
+    BorrowerOperations,
+    StabilityPool,
+    SortedTroves,
+    HintHelpers,
+    PriceFeed,
+    RewardPool,
+    GasPool,
+    CommunityIssuance,
+}
+
+impl ProtocolContract {
+    pub const ALL: [ProtocolContract; 13] = [
+        ProtocolContract::UltraToken,
+        ProtocolContract::ActivePool,
+        ProtocolContract::DefaultPool,
+        ProtocolContract::CollSurplusPool,
+        ProtocolContract::TroveManager,
+        ProtocolContract::BorrowerOperations,
+        ProtocolContract::StabilityPool,
+        ProtocolContract::SortedTroves,
+        ProtocolContract::HintHelpers,
+        ProtocolContract::PriceFeed,
+        ProtocolContract::RewardPool,
+        ProtocolContract::GasPool,
+        ProtocolContract::CommunityIssuance,
+    ];
+
+    /// Well-known key the address of the contract is stored under
+    pub fn key(&self) -> &'static str {
+        match self {
+            ProtocolContract::UltraToken => "ultra_token",
+            ProtocolContract::ActivePool => "active_pool",
+            ProtocolContract::DefaultPool => "default_pool",
+            ProtocolContract::CollSurplusPool => "coll_surplus_pool",
+            ProtocolContract::TroveManager => "trove_manager",
+            ProtocolContract::BorrowerOperations => "borrower_operations",
+            ProtocolContract::StabilityPool => "stability_pool",
+            ProtocolContract::SortedTroves => "sorted_troves",
+            ProtocolContract::HintHelpers => "hint_helpers",
+            ProtocolContract::PriceFeed => "price_feed",
+            ProtocolContract::RewardPool => "reward_pool",
+            ProtocolContract::GasPool => "gas_pool",
+            ProtocolContract::CommunityIssuance => "community_issuance",
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct InstantiateMsg {
+    pub name: String,
+    pub owner: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ContractAddress {
+    pub contract: ProtocolContract,
+    pub address: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Register the address of each given contract, replacing the previous one.
+    /// Can only be called by the owner
+    SetAddresses { addresses: Vec<ContractAddress> },
+    /// Unregister the address of `contract`. Can only be called by the owner
+    RemoveAddress { contract: ProtocolContract },
+    /// Register a collateral type with its contracts and risk parameters, replacing the
+    /// previous registration of its asset. Can only be called by the owner, even once the
+    /// addresses are locked
+    RegisterCollateral { collateral: Collateral },
+    /// Unregister the collateral type of `asset`. Can only be called by the owner, even once
+    /// the addresses are locked
+    RemoveCollateral { asset: AssetInfo },
+    /// Propose a new owner, who becomes owner once it accepts.
+    /// Can only be called by the owner
+    ProposeOwner { owner: String },
+    /// Become the owner. Can only be called by the pending owner
+    AcceptOwner {},
+    /// Leave the registry without owner. Can only be called by the owner
+    RenounceOwnership {},
+    /// Permanently prevent the registered addresses from being changed, collateral types can
+    /// still be registered. Can only be called by the owner
+    LockAddresses {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    GetParams {},
+    /// Returns the owner, pending owner and addresses lock status
+    GetOwnership {},
+    /// Returns the address registered for `contract`, fails if there is none
+    GetAddress {
+        contract: ProtocolContract,
+    },
+    /// Lists every registered address
+    ListAddresses {},
+    /// Returns the risk parameters set by governance
+    GetRiskParams {},
+    /// Returns the collateral type of `asset`, fails if it is not registered
+    GetCollateral {
+        asset: AssetInfo,
+    },
+    /// Lists the registered collateral types, ordered by key
+    ListCollaterals {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    /// Update the contract parameters
+    /// Can only be called by governance
+    UpdateParams {
+        name: Option<String>,
+        owner: Option<Addr>,
+    },
+    /// Update the protocol risk parameters, the missing ones are left unchanged
+    /// Can only be called by governance
+    UpdateRiskParams { params: Box<RiskParamsUpdate> },
+    /// Update the risk parameters of the collateral type of `asset`, the missing ones are
+    /// left unchanged. Can only be called by governance
+    UpdateCollateralParams {
+        asset: AssetInfo,
+        params: CollateralParamsUpdate,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ParamsResponse {
+    pub name: String,
+    pub owner: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AddressResponse {
+    pub contract: ProtocolContract,
+    pub address: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct AddressesResponse {
+    pub addresses: Vec<AddressResponse>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralsResponse {
+    pub collaterals: Vec<Collateral>,
+}
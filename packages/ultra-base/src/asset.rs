@@ -4,8 +4,8 @@ use std::fmt;
 
 use crate::querier::{query_balance, query_token_balance};
 use cosmwasm_std::{
-    coin, to_binary, Addr, BankMsg, CosmosMsg, MessageInfo, QuerierWrapper, StdError, StdResult,
-    Uint128, WasmMsg,
+    coin, to_binary, Addr, BankMsg, Binary, CosmosMsg, MessageInfo, QuerierWrapper, StdError,
+    StdResult, Uint128, WasmMsg,
 };
 use cw20::Cw20ExecuteMsg;
 
@@ -52,6 +52,32 @@ impl Asset {
         }
     }
 
+    /// Sends the asset to `contract` and executes `msg` on it, along with the funds for a native
+    /// token or as the hook of a cw20 `Send`
+    pub fn into_execute_msg(
+        self,
+        contract: impl Into<String>,
+        msg: Binary,
+    ) -> StdResult<CosmosMsg> {
+        let contract = contract.into();
+        match self.info {
+            AssetInfo::Cw20Token { contract_addr } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract_addr.to_string(),
+                msg: to_binary(&Cw20ExecuteMsg::Send {
+                    contract,
+                    amount: self.amount,
+                    msg,
+                })?,
+                funds: vec![],
+            })),
+            AssetInfo::NativeToken { denom } => Ok(CosmosMsg::Wasm(WasmMsg::Execute {
+                contract_addr: contract,
+                msg,
+                funds: vec![coin(self.amount.u128(), denom)],
+            })),
+        }
+    }
+
     pub fn assert_sent_native_token_balance(&self, message_info: &MessageInfo) -> StdResult<()> {
         if let AssetInfo::NativeToken { denom } = &self.info {
             match message_info.funds.iter().find(|x| x.denom == *denom) {
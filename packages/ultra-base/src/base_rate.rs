@@ -0,0 +1,429 @@
+use cosmwasm_std::{Decimal256, StdError, StdResult, Timestamp, Uint128, Uint256};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::risk_params::RiskParams;
+use crate::ultra_math::{
+    dec_mul, dec_pow, decimal256_to_uint128, min, uint128_to_decimal256, DECIMAL_PRECISION,
+};
+
+const SECONDS_IN_ONE_MINUTE: u64 = 60;
+
+/// Divisor of the redeemed supply fraction added to the base rate on each redemption
+const BETA: u8 = 2;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum BaseRateError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("BaseRate: There is no ULTRA supply to redeem")]
+    NoUltraSupply {},
+
+    #[error("BaseRate: Fee would eat up all returned collateral")]
+    FeeEatsUpCollateral {},
+
+    #[error("BaseRate: Fee exceeded provided maximum")]
+    FeeExceedsMaximum {},
+
+    #[error("BaseRate: Max fee percentage must be between {min} and 100%")]
+    InvalidMaxFeePercentage { min: Decimal256 },
+}
+
+/// Base rate shared by borrowing and redemption fees.
+///
+/// Redemptions increase it proportionally to the fraction of the supply they redeem, and it
+/// decays by `RiskParams::base_rate_minute_decay_factor` every minute until the next fee
+/// operation, halving every `RiskParams::base_rate_half_life`.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct BaseRate {
+    pub base_rate: Decimal256,
+    /// Time of the last fee operation, only moved forward by whole minutes
+    pub last_fee_operation_time: Timestamp,
+}
+
+/// Rate currently charged and the fee it amounts to
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FeeQuote {
+    pub rate: Decimal256,
+    pub fee: Uint128,
+}
+
+impl BaseRate {
+    /// Base rate decayed over the whole minutes elapsed since the last fee operation
+    pub fn decayed(&self, now: Timestamp, params: &RiskParams) -> StdResult<Decimal256> {
+        let minutes_passed = now
+            .seconds()
+            .saturating_sub(self.last_fee_operation_time.seconds())
+            / SECONDS_IN_ONE_MINUTE;
+        let decay_factor = dec_pow(params.base_rate_minute_decay_factor, minutes_passed)?;
+        Ok(self.base_rate.checked_mul(decay_factor)?)
+    }
+
+    /// Stores the decayed base rate, as done before charging a borrowing fee
+    pub fn decay_from_borrowing(&mut self, now: Timestamp, params: &RiskParams) -> StdResult<()> {
+        self.base_rate = self.decayed(now, params)?;
+        self.update_last_fee_operation_time(now);
+        Ok(())
+    }
+
+    /// Adds half the fraction of the ULTRA supply redeemed by `coll_drawn` at `price` to the
+    /// decayed base rate, capped to 100%, and returns the new base rate
+    pub fn update_from_redemption(
+        &mut self,
+        coll_drawn: Uint128,
+        price: Decimal256,
+        total_ultra_supply: Uint128,
+        now: Timestamp,
+        params: &RiskParams,
+    ) -> Result<Decimal256, BaseRateError> {
+        if total_ultra_supply.is_zero() {
+            return Err(BaseRateError::NoUltraSupply {});
+        }
+        let redeemed_ultra_fraction = Decimal256::from_ratio(coll_drawn, total_ultra_supply)
+            .checked_mul(price)
+            .map_err(StdError::overflow)?;
+        let increase = redeemed_ultra_fraction / Uint256::from(BETA);
+        let new_base_rate = min(self.decayed(now, params)? + increase, Decimal256::one());
+
+        self.base_rate = new_base_rate;
+        self.update_last_fee_operation_time(now);
+        Ok(new_base_rate)
+    }
+
+    /// Borrowing fee for `ultra_debt` at the stored base rate
+    pub fn borrowing_fee(&self, ultra_debt: Uint128, params: &RiskParams) -> StdResult<FeeQuote> {
+        fee_quote(borrowing_rate(self.base_rate, params), ultra_debt)
+    }
+
+    /// Borrowing fee for `ultra_debt` at the base rate decayed until `now`
+    pub fn borrowing_fee_with_decay(
+        &self,
+        ultra_debt: Uint128,
+        now: Timestamp,
+        params: &RiskParams,
+    ) -> StdResult<FeeQuote> {
+        fee_quote(
+            borrowing_rate(self.decayed(now, params)?, params),
+            ultra_debt,
+        )
+    }
+
+    /// Redemption fee for `coll_drawn` at the stored base rate
+    pub fn redemption_fee(
+        &self,
+        coll_drawn: Uint128,
+        params: &RiskParams,
+    ) -> Result<FeeQuote, BaseRateError> {
+        redemption_fee_quote(redemption_rate(self.base_rate, params), coll_drawn)
+    }
+
+    /// Redemption fee for `coll_drawn` at the base rate decayed until `now`
+    pub fn redemption_fee_with_decay(
+        &self,
+        coll_drawn: Uint128,
+        now: Timestamp,
+        params: &RiskParams,
+    ) -> Result<FeeQuote, BaseRateError> {
+        redemption_fee_quote(
+            redemption_rate(self.decayed(now, params)?, params),
+            coll_drawn,
+        )
+    }
+
+    // Only move forward by whole minutes, so that frequent operations can't prevent the decay
+    fn update_last_fee_operation_time(&mut self, now: Timestamp) {
+        let time_passed = now
+            .seconds()
+            .saturating_sub(self.last_fee_operation_time.seconds());
+        if time_passed >= SECONDS_IN_ONE_MINUTE {
+            self.last_fee_operation_time = now;
+        }
+    }
+}
+
+/// Borrowing floor added to `base_rate`, capped to the max borrowing fee
+pub fn borrowing_rate(base_rate: Decimal256, params: &RiskParams) -> Decimal256 {
+    min(
+        params.borrowing_fee_floor + base_rate,
+        params.max_borrowing_fee,
+    )
+}
+
+/// Redemption floor added to `base_rate`, capped to 100%
+pub fn redemption_rate(base_rate: Decimal256, params: &RiskParams) -> Decimal256 {
+    min(params.redemption_fee_floor + base_rate, Decimal256::one())
+}
+
+/// Factor by which the base rate decays every minute, so that it halves every `half_life`
+/// seconds. It's found by bisection, as the largest factor decaying by at least half, so it's
+/// computed once when the half-life is set.
+pub fn minute_decay_factor(half_life: u64) -> StdResult<Decimal256> {
+    let minutes = (half_life / SECONDS_IN_ONE_MINUTE).max(1);
+    let half = Decimal256::percent(50);
+
+    let (mut lo, mut hi) = (Uint256::zero(), DECIMAL_PRECISION);
+    while hi - lo > Uint256::from(1u8) {
+        let mid = (lo + hi) / Uint256::from(2u8);
+        if dec_pow(Decimal256::new(mid), minutes)? > half {
+            hi = mid;
+        } else {
+            lo = mid;
+        }
+    }
+    Ok(Decimal256::new(lo))
+}
+
+/// Checks the max fee percentage accepted by a borrower, which can't be below the borrowing
+/// fee floor outside of Recovery Mode
+pub fn require_valid_max_fee_percentage(
+    max_fee_percentage: Decimal256,
+    recovery_mode: bool,
+    params: &RiskParams,
+) -> Result<(), BaseRateError> {
+    let min_fee_percentage = if recovery_mode {
+        Decimal256::zero()
+    } else {
+        params.borrowing_fee_floor
+    };
+    if max_fee_percentage < min_fee_percentage || max_fee_percentage > Decimal256::one() {
+        return Err(BaseRateError::InvalidMaxFeePercentage {
+            min: min_fee_percentage,
+        });
+    }
+    Ok(())
+}
+
+/// Checks `fee` is not a greater share of `amount` than the borrower accepted
+pub fn require_user_accepts_fee(
+    fee: Uint128,
+    amount: Uint128,
+    max_fee_percentage: Decimal256,
+) -> Result<(), BaseRateError> {
+    if !amount.is_zero() && Decimal256::from_ratio(fee, amount) > max_fee_percentage {
+        return Err(BaseRateError::FeeExceedsMaximum {});
+    }
+    Ok(())
+}
+
+fn fee_quote(rate: Decimal256, amount: Uint128) -> StdResult<FeeQuote> {
+    let fee = decimal256_to_uint128(dec_mul(rate, uint128_to_decimal256(amount))?)?;
+    Ok(FeeQuote { rate, fee })
+}
+
+fn redemption_fee_quote(rate: Decimal256, coll_drawn: Uint128) -> Result<FeeQuote, BaseRateError> {
+    let quote = fee_quote(rate, coll_drawn)?;
+    if quote.fee >= coll_drawn {
+        return Err(BaseRateError::FeeEatsUpCollateral {});
+    }
+    Ok(quote)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::ultra_math::get_absolute_difference;
+
+    /// Twelve hours, the default half-life
+    const HALF_LIFE: u64 = 12 * 60 * 60;
+
+    fn at(seconds: u64) -> Timestamp {
+        Timestamp::from_seconds(seconds)
+    }
+
+    #[test]
+    fn decay_factor_matches_liquity() {
+        // Liquity's MINUTE_DECAY_FACTOR for a 12 hours half-life
+        let liquity_factor = Decimal256::new(Uint256::from(999_037_758_833_783_000u128));
+        let factor = minute_decay_factor(HALF_LIFE).unwrap();
+        assert!(get_absolute_difference(factor, liquity_factor) < Decimal256::raw(1_000));
+
+        assert_eq!(minute_decay_factor(60).unwrap(), Decimal256::percent(50));
+        // Half-lives shorter than a minute decay by half every minute
+        assert_eq!(minute_decay_factor(1).unwrap(), Decimal256::percent(50));
+    }
+
+    #[test]
+    fn base_rate_halves_every_half_life() {
+        let params = RiskParams::default();
+        let base_rate = BaseRate {
+            base_rate: Decimal256::percent(10),
+            last_fee_operation_time: at(1_000),
+        };
+        // Less than a minute doesn't decay
+        assert_eq!(
+            base_rate.decayed(at(1_059), &params).unwrap(),
+            Decimal256::percent(10)
+        );
+        for (elapsed, expected) in [
+            (HALF_LIFE, Decimal256::percent(5)),
+            (2 * HALF_LIFE, Decimal256::permille(25)),
+        ] {
+            let decayed = base_rate.decayed(at(1_000 + elapsed), &params).unwrap();
+            assert!(get_absolute_difference(decayed, expected) < Decimal256::raw(1_000_000));
+        }
+    }
+
+    #[test]
+    fn redemption_increases_base_rate() {
+        let params = RiskParams::default();
+        let mut base_rate = BaseRate::default();
+
+        // Redeeming 10% of the supply adds 5% to the base rate
+        let new_base_rate = base_rate
+            .update_from_redemption(
+                Uint128::new(50),
+                Decimal256::percent(200),
+                Uint128::new(1_000),
+                at(120),
+                &params,
+            )
+            .unwrap();
+        assert_eq!(new_base_rate, Decimal256::percent(5));
+        assert_eq!(
+            base_rate,
+            BaseRate {
+                base_rate: Decimal256::percent(5),
+                last_fee_operation_time: at(120),
+            }
+        );
+
+        // The base rate is capped to 100%
+        let new_base_rate = base_rate
+            .update_from_redemption(
+                Uint128::new(2_000),
+                Decimal256::one(),
+                Uint128::new(1_000),
+                at(150),
+                &params,
+            )
+            .unwrap();
+        assert_eq!(new_base_rate, Decimal256::one());
+        // Less than a minute since the last fee operation keeps its time
+        assert_eq!(base_rate.last_fee_operation_time, at(120));
+
+        let err = base_rate
+            .update_from_redemption(
+                Uint128::new(1),
+                Decimal256::one(),
+                Uint128::zero(),
+                at(180),
+                &params,
+            )
+            .unwrap_err();
+        assert_eq!(err, BaseRateError::NoUltraSupply {});
+    }
+
+    #[test]
+    fn borrowing_decays_base_rate() {
+        let params = RiskParams::default();
+        let mut base_rate = BaseRate {
+            base_rate: Decimal256::percent(4),
+            last_fee_operation_time: at(0),
+        };
+        base_rate
+            .decay_from_borrowing(at(HALF_LIFE), &params)
+            .unwrap();
+        assert!(
+            get_absolute_difference(base_rate.base_rate, Decimal256::percent(2))
+                < Decimal256::raw(1_000_000)
+        );
+        assert_eq!(base_rate.last_fee_operation_time, at(HALF_LIFE));
+    }
+
+    #[test]
+    fn borrowing_fees() {
+        let params = RiskParams::default();
+        let mut base_rate = BaseRate::default();
+        // The floor applies without base rate
+        assert_eq!(
+            base_rate
+                .borrowing_fee(Uint128::new(10_000), &params)
+                .unwrap(),
+            FeeQuote {
+                rate: Decimal256::permille(5),
+                fee: Uint128::new(50),
+            }
+        );
+
+        // The rate is capped to the max borrowing fee
+        base_rate.base_rate = Decimal256::percent(20);
+        assert_eq!(
+            base_rate
+                .borrowing_fee(Uint128::new(10_000), &params)
+                .unwrap(),
+            FeeQuote {
+                rate: Decimal256::percent(5),
+                fee: Uint128::new(500),
+            }
+        );
+
+        base_rate.base_rate = Decimal256::percent(2);
+        let quote = base_rate
+            .borrowing_fee_with_decay(Uint128::new(10_000), at(HALF_LIFE), &params)
+            .unwrap();
+        // The decay factor decays by at least half, so the fee is just below 1.5%
+        assert_eq!(quote.fee, Uint128::new(149));
+    }
+
+    #[test]
+    fn redemption_fees() {
+        let params = RiskParams::default();
+        let mut base_rate = BaseRate {
+            base_rate: Decimal256::percent(10),
+            last_fee_operation_time: at(0),
+        };
+        assert_eq!(
+            base_rate
+                .redemption_fee(Uint128::new(1_000), &params)
+                .unwrap(),
+            FeeQuote {
+                rate: Decimal256::permille(105),
+                fee: Uint128::new(105),
+            }
+        );
+        let quote = base_rate
+            .redemption_fee_with_decay(Uint128::new(1_000), at(HALF_LIFE), &params)
+            .unwrap();
+        // The decay factor decays by at least half, so the fee is just below 5.5%
+        assert_eq!(quote.fee, Uint128::new(54));
+
+        base_rate.base_rate = Decimal256::one();
+        let err = base_rate
+            .redemption_fee(Uint128::new(1_000), &params)
+            .unwrap_err();
+        assert_eq!(err, BaseRateError::FeeEatsUpCollateral {});
+    }
+
+    #[test]
+    fn max_fee_percentage() {
+        let params = RiskParams::default();
+        require_valid_max_fee_percentage(Decimal256::percent(1), false, &params).unwrap();
+        require_valid_max_fee_percentage(Decimal256::zero(), true, &params).unwrap();
+        assert_eq!(
+            require_valid_max_fee_percentage(Decimal256::permille(4), false, &params).unwrap_err(),
+            BaseRateError::InvalidMaxFeePercentage {
+                min: Decimal256::permille(5)
+            }
+        );
+        require_valid_max_fee_percentage(Decimal256::percent(101), true, &params).unwrap_err();
+
+        require_user_accepts_fee(
+            Uint128::new(50),
+            Uint128::new(1_000),
+            Decimal256::percent(5),
+        )
+        .unwrap();
+        assert_eq!(
+            require_user_accepts_fee(
+                Uint128::new(51),
+                Uint128::new(1_000),
+                Decimal256::percent(5)
+            )
+            .unwrap_err(),
+            BaseRateError::FeeExceedsMaximum {}
+        );
+    }
+}
@@ -1,7 +1,10 @@
 use cosmwasm_std::{Addr, Decimal256, Uint128};
+use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::asset::AssetInfo;
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMsg {
     pub name: String,
@@ -11,10 +14,16 @@ pub struct InstantiateMsg {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    /// Send JUNO as collateral to a trove
-    AddColl {},
+    /// Run the trove operation of the `Cw20HookMsg` on the trove of the sender, backed by the
+    /// received cw20 collateral
+    Receive(Cw20ReceiveMsg),
+    /// Send collateral to the trove of the sender backed by `collateral`
+    AddColl {
+        collateral: AssetInfo,
+    },
     /// Alongside a debt change, this function can perform either a collateral top-up or a collateral withdrawal.
     AdjustTrove {
+        collateral: AssetInfo,
         borrower: Addr,
         coll_withdrawal: Uint128,
         ultra_change: Uint128,
@@ -22,13 +31,20 @@ pub enum ExecuteMsg {
         max_fee_percentage: Decimal256,
     },
     /// Claim remaining collateral from a redemption or from a liquidation with ICR > MCR in Recovery Mode
-    ClaimCollateral {},
-    CloseTrove {},
-    /// Send JUNO as collateral to a trove. Called by only the Stability Pool.
-    MoveJUNOGainToTrove {
+    ClaimCollateral {
+        collateral: AssetInfo,
+    },
+    CloseTrove {
+        collateral: AssetInfo,
+    },
+    /// Send the collateral gains of a depositor to its trove. Called by only the Stability Pool.
+    MoveCollGainToTrove {
+        collateral: AssetInfo,
         borrower: Addr,
     },
+    /// Open a trove backed by `collateral`, drawing debt up to the debt ceiling of the collateral
     OpenTrove {
+        collateral: AssetInfo,
         max_fee_percentage: Decimal256,
         ultra_amount: Uint128,
     },
@@ -39,39 +55,71 @@ pub enum ExecuteMsg {
         account: Addr,
         ultra_amount: Uint128,
     },
-    SetAddresses {
-        trove_manager_address: String,
-        active_pool_address: String,
-        default_pool_address: String,
-        stability_pool_address: String,
-        coll_surplus_pool_address: String,
-        price_feed_contract_address: String,
-        ultra_token_address: String,
-        reward_pool_address: String,
+    /// Point the contract to the address registry its peers are resolved from.
+    /// Can only be called by the owner while the addresses are not locked
+    SetAddressRegistry {
+        address_registry: String,
     },
-    /// Withdraw JUNO collateral from a trove
+    /// Refresh the cached peer addresses from the address registry.
+    /// Can be called by anyone while the addresses are not locked
+    SyncAddresses {},
+    /// Propose a new owner, who becomes owner once it accepts.
+    /// Can only be called by the owner
+    ProposeOwner {
+        owner: String,
+    },
+    /// Become the owner. Can only be called by the pending owner
+    AcceptOwner {},
+    /// Leave the contract without owner. Can only be called by the owner
+    RenounceOwnership {},
+    /// Permanently prevent the addresses from being changed again.
+    /// Can only be called by the owner once the addresses are synced
+    LockAddresses {},
+    /// Withdraw collateral from a trove
     WithdrawColl {
+        collateral: AssetInfo,
         coll_amount: Uint128,
     },
     /// Withdraw ULTRA tokens from a trove: mint new ULTRA tokens to the owner, and increase the trove's debt accordingly
     WithdrawULTRA {
+        collateral: AssetInfo,
         max_fee_percentage: Uint128,
         ultra_amount: Uint128,
     },
 }
 
+/// Trove operations depositing cw20 collateral, the collateral being the received token
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Open a trove backed by the received collateral
+    OpenTrove {
+        max_fee_percentage: Decimal256,
+        ultra_amount: Uint128,
+    },
+    /// Add the received collateral to the trove of the sender
+    AddColl {},
+    /// Add the received collateral to the trove of the sender alongside a debt change
+    AdjustTrove {
+        ultra_change: Uint128,
+        is_debt_increase: bool,
+        max_fee_percentage: Decimal256,
+    },
+}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetParams {},
+    GetOwnership {},
     GetCompositeDebt { debt: Uint128 },
-    GetEntireSystemColl {},
-    GetEntireSystemDebt {},
-    GetActivePoolAddress {},
-    GetDefaultPoolAddress {},
+    GetEntireSystemColl { collateral: AssetInfo },
+    GetEntireSystemDebt { collateral: AssetInfo },
+    GetActivePoolAddress { collateral: AssetInfo },
+    GetDefaultPoolAddress { collateral: AssetInfo },
     GetTroveManagerAddress {},
     GetULTRATokenContractAddress {},
-    GetPriceFeedContractAddress {},
+    GetPriceFeedContractAddress { collateral: AssetInfo },
     GetRewardPoolAddress {},
 }
 
@@ -1,11 +1,16 @@
 use cosmwasm_std::{Addr, Uint128};
+use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::asset::AssetInfo;
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMsg {
     pub name: String,
     pub owner: String,
+    /// Collateral type held by the pool
+    pub collateral: AssetInfo,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -15,27 +20,70 @@ pub enum ExecuteMsg {
         account: Addr,
         amount: Uint128,
     },
+    /// Send the surplus of `account` to `recipient`, or to `account` itself if none is given.
+    /// Can only be called by borrower operations
     ClaimColl {
         account: Addr,
+        recipient: Option<Addr>,
+    },
+    /// Take the cw20 collateral sent with a `Cw20HookMsg::ReceiveCollateral`
+    Receive(Cw20ReceiveMsg),
+    /// Point the pool to the address registry its peers are resolved from.
+    /// Can only be called by the owner while the addresses are not locked
+    SetAddressRegistry {
+        address_registry: String,
     },
-    SetAddresses {
-        borrower_operations_address: String,
-        trove_manager_address: String,
-        active_pool_address: String,
+    /// Refresh the cached peer addresses from the address registry.
+    /// Can be called by anyone while the addresses are not locked
+    SyncAddresses {},
+    /// Propose a new owner, who becomes owner once it accepts.
+    /// Can only be called by the owner
+    ProposeOwner {
+        owner: String,
     },
+    /// Become the owner. Can only be called by the pending owner
+    AcceptOwner {},
+    /// Leave the pool without owner. Can only be called by the owner
+    RenounceOwnership {},
+    /// Permanently prevent the addresses from being changed again.
+    /// Can only be called by the owner once the addresses are synced
+    LockAddresses {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Take the collateral surplus, accounted afterwards by the trove manager.
+    /// Can only be sent by the active pool
+    ReceiveCollateral {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetParams {},
-    GetJUNO {},
-    GetCollateral { account: Addr },
+    /// Returns the owner, pending owner and addresses lock status
+    GetOwnership {},
+    GetTotalCollateral {},
+    /// Returns the collateral type held by the pool
+    GetCollateralInfo {},
+    /// Returns the claimable surplus of `account`, zero if it has none
+    GetCollateral {
+        account: Addr,
+    },
+    /// Lists the accounts with a claimable surplus
+    ListSurpluses {
+        start_after: Option<String>,
+        limit: Option<u32>,
+    },
     GetBorrowerOperationsAddress {},
     GetActivePoolAddress {},
     GetTroveManagerAddress {},
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SudoMsg {
@@ -52,3 +100,14 @@ pub struct ParamsResponse {
     pub name: String,
     pub owner: Addr,
 }
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SurplusResponse {
+    pub account: Addr,
+    pub amount: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SurplusesResponse {
+    pub surpluses: Vec<SurplusResponse>,
+}
@@ -0,0 +1,214 @@
+use cosmwasm_std::{Addr, Decimal256, StdError, Uint128};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::asset::AssetInfo;
+use crate::risk_params::{RiskParams, RiskParamsError};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum CollateralError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    RiskParams(#[from] RiskParamsError),
+
+    #[error("Collateral: MCR must be greater than 100%")]
+    McrTooLow {},
+
+    #[error("Collateral: Debt of {collateral} would exceed its debt ceiling of {debt_ceiling}")]
+    DebtCeilingExceeded {
+        collateral: String,
+        debt_ceiling: Uint128,
+    },
+}
+
+/// Contracts dedicated to a collateral type, troves being opened against a single collateral
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralContracts {
+    pub active_pool: Addr,
+    pub default_pool: Addr,
+    pub coll_surplus_pool: Addr,
+    pub sorted_troves: Addr,
+    /// Price source of the collateral in ULTRA
+    pub price_feed: Addr,
+}
+
+/// Risk parameters of a collateral type, set by governance
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralParams {
+    /// Minimum collateral ratio of the troves backed by the collateral
+    pub mcr: Decimal256,
+    /// Maximum ULTRA debt backed by the collateral, zero stops new debt from being drawn
+    pub debt_ceiling: Uint128,
+}
+
+/// Parameters to change, the missing ones keep their current value
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct CollateralParamsUpdate {
+    pub mcr: Option<Decimal256>,
+    pub debt_ceiling: Option<Uint128>,
+}
+
+impl CollateralParams {
+    pub fn validate(&self) -> Result<(), CollateralError> {
+        if self.mcr <= Decimal256::one() {
+            return Err(CollateralError::McrTooLow {});
+        }
+        Ok(())
+    }
+
+    /// Returns the parameters with `update` applied, failing if the result is invalid
+    pub fn apply(&self, update: CollateralParamsUpdate) -> Result<Self, CollateralError> {
+        let params = CollateralParams {
+            mcr: update.mcr.unwrap_or(self.mcr),
+            debt_ceiling: update.debt_ceiling.unwrap_or(self.debt_ceiling),
+        };
+        params.validate()?;
+        Ok(params)
+    }
+}
+
+/// Collateral type registered in the address registry.
+///
+/// Troves are keyed by their collateral and their owner, the trove manager and the borrower
+/// operations being shared by every collateral type.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Collateral {
+    pub asset: AssetInfo,
+    pub contracts: CollateralContracts,
+    pub params: CollateralParams,
+}
+
+impl Collateral {
+    /// Key the collateral is registered under: the denom of native tokens, the contract
+    /// address of cw20 tokens
+    pub fn key(&self) -> String {
+        self.asset.to_string()
+    }
+
+    /// Protocol risk parameters applying to the troves of the collateral, failing if its MCR
+    /// is inconsistent with them
+    pub fn risk_params(&self, protocol: &RiskParams) -> Result<RiskParams, CollateralError> {
+        let params = RiskParams {
+            mcr: self.params.mcr,
+            ..protocol.clone()
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Checks that `debt_increase` on top of the `total_debt` backed by the collateral stays
+    /// within its debt ceiling, and returns the new total debt
+    pub fn check_debt_ceiling(
+        &self,
+        total_debt: Uint128,
+        debt_increase: Uint128,
+    ) -> Result<Uint128, CollateralError> {
+        let new_debt = total_debt
+            .checked_add(debt_increase)
+            .map_err(StdError::overflow)?;
+        if !debt_increase.is_zero() && new_debt > self.params.debt_ceiling {
+            return Err(CollateralError::DebtCeilingExceeded {
+                collateral: self.key(),
+                debt_ceiling: self.params.debt_ceiling,
+            });
+        }
+        Ok(new_debt)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{native_asset_info, UJUNO_DENOM};
+
+    fn juno(debt_ceiling: u128) -> Collateral {
+        Collateral {
+            asset: native_asset_info(UJUNO_DENOM.to_string()),
+            contracts: CollateralContracts {
+                active_pool: Addr::unchecked("active-pool"),
+                default_pool: Addr::unchecked("default-pool"),
+                coll_surplus_pool: Addr::unchecked("coll-surplus-pool"),
+                sorted_troves: Addr::unchecked("sorted-troves"),
+                price_feed: Addr::unchecked("price-feed"),
+            },
+            params: CollateralParams {
+                mcr: Decimal256::percent(110),
+                debt_ceiling: Uint128::new(debt_ceiling),
+            },
+        }
+    }
+
+    #[test]
+    fn params_updates() {
+        let params = juno(1_000).params;
+        assert_eq!(
+            params
+                .apply(CollateralParamsUpdate {
+                    mcr: Some(Decimal256::percent(130)),
+                    debt_ceiling: None,
+                })
+                .unwrap(),
+            CollateralParams {
+                mcr: Decimal256::percent(130),
+                debt_ceiling: Uint128::new(1_000),
+            }
+        );
+        assert_eq!(
+            params
+                .apply(CollateralParamsUpdate {
+                    mcr: Some(Decimal256::one()),
+                    debt_ceiling: Some(Uint128::zero()),
+                })
+                .unwrap_err(),
+            CollateralError::McrTooLow {}
+        );
+    }
+
+    #[test]
+    fn debt_ceiling() {
+        let collateral = juno(1_000);
+        assert_eq!(
+            collateral
+                .check_debt_ceiling(Uint128::new(600), Uint128::new(400))
+                .unwrap(),
+            Uint128::new(1_000)
+        );
+        assert_eq!(
+            collateral
+                .check_debt_ceiling(Uint128::new(600), Uint128::new(401))
+                .unwrap_err(),
+            CollateralError::DebtCeilingExceeded {
+                collateral: UJUNO_DENOM.to_string(),
+                debt_ceiling: Uint128::new(1_000),
+            }
+        );
+
+        // Troves can still be adjusted without drawing debt once the ceiling is lowered
+        let collateral = juno(0);
+        assert_eq!(
+            collateral
+                .check_debt_ceiling(Uint128::new(600), Uint128::zero())
+                .unwrap(),
+            Uint128::new(600)
+        );
+    }
+
+    #[test]
+    fn collateral_mcr_overrides_protocol_mcr() {
+        let mut collateral = juno(1_000);
+        collateral.params.mcr = Decimal256::percent(130);
+        let params = collateral.risk_params(&RiskParams::default()).unwrap();
+        assert_eq!(params.mcr, Decimal256::percent(130));
+        assert_eq!(params.ccr, RiskParams::default().ccr);
+
+        // The CCR must stay above the MCR of the collateral
+        collateral.params.mcr = Decimal256::percent(150);
+        assert_eq!(
+            collateral.risk_params(&RiskParams::default()).unwrap_err(),
+            CollateralError::RiskParams(RiskParamsError::CcrNotAboveMcr {})
+        );
+    }
+}
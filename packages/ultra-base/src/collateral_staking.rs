@@ -0,0 +1,635 @@
+use cosmwasm_std::{Decimal256, StdError, StdResult, Timestamp, Uint128, Uint256};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::ultra_math::{min, uint256_to_uint128};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum CollateralStakingError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("ActivePool: Staked ratio must be in range [0,1]")]
+    InvalidStakedRatio {},
+
+    #[error("ActivePool: Staking requires at least one validator")]
+    NoValidators {},
+
+    #[error("ActivePool: Validator {validator} is listed more than once")]
+    DuplicateValidator { validator: String },
+}
+
+/// Unbonding entries the chain allows between a delegator and a validator. The pool never has
+/// more unbondings pending, each one holding at most an entry per validator
+pub const MAX_UNBONDINGS: usize = 7;
+
+/// Staking mode of the active pool, which delegates `staked_ratio` of its JUNO collateral
+/// across `validators` and keeps the rest liquid for withdrawals and liquidations
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StakingConfig {
+    pub validators: Vec<String>,
+    pub staked_ratio: Decimal256,
+    /// Unbonding period of the chain in seconds, as contracts can't query it.
+    /// Must not be shorter than the chain's, or the unbonding entries could run out
+    pub unbonding_period: u64,
+    /// Smallest delegation or undelegation made by a rebalance, so that rebalancing after
+    /// every small collateral change can't use up the unbonding entries
+    pub min_rebalance: Uint128,
+}
+
+impl StakingConfig {
+    pub fn validate(&self) -> Result<(), CollateralStakingError> {
+        if self.staked_ratio > Decimal256::one() {
+            return Err(CollateralStakingError::InvalidStakedRatio {});
+        }
+        if self.validators.is_empty() {
+            return Err(CollateralStakingError::NoValidators {});
+        }
+        for (i, validator) in self.validators.iter().enumerate() {
+            if self.validators[..i].contains(validator) {
+                return Err(CollateralStakingError::DuplicateValidator {
+                    validator: validator.clone(),
+                });
+            }
+        }
+        Ok(())
+    }
+}
+
+/// JUNO undelegated by the pool, back in its bank balance at `completion_time`
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Unbonding {
+    pub amount: Uint128,
+    pub completion_time: Timestamp,
+}
+
+/// JUNO delegated by a validator of the pool
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ValidatorDelegation {
+    pub validator: String,
+    pub amount: Uint128,
+}
+
+/// Delegation needed to bring the staked JUNO back to its target
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Rebalance {
+    Delegate(Uint128),
+    Undelegate(Uint128),
+    Balanced,
+}
+
+/// Collateral of the active pool out of its bank balance, and the staking rewards it holds
+/// for the trove owners.
+///
+/// Each trove earns `coll * (reward_per_unit_coll - snapshot)` of staking rewards since its
+/// snapshot was last updated, which must happen whenever its collateral changes. It loses
+/// `coll * (slash_per_unit_coll - snapshot)` of collateral to the slashing of the delegations
+/// the same way.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct CollateralStaking {
+    pub delegated: Uint128,
+    pub unbonding: Vec<Unbonding>,
+    /// Collateral sent out while it was delegated or unbonding, paid out once liquid again
+    pub queued_payouts: Uint128,
+    /// Staking rewards distributed but not yet paid out to the trove owners
+    pub rewards_held: Uint128,
+    pub reward_per_unit_coll: Decimal256,
+    /// Missing from pools staking before slashing was accounted for
+    #[serde(default)]
+    pub slash_per_unit_coll: Decimal256,
+}
+
+impl CollateralStaking {
+    pub fn total_unbonding(&self) -> StdResult<Uint128> {
+        self.unbonding
+            .iter()
+            .try_fold(Uint128::zero(), |total, unbonding| {
+                total.checked_add(unbonding.amount)
+            })
+            .map_err(StdError::overflow)
+    }
+
+    /// Removes the unbondings completed at `now`, and returns the JUNO they released
+    pub fn mature_unbondings(&mut self, now: Timestamp) -> StdResult<Uint128> {
+        let (matured, pending) = self
+            .unbonding
+            .drain(..)
+            .partition::<Vec<_>, _>(|unbonding| unbonding.completion_time <= now);
+        self.unbonding = pending;
+        matured
+            .iter()
+            .try_fold(Uint128::zero(), |total, unbonding| {
+                total.checked_add(unbonding.amount)
+            })
+            .map_err(StdError::overflow)
+    }
+
+    /// Collateral in the bank balance of the pool that can be sent, given the recorded
+    /// collateral `coll`. The queued payouts come first, the collateral being all
+    /// delegated or unbonding while they are not paid
+    pub fn liquid_coll(&self, coll: Uint128) -> StdResult<Uint128> {
+        Ok(coll.saturating_sub(self.delegated.checked_add(self.total_unbonding()?)?))
+    }
+
+    /// Queued payouts in the bank balance of the pool, given the recorded collateral `coll`
+    pub fn liquid_payouts(&self, coll: Uint128) -> StdResult<Uint128> {
+        let liquid = coll
+            .checked_add(self.queued_payouts)?
+            .saturating_sub(self.delegated.checked_add(self.total_unbonding()?)?);
+        Ok(min(liquid, self.queued_payouts))
+    }
+
+    /// Delegation bringing the staked JUNO to `staked_ratio` of `coll`, no more than the
+    /// liquid collateral being delegated. Without staking config everything is undelegated.
+    ///
+    /// Changes smaller than `min_rebalance` wait, unless staking is unwound or the queued
+    /// payouts need the JUNO, and undelegations wait while `MAX_UNBONDINGS` are pending
+    pub fn rebalance(&self, coll: Uint128, config: Option<&StakingConfig>) -> StdResult<Rebalance> {
+        let (target, min_rebalance) = match config {
+            Some(config) => (
+                uint256_to_uint128(Uint256::from(coll) * config.staked_ratio)?,
+                config.min_rebalance,
+            ),
+            None => (Uint128::zero(), Uint128::zero()),
+        };
+        let rebalance = if self.delegated < target {
+            let amount = min(target - self.delegated, self.liquid_coll(coll)?);
+            if amount.is_zero() || amount < min_rebalance {
+                Rebalance::Balanced
+            } else {
+                Rebalance::Delegate(amount)
+            }
+        } else if self.delegated > target {
+            let amount = self.delegated - target;
+            // The queued payouts are short of JUNO while more is delegated than recorded
+            let required = target.is_zero() || self.delegated > coll;
+            if self.unbonding.len() >= MAX_UNBONDINGS || (amount < min_rebalance && !required) {
+                Rebalance::Balanced
+            } else {
+                Rebalance::Undelegate(amount)
+            }
+        } else {
+            Rebalance::Balanced
+        };
+        Ok(rebalance)
+    }
+
+    pub fn delegate(&mut self, amount: Uint128) -> StdResult<()> {
+        self.delegated = self.delegated.checked_add(amount)?;
+        Ok(())
+    }
+
+    pub fn undelegate(&mut self, amount: Uint128, completion_time: Timestamp) -> StdResult<()> {
+        self.delegated = self.delegated.checked_sub(amount)?;
+        self.unbonding.push(Unbonding {
+            amount,
+            completion_time,
+        });
+        Ok(())
+    }
+
+    /// Shares `rewards` between the troves, proportionally to their collateral. Returns
+    /// false, leaving the rewards undistributed, while there is no collateral
+    pub fn distribute_rewards(&mut self, rewards: Uint128, coll: Uint128) -> StdResult<bool> {
+        if rewards.is_zero() || coll.is_zero() {
+            return Ok(false);
+        }
+        self.reward_per_unit_coll += Decimal256::from_ratio(rewards, coll);
+        self.rewards_held = self.rewards_held.checked_add(rewards)?;
+        Ok(true)
+    }
+
+    /// Staking rewards earned by a trove holding `coll` since `snapshot`
+    pub fn pending_reward(&self, coll: Uint128, snapshot: Decimal256) -> StdResult<Uint128> {
+        if snapshot > self.reward_per_unit_coll {
+            return Err(StdError::generic_err(
+                "ActivePool: Snapshot is ahead of the staking rewards",
+            ));
+        }
+        let reward = Uint256::from(coll) * (self.reward_per_unit_coll - snapshot);
+        Ok(min(uint256_to_uint128(reward)?, self.rewards_held))
+    }
+
+    /// Removes `slashed` JUNO lost by the delegations, and writes it off the collateral `coll`
+    /// of the troves, proportionally to their collateral. Returns the collateral written off,
+    /// which the slash exceeds only when more is delegated than recorded for the troves
+    pub fn write_off_slash(&mut self, slashed: Uint128, coll: Uint128) -> StdResult<Uint128> {
+        self.delegated = self.delegated.checked_sub(slashed)?;
+        let written_off = min(slashed, coll);
+        if written_off.is_zero() {
+            return Ok(written_off);
+        }
+        self.slash_per_unit_coll += Decimal256::from_ratio(written_off, coll);
+        Ok(written_off)
+    }
+
+    /// Collateral lost to slashing by a trove holding `coll` since `snapshot`
+    pub fn pending_slash(&self, coll: Uint128, snapshot: Decimal256) -> StdResult<Uint128> {
+        if snapshot > self.slash_per_unit_coll {
+            return Err(StdError::generic_err(
+                "ActivePool: Snapshot is ahead of the slashing",
+            ));
+        }
+        let slash = Uint256::from(coll) * (self.slash_per_unit_coll - snapshot);
+        uint256_to_uint128(slash)
+    }
+
+    pub fn pay_reward(&mut self, amount: Uint128) -> StdResult<()> {
+        self.rewards_held = self.rewards_held.checked_sub(amount)?;
+        Ok(())
+    }
+
+    pub fn queue_payout(&mut self, amount: Uint128) -> StdResult<()> {
+        self.queued_payouts = self.queued_payouts.checked_add(amount)?;
+        Ok(())
+    }
+
+    pub fn pay_payout(&mut self, amount: Uint128) -> StdResult<()> {
+        self.queued_payouts = self.queued_payouts.checked_sub(amount)?;
+        Ok(())
+    }
+}
+
+/// Splits `amount` evenly between the validators, the first ones getting the remainder
+pub fn delegation_plan(validators: &[String], amount: Uint128) -> Vec<ValidatorDelegation> {
+    if validators.is_empty() {
+        return vec![];
+    }
+    let count = Uint128::from(validators.len() as u128);
+    let (share, remainder) = (amount / count, amount % count);
+    validators
+        .iter()
+        .enumerate()
+        .map(|(i, validator)| ValidatorDelegation {
+            validator: validator.clone(),
+            amount: if Uint128::from(i as u128) < remainder {
+                share + Uint128::new(1)
+            } else {
+                share
+            },
+        })
+        .filter(|delegation| !delegation.amount.is_zero())
+        .collect()
+}
+
+/// Takes `amount` from the current `delegations`, first from the validators that are no longer
+/// in `validators`, then from the largest delegations
+pub fn undelegation_plan(
+    delegations: &[ValidatorDelegation],
+    validators: &[String],
+    amount: Uint128,
+) -> Vec<ValidatorDelegation> {
+    let mut delegations = delegations.to_vec();
+    delegations.sort_by(|a, b| {
+        let (a_kept, b_kept) = (
+            validators.contains(&a.validator),
+            validators.contains(&b.validator),
+        );
+        a_kept.cmp(&b_kept).then(b.amount.cmp(&a.amount))
+    });
+
+    let mut left = amount;
+    let mut plan = vec![];
+    for delegation in delegations {
+        if left.is_zero() {
+            break;
+        }
+        let undelegated = min(delegation.amount, left);
+        if undelegated.is_zero() {
+            continue;
+        }
+        left -= undelegated;
+        plan.push(ValidatorDelegation {
+            validator: delegation.validator,
+            amount: undelegated,
+        });
+    }
+    plan
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn validators(addresses: &[&str]) -> Vec<String> {
+        addresses
+            .iter()
+            .map(|address| address.to_string())
+            .collect()
+    }
+
+    fn config(staked_ratio: Decimal256) -> StakingConfig {
+        StakingConfig {
+            validators: validators(&["val1", "val2"]),
+            staked_ratio,
+            unbonding_period: 100,
+            min_rebalance: Uint128::zero(),
+        }
+    }
+
+    #[test]
+    fn config_validation() {
+        config(Decimal256::percent(80)).validate().unwrap();
+        let err = config(Decimal256::percent(101)).validate().unwrap_err();
+        assert_eq!(err, CollateralStakingError::InvalidStakedRatio {});
+        let err = StakingConfig {
+            validators: vec![],
+            ..config(Decimal256::percent(80))
+        }
+        .validate()
+        .unwrap_err();
+        assert_eq!(err, CollateralStakingError::NoValidators {});
+        let err = StakingConfig {
+            validators: validators(&["val1", "val2", "val1"]),
+            ..config(Decimal256::percent(80))
+        }
+        .validate()
+        .unwrap_err();
+        assert_eq!(
+            err,
+            CollateralStakingError::DuplicateValidator {
+                validator: String::from("val1")
+            }
+        );
+    }
+
+    #[test]
+    fn rebalance_keeps_a_liquid_buffer() {
+        let config = config(Decimal256::percent(80));
+        let mut staking = CollateralStaking::default();
+        let coll = Uint128::new(1_000);
+        assert_eq!(
+            staking.rebalance(coll, Some(&config)).unwrap(),
+            Rebalance::Delegate(Uint128::new(800))
+        );
+        staking.delegate(Uint128::new(800)).unwrap();
+        assert_eq!(staking.liquid_coll(coll).unwrap(), Uint128::new(200));
+        assert_eq!(
+            staking.rebalance(coll, Some(&config)).unwrap(),
+            Rebalance::Balanced
+        );
+
+        // Withdrawals from the buffer are replenished by undelegating
+        let coll = Uint128::new(900);
+        assert_eq!(
+            staking.rebalance(coll, Some(&config)).unwrap(),
+            Rebalance::Undelegate(Uint128::new(80))
+        );
+        staking
+            .undelegate(Uint128::new(80), Timestamp::from_seconds(100))
+            .unwrap();
+        assert_eq!(staking.liquid_coll(coll).unwrap(), Uint128::new(100));
+        // The unbonding JUNO is not delegated again, nor more than the liquid collateral
+        assert_eq!(
+            staking
+                .rebalance(Uint128::new(1_000), Some(&config))
+                .unwrap(),
+            Rebalance::Delegate(Uint128::new(80))
+        );
+        assert_eq!(
+            staking
+                .rebalance(
+                    coll,
+                    Some(&StakingConfig {
+                        staked_ratio: Decimal256::one(),
+                        ..config.clone()
+                    })
+                )
+                .unwrap(),
+            Rebalance::Delegate(Uint128::new(100))
+        );
+
+        assert_eq!(
+            staking
+                .mature_unbondings(Timestamp::from_seconds(99))
+                .unwrap(),
+            Uint128::zero()
+        );
+        assert_eq!(
+            staking
+                .mature_unbondings(Timestamp::from_seconds(100))
+                .unwrap(),
+            Uint128::new(80)
+        );
+        assert_eq!(staking.unbonding, vec![]);
+
+        // Disabling staking undelegates everything
+        assert_eq!(
+            staking.rebalance(coll, None).unwrap(),
+            Rebalance::Undelegate(Uint128::new(720))
+        );
+    }
+
+    #[test]
+    fn rebalance_skips_small_changes() {
+        let config = StakingConfig {
+            min_rebalance: Uint128::new(50),
+            ..config(Decimal256::percent(80))
+        };
+        let mut staking = CollateralStaking::default();
+        staking.delegate(Uint128::new(790)).unwrap();
+        assert_eq!(
+            staking
+                .rebalance(Uint128::new(1_000), Some(&config))
+                .unwrap(),
+            Rebalance::Balanced
+        );
+        assert_eq!(
+            staking.rebalance(Uint128::new(950), Some(&config)).unwrap(),
+            Rebalance::Balanced
+        );
+        assert_eq!(
+            staking.rebalance(Uint128::new(900), Some(&config)).unwrap(),
+            Rebalance::Undelegate(Uint128::new(70))
+        );
+
+        // Unwinding undelegates whatever is left
+        assert_eq!(
+            staking
+                .rebalance(
+                    Uint128::new(1_000),
+                    Some(&StakingConfig {
+                        staked_ratio: Decimal256::zero(),
+                        min_rebalance: Uint128::new(1_000),
+                        ..config.clone()
+                    })
+                )
+                .unwrap(),
+            Rebalance::Undelegate(Uint128::new(790))
+        );
+
+        // No more undelegations than the chain allows entries
+        for i in 0..MAX_UNBONDINGS as u64 {
+            staking
+                .undelegate(Uint128::new(60), Timestamp::from_seconds(100 + i))
+                .unwrap();
+        }
+        assert_eq!(
+            staking.rebalance(Uint128::new(200), Some(&config)).unwrap(),
+            Rebalance::Balanced
+        );
+        staking
+            .mature_unbondings(Timestamp::from_seconds(100))
+            .unwrap();
+        assert_eq!(
+            staking.rebalance(Uint128::new(200), Some(&config)).unwrap(),
+            Rebalance::Undelegate(Uint128::new(210))
+        );
+    }
+
+    #[test]
+    fn queued_payouts_come_first() {
+        let config = StakingConfig {
+            min_rebalance: Uint128::new(1_000),
+            ..config(Decimal256::percent(80))
+        };
+        let mut staking = CollateralStaking::default();
+        staking.delegate(Uint128::new(800)).unwrap();
+
+        // 500 sent out of the 200 liquid
+        staking.queue_payout(Uint128::new(300)).unwrap();
+        let coll = Uint128::new(500);
+        assert_eq!(staking.liquid_coll(coll).unwrap(), Uint128::zero());
+        assert_eq!(staking.liquid_payouts(coll).unwrap(), Uint128::zero());
+
+        // The payouts are short of JUNO, whatever the minimum rebalance
+        assert_eq!(
+            staking.rebalance(coll, Some(&config)).unwrap(),
+            Rebalance::Undelegate(Uint128::new(400))
+        );
+        staking
+            .undelegate(Uint128::new(400), Timestamp::from_seconds(100))
+            .unwrap();
+        assert_eq!(staking.liquid_payouts(coll).unwrap(), Uint128::zero());
+        staking
+            .mature_unbondings(Timestamp::from_seconds(100))
+            .unwrap();
+        assert_eq!(staking.liquid_payouts(coll).unwrap(), Uint128::new(300));
+        assert_eq!(staking.liquid_coll(coll).unwrap(), Uint128::new(100));
+
+        staking.pay_payout(Uint128::new(300)).unwrap();
+        assert_eq!(staking.liquid_coll(coll).unwrap(), Uint128::new(100));
+        assert_eq!(staking.liquid_payouts(coll).unwrap(), Uint128::zero());
+        staking.pay_payout(Uint128::new(1)).unwrap_err();
+    }
+
+    #[test]
+    fn rewards_are_shared_by_collateral() {
+        let mut staking = CollateralStaking::default();
+        assert!(!staking
+            .distribute_rewards(Uint128::new(10), Uint128::zero())
+            .unwrap());
+
+        let snapshot = staking.reward_per_unit_coll;
+        assert!(staking
+            .distribute_rewards(Uint128::new(50), Uint128::new(1_000))
+            .unwrap());
+        assert_eq!(staking.rewards_held, Uint128::new(50));
+        assert_eq!(
+            staking.pending_reward(Uint128::new(400), snapshot).unwrap(),
+            Uint128::new(20)
+        );
+
+        staking.pay_reward(Uint128::new(20)).unwrap();
+        let snapshot = staking.reward_per_unit_coll;
+        assert_eq!(
+            staking.pending_reward(Uint128::new(400), snapshot).unwrap(),
+            Uint128::zero()
+        );
+        staking.pay_reward(Uint128::new(31)).unwrap_err();
+    }
+
+    #[test]
+    fn slashing_is_written_off_by_collateral() {
+        let mut staking = CollateralStaking::default();
+        staking.delegate(Uint128::new(800)).unwrap();
+
+        let snapshot = staking.slash_per_unit_coll;
+        let written_off = staking
+            .write_off_slash(Uint128::new(100), Uint128::new(1_000))
+            .unwrap();
+        assert_eq!(written_off, Uint128::new(100));
+        assert_eq!(staking.delegated, Uint128::new(700));
+        assert_eq!(
+            staking.pending_slash(Uint128::new(400), snapshot).unwrap(),
+            Uint128::new(40)
+        );
+        assert_eq!(
+            staking
+                .pending_slash(Uint128::new(400), staking.slash_per_unit_coll)
+                .unwrap(),
+            Uint128::zero()
+        );
+
+        // Only the recorded collateral can be written off
+        let written_off = staking
+            .write_off_slash(Uint128::new(300), Uint128::new(200))
+            .unwrap();
+        assert_eq!(written_off, Uint128::new(200));
+        assert_eq!(staking.delegated, Uint128::new(400));
+        staking
+            .write_off_slash(Uint128::new(401), Uint128::new(200))
+            .unwrap_err();
+    }
+
+    #[test]
+    fn delegation_plans() {
+        let vals = validators(&["val1", "val2", "val3"]);
+        assert_eq!(
+            delegation_plan(&vals, Uint128::new(5)),
+            vec![
+                ValidatorDelegation {
+                    validator: String::from("val1"),
+                    amount: Uint128::new(2)
+                },
+                ValidatorDelegation {
+                    validator: String::from("val2"),
+                    amount: Uint128::new(2)
+                },
+                ValidatorDelegation {
+                    validator: String::from("val3"),
+                    amount: Uint128::new(1)
+                },
+            ]
+        );
+        assert_eq!(delegation_plan(&vals, Uint128::new(1)).len(), 1);
+
+        // Validators removed from the set are undelegated first
+        let delegations = vec![
+            ValidatorDelegation {
+                validator: String::from("val1"),
+                amount: Uint128::new(100),
+            },
+            ValidatorDelegation {
+                validator: String::from("old"),
+                amount: Uint128::new(30),
+            },
+            ValidatorDelegation {
+                validator: String::from("val2"),
+                amount: Uint128::new(200),
+            },
+        ];
+        assert_eq!(
+            undelegation_plan(&delegations, &vals, Uint128::new(80)),
+            vec![
+                ValidatorDelegation {
+                    validator: String::from("old"),
+                    amount: Uint128::new(30)
+                },
+                ValidatorDelegation {
+                    validator: String::from("val2"),
+                    amount: Uint128::new(50)
+                },
+            ]
+        );
+        assert_eq!(
+            undelegation_plan(&delegations, &vals, Uint128::new(1_000))
+                .iter()
+                .map(|delegation| delegation.amount)
+                .sum::<Uint128>(),
+            Uint128::new(330)
+        );
+    }
+}
@@ -0,0 +1,212 @@
+use cosmwasm_std::{Addr, Decimal256, StdResult, Timestamp, Uint128, Uint256};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::base_rate::minute_decay_factor;
+use crate::ultra_math::{dec_pow, uint256_to_uint128, DECIMAL_PRECISION};
+
+const SECONDS_IN_ONE_MINUTE: u64 = 60;
+
+/// Half-life of a yearly halving issuance
+pub const SECONDS_IN_ONE_YEAR: u64 = 31_536_000;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct InstantiateMsg {
+    pub name: String,
+    pub owner: String,
+    /// cw20 token issued to the stability pool depositors, held by the contract
+    pub reward_token: String,
+    /// Amount of reward tokens issued over the whole life of the contract
+    pub issuance_cap: Uint128,
+    /// Seconds after which half of the reward tokens left to issue are issued
+    pub issuance_half_life: u64,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Send the reward tokens issued since the previous issuance to the stability pool.
+    /// Can only be called by the stability pool
+    IssueRewards {},
+    /// Point the contract to the address registry its peers are resolved from.
+    /// Can only be called by the owner while the addresses are not locked
+    SetAddressRegistry { address_registry: String },
+    /// Refresh the cached peer addresses from the address registry.
+    /// Can be called by anyone while the addresses are not locked
+    SyncAddresses {},
+    /// Propose a new owner, who becomes owner once it accepts.
+    /// Can only be called by the owner
+    ProposeOwner { owner: String },
+    /// Become the owner. Can only be called by the pending owner
+    AcceptOwner {},
+    /// Leave the contract without owner. Can only be called by the owner
+    RenounceOwnership {},
+    /// Permanently prevent the addresses from being changed again.
+    /// Can only be called by the owner once the addresses are synced
+    LockAddresses {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    GetParams {},
+    /// Returns the owner, pending owner and addresses lock status
+    GetOwnership {},
+    GetIssuanceCurve {},
+    /// Returns the reward tokens issued so far
+    GetTotalIssued {},
+    /// Returns the reward tokens the next `IssueRewards` in this block would issue
+    GetIssuableRewards {},
+    GetStabilityPoolAddress {},
+    GetRewardTokenAddress {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    /// Update the contract parameters
+    /// Can only be called by governance
+    UpdateParams {
+        name: Option<String>,
+        owner: Option<Addr>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ParamsResponse {
+    pub name: String,
+    pub owner: Addr,
+}
+
+/// Decaying issuance of the reward tokens.
+///
+/// The fraction of `issuance_cap` issued `t` minutes after deployment is `1 - f^t`, where `f`
+/// is the minute factor halving the tokens left to issue every half-life, as in Liquity's
+/// community issuance.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct IssuanceCurve {
+    pub issuance_cap: Uint128,
+    pub issuance_factor: Decimal256,
+    pub deployment_time: Timestamp,
+}
+
+impl IssuanceCurve {
+    pub fn new(
+        issuance_cap: Uint128,
+        half_life: u64,
+        deployment_time: Timestamp,
+    ) -> StdResult<Self> {
+        Ok(IssuanceCurve {
+            issuance_cap,
+            issuance_factor: minute_decay_factor(half_life)?,
+            deployment_time,
+        })
+    }
+
+    /// Fraction of the cap issued by `now`, counted in whole minutes since the deployment
+    pub fn cumulative_issuance_fraction(&self, now: Timestamp) -> StdResult<Decimal256> {
+        let minutes_passed =
+            now.seconds().saturating_sub(self.deployment_time.seconds()) / SECONDS_IN_ONE_MINUTE;
+        let power = dec_pow(self.issuance_factor, minutes_passed)?;
+        Ok(Decimal256::one() - power)
+    }
+
+    /// Reward tokens issued in total by `now`
+    pub fn total_issued_at(&self, now: Timestamp) -> StdResult<Uint128> {
+        let fraction = self.cumulative_issuance_fraction(now)?;
+        let total =
+            Uint256::from(self.issuance_cap).checked_mul(fraction.atomics())? / DECIMAL_PRECISION;
+        uint256_to_uint128(total)
+    }
+
+    /// Reward tokens to issue at `now`, given the `total_issued` so far
+    pub fn issuance(&self, now: Timestamp, total_issued: Uint128) -> StdResult<Uint128> {
+        Ok(self.total_issued_at(now)?.saturating_sub(total_issued))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::ultra_math::get_absolute_difference;
+
+    const CAP: u128 = 32_000_000_000_000;
+
+    fn curve() -> IssuanceCurve {
+        IssuanceCurve::new(
+            Uint128::new(CAP),
+            SECONDS_IN_ONE_YEAR,
+            Timestamp::from_seconds(1_000),
+        )
+        .unwrap()
+    }
+
+    fn assert_close(actual: Uint128, expected: u128) {
+        // Within one millionth of the expected issuance
+        assert!(
+            get_absolute_difference(actual.u128(), expected) <= expected / 1_000_000,
+            "issued {}, expected {}",
+            actual,
+            expected
+        );
+    }
+
+    #[test]
+    fn issuance_halves_every_year() {
+        let curve = curve();
+        let after = |seconds: u64| curve.deployment_time.plus_seconds(seconds);
+
+        assert_eq!(curve.total_issued_at(after(0)).unwrap(), Uint128::zero());
+        // Nothing is issued within the first minute
+        assert_eq!(curve.total_issued_at(after(59)).unwrap(), Uint128::zero());
+        assert_close(
+            curve.total_issued_at(after(SECONDS_IN_ONE_YEAR)).unwrap(),
+            CAP / 2,
+        );
+        assert_close(
+            curve
+                .total_issued_at(after(2 * SECONDS_IN_ONE_YEAR))
+                .unwrap(),
+            CAP / 4 * 3,
+        );
+        assert_close(
+            curve
+                .total_issued_at(after(5 * SECONDS_IN_ONE_YEAR))
+                .unwrap(),
+            CAP / 32 * 31,
+        );
+        // The cap is never exceeded
+        assert!(
+            curve
+                .total_issued_at(after(100 * SECONDS_IN_ONE_YEAR))
+                .unwrap()
+                <= Uint128::new(CAP)
+        );
+    }
+
+    #[test]
+    fn issuance_is_the_increase_of_the_total_issued() {
+        let curve = curve();
+        let mut total_issued = Uint128::zero();
+        let mut now = curve.deployment_time;
+        for _ in 0..12 {
+            now = now.plus_seconds(SECONDS_IN_ONE_YEAR / 12);
+            let issuance = curve.issuance(now, total_issued).unwrap();
+            assert!(!issuance.is_zero());
+            total_issued += issuance;
+        }
+        assert_eq!(total_issued, curve.total_issued_at(now).unwrap());
+        // Issuing again in the same block issues nothing
+        assert_eq!(curve.issuance(now, total_issued).unwrap(), Uint128::zero());
+        // Neither does a block before the deployment
+        assert_eq!(
+            curve
+                .issuance(Timestamp::from_seconds(0), Uint128::zero())
+                .unwrap(),
+            Uint128::zero()
+        );
+    }
+}
@@ -1,11 +1,16 @@
 use cosmwasm_std::{Addr, Uint128};
+use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::asset::AssetInfo;
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMsg {
     pub name: String,
     pub owner: String,
+    /// Collateral type held by the pool
+    pub collateral: AssetInfo,
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -17,25 +22,61 @@ pub enum ExecuteMsg {
     IncreaseULTRADebt {
         amount: Uint128,
     },
-    SendJUNOToActivePool {
+    SendCollateralToActivePool {
         amount: Uint128,
     },
-    SetAddresses {
-        trove_manager_address: String,
-        active_pool_address: String,
+    /// Record the native collateral sent along with this message by the active pool during
+    /// redistribution. Can only be called by the active pool
+    ReceiveCollateral {},
+    /// Record the cw20 collateral sent with a `Cw20HookMsg::ReceiveCollateral`
+    Receive(Cw20ReceiveMsg),
+    /// Point the pool to the address registry its peers are resolved from.
+    /// Can only be called by the owner while the addresses are not locked
+    SetAddressRegistry {
+        address_registry: String,
+    },
+    /// Refresh the cached peer addresses from the address registry.
+    /// Can be called by anyone while the addresses are not locked
+    SyncAddresses {},
+    /// Propose a new owner, who becomes owner once it accepts.
+    /// Can only be called by the owner
+    ProposeOwner {
+        owner: String,
     },
+    /// Become the owner. Can only be called by the pending owner
+    AcceptOwner {},
+    /// Leave the pool without owner. Can only be called by the owner
+    RenounceOwnership {},
+    /// Permanently prevent the addresses from being changed again.
+    /// Can only be called by the owner once the addresses are synced
+    LockAddresses {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Record the collateral received during redistribution.
+    /// Can only be sent by the active pool
+    ReceiveCollateral {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetParams {},
-    GetJUNO {},
+    /// Returns the owner, pending owner and addresses lock status
+    GetOwnership {},
+    GetCollateral {},
+    /// Returns the collateral type held by the pool
+    GetCollateralInfo {},
     GetULTRADebt {},
     GetActivePoolAddress {},
     GetTroveManagerAddress {},
 }
 
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum SudoMsg {
@@ -0,0 +1,78 @@
+use cosmwasm_std::{Addr, Uint256};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::collateral::CollateralParams;
+
+/// Code IDs of the stored protocol contracts
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct CodeIds {
+    pub address_registry: u64,
+    pub ultra_token: u64,
+    pub active_pool: u64,
+    pub default_pool: u64,
+    pub coll_surplus_pool: u64,
+    pub trove_manager: u64,
+    pub borrower_operations: u64,
+    pub stability_pool: u64,
+    pub sorted_troves: u64,
+    pub hint_helpers: u64,
+    pub price_feed: u64,
+    pub gas_pool: u64,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct UltraTokenParams {
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct InstantiateMsg {
+    /// Owner and admin of the deployed contracts, it has to accept the ownership of each of them
+    pub owner: String,
+    pub code_ids: CodeIds,
+    pub ultra_token: UltraTokenParams,
+    /// Maximum number of troves in the sorted troves list
+    pub sorted_troves_size: Uint256,
+    /// Junoswap pool the price feed reads the JUNO price from
+    pub price_feed_pool_address: String,
+    /// Risk parameters of JUNO, the collateral type of the deployed pools
+    pub juno_collateral: CollateralParams,
+    /// Reward pool the protocol fees go to. It is set up with the reward token, so it is
+    /// deployed beforehand and only registered by the factory
+    pub reward_pool: String,
+    /// Community issuance rewarding the stability pool depositors. It holds the reward tokens
+    /// to issue, so it is deployed beforehand and only registered by the factory
+    pub community_issuance: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Returns the addresses of the deployed contracts
+    GetDeployment {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct DeploymentResponse {
+    pub owner: Addr,
+    pub address_registry: Addr,
+    pub ultra_token: Addr,
+    pub active_pool: Addr,
+    pub default_pool: Addr,
+    pub coll_surplus_pool: Addr,
+    pub trove_manager: Addr,
+    pub borrower_operations: Addr,
+    pub stability_pool: Addr,
+    pub sorted_troves: Addr,
+    pub hint_helpers: Addr,
+    pub price_feed: Addr,
+    pub reward_pool: Addr,
+    pub gas_pool: Addr,
+    pub community_issuance: Addr,
+}
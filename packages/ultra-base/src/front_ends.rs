@@ -0,0 +1,236 @@
+use cosmwasm_std::{Addr, Decimal256, StdError, StdResult, Storage, Uint128};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::stability_accounting::{DepositSnapshot, StabilityPoolSums};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum FrontEndError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("StabilityPool: Kickback rate must be in range [0,1]")]
+    InvalidKickbackRate {},
+
+    #[error("StabilityPool: Must not already be a registered front end")]
+    AlreadyRegistered {},
+
+    #[error("StabilityPool: User must have no deposit")]
+    HasDeposit {},
+
+    #[error("StabilityPool: Tag must be a registered front end, or none")]
+    NotRegistered {},
+}
+
+/// Front end operator tagging the deposits made through its UI.
+///
+/// Depositors get `kickback_rate` of the reward tokens earned by their deposit, the front end
+/// keeps the rest.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct FrontEnd {
+    pub kickback_rate: Decimal256,
+    pub registered: bool,
+}
+
+impl FrontEnd {
+    /// Registers a front end, which can't be a depositor nor change its kickback rate later
+    pub fn register(
+        existing: Option<&FrontEnd>,
+        has_deposit: bool,
+        kickback_rate: Decimal256,
+    ) -> Result<FrontEnd, FrontEndError> {
+        if matches!(existing, Some(front_end) if front_end.registered) {
+            return Err(FrontEndError::AlreadyRegistered {});
+        }
+        if has_deposit {
+            return Err(FrontEndError::HasDeposit {});
+        }
+        if kickback_rate > Decimal256::one() {
+            return Err(FrontEndError::InvalidKickbackRate {});
+        }
+        Ok(FrontEnd {
+            kickback_rate,
+            registered: true,
+        })
+    }
+}
+
+/// Checks that a deposit is tagged with a registered front end
+pub fn require_registered(front_end: Option<&FrontEnd>) -> Result<&FrontEnd, FrontEndError> {
+    match front_end {
+        Some(front_end) if front_end.registered => Ok(front_end),
+        _ => Err(FrontEndError::NotRegistered {}),
+    }
+}
+
+/// Checks that a depositor is not a registered front end
+pub fn require_not_registered(front_end: Option<&FrontEnd>) -> Result<(), FrontEndError> {
+    if matches!(front_end, Some(front_end) if front_end.registered) {
+        return Err(FrontEndError::AlreadyRegistered {});
+    }
+    Ok(())
+}
+
+/// Share of the reward gains left to a depositor, all of them if its deposit is untagged
+pub fn kickback_rate(front_end: Option<&FrontEnd>) -> Decimal256 {
+    front_end.map_or(Decimal256::one(), |front_end| front_end.kickback_rate)
+}
+
+/// Front end of a deposit after a top-up tagged with `requested`. Deposits keep the tag of
+/// their first top-up until they are fully withdrawn
+pub fn deposit_front_end_tag(
+    initial_deposit: Uint128,
+    current: Option<Addr>,
+    requested: Option<Addr>,
+) -> Option<Addr> {
+    if initial_deposit.is_zero() {
+        requested
+    } else {
+        current
+    }
+}
+
+/// Sum of the deposits tagged with a front end, compounded by the offsets alongside them
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct FrontEndStake {
+    pub stake: Uint128,
+    pub snapshot: DepositSnapshot,
+}
+
+impl FrontEndStake {
+    pub fn compounded_stake(&self, sums: &StabilityPoolSums) -> StdResult<Uint128> {
+        sums.compounded_stake(self.stake, &self.snapshot)
+    }
+
+    /// Reward tokens the front end earned since its stake was last updated
+    pub fn reward_gain(
+        &self,
+        store: &dyn Storage,
+        sums: &StabilityPoolSums,
+        front_end: &FrontEnd,
+    ) -> StdResult<Uint128> {
+        sums.front_end_reward_gain(store, self.stake, &self.snapshot, front_end.kickback_rate)
+    }
+
+    /// Sets the stake to its compounded value plus `deposit_increase` less
+    /// `deposit_decrease`, and snapshots the sums. The gains must be paid out beforehand
+    pub fn update(
+        &mut self,
+        store: &dyn Storage,
+        sums: &StabilityPoolSums,
+        deposit_increase: Uint128,
+        deposit_decrease: Uint128,
+    ) -> StdResult<Uint128> {
+        let new_stake = self
+            .compounded_stake(sums)?
+            .checked_add(deposit_increase)?
+            .checked_sub(deposit_decrease)?;
+        self.stake = new_stake;
+        self.snapshot = if new_stake.is_zero() {
+            DepositSnapshot::default()
+        } else {
+            sums.snapshot(store)?
+        };
+        Ok(new_stake)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{native_asset_info, UJUNO_DENOM};
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn registration() {
+        let rate = Decimal256::percent(80);
+        let front_end = FrontEnd::register(None, false, rate).unwrap();
+        assert_eq!(
+            front_end,
+            FrontEnd {
+                kickback_rate: rate,
+                registered: true,
+            }
+        );
+        assert_eq!(kickback_rate(Some(&front_end)), rate);
+        assert_eq!(kickback_rate(None), Decimal256::one());
+
+        let err = FrontEnd::register(Some(&front_end), false, rate).unwrap_err();
+        assert_eq!(err, FrontEndError::AlreadyRegistered {});
+        let err = FrontEnd::register(None, true, rate).unwrap_err();
+        assert_eq!(err, FrontEndError::HasDeposit {});
+        let err = FrontEnd::register(None, false, Decimal256::percent(101)).unwrap_err();
+        assert_eq!(err, FrontEndError::InvalidKickbackRate {});
+        FrontEnd::register(None, false, Decimal256::zero()).unwrap();
+        FrontEnd::register(None, false, Decimal256::one()).unwrap();
+
+        // Front ends can tag deposits but not deposit themselves
+        require_registered(Some(&front_end)).unwrap();
+        let err = require_registered(None).unwrap_err();
+        assert_eq!(err, FrontEndError::NotRegistered {});
+        let err = require_not_registered(Some(&front_end)).unwrap_err();
+        assert_eq!(err, FrontEndError::AlreadyRegistered {});
+        require_not_registered(None).unwrap();
+    }
+
+    #[test]
+    fn deposits_keep_their_first_tag() {
+        let (first, second) = (Addr::unchecked("first"), Addr::unchecked("second"));
+        assert_eq!(
+            deposit_front_end_tag(Uint128::zero(), None, Some(first.clone())),
+            Some(first.clone())
+        );
+        assert_eq!(
+            deposit_front_end_tag(Uint128::new(100), Some(first.clone()), Some(second.clone())),
+            Some(first)
+        );
+        assert_eq!(
+            deposit_front_end_tag(Uint128::new(100), None, Some(second)),
+            None
+        );
+    }
+
+    #[test]
+    fn front_end_stake_compounds_with_deposits() {
+        let mut store = MockStorage::new();
+        let front_end = FrontEnd::register(None, false, Decimal256::percent(80)).unwrap();
+        let mut sums = StabilityPoolSums {
+            total_ultra_deposits: Uint128::new(2_000),
+            ..StabilityPoolSums::default()
+        };
+        let mut stake = FrontEndStake::default();
+        stake
+            .update(&store, &sums, Uint128::new(1_000), Uint128::zero())
+            .unwrap();
+
+        sums.update_g(&mut store, Uint128::new(400)).unwrap();
+        let juno = native_asset_info(UJUNO_DENOM.to_string());
+        sums.offset(&mut store, &juno, Uint128::new(1_000), Uint128::zero())
+            .unwrap();
+        assert_eq!(stake.compounded_stake(&sums).unwrap(), Uint128::new(499));
+        // The front end keeps 20% of the 200 earned by its deposits
+        assert_eq!(
+            stake.reward_gain(&store, &sums, &front_end).unwrap(),
+            Uint128::new(40)
+        );
+
+        let new_stake = stake
+            .update(&store, &sums, Uint128::new(501), Uint128::zero())
+            .unwrap();
+        assert_eq!(new_stake, Uint128::new(1_000));
+        assert_eq!(stake.snapshot, sums.snapshot(&store).unwrap());
+        assert_eq!(
+            stake.reward_gain(&store, &sums, &front_end).unwrap(),
+            Uint128::zero()
+        );
+
+        stake
+            .update(&store, &sums, Uint128::zero(), Uint128::new(1_000))
+            .unwrap();
+        assert_eq!(stake, FrontEndStake::default());
+        stake
+            .update(&store, &sums, Uint128::zero(), Uint128::new(1))
+            .unwrap_err();
+    }
+}
@@ -0,0 +1,93 @@
+use cosmwasm_std::{Addr, Uint128};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::asset::AssetInfo;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct InstantiateMsg {
+    pub name: String,
+    pub owner: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Record the ULTRA liquidation reserve minted to the pool when `borrower` opened a trove
+    /// against `collateral`. Can only be called by borrower operations
+    AccountReserve {
+        collateral: AssetInfo,
+        borrower: Addr,
+        amount: Uint128,
+    },
+    /// Send the ULTRA reserves of the liquidated `borrowers` and `coll_amount` of `collateral`
+    /// to the liquidator. The collateral has to be sent to the pool by the active pool
+    /// beforehand. Can only be called by the trove manager
+    CompensateLiquidator {
+        collateral: AssetInfo,
+        liquidator: Addr,
+        borrowers: Vec<Addr>,
+        coll_amount: Uint128,
+    },
+    /// Send the ULTRA reserve of the trove of `borrower` back to borrower operations, which
+    /// burns it to repay the gas compensation part of the debt when the trove is closed.
+    /// Can only be called by borrower operations
+    RefundReserve {
+        collateral: AssetInfo,
+        borrower: Addr,
+    },
+    /// Point the pool to the address registry its peers are resolved from.
+    /// Can only be called by the owner while the addresses are not locked
+    SetAddressRegistry { address_registry: String },
+    /// Refresh the cached peer addresses from the address registry.
+    /// Can be called by anyone while the addresses are not locked
+    SyncAddresses {},
+    /// Propose a new owner, who becomes owner once it accepts.
+    /// Can only be called by the owner
+    ProposeOwner { owner: String },
+    /// Become the owner. Can only be called by the pending owner
+    AcceptOwner {},
+    /// Leave the pool without owner. Can only be called by the owner
+    RenounceOwnership {},
+    /// Permanently prevent the addresses from being changed again.
+    /// Can only be called by the owner once the addresses are synced
+    LockAddresses {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    GetParams {},
+    /// Returns the owner, pending owner and addresses lock status
+    GetOwnership {},
+    /// Returns the ULTRA reserved for the trove of `borrower`, zero if it has none
+    GetReserve {
+        collateral: AssetInfo,
+        borrower: Addr,
+    },
+    /// Returns the ULTRA reserved for all troves
+    GetTotalReserve {},
+    GetBorrowerOperationsAddress {},
+    GetTroveManagerAddress {},
+    GetULTRATokenAddress {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    /// Update the contract parameters
+    /// Can only be called by governance
+    UpdateParams {
+        name: Option<String>,
+        owner: Option<Addr>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ParamsResponse {
+    pub name: String,
+    pub owner: Addr,
+}
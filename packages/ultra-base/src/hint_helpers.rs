@@ -2,6 +2,8 @@ use cosmwasm_std::{Addr, Uint128};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::asset::AssetInfo;
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMsg {
     pub name: String,
@@ -20,22 +22,48 @@ pub enum ExecuteMsg {
         debt: Uint128,
         price: Uint128,
     },
-    SetAddresses {
-        sorted_troves_address: String,
-        trove_manager_address: String,
+    /// Point the contract to the address registry its peers are resolved from.
+    /// Can only be called by the owner while the addresses are not locked
+    SetAddressRegistry {
+        address_registry: String,
+    },
+    /// Refresh the cached peer addresses from the address registry.
+    /// Can be called by anyone while the addresses are not locked
+    SyncAddresses {},
+    /// Propose a new owner, who becomes owner once it accepts.
+    /// Can only be called by the owner
+    ProposeOwner {
+        owner: String,
     },
+    /// Become the owner. Can only be called by the pending owner
+    AcceptOwner {},
+    /// Leave the contract without owner. Can only be called by the owner
+    RenounceOwnership {},
+    /// Permanently prevent the addresses from being changed again.
+    /// Can only be called by the owner once the addresses are synced
+    LockAddresses {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetParams {},
+    /// Returns the owner, pending owner and addresses lock status
+    GetOwnership {},
+    /// Hints for redeeming `ultra_amount` against the troves of `collateral`
     GetRedemptionHints {
+        collateral: AssetInfo,
         ultra_amount: Uint128,
         price: Uint128,
         max_iterations: Uint128,
     },
-    GetApproxHint {cr: Uint128, num_trials: Uint128, input_random_seed: Uint128},
+    /// Hint for inserting a trove of `collateral` with a collateral ratio of `cr`
+    GetApproxHint {
+        collateral: AssetInfo,
+        cr: Uint128,
+        num_trials: Uint128,
+        input_random_seed: Uint128,
+    },
     GetSortedTrovesAddress {},
     GetTroveManagerAddress {},
 }
@@ -1,12 +1,31 @@
 pub mod active_pool;
+pub mod address_registry;
 pub mod asset;
+pub mod base_rate;
 pub mod borrower_operations;
 pub mod coll_surplus_pool;
+pub mod collateral;
+pub mod collateral_staking;
+pub mod community_issuance;
 pub mod default_pool;
+pub mod factory;
+pub mod front_ends;
+pub mod gas_pool;
 pub mod hint_helpers;
+pub mod liquidation;
+pub mod lp_oracle;
+pub mod migration;
 pub mod oracle;
+pub mod ownership;
 pub mod querier;
+pub mod redemption;
+pub mod redistribution;
+pub mod reward_pool;
+pub mod risk_params;
 pub mod sorted_troves;
+pub mod stability_accounting;
+pub mod stability_deposits;
 pub mod stability_pool;
+pub mod stability_vault;
 pub mod trove_manager;
 pub mod ultra_math;
@@ -0,0 +1,508 @@
+use cosmwasm_std::{Addr, Decimal256, StdError, StdResult, Uint128, Uint256};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::redemption::TroveSnapshot;
+use crate::risk_params::RiskParams;
+use crate::ultra_math::{compute_cr, min};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum LiquidationError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Liquidation: The list of borrowers must not be empty")]
+    EmptyBorrowers {},
+
+    #[error("Liquidation: Nothing to liquidate")]
+    NothingToLiquidate {},
+}
+
+/// How the debt and collateral of liquidated troves are split
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct LiquidationValues {
+    pub entire_trove_debt: Uint128,
+    pub entire_trove_coll: Uint128,
+    /// Collateral sent to the liquidator
+    pub coll_gas_compensation: Uint128,
+    /// ULTRA sent to the liquidator from the gas pool
+    pub ultra_gas_compensation: Uint128,
+    /// Debt cancelled with the ULTRA of the stability pool, in exchange of `coll_to_send_to_sp`
+    pub debt_to_offset: Uint128,
+    pub coll_to_send_to_sp: Uint128,
+    /// Debt and collateral redistributed to the active troves through the default pool
+    pub debt_to_redistribute: Uint128,
+    pub coll_to_redistribute: Uint128,
+    /// Collateral above the MCR of troves liquidated in Recovery Mode, claimable by their
+    /// borrowers from the coll surplus pool
+    pub coll_surplus: Uint128,
+}
+
+impl LiquidationValues {
+    fn add(&mut self, other: &LiquidationValues) {
+        self.entire_trove_debt += other.entire_trove_debt;
+        self.entire_trove_coll += other.entire_trove_coll;
+        self.coll_gas_compensation += other.coll_gas_compensation;
+        self.ultra_gas_compensation += other.ultra_gas_compensation;
+        self.debt_to_offset += other.debt_to_offset;
+        self.coll_to_send_to_sp += other.coll_to_send_to_sp;
+        self.debt_to_redistribute += other.debt_to_redistribute;
+        self.coll_to_redistribute += other.coll_to_redistribute;
+        self.coll_surplus += other.coll_surplus;
+    }
+
+    /// Collateral taken from the troves, other than the gas compensation and surplus
+    pub fn liquidated_coll(&self) -> Uint128 {
+        self.entire_trove_coll - self.coll_gas_compensation - self.coll_surplus
+    }
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TroveLiquidation {
+    pub borrower: Addr,
+    pub values: LiquidationValues,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Liquidation {
+    pub liquidations: Vec<TroveLiquidation>,
+    pub totals: LiquidationValues,
+}
+
+/// State of the system before liquidating
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SystemSnapshot {
+    pub entire_system_coll: Uint128,
+    pub entire_system_debt: Uint128,
+    pub ultra_in_stability_pool: Uint128,
+    pub trove_count: u64,
+}
+
+/// Offsets as much debt as the stability pool can absorb, with the corresponding share of the
+/// collateral, and redistributes the rest
+fn offset_and_redistribute(
+    debt: Uint128,
+    coll: Uint128,
+    ultra_in_stability_pool: Uint128,
+    values: &mut LiquidationValues,
+) {
+    let debt_to_offset = min(debt, ultra_in_stability_pool);
+    let coll_to_send_to_sp = if debt.is_zero() {
+        Uint128::zero()
+    } else {
+        coll.multiply_ratio(debt_to_offset, debt)
+    };
+    values.debt_to_offset = debt_to_offset;
+    values.coll_to_send_to_sp = coll_to_send_to_sp;
+    values.debt_to_redistribute = debt - debt_to_offset;
+    values.coll_to_redistribute = coll - coll_to_send_to_sp;
+}
+
+/// Liquidates a trove with an ICR below the MCR
+pub fn liquidate_normal_mode(
+    trove: &TroveSnapshot,
+    ultra_in_stability_pool: Uint128,
+    params: &RiskParams,
+) -> StdResult<LiquidationValues> {
+    let mut values = LiquidationValues {
+        entire_trove_debt: trove.debt,
+        entire_trove_coll: trove.coll,
+        coll_gas_compensation: params.coll_gas_compensation(trove.coll)?,
+        ultra_gas_compensation: params.gas_compensation,
+        ..LiquidationValues::default()
+    };
+    let coll_to_liquidate = trove.coll.checked_sub(values.coll_gas_compensation)?;
+    offset_and_redistribute(
+        trove.debt,
+        coll_to_liquidate,
+        ultra_in_stability_pool,
+        &mut values,
+    );
+    Ok(values)
+}
+
+/// Liquidates a trove while the system is in Recovery Mode, returns `None` if it can't be.
+///
+/// Troves below 100% ICR are entirely redistributed, and troves below the MCR liquidated as in
+/// Normal Mode. Troves between the MCR and the TCR are only liquidated if the stability pool
+/// can absorb all their debt, their collateral being capped at the MCR and the rest left as
+/// surplus to the borrower.
+pub fn liquidate_recovery_mode(
+    trove: &TroveSnapshot,
+    icr: Decimal256,
+    ultra_in_stability_pool: Uint128,
+    tcr: Decimal256,
+    price: Decimal256,
+    params: &RiskParams,
+) -> StdResult<Option<LiquidationValues>> {
+    if icr <= Decimal256::one() {
+        let coll_gas_compensation = params.coll_gas_compensation(trove.coll)?;
+        return Ok(Some(LiquidationValues {
+            entire_trove_debt: trove.debt,
+            entire_trove_coll: trove.coll,
+            coll_gas_compensation,
+            ultra_gas_compensation: params.gas_compensation,
+            debt_to_redistribute: trove.debt,
+            coll_to_redistribute: trove.coll.checked_sub(coll_gas_compensation)?,
+            ..LiquidationValues::default()
+        }));
+    }
+    if icr < params.mcr {
+        return liquidate_normal_mode(trove, ultra_in_stability_pool, params).map(Some);
+    }
+    if icr < tcr && trove.debt <= ultra_in_stability_pool {
+        return capped_offset_values(trove, price, params).map(Some);
+    }
+    Ok(None)
+}
+
+/// Offsets the whole debt of a trove against collateral worth the MCR
+fn capped_offset_values(
+    trove: &TroveSnapshot,
+    price: Decimal256,
+    params: &RiskParams,
+) -> StdResult<LiquidationValues> {
+    let capped_coll_portion = Uint256::from(trove.debt)
+        .checked_mul(params.mcr.atomics())?
+        .checked_div(price.atomics())
+        .map_err(StdError::divide_by_zero)?;
+    let capped_coll_portion = Uint128::try_from(capped_coll_portion)?;
+    let coll_gas_compensation = params.coll_gas_compensation(capped_coll_portion)?;
+
+    Ok(LiquidationValues {
+        entire_trove_debt: trove.debt,
+        entire_trove_coll: trove.coll,
+        coll_gas_compensation,
+        ultra_gas_compensation: params.gas_compensation,
+        debt_to_offset: trove.debt,
+        coll_to_send_to_sp: capped_coll_portion - coll_gas_compensation,
+        coll_surplus: trove.coll.checked_sub(capped_coll_portion)?,
+        ..LiquidationValues::default()
+    })
+}
+
+/// Liquidates the active `troves` that can be, at `price`.
+///
+/// With `sequence`, the troves are the ones with the lowest ICR in ascending order and the
+/// liquidation stops at the first trove above the MCR, or in Recovery Mode at the first one
+/// above the MCR once the stability pool is empty. Other troves that can't be liquidated are
+/// skipped. While the system is in Recovery Mode its TCR is recomputed after each trove, and
+/// liquidations continue under Normal Mode rules once it's back above the CCR. The last trove
+/// of the system is never liquidated.
+pub fn batch_liquidate<I>(
+    troves: I,
+    system: &SystemSnapshot,
+    price: Decimal256,
+    sequence: bool,
+    params: &RiskParams,
+) -> Result<Liquidation, LiquidationError>
+where
+    I: IntoIterator<Item = StdResult<TroveSnapshot>>,
+{
+    let mut troves = troves.into_iter().peekable();
+    if troves.peek().is_none() {
+        return Err(LiquidationError::EmptyBorrowers {});
+    }
+
+    let mut remaining_ultra_in_stability_pool = system.ultra_in_stability_pool;
+    let mut entire_system_coll = system.entire_system_coll;
+    let mut entire_system_debt = system.entire_system_debt;
+    let mut trove_count = system.trove_count;
+    let mut back_to_normal_mode =
+        compute_cr(entire_system_coll, entire_system_debt, price)? >= params.ccr;
+
+    let mut liquidation = Liquidation {
+        liquidations: vec![],
+        totals: LiquidationValues::default(),
+    };
+    for trove in troves {
+        let trove = trove?;
+        let icr = compute_cr(trove.coll, trove.debt, price)?;
+
+        let values = if trove_count <= 1 {
+            None
+        } else if !back_to_normal_mode {
+            if icr >= params.mcr && remaining_ultra_in_stability_pool.is_zero() {
+                None
+            } else {
+                let tcr = compute_cr(entire_system_coll, entire_system_debt, price)?;
+                let values = liquidate_recovery_mode(
+                    &trove,
+                    icr,
+                    remaining_ultra_in_stability_pool,
+                    tcr,
+                    price,
+                    params,
+                )?;
+                // A trove the stability pool can't absorb may be followed by smaller ones
+                if values.is_none() {
+                    continue;
+                }
+                values
+            }
+        } else if icr < params.mcr {
+            Some(liquidate_normal_mode(
+                &trove,
+                remaining_ultra_in_stability_pool,
+                params,
+            )?)
+        } else {
+            None
+        };
+
+        let values = match values {
+            Some(values) => values,
+            None if sequence => break,
+            None => continue,
+        };
+        remaining_ultra_in_stability_pool -= values.debt_to_offset;
+        trove_count -= 1;
+        if !back_to_normal_mode {
+            entire_system_debt -= values.debt_to_offset;
+            entire_system_coll -=
+                values.coll_to_send_to_sp + values.coll_gas_compensation + values.coll_surplus;
+            back_to_normal_mode =
+                compute_cr(entire_system_coll, entire_system_debt, price)? >= params.ccr;
+        }
+        liquidation.totals.add(&values);
+        liquidation.liquidations.push(TroveLiquidation {
+            borrower: trove.borrower,
+            values,
+        });
+    }
+
+    if liquidation.totals.entire_trove_debt.is_zero() {
+        return Err(LiquidationError::NothingToLiquidate {});
+    }
+    Ok(liquidation)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn trove(borrower: &str, debt: u128, coll: u128) -> TroveSnapshot {
+        TroveSnapshot {
+            borrower: Addr::unchecked(borrower),
+            debt: Uint128::new(debt),
+            coll: Uint128::new(coll),
+        }
+    }
+
+    fn system(coll: u128, debt: u128, ultra_in_stability_pool: u128) -> SystemSnapshot {
+        SystemSnapshot {
+            entire_system_coll: Uint128::new(coll),
+            entire_system_debt: Uint128::new(debt),
+            ultra_in_stability_pool: Uint128::new(ultra_in_stability_pool),
+            trove_count: 10,
+        }
+    }
+
+    fn borrowers(liquidation: &Liquidation) -> Vec<&str> {
+        liquidation
+            .liquidations
+            .iter()
+            .map(|liquidation| liquidation.borrower.as_str())
+            .collect()
+    }
+
+    #[test]
+    fn normal_mode_offsets_then_redistributes() {
+        let params = RiskParams::default();
+        let trove = trove("alice", 10_000, 10_200);
+
+        // The stability pool absorbs the whole debt
+        let values = liquidate_normal_mode(&trove, Uint128::new(20_000), &params).unwrap();
+        assert_eq!(
+            values,
+            LiquidationValues {
+                entire_trove_debt: Uint128::new(10_000),
+                entire_trove_coll: Uint128::new(10_200),
+                coll_gas_compensation: Uint128::new(51),
+                ultra_gas_compensation: Uint128::new(200),
+                debt_to_offset: Uint128::new(10_000),
+                coll_to_send_to_sp: Uint128::new(10_149),
+                ..LiquidationValues::default()
+            }
+        );
+        assert_eq!(values.liquidated_coll(), Uint128::new(10_149));
+
+        // Only part of it
+        let values = liquidate_normal_mode(&trove, Uint128::new(4_000), &params).unwrap();
+        assert_eq!(values.debt_to_offset, Uint128::new(4_000));
+        assert_eq!(values.coll_to_send_to_sp, Uint128::new(4_059));
+        assert_eq!(values.debt_to_redistribute, Uint128::new(6_000));
+        assert_eq!(values.coll_to_redistribute, Uint128::new(6_090));
+
+        // An empty stability pool redistributes everything
+        let values = liquidate_normal_mode(&trove, Uint128::zero(), &params).unwrap();
+        assert_eq!(values.debt_to_redistribute, Uint128::new(10_000));
+        assert_eq!(values.coll_to_redistribute, Uint128::new(10_149));
+    }
+
+    #[test]
+    fn recovery_mode_rules() {
+        let params = RiskParams::default();
+        let price = Decimal256::one();
+        let tcr = Decimal256::percent(140);
+        let sp = Uint128::new(100_000);
+        let liquidate = |trove: &TroveSnapshot, sp: Uint128| {
+            let icr = compute_cr(trove.coll, trove.debt, price).unwrap();
+            liquidate_recovery_mode(trove, icr, sp, tcr, price, &params).unwrap()
+        };
+
+        // Below 100% everything is redistributed
+        let values = liquidate(&trove("alice", 10_000, 9_000), sp).unwrap();
+        assert_eq!(values.debt_to_offset, Uint128::zero());
+        assert_eq!(values.debt_to_redistribute, Uint128::new(10_000));
+        assert_eq!(values.coll_to_redistribute, Uint128::new(8_955));
+
+        // Below the MCR as in Normal Mode
+        let trove_below_mcr = trove("bob", 10_000, 10_500);
+        assert_eq!(
+            liquidate(&trove_below_mcr, sp).unwrap(),
+            liquidate_normal_mode(&trove_below_mcr, sp, &params).unwrap()
+        );
+
+        // Between the MCR and the TCR the collateral is capped at the MCR
+        let values = liquidate(&trove("carol", 10_000, 13_000), sp).unwrap();
+        assert_eq!(
+            values,
+            LiquidationValues {
+                entire_trove_debt: Uint128::new(10_000),
+                entire_trove_coll: Uint128::new(13_000),
+                coll_gas_compensation: Uint128::new(55),
+                ultra_gas_compensation: Uint128::new(200),
+                debt_to_offset: Uint128::new(10_000),
+                coll_to_send_to_sp: Uint128::new(10_945),
+                coll_surplus: Uint128::new(2_000),
+                ..LiquidationValues::default()
+            }
+        );
+        // unless the stability pool can't absorb the whole debt
+        assert_eq!(
+            liquidate(&trove("carol", 10_000, 13_000), Uint128::new(9_999)),
+            None
+        );
+
+        // Above the TCR troves can't be liquidated
+        assert_eq!(liquidate(&trove("dave", 10_000, 15_000), sp), None);
+    }
+
+    #[test]
+    fn batch_liquidation_in_normal_mode() {
+        let params = RiskParams::default();
+        let troves = || {
+            [
+                trove("alice", 10_000, 10_000),
+                trove("bob", 10_000, 12_000),
+                trove("carol", 10_000, 10_500),
+            ]
+            .map(Ok)
+        };
+        // TCR 2
+        let system = system(200_000, 100_000, 15_000);
+
+        let liquidation =
+            batch_liquidate(troves(), &system, Decimal256::one(), false, &params).unwrap();
+        assert_eq!(borrowers(&liquidation), vec!["alice", "carol"]);
+        // The stability pool is emptied by the first liquidation
+        assert_eq!(liquidation.totals.debt_to_offset, Uint128::new(15_000));
+        assert_eq!(liquidation.totals.debt_to_redistribute, Uint128::new(5_000));
+        assert_eq!(liquidation.totals.ultra_gas_compensation, Uint128::new(400));
+
+        // A sequence stops at the first trove above the MCR
+        let liquidation =
+            batch_liquidate(troves(), &system, Decimal256::one(), true, &params).unwrap();
+        assert_eq!(borrowers(&liquidation), vec!["alice"]);
+
+        let err = batch_liquidate(
+            [trove("bob", 10_000, 12_000)].map(Ok),
+            &system,
+            Decimal256::one(),
+            false,
+            &params,
+        )
+        .unwrap_err();
+        assert_eq!(err, LiquidationError::NothingToLiquidate {});
+
+        let err = batch_liquidate(vec![], &system, Decimal256::one(), false, &params).unwrap_err();
+        assert_eq!(err, LiquidationError::EmptyBorrowers {});
+    }
+
+    #[test]
+    fn batch_liquidation_in_recovery_mode() {
+        let params = RiskParams::default();
+        let troves = [
+            trove("alice", 10_000, 12_000),
+            trove("bob", 10_000, 12_500),
+            trove("carol", 10_000, 13_000),
+        ]
+        .map(Ok);
+        // TCR 1.4, below the CCR
+        let system = system(56_000, 40_000, 30_000);
+
+        // Liquidating "alice" and "bob" at the MCR brings the TCR back to
+        // (56_000 - 2 * 11_000) / (40_000 - 20_000) = 1.7, "carol" is then above the MCR
+        let liquidation =
+            batch_liquidate(troves, &system, Decimal256::one(), false, &params).unwrap();
+        assert_eq!(borrowers(&liquidation), vec!["alice", "bob"]);
+        assert_eq!(liquidation.totals.coll_surplus, Uint128::new(2_500));
+        assert_eq!(liquidation.totals.debt_to_offset, Uint128::new(20_000));
+    }
+
+    #[test]
+    fn recovery_mode_sequence_skips_troves() {
+        let params = RiskParams::default();
+        let troves = [
+            trove("alice", 20_000, 24_400),
+            trove("bob", 10_000, 12_500),
+            trove("carol", 10_000, 16_000),
+        ]
+        .map(Ok);
+        // TCR 1.4, below the CCR
+        let system = system(56_000, 40_000, 15_000);
+
+        // The stability pool can't absorb the debt of "alice", "bob" is still liquidated and
+        // "carol" is above the TCR
+        let liquidation =
+            batch_liquidate(troves, &system, Decimal256::one(), true, &params).unwrap();
+        assert_eq!(borrowers(&liquidation), vec!["bob"]);
+        assert_eq!(liquidation.totals.debt_to_offset, Uint128::new(10_000));
+        assert_eq!(liquidation.totals.coll_surplus, Uint128::new(1_500));
+
+        // With an empty stability pool the sequence stops at the first trove above the MCR
+        let troves = [trove("alice", 20_000, 24_400), trove("bob", 10_000, 10_000)].map(Ok);
+        let err = batch_liquidate(
+            troves,
+            &SystemSnapshot {
+                ultra_in_stability_pool: Uint128::zero(),
+                ..system
+            },
+            Decimal256::one(),
+            true,
+            &params,
+        )
+        .unwrap_err();
+        assert_eq!(err, LiquidationError::NothingToLiquidate {});
+    }
+
+    #[test]
+    fn last_trove_is_not_liquidated() {
+        let params = RiskParams::default();
+        let system = SystemSnapshot {
+            trove_count: 2,
+            ..system(200_000, 100_000, 0)
+        };
+        let liquidation = batch_liquidate(
+            [trove("alice", 10_000, 10_000), trove("bob", 10_000, 10_000)].map(Ok),
+            &system,
+            Decimal256::one(),
+            false,
+            &params,
+        )
+        .unwrap();
+        assert_eq!(borrowers(&liquidation), vec!["alice"]);
+    }
+}
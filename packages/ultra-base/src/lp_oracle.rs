@@ -0,0 +1,133 @@
+use cosmwasm_std::{Addr, StdError, StdResult, Uint128, Uint256, Uint512};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::asset::AssetInfo;
+use crate::ultra_math::sqrt_uint512;
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct InstantiateMsg {
+    /// WasmSwap pool whose LP token is priced
+    pub pool_contract_address: String,
+    /// Asset the LP token is priced in, typically ULTRA
+    pub quote: AssetInfo,
+    /// JunoSwap oracle pricing the first token of the pool in the quote asset,
+    /// none if the token is the quote asset
+    pub token1_oracle: Option<String>,
+    /// JunoSwap oracle pricing the second token of the pool in the quote asset,
+    /// none if the token is the quote asset
+    pub token2_oracle: Option<String>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    /// Returns the value of `amount` LP tokens in the quote asset, as a `Uint256`.
+    /// Same shape as the JunoSwap oracle query, so the adapter can be the price feed of a
+    /// collateral
+    Consult {
+        token: AssetInfo,
+        amount: Uint128,
+    },
+    /// Returns the LP token price at the current reserves and TWAP prices
+    GetLpPrice {},
+    GetConfig {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub pool_contract_addr: Addr,
+    pub lp_token: AssetInfo,
+    pub quote: AssetInfo,
+    pub token1_oracle: Option<Addr>,
+    pub token2_oracle: Option<Addr>,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct LpPriceResponse {
+    /// Value of the actual reserves of each token at its TWAP price
+    pub token1_value: Uint256,
+    pub token2_value: Uint256,
+    /// Value of the reserves rebalanced to the TWAP prices
+    pub fair_value: Uint256,
+    pub lp_token_supply: Uint128,
+}
+
+/// Value of the reserves of a constant product pool, rebalanced to the TWAP prices of its
+/// tokens.
+///
+/// Skewing the reserves with a swap moves their spot value but keeps their product `k`. At the
+/// prices `p1` and `p2` the fair reserves are `sqrt(k * p2 / p1)` and `sqrt(k * p1 / p2)`,
+/// worth `2 * sqrt(k * p1 * p2)`, that is `2 * sqrt(v1 * v2)` with `v1` and `v2` the values of
+/// the actual reserves.
+pub fn fair_reserves_value(token1_value: Uint256, token2_value: Uint256) -> StdResult<Uint256> {
+    let root = Uint256::try_from(sqrt_uint512(token1_value.full_mul(token2_value)))?;
+    Ok(root.checked_mul(Uint256::from(2u8))?)
+}
+
+/// Value of `amount` LP tokens out of `lp_token_supply`, the pool reserves being worth
+/// `fair_value`
+pub fn lp_token_value(
+    fair_value: Uint256,
+    amount: Uint128,
+    lp_token_supply: Uint128,
+) -> StdResult<Uint256> {
+    if lp_token_supply.is_zero() {
+        return Err(StdError::generic_err("LP token supply is zero"));
+    }
+    let value = fair_value.full_mul(amount) / Uint512::from(Uint256::from(lp_token_supply));
+    Ok(Uint256::try_from(value)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn uint(value: u128) -> Uint256 {
+        Uint256::from(value)
+    }
+
+    #[test]
+    fn balanced_pool_is_worth_its_reserves() {
+        // 1_000 JUNO at 2 ULTRA against 2_000 ULTRA
+        assert_eq!(
+            fair_reserves_value(uint(2_000), uint(2_000)).unwrap(),
+            uint(4_000)
+        );
+        assert_eq!(fair_reserves_value(uint(0), uint(2_000)).unwrap(), uint(0));
+    }
+
+    #[test]
+    fn skewed_reserves_are_not_worth_more() {
+        // Swapping 1_000 ULTRA into a 1_000 JUNO / 2_000 ULTRA pool, JUNO's TWAP price
+        // staying at 2 ULTRA: k = 2_000_000 and the pool holds 666 JUNO / 3_000 ULTRA
+        let spot_value = uint(666 * 2 + 3_000);
+        let fair_value = fair_reserves_value(uint(666 * 2), uint(3_000)).unwrap();
+        assert!(fair_value < spot_value);
+        // 2 * sqrt(1_332 * 3_000), the root being rounded down
+        assert_eq!(fair_value, uint(3_996));
+    }
+
+    #[test]
+    fn lp_token_share_of_the_fair_value() {
+        assert_eq!(
+            lp_token_value(uint(4_000), Uint128::new(250), Uint128::new(1_000)).unwrap(),
+            uint(1_000)
+        );
+        assert_eq!(
+            lp_token_value(uint(4_000), Uint128::new(1), Uint128::new(3)).unwrap(),
+            uint(1_333)
+        );
+        lp_token_value(uint(4_000), Uint128::new(1), Uint128::zero()).unwrap_err();
+
+        // The fair value of the largest pools still fits
+        let fair_value = fair_reserves_value(Uint256::MAX, Uint256::MAX / uint(4)).unwrap();
+        assert_eq!(
+            lp_token_value(fair_value, Uint128::MAX, Uint128::MAX).unwrap(),
+            fair_value
+        );
+    }
+}
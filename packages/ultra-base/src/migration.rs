@@ -0,0 +1,207 @@
+use std::fmt;
+use std::str::FromStr;
+
+use cosmwasm_std::{Response, StdError, StdResult, Storage};
+use cw2::{get_contract_version, set_contract_version};
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum MigrationError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Cannot migrate from different contract type: {previous_contract}")]
+    CannotMigrate { previous_contract: String },
+
+    #[error("Invalid contract version: {version}")]
+    InvalidVersion { version: String },
+
+    #[error("Cannot migrate from version {previous_version} to older version {new_version}")]
+    CannotDowngrade {
+        previous_version: String,
+        new_version: String,
+    },
+}
+
+/// A `major.minor.patch` version, pre-release and build metadata are not supported
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+pub struct Version {
+    pub major: u64,
+    pub minor: u64,
+    pub patch: u64,
+}
+
+impl FromStr for Version {
+    type Err = MigrationError;
+
+    fn from_str(version: &str) -> Result<Self, Self::Err> {
+        let mut parts = version.split('.').map(|part| part.parse::<u64>());
+        match (parts.next(), parts.next(), parts.next(), parts.next()) {
+            (Some(Ok(major)), Some(Ok(minor)), Some(Ok(patch)), None) => Ok(Version {
+                major,
+                minor,
+                patch,
+            }),
+            _ => Err(MigrationError::InvalidVersion {
+                version: version.to_string(),
+            }),
+        }
+    }
+}
+
+impl fmt::Display for Version {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}.{}.{}", self.major, self.minor, self.patch)
+    }
+}
+
+/// A state migration introduced by `version`.
+/// It runs when a contract older than `version` is migrated to `version` or newer
+pub struct Migration {
+    pub version: &'static str,
+    pub migrate: fn(&mut dyn Storage) -> StdResult<()>,
+}
+
+/// Checks the cw2 version stored by a contract and upgrades it to `contract_version`.
+///
+/// The stored contract name must be `contract_name` and the stored version must not be newer
+/// than `contract_version`. The `migrations` introduced after the stored version, up to
+/// `contract_version`, are applied by ascending version before the new version is stored.
+pub fn migrate_contract(
+    store: &mut dyn Storage,
+    contract_name: &str,
+    contract_version: &str,
+    migrations: &[Migration],
+) -> Result<Response, MigrationError> {
+    let stored = get_contract_version(store)?;
+    if stored.contract != contract_name {
+        return Err(MigrationError::CannotMigrate {
+            previous_contract: stored.contract,
+        });
+    }
+
+    let previous_version: Version = stored.version.parse()?;
+    let new_version: Version = contract_version.parse()?;
+    if new_version < previous_version {
+        return Err(MigrationError::CannotDowngrade {
+            previous_version: stored.version,
+            new_version: contract_version.to_string(),
+        });
+    }
+
+    let mut pending = migrations
+        .iter()
+        .map(|migration| Ok((migration.version.parse::<Version>()?, migration)))
+        .collect::<Result<Vec<_>, MigrationError>>()?;
+    pending.retain(|(version, _)| previous_version < *version && *version <= new_version);
+    pending.sort_by_key(|(version, _)| *version);
+
+    let mut res = Response::new()
+        .add_attribute("action", "migrate")
+        .add_attribute("previous_version", previous_version.to_string())
+        .add_attribute("new_version", new_version.to_string());
+    for (version, migration) in pending {
+        (migration.migrate)(store)?;
+        res = res.add_attribute("applied_migration", version.to_string());
+    }
+
+    set_contract_version(store, contract_name, contract_version)?;
+
+    Ok(res)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::MockStorage;
+    use cw_storage_plus::Item;
+
+    const CONTRACT_NAME: &str = "crates.io:pool";
+    const APPLIED: Item<Vec<String>> = Item::new("applied");
+
+    fn record(store: &mut dyn Storage, version: &str) -> StdResult<()> {
+        let mut applied = APPLIED.may_load(store)?.unwrap_or_default();
+        applied.push(version.to_string());
+        APPLIED.save(store, &applied)
+    }
+
+    // Deliberately unordered, migrations are applied by ascending version
+    const MIGRATIONS: &[Migration] = &[
+        Migration {
+            version: "0.3.0",
+            migrate: |store| record(store, "0.3.0"),
+        },
+        Migration {
+            version: "0.1.1",
+            migrate: |store| record(store, "0.1.1"),
+        },
+        Migration {
+            version: "0.2.0",
+            migrate: |store| record(store, "0.2.0"),
+        },
+    ];
+
+    #[test]
+    fn parse_versions() {
+        assert_eq!(
+            "1.20.3".parse::<Version>().unwrap(),
+            Version {
+                major: 1,
+                minor: 20,
+                patch: 3
+            }
+        );
+        assert!("0.10.0".parse::<Version>().unwrap() > "0.9.9".parse().unwrap());
+        for version in ["", "1.0", "1.0.0.0", "1.0.x", "1.0.0-rc1"] {
+            assert_eq!(
+                version.parse::<Version>().unwrap_err(),
+                MigrationError::InvalidVersion {
+                    version: version.to_string()
+                }
+            );
+        }
+    }
+
+    #[test]
+    fn applies_pending_migrations_in_order() {
+        let mut store = MockStorage::new();
+        set_contract_version(&mut store, CONTRACT_NAME, "0.1.0").unwrap();
+
+        migrate_contract(&mut store, CONTRACT_NAME, "0.2.0", MIGRATIONS).unwrap();
+        assert_eq!(APPLIED.load(&store).unwrap(), vec!["0.1.1", "0.2.0"]);
+
+        // Migrating again to the same version applies nothing
+        migrate_contract(&mut store, CONTRACT_NAME, "0.2.0", MIGRATIONS).unwrap();
+        migrate_contract(&mut store, CONTRACT_NAME, "0.3.1", MIGRATIONS).unwrap();
+        assert_eq!(
+            APPLIED.load(&store).unwrap(),
+            vec!["0.1.1", "0.2.0", "0.3.0"]
+        );
+        assert_eq!(get_contract_version(&store).unwrap().version, "0.3.1");
+    }
+
+    #[test]
+    fn rejects_other_contracts_and_downgrades() {
+        let mut store = MockStorage::new();
+        set_contract_version(&mut store, "crates.io:other", "0.1.0").unwrap();
+        let err = migrate_contract(&mut store, CONTRACT_NAME, "0.2.0", MIGRATIONS).unwrap_err();
+        assert_eq!(
+            err,
+            MigrationError::CannotMigrate {
+                previous_contract: "crates.io:other".to_string()
+            }
+        );
+
+        set_contract_version(&mut store, CONTRACT_NAME, "0.2.0").unwrap();
+        let err = migrate_contract(&mut store, CONTRACT_NAME, "0.1.0", MIGRATIONS).unwrap_err();
+        assert_eq!(
+            err,
+            MigrationError::CannotDowngrade {
+                previous_version: "0.2.0".to_string(),
+                new_version: "0.1.0".to_string()
+            }
+        );
+        assert_eq!(APPLIED.may_load(&store).unwrap(), None);
+    }
+}
@@ -0,0 +1,13 @@
+use cosmwasm_std::Addr;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct OwnershipResponse {
+    /// Current owner, `None` once ownership has been renounced
+    pub owner: Option<Addr>,
+    /// Address proposed as new owner, waiting for it to accept
+    pub pending_owner: Option<Addr>,
+    /// Whether `SetAddresses` has been permanently locked
+    pub addresses_locked: bool,
+}
@@ -1,30 +1,25 @@
 use crate::active_pool::QueryMsg as ActivePoolQueryMsg;
+use crate::address_registry::{
+    AddressResponse, CollateralsResponse, ProtocolContract, QueryMsg as AddressRegistryQueryMsg,
+};
 use crate::asset::{AssetInfo, PoolInfo};
+use crate::collateral::Collateral;
 use crate::default_pool::QueryMsg as DefaultPoolQueryMsg;
+use crate::risk_params::RiskParams;
 use crate::ultra_math;
 
 use cosmwasm_std::{
     Addr, AllBalanceResponse, BankQuery, Coin, Decimal256, QuerierWrapper, QueryRequest, StdError,
-    StdResult, Uint128, Uint256,
+    StdResult, Uint128,
 };
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
 use wasmswap::msg::{InfoResponse, QueryMsg as WasmSwapMsg};
 
 use cw20::{BalanceResponse as Cw20BalanceResponse, Cw20QueryMsg, Denom, TokenInfoResponse};
 
 const NATIVE_TOKEN_PRECISION: u8 = 6;
 
-// Minimum collateral ratio for individual troves
-pub const MCR: Decimal256 = Decimal256::new(Uint256::from_u128(1_100_000_000_000_000_000u128));
-
-// Critical system collateral ratio. If the system's total collateral ratio (TCR) falls below the CCR, Recovery Mode is triggered.
-pub const CCR: Decimal256 = Decimal256::new(Uint256::from_u128(1_500_000_000_000_000_000u128));
-
-// Minimum amount of net ULTRA debt a trove must have
-pub const MIN_NET_DEBT: Uint128 = Uint128::new(2000u128);
-
-pub const BORROWING_FEE_FLOOR: Decimal256 =
-    Decimal256::new(Uint256::from_u128(5_000_000_000_000_000u128)); // 0.5%
-
 /// Returns a native token's balance for a specific account.
 pub fn query_balance(
     querier: &QuerierWrapper,
@@ -36,6 +31,66 @@ pub fn query_balance(
         .map(|coin| coin.amount)
 }
 
+/// Returns the address registered for a protocol contract in the address registry.
+pub fn query_protocol_address(
+    querier: &QuerierWrapper,
+    address_registry_addr: impl Into<String>,
+    contract: ProtocolContract,
+) -> StdResult<Addr> {
+    let resp: AddressResponse = querier.query_wasm_smart(
+        address_registry_addr,
+        &AddressRegistryQueryMsg::GetAddress { contract },
+    )?;
+    Ok(resp.address)
+}
+
+/// Returns the collateral type of `asset` registered in the address registry.
+pub fn query_collateral(
+    querier: &QuerierWrapper,
+    address_registry_addr: impl Into<String>,
+    asset: &AssetInfo,
+) -> StdResult<Collateral> {
+    querier.query_wasm_smart(
+        address_registry_addr,
+        &AddressRegistryQueryMsg::GetCollateral {
+            asset: asset.clone(),
+        },
+    )
+}
+
+/// Returns every collateral type registered in the address registry, reading all the pages.
+pub fn query_collaterals(
+    querier: &QuerierWrapper,
+    address_registry_addr: impl Into<String>,
+) -> StdResult<Vec<Collateral>> {
+    let address_registry_addr = address_registry_addr.into();
+    let mut collaterals: Vec<Collateral> = vec![];
+    loop {
+        let resp: CollateralsResponse = querier.query_wasm_smart(
+            address_registry_addr.clone(),
+            &AddressRegistryQueryMsg::ListCollaterals {
+                start_after: collaterals.last().map(Collateral::key),
+                limit: None,
+            },
+        )?;
+        if resp.collaterals.is_empty() {
+            return Ok(collaterals);
+        }
+        collaterals.extend(resp.collaterals);
+    }
+}
+
+/// Returns the risk parameters currently set by governance in the address registry.
+pub fn query_risk_params(
+    querier: &QuerierWrapper,
+    address_registry_addr: impl Into<String>,
+) -> StdResult<RiskParams> {
+    querier.query_wasm_smart(
+        address_registry_addr,
+        &AddressRegistryQueryMsg::GetRiskParams {},
+    )
+}
+
 /// Returns the total balances for all coins at a specified account address.
 pub fn query_all_balances(querier: &QuerierWrapper, account_addr: Addr) -> StdResult<Vec<Coin>> {
     let all_balances: AllBalanceResponse =
@@ -128,15 +183,16 @@ pub fn query_pool_info(querier: &QuerierWrapper, pool_contract_addr: Addr) -> St
     Ok(res)
 }
 
+/// Returns the collateral held by the active and default pools of a collateral type
 pub fn query_entire_system_coll(
     querier: &QuerierWrapper,
     active_pool_addr: Addr,
     default_pool_addr: Addr,
 ) -> StdResult<Uint128> {
     let active_coll: Uint128 =
-        querier.query_wasm_smart(active_pool_addr, &ActivePoolQueryMsg::GetJUNO {})?;
+        querier.query_wasm_smart(active_pool_addr, &ActivePoolQueryMsg::GetCollateral {})?;
     let liquidated_coll: Uint128 =
-        querier.query_wasm_smart(default_pool_addr, &DefaultPoolQueryMsg::GetJUNO {})?;
+        querier.query_wasm_smart(default_pool_addr, &DefaultPoolQueryMsg::GetCollateral {})?;
     let total = active_coll
         .checked_add(liquidated_coll)
         .map_err(StdError::overflow)?;
@@ -144,6 +200,7 @@ pub fn query_entire_system_coll(
     Ok(total)
 }
 
+/// Returns the debt recorded by the active and default pools of a collateral type
 pub fn query_entire_system_debt(
     querier: &QuerierWrapper,
     active_pool_addr: Addr,
@@ -160,27 +217,154 @@ pub fn query_entire_system_debt(
     Ok(total)
 }
 
-pub fn get_tcr(
-    querier: &QuerierWrapper,
-    price: Decimal256,
-    active_pool_addr: Addr,
-    default_pool_addr: Addr,
-) -> StdResult<Decimal256> {
-    let entire_system_coll =
-        query_entire_system_debt(querier, active_pool_addr.clone(), default_pool_addr.clone())
-            .unwrap();
-    let entire_system_debt =
-        query_entire_system_coll(querier, active_pool_addr, default_pool_addr).unwrap();
-    let tcr = ultra_math::compute_cr(entire_system_coll, entire_system_debt, price).unwrap();
-    Ok(tcr)
+/// Collateral and debt of the whole system, held by the active and default pools
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct SystemStatus {
+    pub total_coll: Uint128,
+    pub total_debt: Uint128,
+    /// Total collateral ratio at the given price, `Decimal256::MAX` without debt
+    pub tcr: Decimal256,
+    /// Whether the TCR is below the critical collateral ratio
+    pub recovery_mode: bool,
 }
 
-pub fn check_recovery_mode(
+/// Returns the system collateral, debt and TCR at `price`, and whether Recovery Mode is active
+/// given the `ccr` read from the risk parameters.
+pub fn query_system_status(
     querier: &QuerierWrapper,
     price: Decimal256,
+    ccr: Decimal256,
     active_pool_addr: Addr,
     default_pool_addr: Addr,
-) -> bool {
-    let tcr = get_tcr(querier, price, active_pool_addr, default_pool_addr).unwrap();
-    tcr < CCR
+) -> StdResult<SystemStatus> {
+    let total_coll =
+        query_entire_system_coll(querier, active_pool_addr.clone(), default_pool_addr.clone())?;
+    let total_debt = query_entire_system_debt(querier, active_pool_addr, default_pool_addr)?;
+    let tcr = ultra_math::compute_cr(total_coll, total_debt, price)?;
+
+    Ok(SystemStatus {
+        total_coll,
+        total_debt,
+        tcr,
+        recovery_mode: tcr < ccr,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use cosmwasm_std::testing::MockQuerier;
+    use cosmwasm_std::{
+        from_binary, to_binary, ContractResult, Empty, SystemError, SystemResult, WasmQuery,
+    };
+
+    const ACTIVE_POOL: &str = "active-pool";
+    const DEFAULT_POOL: &str = "default-pool";
+
+    /// Mocks active and default pools holding the given collateral and debt
+    fn mock_pools(active: (u128, u128), default: (u128, u128)) -> MockQuerier<Empty> {
+        let mut querier = MockQuerier::new(&[]);
+        querier.update_wasm(move |query| {
+            let (contract_addr, msg) = match query {
+                WasmQuery::Smart { contract_addr, msg } => (contract_addr.as_str(), msg),
+                _ => panic!("Unexpected query {:?}", query),
+            };
+            let (coll, debt) = match contract_addr {
+                ACTIVE_POOL => active,
+                DEFAULT_POOL => default,
+                _ => {
+                    return SystemResult::Err(SystemError::NoSuchContract {
+                        addr: contract_addr.to_string(),
+                    })
+                }
+            };
+            // Both pools share the shape of these queries
+            let amount = match from_binary(msg).unwrap() {
+                ActivePoolQueryMsg::GetCollateral {} => coll,
+                ActivePoolQueryMsg::GetULTRADebt {} => debt,
+                msg => panic!("Unexpected query {:?}", msg),
+            };
+            SystemResult::Ok(ContractResult::Ok(
+                to_binary(&Uint128::new(amount)).unwrap(),
+            ))
+        });
+        querier
+    }
+
+    fn system_status(
+        querier: &MockQuerier<Empty>,
+        price: Decimal256,
+        ccr: Decimal256,
+        default_pool_addr: &str,
+    ) -> StdResult<SystemStatus> {
+        query_system_status(
+            &QuerierWrapper::new(querier),
+            price,
+            ccr,
+            Addr::unchecked(ACTIVE_POOL),
+            Addr::unchecked(default_pool_addr),
+        )
+    }
+
+    #[test]
+    fn adds_up_both_pools() {
+        let querier = mock_pools((1_000, 400), (200, 100));
+        let status = system_status(
+            &querier,
+            Decimal256::percent(200),
+            Decimal256::percent(150),
+            DEFAULT_POOL,
+        )
+        .unwrap();
+        assert_eq!(
+            status,
+            SystemStatus {
+                total_coll: Uint128::new(1_200),
+                total_debt: Uint128::new(500),
+                tcr: Decimal256::from_ratio(24u128, 5u128),
+                recovery_mode: false,
+            }
+        );
+    }
+
+    #[test]
+    fn recovery_mode_below_ccr() {
+        // TCR = 1_500 * 0.1 / 100 = 1.5
+        let querier = mock_pools((1_000, 80), (500, 20));
+        let ccr = Decimal256::percent(150);
+        let status = system_status(&querier, Decimal256::percent(10), ccr, DEFAULT_POOL).unwrap();
+        assert_eq!(status.tcr, ccr);
+        assert!(!status.recovery_mode);
+
+        let status = system_status(&querier, Decimal256::percent(9), ccr, DEFAULT_POOL).unwrap();
+        assert_eq!(status.tcr, Decimal256::percent(135));
+        assert!(status.recovery_mode);
+    }
+
+    #[test]
+    fn no_debt_is_never_recovery_mode() {
+        let querier = mock_pools((1_000, 0), (0, 0));
+        let status = system_status(
+            &querier,
+            Decimal256::percent(1),
+            Decimal256::percent(150),
+            DEFAULT_POOL,
+        )
+        .unwrap();
+        assert_eq!(status.tcr, Decimal256::MAX);
+        assert!(!status.recovery_mode);
+    }
+
+    #[test]
+    fn query_errors_are_returned() {
+        let querier = mock_pools((1_000, 400), (200, 100));
+        system_status(
+            &querier,
+            Decimal256::one(),
+            Decimal256::percent(150),
+            "unknown-pool",
+        )
+        .unwrap_err();
+    }
 }
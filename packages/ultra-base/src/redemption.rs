@@ -0,0 +1,465 @@
+use cosmwasm_std::{Addr, Decimal256, StdError, StdResult, Timestamp, Uint128, Uint256};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::base_rate::{require_user_accepts_fee, BaseRate, BaseRateError, FeeQuote};
+use crate::risk_params::RiskParams;
+use crate::ultra_math::{compute_cr, compute_nominal_cr, min, DECIMAL_PRECISION};
+
+#[derive(Error, Debug, PartialEq)]
+pub enum RedemptionError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("{0}")]
+    BaseRate(#[from] BaseRateError),
+
+    #[error("Redemption: Amount must be greater than zero")]
+    ZeroAmount {},
+
+    #[error("Redemption: Cannot redeem when TCR < MCR")]
+    TcrBelowMcr {},
+
+    #[error("Redemption: Unable to redeem any amount")]
+    NothingRedeemed {},
+}
+
+/// Entire debt and collateral of a trove, pending rewards included
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct TroveSnapshot {
+    pub borrower: Addr,
+    pub debt: Uint128,
+    pub coll: Uint128,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RedemptionRequest {
+    pub ultra_amount: Uint128,
+    pub price: Decimal256,
+    /// NICR the last redeemed trove must end up with to be partially redeemed
+    pub partial_redemption_hint_nicr: Uint256,
+    /// Maximum number of troves to redeem from, zero for no limit
+    pub max_iterations: u128,
+    pub max_fee_percentage: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum TroveRedemption {
+    /// The whole net debt was redeemed. The trove is closed, its gas compensation is burnt from
+    /// the gas pool and the remaining collateral goes to the coll surplus pool
+    Closed {
+        borrower: Addr,
+        ultra_lot: Uint128,
+        coll_lot: Uint128,
+        surplus: Uint128,
+    },
+    /// Part of the debt was redeemed, the trove has to be reinserted at `new_nicr` using the
+    /// upper and lower partial redemption hints
+    Partial {
+        borrower: Addr,
+        ultra_lot: Uint128,
+        coll_lot: Uint128,
+        new_debt: Uint128,
+        new_coll: Uint128,
+        new_nicr: Uint256,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct Redemption {
+    pub troves: Vec<TroveRedemption>,
+    /// ULTRA to burn from the redeemer
+    pub ultra_redeemed: Uint128,
+    pub coll_drawn: Uint128,
+    /// Redemption fee, taken from the collateral drawn
+    pub fee: FeeQuote,
+    pub coll_to_redeemer: Uint128,
+}
+
+/// A first redemption hint is valid when its trove is the one with the lowest ICR not below
+/// the MCR. `hint_icr` is the ICR of the hinted trove if it exists, and `next_icr` the ICR of
+/// the next trove in the sorted list, with a lower NICR, if any.
+pub fn is_valid_first_redemption_hint(
+    hint_icr: Option<Decimal256>,
+    next_icr: Option<Decimal256>,
+    mcr: Decimal256,
+) -> bool {
+    match hint_icr {
+        Some(hint_icr) if hint_icr >= mcr => !matches!(next_icr, Some(next_icr) if next_icr >= mcr),
+        _ => false,
+    }
+}
+
+/// Redeems `request.ultra_amount` from `troves`, which are walked from the lowest ICR upwards
+/// starting either at a valid first redemption hint or at the last trove of the sorted list.
+///
+/// Leading troves below the MCR are skipped. Each trove is redeemed down to its gas
+/// compensation, in which case it's closed, until the amount is exhausted. The last trove is
+/// only partially redeemed if its new NICR matches the hint and its net debt stays above the
+/// minimum, otherwise the redemption stops before it. The base rate is then increased by the
+/// redeemed fraction of `total_ultra_supply` and the redemption fee charged on the collateral.
+pub fn redeem_collateral<I>(
+    troves: I,
+    request: &RedemptionRequest,
+    tcr: Decimal256,
+    total_ultra_supply: Uint128,
+    base_rate: &mut BaseRate,
+    now: Timestamp,
+    params: &RiskParams,
+) -> Result<Redemption, RedemptionError>
+where
+    I: IntoIterator<Item = StdResult<TroveSnapshot>>,
+{
+    if request.max_fee_percentage < params.redemption_fee_floor
+        || request.max_fee_percentage > Decimal256::one()
+    {
+        return Err(BaseRateError::InvalidMaxFeePercentage {
+            min: params.redemption_fee_floor,
+        }
+        .into());
+    }
+    if tcr < params.mcr {
+        return Err(RedemptionError::TcrBelowMcr {});
+    }
+    if request.ultra_amount.is_zero() {
+        return Err(RedemptionError::ZeroAmount {});
+    }
+
+    let mut troves = troves.into_iter().peekable();
+    while let Some(Ok(trove)) = troves.peek() {
+        if compute_cr(trove.coll, trove.debt, request.price)? >= params.mcr {
+            break;
+        }
+        troves.next();
+    }
+
+    let max_iterations = match request.max_iterations {
+        0 => usize::MAX,
+        max_iterations => usize::try_from(max_iterations).unwrap_or(usize::MAX),
+    };
+    let mut remaining_ultra = request.ultra_amount;
+    let mut redemptions = vec![];
+    let (mut ultra_redeemed, mut coll_drawn) = (Uint128::zero(), Uint128::zero());
+    for trove in troves.take(max_iterations) {
+        if remaining_ultra.is_zero() {
+            break;
+        }
+        let redemption = match redeem_trove(trove?, remaining_ultra, request, params)? {
+            Some(redemption) => redemption,
+            // A cancelled partial redemption ends the walk
+            None => break,
+        };
+        let (ultra_lot, coll_lot) = match &redemption {
+            TroveRedemption::Closed {
+                ultra_lot,
+                coll_lot,
+                ..
+            }
+            | TroveRedemption::Partial {
+                ultra_lot,
+                coll_lot,
+                ..
+            } => (*ultra_lot, *coll_lot),
+        };
+        remaining_ultra -= ultra_lot;
+        ultra_redeemed += ultra_lot;
+        coll_drawn += coll_lot;
+        redemptions.push(redemption);
+    }
+
+    if coll_drawn.is_zero() {
+        return Err(RedemptionError::NothingRedeemed {});
+    }
+
+    // The fee depends on the base rate updated by this redemption
+    base_rate.update_from_redemption(coll_drawn, request.price, total_ultra_supply, now, params)?;
+    let fee = base_rate.redemption_fee(coll_drawn, params)?;
+    require_user_accepts_fee(fee.fee, coll_drawn, request.max_fee_percentage)?;
+
+    Ok(Redemption {
+        troves: redemptions,
+        ultra_redeemed,
+        coll_drawn,
+        coll_to_redeemer: coll_drawn - fee.fee,
+        fee,
+    })
+}
+
+/// Redeems up to `max_ultra` from `trove`, returns `None` if the partial redemption is cancelled
+fn redeem_trove(
+    trove: TroveSnapshot,
+    max_ultra: Uint128,
+    request: &RedemptionRequest,
+    params: &RiskParams,
+) -> StdResult<Option<TroveRedemption>> {
+    // The gas compensation can't be redeemed
+    let ultra_lot = min(
+        max_ultra,
+        trove.debt.saturating_sub(params.gas_compensation),
+    );
+    let coll_lot = Uint256::from(ultra_lot)
+        .checked_mul(DECIMAL_PRECISION)?
+        .checked_div(request.price.atomics())
+        .map_err(StdError::divide_by_zero)?;
+    let coll_lot = Uint128::try_from(coll_lot)?;
+
+    let new_debt = trove.debt - ultra_lot;
+    let new_coll = trove.coll.checked_sub(coll_lot)?;
+    if new_debt == params.gas_compensation {
+        return Ok(Some(TroveRedemption::Closed {
+            borrower: trove.borrower,
+            ultra_lot,
+            coll_lot,
+            surplus: new_coll,
+        }));
+    }
+
+    // The hint may be outdated by the time the redemption is executed, or the trove may be left
+    // with too little debt. Either way redeeming it would break the list or the trove
+    let new_nicr = compute_nominal_cr(new_coll, new_debt)?;
+    if new_nicr != request.partial_redemption_hint_nicr
+        || new_debt.saturating_sub(params.gas_compensation) < params.min_net_debt
+    {
+        return Ok(None);
+    }
+    Ok(Some(TroveRedemption::Partial {
+        borrower: trove.borrower,
+        ultra_lot,
+        coll_lot,
+        new_debt,
+        new_coll,
+        new_nicr,
+    }))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const SUPPLY: u128 = 100_000;
+
+    fn trove(borrower: &str, debt: u128, coll: u128) -> StdResult<TroveSnapshot> {
+        Ok(TroveSnapshot {
+            borrower: Addr::unchecked(borrower),
+            debt: Uint128::new(debt),
+            coll: Uint128::new(coll),
+        })
+    }
+
+    /// Troves from the lowest ICR upwards, at a price of 2
+    fn troves() -> Vec<StdResult<TroveSnapshot>> {
+        vec![
+            // ICR 0.91, skipped
+            trove("undercollateralized", 2_200, 1_000),
+            // ICR 1.18, fully redeemable
+            trove("first", 2_200, 1_300),
+            // ICR 1.43
+            trove("second", 4_200, 3_000),
+            trove("third", 10_000, 10_000),
+        ]
+    }
+
+    fn request(ultra_amount: u128) -> RedemptionRequest {
+        RedemptionRequest {
+            ultra_amount: Uint128::new(ultra_amount),
+            price: Decimal256::percent(200),
+            // "second" left with 2_500 collateral for 3_200 debt
+            partial_redemption_hint_nicr: Uint256::from(78_125_000_000_000_000_000u128),
+            max_iterations: 0,
+            max_fee_percentage: Decimal256::percent(5),
+        }
+    }
+
+    fn redeem(
+        troves: Vec<StdResult<TroveSnapshot>>,
+        request: &RedemptionRequest,
+        base_rate: &mut BaseRate,
+    ) -> Result<Redemption, RedemptionError> {
+        redeem_collateral(
+            troves,
+            request,
+            Decimal256::percent(150),
+            Uint128::new(SUPPLY),
+            base_rate,
+            Timestamp::from_seconds(600),
+            &RiskParams::default(),
+        )
+    }
+
+    fn first_closed() -> TroveRedemption {
+        TroveRedemption::Closed {
+            borrower: Addr::unchecked("first"),
+            ultra_lot: Uint128::new(2_000),
+            coll_lot: Uint128::new(1_000),
+            surplus: Uint128::new(300),
+        }
+    }
+
+    #[test]
+    fn first_redemption_hint() {
+        let mcr = Decimal256::percent(110);
+        assert!(is_valid_first_redemption_hint(
+            Some(Decimal256::percent(120)),
+            Some(Decimal256::percent(100)),
+            mcr
+        ));
+        assert!(is_valid_first_redemption_hint(Some(mcr), None, mcr));
+        assert!(!is_valid_first_redemption_hint(
+            Some(Decimal256::percent(120)),
+            Some(Decimal256::percent(115)),
+            mcr
+        ));
+        assert!(!is_valid_first_redemption_hint(
+            Some(Decimal256::percent(100)),
+            None,
+            mcr
+        ));
+        assert!(!is_valid_first_redemption_hint(None, None, mcr));
+    }
+
+    #[test]
+    fn closes_and_partially_redeems_troves() {
+        let mut base_rate = BaseRate::default();
+        let res = redeem(troves(), &request(3_000), &mut base_rate).unwrap();
+        assert_eq!(
+            res.troves,
+            vec![
+                first_closed(),
+                TroveRedemption::Partial {
+                    borrower: Addr::unchecked("second"),
+                    ultra_lot: Uint128::new(1_000),
+                    coll_lot: Uint128::new(500),
+                    new_debt: Uint128::new(3_200),
+                    new_coll: Uint128::new(2_500),
+                    new_nicr: Uint256::from(78_125_000_000_000_000_000u128),
+                },
+            ]
+        );
+        assert_eq!(res.ultra_redeemed, Uint128::new(3_000));
+        assert_eq!(res.coll_drawn, Uint128::new(1_500));
+
+        // 3% of the supply was redeemed, the base rate grows by half of it
+        assert_eq!(base_rate.base_rate, Decimal256::permille(15));
+        assert_eq!(
+            res.fee,
+            FeeQuote {
+                rate: Decimal256::percent(2),
+                fee: Uint128::new(30),
+            }
+        );
+        assert_eq!(res.coll_to_redeemer, Uint128::new(1_470));
+    }
+
+    #[test]
+    fn cancels_partial_redemptions() {
+        // Outdated hint
+        let mut base_rate = BaseRate::default();
+        let res = redeem(
+            troves(),
+            &RedemptionRequest {
+                partial_redemption_hint_nicr: Uint256::from(1u8),
+                ..request(3_000)
+            },
+            &mut base_rate,
+        )
+        .unwrap();
+        assert_eq!(res.troves, vec![first_closed()]);
+        assert_eq!(res.ultra_redeemed, Uint128::new(2_000));
+        assert_eq!(res.fee.fee, Uint128::new(15));
+
+        // "second" would be left with 1_500 net debt, below the minimum
+        let res = redeem(
+            troves(),
+            &RedemptionRequest {
+                partial_redemption_hint_nicr: compute_nominal_cr(
+                    Uint128::new(1_750),
+                    Uint128::new(1_700),
+                )
+                .unwrap(),
+                ..request(4_500)
+            },
+            &mut BaseRate::default(),
+        )
+        .unwrap();
+        assert_eq!(res.troves, vec![first_closed()]);
+
+        // Nothing is redeemed when the first partial redemption is cancelled
+        let err = redeem(
+            troves().split_off(2),
+            &RedemptionRequest {
+                partial_redemption_hint_nicr: Uint256::from(1u8),
+                ..request(1_000)
+            },
+            &mut BaseRate::default(),
+        )
+        .unwrap_err();
+        assert_eq!(err, RedemptionError::NothingRedeemed {});
+    }
+
+    #[test]
+    fn stops_after_max_iterations() {
+        let res = redeem(
+            troves(),
+            &RedemptionRequest {
+                max_iterations: 1,
+                ..request(3_000)
+            },
+            &mut BaseRate::default(),
+        )
+        .unwrap();
+        assert_eq!(res.troves, vec![first_closed()]);
+    }
+
+    #[test]
+    fn rejects_invalid_redemptions() {
+        let cases = [
+            (request(0), RedemptionError::ZeroAmount {}),
+            (
+                RedemptionRequest {
+                    max_fee_percentage: Decimal256::permille(4),
+                    ..request(3_000)
+                },
+                RedemptionError::BaseRate(BaseRateError::InvalidMaxFeePercentage {
+                    min: Decimal256::permille(5),
+                }),
+            ),
+            // The fee is 2%
+            (
+                RedemptionRequest {
+                    max_fee_percentage: Decimal256::percent(1),
+                    ..request(3_000)
+                },
+                RedemptionError::BaseRate(BaseRateError::FeeExceedsMaximum {}),
+            ),
+        ];
+        for (request, err) in cases {
+            assert_eq!(
+                redeem(troves(), &request, &mut BaseRate::default()).unwrap_err(),
+                err
+            );
+        }
+
+        let err = redeem_collateral(
+            troves(),
+            &request(3_000),
+            Decimal256::percent(109),
+            Uint128::new(SUPPLY),
+            &mut BaseRate::default(),
+            Timestamp::from_seconds(600),
+            &RiskParams::default(),
+        )
+        .unwrap_err();
+        assert_eq!(err, RedemptionError::TcrBelowMcr {});
+
+        // Only undercollateralized troves
+        let err = redeem(
+            vec![trove("low", 2_200, 1_000)],
+            &request(3_000),
+            &mut BaseRate::default(),
+        )
+        .unwrap_err();
+        assert_eq!(err, RedemptionError::NothingRedeemed {});
+    }
+}
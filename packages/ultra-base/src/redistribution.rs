@@ -0,0 +1,284 @@
+use cosmwasm_std::{Decimal256, StdError, StdResult, Uint128, Uint256};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::ultra_math::DECIMAL_PRECISION;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum RedistributionError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Redistribution: Cannot redistribute without any stake in the system")]
+    NoStakes {},
+}
+
+/// Rewards accumulated per unit staked from the redistribution of liquidated troves.
+///
+/// Each trove earns `stake * (L - snapshot)` of collateral and debt since its reward snapshot
+/// was last updated. The remainders of the divisions are carried over to the next
+/// redistribution so that no collateral or debt is lost to rounding.
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Redistribution {
+    /// JUNO reward per unit staked
+    pub l_juno: Decimal256,
+    /// ULTRA debt reward per unit staked
+    pub l_ultra_debt: Decimal256,
+    pub last_juno_error_redistribution: Uint256,
+    pub last_ultra_debt_error_redistribution: Uint256,
+    pub total_stakes: Uint128,
+    /// Total stakes right after the latest liquidation
+    pub total_stakes_snapshot: Uint128,
+    /// Collateral of the active troves right after the latest liquidation, pending rewards
+    /// included and gas compensation excluded
+    pub total_collateral_snapshot: Uint128,
+}
+
+/// Values of `L_JUNO` and `L_ULTRADebt` when the rewards of a trove were last applied
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct RewardSnapshot {
+    pub juno: Decimal256,
+    pub ultra_debt: Decimal256,
+}
+
+impl Redistribution {
+    /// Stake of a trove holding `coll`. Troves opened after redistributions get a stake
+    /// proportionally lower than their collateral, as it doesn't earn past rewards
+    pub fn compute_new_stake(&self, coll: Uint128) -> Uint128 {
+        if self.total_collateral_snapshot.is_zero() {
+            return coll;
+        }
+        coll.multiply_ratio(self.total_stakes_snapshot, self.total_collateral_snapshot)
+    }
+
+    /// Replaces `old_stake` by the stake of `coll` in the total stakes and returns it
+    pub fn update_stake_and_total_stakes(
+        &mut self,
+        old_stake: Uint128,
+        coll: Uint128,
+    ) -> StdResult<Uint128> {
+        let new_stake = self.compute_new_stake(coll);
+        self.total_stakes = self
+            .total_stakes
+            .checked_sub(old_stake)?
+            .checked_add(new_stake)?;
+        Ok(new_stake)
+    }
+
+    pub fn remove_stake(&mut self, stake: Uint128) -> StdResult<()> {
+        self.total_stakes = self.total_stakes.checked_sub(stake)?;
+        Ok(())
+    }
+
+    /// Distributes `debt` and `coll` to all active troves, proportionally to their stake
+    pub fn redistribute_debt_and_coll(
+        &mut self,
+        debt: Uint128,
+        coll: Uint128,
+    ) -> Result<(), RedistributionError> {
+        if debt.is_zero() {
+            return Ok(());
+        }
+        if self.total_stakes.is_zero() {
+            return Err(RedistributionError::NoStakes {});
+        }
+        let (juno_reward_per_unit_staked, juno_error) =
+            reward_per_unit_staked(coll, self.last_juno_error_redistribution, self.total_stakes)?;
+        let (ultra_debt_reward_per_unit_staked, ultra_debt_error) = reward_per_unit_staked(
+            debt,
+            self.last_ultra_debt_error_redistribution,
+            self.total_stakes,
+        )?;
+
+        self.last_juno_error_redistribution = juno_error;
+        self.last_ultra_debt_error_redistribution = ultra_debt_error;
+        self.l_juno += juno_reward_per_unit_staked;
+        self.l_ultra_debt += ultra_debt_reward_per_unit_staked;
+        Ok(())
+    }
+
+    /// Snapshots the stakes and collateral once a liquidation is done, `coll_remainder` being
+    /// the collateral still in the active pool that is sent to the liquidator
+    pub fn update_system_snapshots(
+        &mut self,
+        active_pool_coll: Uint128,
+        default_pool_coll: Uint128,
+        coll_remainder: Uint128,
+    ) -> StdResult<()> {
+        self.total_stakes_snapshot = self.total_stakes;
+        self.total_collateral_snapshot = active_pool_coll
+            .checked_sub(coll_remainder)?
+            .checked_add(default_pool_coll)?;
+        Ok(())
+    }
+
+    pub fn snapshot(&self) -> RewardSnapshot {
+        RewardSnapshot {
+            juno: self.l_juno,
+            ultra_debt: self.l_ultra_debt,
+        }
+    }
+
+    pub fn has_pending_rewards(&self, snapshot: &RewardSnapshot) -> bool {
+        snapshot.juno < self.l_juno
+    }
+
+    pub fn pending_juno_reward(
+        &self,
+        stake: Uint128,
+        snapshot: &RewardSnapshot,
+    ) -> StdResult<Uint128> {
+        pending_reward(stake, self.l_juno, snapshot.juno)
+    }
+
+    pub fn pending_ultra_debt_reward(
+        &self,
+        stake: Uint128,
+        snapshot: &RewardSnapshot,
+    ) -> StdResult<Uint128> {
+        pending_reward(stake, self.l_ultra_debt, snapshot.ultra_debt)
+    }
+}
+
+/// Share of `amount` per unit staked, with the error of the previous division added back, and
+/// the error of this one
+fn reward_per_unit_staked(
+    amount: Uint128,
+    last_error: Uint256,
+    total_stakes: Uint128,
+) -> StdResult<(Decimal256, Uint256)> {
+    let total_stakes = Uint256::from(total_stakes);
+    let numerator = Uint256::from(amount)
+        .checked_mul(DECIMAL_PRECISION)?
+        .checked_add(last_error)?;
+    let reward_per_unit_staked = numerator / total_stakes;
+    let error = numerator - reward_per_unit_staked * total_stakes;
+    Ok((Decimal256::new(reward_per_unit_staked), error))
+}
+
+fn pending_reward(stake: Uint128, l: Decimal256, snapshot: Decimal256) -> StdResult<Uint128> {
+    let reward_per_unit_staked = l
+        .atomics()
+        .checked_sub(snapshot.atomics())
+        .map_err(StdError::overflow)?;
+    let reward = Uint256::from(stake).checked_mul(reward_per_unit_staked)? / DECIMAL_PRECISION;
+    Ok(Uint128::try_from(reward)?)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn stakes_follow_collateral_snapshots() {
+        let mut redistribution = Redistribution::default();
+        let stake = redistribution
+            .update_stake_and_total_stakes(Uint128::zero(), Uint128::new(1_000))
+            .unwrap();
+        assert_eq!(stake, Uint128::new(1_000));
+
+        // The system collateral doubled through redistributions
+        redistribution
+            .update_system_snapshots(Uint128::new(1_500), Uint128::new(510), Uint128::new(10))
+            .unwrap();
+        assert_eq!(
+            redistribution.total_collateral_snapshot,
+            Uint128::new(2_000)
+        );
+        let stake = redistribution
+            .update_stake_and_total_stakes(stake, Uint128::new(3_000))
+            .unwrap();
+        assert_eq!(stake, Uint128::new(1_500));
+        assert_eq!(redistribution.total_stakes, Uint128::new(1_500));
+
+        redistribution.remove_stake(stake).unwrap();
+        assert_eq!(redistribution.total_stakes, Uint128::zero());
+        redistribution.remove_stake(Uint128::new(1)).unwrap_err();
+    }
+
+    #[test]
+    fn rewards_are_shared_by_stake() {
+        let mut redistribution = Redistribution {
+            total_stakes: Uint128::new(4_000),
+            ..Redistribution::default()
+        };
+        let snapshot = redistribution.snapshot();
+        redistribution
+            .redistribute_debt_and_coll(Uint128::new(2_000), Uint128::new(2_200))
+            .unwrap();
+        assert!(redistribution.has_pending_rewards(&snapshot));
+        assert!(!redistribution.has_pending_rewards(&redistribution.snapshot()));
+
+        let stake = Uint128::new(1_000);
+        assert_eq!(
+            redistribution
+                .pending_juno_reward(stake, &snapshot)
+                .unwrap(),
+            Uint128::new(550)
+        );
+        assert_eq!(
+            redistribution
+                .pending_ultra_debt_reward(stake, &snapshot)
+                .unwrap(),
+            Uint128::new(500)
+        );
+        assert_eq!(
+            redistribution
+                .pending_juno_reward(stake, &redistribution.snapshot())
+                .unwrap(),
+            Uint128::zero()
+        );
+    }
+
+    #[test]
+    fn division_errors_are_carried_over() {
+        let mut redistribution = Redistribution {
+            total_stakes: Uint128::new(3),
+            ..Redistribution::default()
+        };
+        let snapshot = redistribution.snapshot();
+        for _ in 0..3 {
+            redistribution
+                .redistribute_debt_and_coll(Uint128::new(1), Uint128::new(1))
+                .unwrap();
+        }
+        assert_eq!(
+            redistribution.last_juno_error_redistribution,
+            Uint256::zero()
+        );
+        // Each of the three stakes gets exactly one of the redistributed units
+        assert_eq!(
+            redistribution.l_juno,
+            Decimal256::one(),
+            "error feedback must make up for the truncated divisions"
+        );
+        assert_eq!(
+            redistribution
+                .pending_ultra_debt_reward(Uint128::new(1), &snapshot)
+                .unwrap(),
+            Uint128::new(1)
+        );
+
+        redistribution
+            .redistribute_debt_and_coll(Uint128::new(1), Uint128::new(1))
+            .unwrap();
+        assert_eq!(
+            redistribution.last_ultra_debt_error_redistribution,
+            Uint256::from(1u8)
+        );
+    }
+
+    #[test]
+    fn redistribution_requires_stakes() {
+        let mut redistribution = Redistribution::default();
+        // Nothing to redistribute
+        redistribution
+            .redistribute_debt_and_coll(Uint128::zero(), Uint128::zero())
+            .unwrap();
+        let err = redistribution
+            .redistribute_debt_and_coll(Uint128::new(1), Uint128::new(1))
+            .unwrap_err();
+        assert_eq!(err, RedistributionError::NoStakes {});
+    }
+}
@@ -0,0 +1,146 @@
+use cosmwasm_std::{Addr, Decimal256, Uint128};
+use cw20::Cw20ReceiveMsg;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::asset::AssetInfo;
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct InstantiateMsg {
+    pub name: String,
+    pub owner: String,
+    /// cw20 token staked to earn a share of the protocol fees
+    pub reward_token: String,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Stake the reward tokens sent with a `Cw20HookMsg::Stake`
+    Receive(Cw20ReceiveMsg),
+    /// Unstake `amount` reward tokens, or the whole stake if it is lower, and claim the gains
+    Unstake { amount: Uint128 },
+    /// Send the collateral and ULTRA gains of the caller
+    Claim {},
+    /// Share the redemption fee in the native `collateral` sent along with this message among
+    /// the stakers, cw20 collateral being sent with a `Cw20HookMsg::IncreaseCollateralFee`.
+    /// Can only be called by the trove manager with a registered collateral type
+    IncreaseCollateralFee { collateral: AssetInfo },
+    /// Share the `ultra_fee` ULTRA borrowing fee minted to the pool among the stakers.
+    /// Can only be called by borrower operations
+    IncreaseULTRAFee { ultra_fee: Uint128 },
+    /// Point the pool to the address registry its peers are resolved from.
+    /// Can only be called by the owner while the addresses are not locked
+    SetAddressRegistry { address_registry: String },
+    /// Refresh the cached peer addresses from the address registry.
+    /// Can be called by anyone while the addresses are not locked
+    SyncAddresses {},
+    /// Propose a new owner, who becomes owner once it accepts.
+    /// Can only be called by the owner
+    ProposeOwner { owner: String },
+    /// Become the owner. Can only be called by the pending owner
+    AcceptOwner {},
+    /// Leave the pool without owner. Can only be called by the owner
+    RenounceOwnership {},
+    /// Permanently prevent the addresses from being changed again.
+    /// Can only be called by the owner once the addresses are synced
+    LockAddresses {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Stake the received reward tokens and claim the gains of the sender
+    Stake {},
+    /// Share the received cw20 collateral as a redemption fee among the stakers.
+    /// Can only be sent by the trove manager with a registered collateral type
+    IncreaseCollateralFee {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    GetParams {},
+    /// Returns the owner, pending owner and addresses lock status
+    GetOwnership {},
+    /// Returns the reward tokens staked by `staker`, zero if none
+    GetStake {
+        staker: Addr,
+    },
+    GetTotalStaked {},
+    /// Returns the fees earned per unit staked since the pool was created
+    GetFeesPerUnitStaked {},
+    /// Returns the `collateral` earned by `staker` since its gains were last paid out
+    GetPendingCollGain {
+        collateral: AssetInfo,
+        staker: Addr,
+    },
+    GetPendingULTRAGain {
+        staker: Addr,
+    },
+    GetBorrowerOperationsAddress {},
+    GetTroveManagerAddress {},
+    GetULTRATokenAddress {},
+    GetRewardTokenAddress {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    /// Update the contract parameters
+    /// Can only be called by governance
+    UpdateParams {
+        name: Option<String>,
+        owner: Option<Addr>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ParamsResponse {
+    pub name: String,
+    pub owner: Addr,
+}
+
+/// Running sums of the fees earned per reward token staked, also snapshotted for each staker
+/// when their gains are paid out
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct FeesPerUnitStaked {
+    /// Redemption fees of each collateral type redeemed so far
+    pub f_colls: Vec<CollateralFee>,
+    pub f_ultra: Decimal256,
+}
+
+/// Running sum of the redemption fees in a collateral type earned per reward token staked
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralFee {
+    pub collateral: AssetInfo,
+    pub fee: Decimal256,
+}
+
+impl FeesPerUnitStaked {
+    /// Fees earned in `collateral` per unit staked, zero if it wasn't redeemed yet
+    pub fn f_coll(&self, collateral: &AssetInfo) -> Decimal256 {
+        self.f_colls
+            .iter()
+            .find(|item| item.collateral == *collateral)
+            .map(|item| item.fee)
+            .unwrap_or_default()
+    }
+
+    pub fn add_coll_fee(&mut self, collateral: &AssetInfo, fee_per_unit_staked: Decimal256) {
+        match self
+            .f_colls
+            .iter_mut()
+            .find(|item| item.collateral == *collateral)
+        {
+            Some(item) => item.fee += fee_per_unit_staked,
+            None => self.f_colls.push(CollateralFee {
+                collateral: collateral.clone(),
+                fee: fee_per_unit_staked,
+            }),
+        }
+    }
+}
@@ -0,0 +1,279 @@
+use cosmwasm_std::{Decimal256, StdError, StdResult, Uint128, Uint256};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::base_rate::minute_decay_factor;
+
+/// Minute decay factor of the default 12 hours half-life, as found by `minute_decay_factor`
+const DEFAULT_BASE_RATE_MINUTE_DECAY_FACTOR: u128 = 999_037_758_833_783_388;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum RiskParamsError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("RiskParams: MCR must be greater than 100%")]
+    McrTooLow {},
+
+    #[error("RiskParams: CCR must be greater than MCR")]
+    CcrNotAboveMcr {},
+
+    #[error("RiskParams: {param} must be greater than zero")]
+    Zero { param: String },
+
+    #[error("RiskParams: {param} must not be greater than 100%")]
+    FeeTooHigh { param: String },
+
+    #[error("RiskParams: Borrowing fee floor must not be greater than the max borrowing fee")]
+    BorrowingFeeFloorAboveMax {},
+}
+
+/// Risk parameters of the protocol, set by governance and read by the contracts at runtime
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct RiskParams {
+    /// Minimum collateral ratio for individual troves
+    pub mcr: Decimal256,
+    /// Critical system collateral ratio. If the system's total collateral ratio (TCR) falls below the CCR, Recovery Mode is triggered.
+    pub ccr: Decimal256,
+    /// Minimum amount of net ULTRA debt a trove must have
+    pub min_net_debt: Uint128,
+    pub borrowing_fee_floor: Decimal256,
+    pub redemption_fee_floor: Decimal256,
+    pub max_borrowing_fee: Decimal256,
+    /// Amount of ULTRA reserved in each trove to compensate liquidators for gas
+    pub gas_compensation: Uint128,
+    /// Share of the collateral of liquidated troves sent to the liquidator
+    pub coll_gas_compensation: Decimal256,
+    /// Time in seconds it takes for the base rate to decay by half
+    pub base_rate_half_life: u64,
+    /// Factor by which the base rate decays every minute, computed when the half-life is set
+    pub base_rate_minute_decay_factor: Decimal256,
+}
+
+impl Default for RiskParams {
+    fn default() -> Self {
+        RiskParams {
+            mcr: Decimal256::percent(110),
+            ccr: Decimal256::percent(150),
+            min_net_debt: Uint128::new(2000u128),
+            borrowing_fee_floor: Decimal256::permille(5),
+            redemption_fee_floor: Decimal256::permille(5),
+            max_borrowing_fee: Decimal256::percent(5),
+            gas_compensation: Uint128::new(200u128),
+            coll_gas_compensation: Decimal256::permille(5),
+            base_rate_half_life: 12 * 60 * 60,
+            base_rate_minute_decay_factor: Decimal256::new(Uint256::from(
+                DEFAULT_BASE_RATE_MINUTE_DECAY_FACTOR,
+            )),
+        }
+    }
+}
+
+/// Parameters to change, the missing ones keep their current value
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct RiskParamsUpdate {
+    pub mcr: Option<Decimal256>,
+    pub ccr: Option<Decimal256>,
+    pub min_net_debt: Option<Uint128>,
+    pub borrowing_fee_floor: Option<Decimal256>,
+    pub redemption_fee_floor: Option<Decimal256>,
+    pub max_borrowing_fee: Option<Decimal256>,
+    pub gas_compensation: Option<Uint128>,
+    pub coll_gas_compensation: Option<Decimal256>,
+    pub base_rate_half_life: Option<u64>,
+}
+
+impl RiskParams {
+    /// Checks the parameters are within bounds and consistent with each other
+    pub fn validate(&self) -> Result<(), RiskParamsError> {
+        if self.mcr <= Decimal256::one() {
+            return Err(RiskParamsError::McrTooLow {});
+        }
+        if self.ccr <= self.mcr {
+            return Err(RiskParamsError::CcrNotAboveMcr {});
+        }
+        for (param, is_zero) in [
+            ("min_net_debt", self.min_net_debt.is_zero()),
+            ("gas_compensation", self.gas_compensation.is_zero()),
+            ("base_rate_half_life", self.base_rate_half_life == 0),
+        ] {
+            if is_zero {
+                return Err(RiskParamsError::Zero {
+                    param: param.to_string(),
+                });
+            }
+        }
+        for (param, fee) in [
+            ("borrowing_fee_floor", self.borrowing_fee_floor),
+            ("redemption_fee_floor", self.redemption_fee_floor),
+            ("max_borrowing_fee", self.max_borrowing_fee),
+            ("coll_gas_compensation", self.coll_gas_compensation),
+        ] {
+            if fee > Decimal256::one() {
+                return Err(RiskParamsError::FeeTooHigh {
+                    param: param.to_string(),
+                });
+            }
+        }
+        if self.borrowing_fee_floor > self.max_borrowing_fee {
+            return Err(RiskParamsError::BorrowingFeeFloorAboveMax {});
+        }
+        Ok(())
+    }
+
+    /// Returns the parameters with `update` applied, once validated
+    pub fn apply(&self, update: RiskParamsUpdate) -> Result<RiskParams, RiskParamsError> {
+        let base_rate_half_life = update
+            .base_rate_half_life
+            .unwrap_or(self.base_rate_half_life);
+        let base_rate_minute_decay_factor = if base_rate_half_life == self.base_rate_half_life {
+            self.base_rate_minute_decay_factor
+        } else {
+            minute_decay_factor(base_rate_half_life)?
+        };
+        let params = RiskParams {
+            mcr: update.mcr.unwrap_or(self.mcr),
+            ccr: update.ccr.unwrap_or(self.ccr),
+            min_net_debt: update.min_net_debt.unwrap_or(self.min_net_debt),
+            borrowing_fee_floor: update
+                .borrowing_fee_floor
+                .unwrap_or(self.borrowing_fee_floor),
+            redemption_fee_floor: update
+                .redemption_fee_floor
+                .unwrap_or(self.redemption_fee_floor),
+            max_borrowing_fee: update.max_borrowing_fee.unwrap_or(self.max_borrowing_fee),
+            gas_compensation: update.gas_compensation.unwrap_or(self.gas_compensation),
+            coll_gas_compensation: update
+                .coll_gas_compensation
+                .unwrap_or(self.coll_gas_compensation),
+            base_rate_half_life,
+            base_rate_minute_decay_factor,
+        };
+        params.validate()?;
+        Ok(params)
+    }
+
+    /// Debt of a trove including the ULTRA gas compensation reserved in the gas pool
+    pub fn composite_debt(&self, debt: Uint128) -> StdResult<Uint128> {
+        Ok(debt.checked_add(self.gas_compensation)?)
+    }
+
+    /// Debt of a trove the borrower has to repay, excluding the ULTRA gas compensation
+    pub fn net_debt(&self, debt: Uint128) -> StdResult<Uint128> {
+        Ok(debt.checked_sub(self.gas_compensation)?)
+    }
+
+    /// Collateral sent to the liquidator of a trove holding `coll`
+    pub fn coll_gas_compensation(&self, coll: Uint128) -> StdResult<Uint128> {
+        Ok(Uint128::try_from(
+            Uint256::from(coll) * self.coll_gas_compensation,
+        )?)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn default_params_are_valid() {
+        let params = RiskParams::default();
+        params.validate().unwrap();
+        assert_eq!(params.mcr.to_string(), "1.1");
+        assert_eq!(params.ccr.to_string(), "1.5");
+        assert_eq!(params.borrowing_fee_floor.to_string(), "0.005");
+        assert_eq!(params.coll_gas_compensation.to_string(), "0.005");
+        assert_eq!(
+            params.base_rate_minute_decay_factor,
+            minute_decay_factor(params.base_rate_half_life).unwrap()
+        );
+    }
+
+    #[test]
+    fn gas_compensation() {
+        let params = RiskParams::default();
+        assert_eq!(
+            params.composite_debt(Uint128::new(1_800)).unwrap(),
+            Uint128::new(2_000)
+        );
+        assert_eq!(
+            params.net_debt(Uint128::new(2_000)).unwrap(),
+            Uint128::new(1_800)
+        );
+        params.net_debt(Uint128::new(199)).unwrap_err();
+        assert_eq!(
+            params.coll_gas_compensation(Uint128::new(10_199)).unwrap(),
+            Uint128::new(50)
+        );
+    }
+
+    #[test]
+    fn apply_keeps_missing_params() {
+        let params = RiskParams::default()
+            .apply(RiskParamsUpdate {
+                ccr: Some(Decimal256::percent(160)),
+                base_rate_half_life: Some(3600),
+                ..RiskParamsUpdate::default()
+            })
+            .unwrap();
+        assert_eq!(
+            params,
+            RiskParams {
+                ccr: Decimal256::percent(160),
+                base_rate_half_life: 3600,
+                base_rate_minute_decay_factor: minute_decay_factor(3600).unwrap(),
+                ..RiskParams::default()
+            }
+        );
+    }
+
+    #[test]
+    fn apply_rejects_out_of_bounds_params() {
+        let params = RiskParams::default();
+        let cases = [
+            (
+                RiskParamsUpdate {
+                    mcr: Some(Decimal256::one()),
+                    ..RiskParamsUpdate::default()
+                },
+                RiskParamsError::McrTooLow {},
+            ),
+            (
+                RiskParamsUpdate {
+                    mcr: Some(Decimal256::percent(150)),
+                    ..RiskParamsUpdate::default()
+                },
+                RiskParamsError::CcrNotAboveMcr {},
+            ),
+            (
+                RiskParamsUpdate {
+                    min_net_debt: Some(Uint128::zero()),
+                    ..RiskParamsUpdate::default()
+                },
+                RiskParamsError::Zero {
+                    param: "min_net_debt".to_string(),
+                },
+            ),
+            (
+                RiskParamsUpdate {
+                    redemption_fee_floor: Some(Decimal256::percent(101)),
+                    ..RiskParamsUpdate::default()
+                },
+                RiskParamsError::FeeTooHigh {
+                    param: "redemption_fee_floor".to_string(),
+                },
+            ),
+            (
+                RiskParamsUpdate {
+                    borrowing_fee_floor: Some(Decimal256::percent(6)),
+                    ..RiskParamsUpdate::default()
+                },
+                RiskParamsError::BorrowingFeeFloorAboveMax {},
+            ),
+        ];
+        for (update, err) in cases {
+            assert_eq!(params.apply(update).unwrap_err(), err);
+        }
+    }
+}
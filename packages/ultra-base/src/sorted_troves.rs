@@ -28,14 +28,34 @@ pub enum ExecuteMsg {
     },
     SetParams {
         size: Uint256,
-        borrower_operation_address: String,
-        trove_manager_address: String,
     },
+    /// Point the contract to the address registry its peers are resolved from.
+    /// Can only be called by the owner while the addresses are not locked
+    SetAddressRegistry {
+        address_registry: String,
+    },
+    /// Refresh the cached peer addresses from the address registry.
+    /// Can be called by anyone while the addresses are not locked
+    SyncAddresses {},
+    /// Propose a new owner, who becomes owner once it accepts.
+    /// Can only be called by the owner
+    ProposeOwner {
+        owner: String,
+    },
+    /// Become the owner. Can only be called by the pending owner
+    AcceptOwner {},
+    /// Leave the contract without owner. Can only be called by the owner
+    RenounceOwnership {},
+    /// Permanently prevent the addresses from being changed again.
+    /// Can only be called by the owner once the addresses are synced
+    LockAddresses {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 pub enum QueryMsg {
     GetParams {},
+    /// Returns the owner, pending owner and addresses lock status
+    GetOwnership {},
     GetData {},
     GetSize {},
     GetMaxSize {},
@@ -0,0 +1,636 @@
+use cosmwasm_std::{Decimal256, StdError, StdResult, Storage, Uint128, Uint256};
+use cw_storage_plus::Map;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::asset::{Asset, AssetInfo};
+use crate::ultra_math::{uint256_to_uint128, DECIMAL_PRECISION};
+
+/// 1e9, the factor `P` is scaled up by whenever it would drop below it
+pub const SCALE_FACTOR: Uint256 = Uint256::from_u128(1_000_000_000u128);
+
+/// Sums of each scale of each epoch, keyed by epoch and then by scale. Kept apart from
+/// `StabilityPoolSums` so that operations only load the sums they touch
+pub const EPOCH_TO_SCALE_TO_SUMS: Map<(u64, u64), ScaleSums> = Map::new("epoch_to_scale_to_sums");
+
+#[derive(Error, Debug, PartialEq)]
+pub enum StabilityAccountingError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("StabilityPool: There are no ULTRA deposits to offset debt with")]
+    NoDeposits {},
+
+    #[error("StabilityPool: Debt to offset exceeds the ULTRA deposits")]
+    DebtExceedsDeposits {},
+}
+
+/// Running sums of a scale of an epoch
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct ScaleSums {
+    /// Gain per unit deposited of each collateral type offset against the pool, times `P`
+    pub s: Vec<CollateralSum>,
+    /// Reward token gain per unit deposited, times `P`
+    pub g: Uint256,
+}
+
+/// Value of a sum, or of a division error, for one collateral type
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct CollateralSum {
+    pub collateral: AssetInfo,
+    pub sum: Uint256,
+}
+
+/// Value of `collateral` in `sums`, zero if it has none yet
+pub fn collateral_sum(sums: &[CollateralSum], collateral: &AssetInfo) -> Uint256 {
+    sums.iter()
+        .find(|item| item.collateral == *collateral)
+        .map(|item| item.sum)
+        .unwrap_or_else(Uint256::zero)
+}
+
+fn set_collateral_sum(sums: &mut Vec<CollateralSum>, collateral: &AssetInfo, sum: Uint256) {
+    match sums.iter_mut().find(|item| item.collateral == *collateral) {
+        Some(item) => item.sum = sum,
+        None => sums.push(CollateralSum {
+            collateral: collateral.clone(),
+            sum,
+        }),
+    }
+}
+
+/// Liquity's stability pool accounting of compounded deposits and their gains.
+///
+/// Each liquidation offset against the pool multiplies the product `P` by the fraction of the
+/// deposits left, and adds the collateral gained per unit deposited to the sum `S` of its
+/// collateral type. Reward token issuances are added to the sum `G` the same way. A deposit
+/// made when the product was `P_0` is compounded to `deposit * P / P_0` and earns
+/// `deposit * (S - S_0) / P_0` of each collateral type.
+///
+/// `P`, `S` and `G` have a precision of 1e18. To keep `P` precise it is scaled up by
+/// `SCALE_FACTOR` when it would drop below 1e-9, which starts a new scale, and an offset
+/// emptying the pool starts a new epoch where all previous deposits are worth zero.
+///
+/// The sums themselves are stored in `EPOCH_TO_SCALE_TO_SUMS`.
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct StabilityPoolSums {
+    pub p: Uint256,
+    pub current_scale: u64,
+    pub current_epoch: u64,
+    pub total_ultra_deposits: Uint128,
+    pub last_coll_error_offsets: Vec<CollateralSum>,
+    pub last_ultra_loss_error_offset: Uint256,
+    pub last_reward_error: Uint256,
+}
+
+/// Values of the sums when a deposit or front end stake was last updated
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct DepositSnapshot {
+    pub p: Uint256,
+    pub s: Vec<CollateralSum>,
+    pub g: Uint256,
+    pub scale: u64,
+    pub epoch: u64,
+}
+
+impl Default for StabilityPoolSums {
+    fn default() -> Self {
+        StabilityPoolSums {
+            p: DECIMAL_PRECISION,
+            current_scale: 0,
+            current_epoch: 0,
+            total_ultra_deposits: Uint128::zero(),
+            last_coll_error_offsets: vec![],
+            last_ultra_loss_error_offset: Uint256::zero(),
+            last_reward_error: Uint256::zero(),
+        }
+    }
+}
+
+impl StabilityPoolSums {
+    /// Sums of `scale` in `epoch`, zero if no offset or issuance reached it yet
+    pub fn sums(&self, store: &dyn Storage, epoch: u64, scale: u64) -> StdResult<ScaleSums> {
+        Ok(EPOCH_TO_SCALE_TO_SUMS
+            .may_load(store, (epoch, scale))?
+            .unwrap_or_default())
+    }
+
+    /// Applies `update` to the sums of the current scale of the current epoch
+    fn update_current_sums(
+        &self,
+        store: &mut dyn Storage,
+        update: impl FnOnce(&mut ScaleSums) -> StdResult<()>,
+    ) -> StdResult<()> {
+        let key = (self.current_epoch, self.current_scale);
+        let mut sums = self.sums(store, key.0, key.1)?;
+        update(&mut sums)?;
+        EPOCH_TO_SCALE_TO_SUMS.save(store, key, &sums)
+    }
+
+    pub fn snapshot(&self, store: &dyn Storage) -> StdResult<DepositSnapshot> {
+        let sums = self.sums(store, self.current_epoch, self.current_scale)?;
+        Ok(DepositSnapshot {
+            p: self.p,
+            s: sums.s,
+            g: sums.g,
+            scale: self.current_scale,
+            epoch: self.current_epoch,
+        })
+    }
+
+    /// Shares the reward tokens issued by the community issuance among the deposits.
+    ///
+    /// Nothing is shared while the pool is empty, the issued tokens then stay in the pool.
+    pub fn update_g(&mut self, store: &mut dyn Storage, reward_issuance: Uint128) -> StdResult<()> {
+        if self.total_ultra_deposits.is_zero() || reward_issuance.is_zero() {
+            return Ok(());
+        }
+        let (reward_per_unit_staked, error) = per_unit_staked(
+            reward_issuance,
+            self.last_reward_error,
+            self.total_ultra_deposits,
+        )?;
+        self.last_reward_error = error;
+
+        let marginal_reward_gain = reward_per_unit_staked.checked_mul(self.p)?;
+        self.update_current_sums(store, |sums| {
+            sums.g = sums.g.checked_add(marginal_reward_gain)?;
+            Ok(())
+        })
+    }
+
+    /// Cancels `debt_to_offset` against the deposits, which earn `coll_to_add` of `collateral`
+    /// in return
+    pub fn offset(
+        &mut self,
+        store: &mut dyn Storage,
+        collateral: &AssetInfo,
+        debt_to_offset: Uint128,
+        coll_to_add: Uint128,
+    ) -> Result<(), StabilityAccountingError> {
+        // Nothing to offset, even from an empty pool
+        if debt_to_offset.is_zero() {
+            return Ok(());
+        }
+        if self.total_ultra_deposits.is_zero() {
+            return Err(StabilityAccountingError::NoDeposits {});
+        }
+        if debt_to_offset > self.total_ultra_deposits {
+            return Err(StabilityAccountingError::DebtExceedsDeposits {});
+        }
+        self.update_sum_and_product(store, collateral, debt_to_offset, coll_to_add)?;
+        Ok(())
+    }
+
+    fn update_sum_and_product(
+        &mut self,
+        store: &mut dyn Storage,
+        collateral: &AssetInfo,
+        debt_to_offset: Uint128,
+        coll_to_add: Uint128,
+    ) -> StdResult<()> {
+        let (coll_gain_per_unit_staked, coll_error) = per_unit_staked(
+            coll_to_add,
+            collateral_sum(&self.last_coll_error_offsets, collateral),
+            self.total_ultra_deposits,
+        )?;
+        set_collateral_sum(&mut self.last_coll_error_offsets, collateral, coll_error);
+
+        let ultra_loss_per_unit_staked = if debt_to_offset == self.total_ultra_deposits {
+            // the pool is emptied
+            self.last_ultra_loss_error_offset = Uint256::zero();
+            DECIMAL_PRECISION
+        } else {
+            // rounded up so that the deposits are never worth more than what the pool holds
+            let total = Uint256::from(self.total_ultra_deposits);
+            let numerator = Uint256::from(debt_to_offset)
+                .checked_mul(DECIMAL_PRECISION)?
+                .checked_sub(self.last_ultra_loss_error_offset)?;
+            let loss = numerator / total + Uint256::from(1u8);
+            self.last_ultra_loss_error_offset = loss * total - numerator;
+            loss
+        };
+
+        let marginal_coll_gain = coll_gain_per_unit_staked.checked_mul(self.p)?;
+        self.update_current_sums(store, |sums| {
+            let s = collateral_sum(&sums.s, collateral).checked_add(marginal_coll_gain)?;
+            set_collateral_sum(&mut sums.s, collateral, s);
+            Ok(())
+        })?;
+
+        let new_product_factor = DECIMAL_PRECISION - ultra_loss_per_unit_staked;
+        if new_product_factor.is_zero() {
+            self.current_epoch += 1;
+            self.current_scale = 0;
+            self.p = DECIMAL_PRECISION;
+        } else {
+            let new_p = self.p.checked_mul(new_product_factor)? / DECIMAL_PRECISION;
+            if new_p < SCALE_FACTOR {
+                self.p = self
+                    .p
+                    .checked_mul(new_product_factor)?
+                    .checked_mul(SCALE_FACTOR)?
+                    / DECIMAL_PRECISION;
+                self.current_scale += 1;
+            } else {
+                self.p = new_p;
+            }
+        }
+
+        self.total_ultra_deposits = self.total_ultra_deposits.checked_sub(debt_to_offset)?;
+        Ok(())
+    }
+
+    /// Value of `initial_stake` compounded by the offsets since `snapshot`. Stakes made in a
+    /// previous epoch, or more than one scale ago, are worth zero
+    pub fn compounded_stake(
+        &self,
+        initial_stake: Uint128,
+        snapshot: &DepositSnapshot,
+    ) -> StdResult<Uint128> {
+        if initial_stake.is_zero() || snapshot.epoch < self.current_epoch {
+            return Ok(Uint128::zero());
+        }
+        let compounded = match self.current_scale - snapshot.scale {
+            0 => Uint256::from(initial_stake).checked_mul(self.p)? / snapshot.p,
+            1 => Uint256::from(initial_stake).checked_mul(self.p)? / snapshot.p / SCALE_FACTOR,
+            _ => Uint256::zero(),
+        };
+        // a stake reduced by more than a factor 1e9 is dust left by rounding errors
+        if compounded < Uint256::from(initial_stake) / SCALE_FACTOR {
+            return Ok(Uint128::zero());
+        }
+        uint256_to_uint128(compounded)
+    }
+
+    /// `collateral` earned by `initial_deposit` since `snapshot`
+    pub fn depositor_coll_gain(
+        &self,
+        store: &dyn Storage,
+        collateral: &AssetInfo,
+        initial_deposit: Uint128,
+        snapshot: &DepositSnapshot,
+    ) -> StdResult<Uint128> {
+        self.gain_from_snapshot(
+            store,
+            initial_deposit,
+            snapshot,
+            collateral_sum(&snapshot.s, collateral),
+            |sums| collateral_sum(&sums.s, collateral),
+        )
+    }
+
+    /// Non-zero gains of `initial_deposit` since `snapshot`, one per collateral type offset
+    /// against the pool in the scales the gains are counted from
+    pub fn depositor_coll_gains(
+        &self,
+        store: &dyn Storage,
+        initial_deposit: Uint128,
+        snapshot: &DepositSnapshot,
+    ) -> StdResult<Vec<Asset>> {
+        if initial_deposit.is_zero() {
+            return Ok(vec![]);
+        }
+        let mut collaterals: Vec<AssetInfo> = vec![];
+        for scale in [snapshot.scale, snapshot.scale + 1] {
+            for item in self.sums(store, snapshot.epoch, scale)?.s {
+                if !collaterals.contains(&item.collateral) {
+                    collaterals.push(item.collateral);
+                }
+            }
+        }
+        let mut gains = vec![];
+        for info in collaterals {
+            let amount = self.depositor_coll_gain(store, &info, initial_deposit, snapshot)?;
+            if !amount.is_zero() {
+                gains.push(Asset { info, amount });
+            }
+        }
+        Ok(gains)
+    }
+
+    /// Reward tokens earned by `initial_deposit` since `snapshot`. Deposits tagged with a
+    /// front end only get its `kickback_rate` of them, the rest going to the front end
+    pub fn depositor_reward_gain(
+        &self,
+        store: &dyn Storage,
+        initial_deposit: Uint128,
+        snapshot: &DepositSnapshot,
+        kickback_rate: Decimal256,
+    ) -> StdResult<Uint128> {
+        let gain =
+            self.gain_from_snapshot(store, initial_deposit, snapshot, snapshot.g, |sums| sums.g)?;
+        uint256_to_uint128(Uint256::from(gain) * kickback_rate)
+    }
+
+    /// Reward tokens earned by the deposits tagged with a front end since `snapshot`, less
+    /// the `kickback_rate` left to the depositors
+    pub fn front_end_reward_gain(
+        &self,
+        store: &dyn Storage,
+        front_end_stake: Uint128,
+        snapshot: &DepositSnapshot,
+        kickback_rate: Decimal256,
+    ) -> StdResult<Uint128> {
+        let gain =
+            self.gain_from_snapshot(store, front_end_stake, snapshot, snapshot.g, |sums| sums.g)?;
+        uint256_to_uint128(Uint256::from(gain) * (Decimal256::one() - kickback_rate))
+    }
+
+    /// Gain of `initial_stake` from the increase of a sum since `snapshot`. Offsets can only
+    /// move the stake one scale further before it is worth zero, so the gains of the next
+    /// scale are counted as well, scaled back down
+    fn gain_from_snapshot(
+        &self,
+        store: &dyn Storage,
+        initial_stake: Uint128,
+        snapshot: &DepositSnapshot,
+        sum_snapshot: Uint256,
+        sum: impl Fn(&ScaleSums) -> Uint256,
+    ) -> StdResult<Uint128> {
+        if initial_stake.is_zero() {
+            return Ok(Uint128::zero());
+        }
+        let first_portion = sum(&self.sums(store, snapshot.epoch, snapshot.scale)?)
+            .checked_sub(sum_snapshot)
+            .map_err(StdError::overflow)?;
+        let second_portion =
+            sum(&self.sums(store, snapshot.epoch, snapshot.scale + 1)?) / SCALE_FACTOR;
+
+        let gain = Uint256::from(initial_stake)
+            .checked_mul(first_portion.checked_add(second_portion)?)?
+            / snapshot.p
+            / DECIMAL_PRECISION;
+        uint256_to_uint128(gain)
+    }
+}
+
+/// Share of `amount` per unit deposited with a precision of 1e18, with the error of the
+/// previous division added back, and the error of this one
+fn per_unit_staked(
+    amount: Uint128,
+    last_error: Uint256,
+    total_deposits: Uint128,
+) -> StdResult<(Uint256, Uint256)> {
+    let total_deposits = Uint256::from(total_deposits);
+    let numerator = Uint256::from(amount)
+        .checked_mul(DECIMAL_PRECISION)?
+        .checked_add(last_error)?;
+    let per_unit_staked = numerator / total_deposits;
+    let error = numerator - per_unit_staked * total_deposits;
+    Ok((per_unit_staked, error))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{native_asset, native_asset_info, UJUNO_DENOM};
+    use cosmwasm_std::testing::MockStorage;
+    use cosmwasm_std::Order;
+
+    fn juno() -> AssetInfo {
+        native_asset_info(UJUNO_DENOM.to_string())
+    }
+
+    fn sums_with_deposits(total: u128) -> StabilityPoolSums {
+        StabilityPoolSums {
+            total_ultra_deposits: Uint128::new(total),
+            ..StabilityPoolSums::default()
+        }
+    }
+
+    #[test]
+    fn rewards_are_shared_by_deposit() {
+        let mut store = MockStorage::new();
+        let mut sums = sums_with_deposits(4_000);
+        let snapshot = sums.snapshot(&store).unwrap();
+        sums.update_g(&mut store, Uint128::new(1_000)).unwrap();
+
+        let gain = |deposit: u128, kickback_rate| {
+            sums.depositor_reward_gain(&store, Uint128::new(deposit), &snapshot, kickback_rate)
+                .unwrap()
+        };
+        assert_eq!(gain(1_000, Decimal256::one()), Uint128::new(250));
+        assert_eq!(gain(3_000, Decimal256::one()), Uint128::new(750));
+        // A front end keeping 20% of the gains of the 3000 tagged with it
+        assert_eq!(gain(3_000, Decimal256::percent(80)), Uint128::new(600));
+        assert_eq!(
+            sums.front_end_reward_gain(
+                &store,
+                Uint128::new(3_000),
+                &snapshot,
+                Decimal256::percent(80)
+            )
+            .unwrap(),
+            Uint128::new(150)
+        );
+        // Nothing was earned since the latest snapshot
+        let latest = sums.snapshot(&store).unwrap();
+        assert_eq!(
+            sums.depositor_reward_gain(&store, Uint128::new(1_000), &latest, Decimal256::one())
+                .unwrap(),
+            Uint128::zero()
+        );
+    }
+
+    #[test]
+    fn rewards_are_not_shared_without_deposits() {
+        let mut store = MockStorage::new();
+        let mut sums = StabilityPoolSums::default();
+        sums.update_g(&mut store, Uint128::new(1_000)).unwrap();
+        assert_eq!(sums.sums(&store, 0, 0).unwrap(), ScaleSums::default());
+        assert_eq!(sums.last_reward_error, Uint256::zero());
+    }
+
+    #[test]
+    fn reward_division_errors_are_carried_over() {
+        let mut store = MockStorage::new();
+        let mut sums = sums_with_deposits(3);
+        let snapshot = sums.snapshot(&store).unwrap();
+        for _ in 0..3 {
+            sums.update_g(&mut store, Uint128::new(1)).unwrap();
+        }
+        assert_eq!(sums.last_reward_error, Uint256::zero());
+        assert_eq!(
+            sums.depositor_reward_gain(&store, Uint128::new(1), &snapshot, Decimal256::one())
+                .unwrap(),
+            Uint128::new(1)
+        );
+    }
+
+    #[test]
+    fn offsets_compound_deposits() {
+        let mut store = MockStorage::new();
+        let mut sums = sums_with_deposits(1_000);
+        let snapshot = sums.snapshot(&store).unwrap();
+        sums.offset(&mut store, &juno(), Uint128::new(400), Uint128::new(500))
+            .unwrap();
+
+        assert_eq!(sums.total_ultra_deposits, Uint128::new(600));
+        assert_eq!(
+            sums.compounded_stake(Uint128::new(100), &snapshot).unwrap(),
+            Uint128::new(59)
+        );
+        assert_eq!(
+            sums.depositor_coll_gain(&store, &juno(), Uint128::new(100), &snapshot)
+                .unwrap(),
+            Uint128::new(50)
+        );
+
+        // Rewards issued after the offset are shared by the compounded deposits
+        let later_snapshot = sums.snapshot(&store).unwrap();
+        sums.update_g(&mut store, Uint128::new(60)).unwrap();
+        assert_eq!(
+            sums.depositor_reward_gain(
+                &store,
+                Uint128::new(600),
+                &later_snapshot,
+                Decimal256::one()
+            )
+            .unwrap(),
+            Uint128::new(60)
+        );
+        assert_eq!(
+            sums.depositor_reward_gain(&store, Uint128::new(1_000), &snapshot, Decimal256::one())
+                .unwrap(),
+            Uint128::new(59)
+        );
+    }
+
+    #[test]
+    fn collateral_types_have_their_own_sums() {
+        let mut store = MockStorage::new();
+        let atom = native_asset_info("uatom".to_string());
+        let mut sums = sums_with_deposits(1_000);
+        let snapshot = sums.snapshot(&store).unwrap();
+        sums.offset(&mut store, &juno(), Uint128::new(400), Uint128::new(300))
+            .unwrap();
+        sums.offset(&mut store, &atom, Uint128::new(300), Uint128::new(7))
+            .unwrap();
+
+        let gain = |collateral: &AssetInfo| {
+            sums.depositor_coll_gain(&store, collateral, Uint128::new(500), &snapshot)
+                .unwrap()
+        };
+        assert_eq!(gain(&juno()), Uint128::new(150));
+        assert_eq!(gain(&atom), Uint128::new(3));
+        assert_eq!(
+            sums.depositor_coll_gains(&store, Uint128::new(500), &snapshot)
+                .unwrap(),
+            vec![
+                native_asset(UJUNO_DENOM.to_string(), Uint128::new(150)),
+                native_asset("uatom".to_string(), Uint128::new(3)),
+            ]
+        );
+        // The division errors are carried over for each collateral type
+        assert_eq!(
+            collateral_sum(&sums.last_coll_error_offsets, &juno()),
+            Uint256::zero()
+        );
+        assert_ne!(
+            collateral_sum(&sums.last_coll_error_offsets, &atom),
+            Uint256::zero()
+        );
+
+        // Deposits made after an offset don't earn from it
+        let later_snapshot = sums.snapshot(&store).unwrap();
+        sums.offset(&mut store, &atom, Uint128::new(100), Uint128::new(80))
+            .unwrap();
+        assert_eq!(
+            sums.depositor_coll_gains(&store, Uint128::new(300), &later_snapshot)
+                .unwrap(),
+            vec![native_asset("uatom".to_string(), Uint128::new(80))]
+        );
+    }
+
+    #[test]
+    fn emptying_offset_starts_a_new_epoch() {
+        let mut store = MockStorage::new();
+        let mut sums = sums_with_deposits(1_000);
+        let snapshot = sums.snapshot(&store).unwrap();
+        sums.update_g(&mut store, Uint128::new(100)).unwrap();
+        sums.offset(
+            &mut store,
+            &juno(),
+            Uint128::new(1_000),
+            Uint128::new(1_100),
+        )
+        .unwrap();
+
+        assert_eq!(sums.current_epoch, 1);
+        assert_eq!(sums.current_scale, 0);
+        assert_eq!(sums.p, DECIMAL_PRECISION);
+        assert_eq!(
+            sums.compounded_stake(Uint128::new(1_000), &snapshot)
+                .unwrap(),
+            Uint128::zero()
+        );
+        // The gains earned before the pool was emptied are kept
+        assert_eq!(
+            sums.depositor_coll_gain(&store, &juno(), Uint128::new(1_000), &snapshot)
+                .unwrap(),
+            Uint128::new(1_100)
+        );
+        assert_eq!(
+            sums.depositor_reward_gain(&store, Uint128::new(1_000), &snapshot, Decimal256::one())
+                .unwrap(),
+            Uint128::new(100)
+        );
+        // The new epoch starts from zero sums
+        assert_eq!(sums.sums(&store, 1, 0).unwrap(), ScaleSums::default());
+
+        let err = sums
+            .offset(&mut store, &juno(), Uint128::new(1), Uint128::new(1))
+            .unwrap_err();
+        assert_eq!(err, StabilityAccountingError::NoDeposits {});
+        // Offsetting no debt from the empty pool changes nothing
+        sums.offset(&mut store, &juno(), Uint128::zero(), Uint128::zero())
+            .unwrap();
+        assert_eq!(sums.current_epoch, 1);
+        assert_eq!(sums.p, DECIMAL_PRECISION);
+    }
+
+    #[test]
+    fn small_product_starts_a_new_scale() {
+        let mut store = MockStorage::new();
+        // P is already down to 1e-8
+        let mut sums = StabilityPoolSums {
+            p: Uint256::from(10_000_000_000u128),
+            ..sums_with_deposits(1_000)
+        };
+        let snapshot = sums.snapshot(&store).unwrap();
+        // Leaves a tenth of the deposits, P would drop below 1e-9
+        sums.offset(&mut store, &juno(), Uint128::new(900), Uint128::zero())
+            .unwrap();
+        assert_eq!(sums.current_scale, 1);
+        assert_eq!(sums.p, Uint256::from(999_999_999_999_999_990u128));
+        assert_eq!(
+            sums.compounded_stake(Uint128::new(1_000), &snapshot)
+                .unwrap(),
+            Uint128::new(99)
+        );
+
+        // Rewards issued in the new scale still reach the deposits made in the previous one,
+        // all but the rounding of the loss
+        sums.update_g(&mut store, Uint128::new(50)).unwrap();
+        assert_eq!(
+            sums.depositor_reward_gain(&store, Uint128::new(1_000), &snapshot, Decimal256::one())
+                .unwrap(),
+            Uint128::new(49)
+        );
+        assert_eq!(sums.sums(&store, 0, 0).unwrap().g, Uint256::zero());
+        assert_eq!(
+            EPOCH_TO_SCALE_TO_SUMS
+                .keys(&store, None, None, Order::Ascending)
+                .collect::<StdResult<Vec<_>>>()
+                .unwrap(),
+            vec![(0, 0), (0, 1)]
+        );
+
+        let err = sums
+            .offset(&mut store, &juno(), Uint128::new(101), Uint128::zero())
+            .unwrap_err();
+        assert_eq!(err, StabilityAccountingError::DebtExceedsDeposits {});
+    }
+}
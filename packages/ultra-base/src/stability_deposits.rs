@@ -0,0 +1,310 @@
+use cosmwasm_std::{Addr, Decimal256, StdError, StdResult, Storage, Uint128};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::asset::{Asset, AssetInfo};
+use crate::front_ends::{deposit_front_end_tag, kickback_rate, FrontEnd};
+use crate::stability_accounting::{DepositSnapshot, StabilityPoolSums};
+use crate::ultra_math::min;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum StabilityDepositError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("StabilityPool: Amount must be non-zero")]
+    ZeroAmount {},
+
+    #[error("StabilityPool: User must have a non-zero deposit")]
+    NoDeposit {},
+
+    #[error("StabilityPool: Cannot withdraw while there are troves with ICR < MCR")]
+    UnderCollateralizedTroves {},
+}
+
+/// ULTRA deposit, compounded by the offsets since its snapshot was taken
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct Deposit {
+    pub initial_value: Uint128,
+    pub front_end_tag: Option<Addr>,
+    pub snapshot: DepositSnapshot,
+}
+
+/// Outcome of a deposit change, the gains and withdrawn ULTRA being due to the depositor
+#[derive(Serialize, Deserialize, Clone, Debug, Default, PartialEq, JsonSchema)]
+pub struct DepositUpdate {
+    /// Non-zero gain of each collateral type
+    pub coll_gains: Vec<Asset>,
+    pub reward_gain: Uint128,
+    /// ULTRA of the deposit burnt by the offsets since its snapshot
+    pub ultra_loss: Uint128,
+    pub withdrawn: Uint128,
+    pub new_deposit: Uint128,
+}
+
+impl Deposit {
+    pub fn compounded(&self, sums: &StabilityPoolSums) -> StdResult<Uint128> {
+        sums.compounded_stake(self.initial_value, &self.snapshot)
+    }
+
+    pub fn coll_gain(
+        &self,
+        store: &dyn Storage,
+        sums: &StabilityPoolSums,
+        collateral: &AssetInfo,
+    ) -> StdResult<Uint128> {
+        sums.depositor_coll_gain(store, collateral, self.initial_value, &self.snapshot)
+    }
+
+    pub fn coll_gains(
+        &self,
+        store: &dyn Storage,
+        sums: &StabilityPoolSums,
+    ) -> StdResult<Vec<Asset>> {
+        sums.depositor_coll_gains(store, self.initial_value, &self.snapshot)
+    }
+
+    /// Reward tokens earned by the deposit, less the share of the front end it is tagged with
+    pub fn reward_gain(
+        &self,
+        store: &dyn Storage,
+        sums: &StabilityPoolSums,
+        front_end: Option<&FrontEnd>,
+    ) -> StdResult<Uint128> {
+        sums.depositor_reward_gain(
+            store,
+            self.initial_value,
+            &self.snapshot,
+            kickback_rate(front_end),
+        )
+    }
+
+    /// Tops up the compounded deposit with `amount`. First deposits are tagged with
+    /// `front_end_tag`, whose registration must be checked beforehand.
+    ///
+    /// The reward issuance must be added to the sums beforehand, and the front end stake
+    /// updated afterwards with the same amount.
+    pub fn provide(
+        &mut self,
+        store: &mut dyn Storage,
+        sums: &mut StabilityPoolSums,
+        amount: Uint128,
+        front_end_tag: Option<Addr>,
+        front_end: Option<&FrontEnd>,
+    ) -> Result<DepositUpdate, StabilityDepositError> {
+        if amount.is_zero() {
+            return Err(StabilityDepositError::ZeroAmount {});
+        }
+        self.front_end_tag =
+            deposit_front_end_tag(self.initial_value, self.front_end_tag.take(), front_end_tag);
+        let mut update = self.take_gains(store, sums, front_end)?;
+
+        update.new_deposit = update
+            .new_deposit
+            .checked_add(amount)
+            .map_err(StdError::overflow)?;
+        sums.total_ultra_deposits = sums
+            .total_ultra_deposits
+            .checked_add(amount)
+            .map_err(StdError::overflow)?;
+        self.update_deposit_and_snapshot(store, sums, update.new_deposit)?;
+        Ok(update)
+    }
+
+    /// Withdraws up to `amount` of the compounded deposit, and pays out the gains. A zero
+    /// `amount` only pays out the gains, otherwise `require_no_under_collateralized_troves`
+    /// must be checked beforehand.
+    ///
+    /// The reward issuance must be added to the sums beforehand, and the front end stake
+    /// updated afterwards with the withdrawn amount.
+    pub fn withdraw(
+        &mut self,
+        store: &mut dyn Storage,
+        sums: &mut StabilityPoolSums,
+        amount: Uint128,
+        front_end: Option<&FrontEnd>,
+    ) -> Result<DepositUpdate, StabilityDepositError> {
+        if self.initial_value.is_zero() {
+            return Err(StabilityDepositError::NoDeposit {});
+        }
+        let mut update = self.take_gains(store, sums, front_end)?;
+
+        update.withdrawn = min(amount, update.new_deposit);
+        update.new_deposit -= update.withdrawn;
+        sums.total_ultra_deposits = sums
+            .total_ultra_deposits
+            .checked_sub(update.withdrawn)
+            .map_err(StdError::overflow)?;
+        self.update_deposit_and_snapshot(store, sums, update.new_deposit)?;
+        Ok(update)
+    }
+
+    /// Gains and compounded value of the deposit
+    fn take_gains(
+        &self,
+        store: &dyn Storage,
+        sums: &StabilityPoolSums,
+        front_end: Option<&FrontEnd>,
+    ) -> StdResult<DepositUpdate> {
+        let compounded = self.compounded(sums)?;
+        Ok(DepositUpdate {
+            coll_gains: self.coll_gains(store, sums)?,
+            reward_gain: self.reward_gain(store, sums, front_end)?,
+            ultra_loss: self.initial_value.checked_sub(compounded)?,
+            withdrawn: Uint128::zero(),
+            new_deposit: compounded,
+        })
+    }
+
+    /// Fully withdrawn deposits lose their snapshot and front end tag
+    fn update_deposit_and_snapshot(
+        &mut self,
+        store: &dyn Storage,
+        sums: &StabilityPoolSums,
+        new_deposit: Uint128,
+    ) -> StdResult<()> {
+        if new_deposit.is_zero() {
+            *self = Deposit::default();
+            return Ok(());
+        }
+        self.initial_value = new_deposit;
+        self.snapshot = sums.snapshot(store)?;
+        Ok(())
+    }
+}
+
+/// Checks that no trove can be liquidated, given the ICR of the trove with the lowest one.
+/// Otherwise depositors could withdraw ahead of the liquidation to avoid its losses
+pub fn require_no_under_collateralized_troves(
+    lowest_icr: Option<Decimal256>,
+    mcr: Decimal256,
+) -> Result<(), StabilityDepositError> {
+    if matches!(lowest_icr, Some(icr) if icr < mcr) {
+        return Err(StabilityDepositError::UnderCollateralizedTroves {});
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asset::{native_asset, native_asset_info, UJUNO_DENOM};
+    use cosmwasm_std::testing::MockStorage;
+
+    #[test]
+    fn provide_tags_first_deposits() {
+        let mut store = MockStorage::new();
+        let mut sums = StabilityPoolSums::default();
+        let front_end = FrontEnd::register(None, false, Decimal256::percent(80)).unwrap();
+        let (first, second) = (Addr::unchecked("first"), Addr::unchecked("second"));
+
+        let mut deposit = Deposit::default();
+        let err = deposit
+            .provide(&mut store, &mut sums, Uint128::zero(), None, None)
+            .unwrap_err();
+        assert_eq!(err, StabilityDepositError::ZeroAmount {});
+
+        let update = deposit
+            .provide(
+                &mut store,
+                &mut sums,
+                Uint128::new(1_000),
+                Some(first.clone()),
+                Some(&front_end),
+            )
+            .unwrap();
+        assert_eq!(update.new_deposit, Uint128::new(1_000));
+        assert_eq!(deposit.front_end_tag, Some(first.clone()));
+        assert_eq!(deposit.snapshot, sums.snapshot(&store).unwrap());
+        assert_eq!(sums.total_ultra_deposits, Uint128::new(1_000));
+
+        // Top-ups pay out the gains and keep the first tag
+        sums.update_g(&mut store, Uint128::new(100)).unwrap();
+        let update = deposit
+            .provide(
+                &mut store,
+                &mut sums,
+                Uint128::new(500),
+                Some(second),
+                Some(&front_end),
+            )
+            .unwrap();
+        assert_eq!(update.reward_gain, Uint128::new(80));
+        assert_eq!(update.new_deposit, Uint128::new(1_500));
+        assert_eq!(deposit.front_end_tag, Some(first));
+        assert_eq!(sums.total_ultra_deposits, Uint128::new(1_500));
+    }
+
+    #[test]
+    fn partial_withdrawals() {
+        let mut store = MockStorage::new();
+        let mut sums = StabilityPoolSums::default();
+        let mut deposit = Deposit::default();
+        let err = deposit
+            .withdraw(&mut store, &mut sums, Uint128::new(1), None)
+            .unwrap_err();
+        assert_eq!(err, StabilityDepositError::NoDeposit {});
+
+        deposit
+            .provide(&mut store, &mut sums, Uint128::new(1_000), None, None)
+            .unwrap();
+        let mut other = Deposit::default();
+        other
+            .provide(&mut store, &mut sums, Uint128::new(1_000), None, None)
+            .unwrap();
+        let juno = native_asset_info(UJUNO_DENOM.to_string());
+        sums.offset(&mut store, &juno, Uint128::new(500), Uint128::new(600))
+            .unwrap();
+
+        let update = deposit
+            .withdraw(&mut store, &mut sums, Uint128::new(300), None)
+            .unwrap();
+        assert_eq!(
+            update,
+            DepositUpdate {
+                coll_gains: vec![native_asset(UJUNO_DENOM.to_string(), Uint128::new(300))],
+                reward_gain: Uint128::zero(),
+                ultra_loss: Uint128::new(251),
+                withdrawn: Uint128::new(300),
+                new_deposit: Uint128::new(449),
+            }
+        );
+        assert_eq!(deposit.initial_value, Uint128::new(449));
+        assert_eq!(sums.total_ultra_deposits, Uint128::new(1_200));
+
+        // Withdrawing nothing only pays out the gains
+        let update = other
+            .withdraw(&mut store, &mut sums, Uint128::zero(), None)
+            .unwrap();
+        assert_eq!(
+            other.coll_gain(&store, &sums, &juno).unwrap(),
+            Uint128::zero()
+        );
+        assert_eq!(
+            update.coll_gains,
+            vec![native_asset(UJUNO_DENOM.to_string(), Uint128::new(300))]
+        );
+        assert_eq!(update.withdrawn, Uint128::zero());
+        assert_eq!(other.initial_value, Uint128::new(749));
+
+        // Withdrawing more than the deposit withdraws all of it
+        let update = deposit
+            .withdraw(&mut store, &mut sums, Uint128::new(1_000), None)
+            .unwrap();
+        assert_eq!(update.withdrawn, Uint128::new(449));
+        assert_eq!(update.coll_gains, vec![]);
+        assert_eq!(deposit, Deposit::default());
+        assert_eq!(sums.total_ultra_deposits, Uint128::new(751));
+    }
+
+    #[test]
+    fn withdrawals_are_blocked_by_liquidatable_troves() {
+        let mcr = Decimal256::percent(110);
+        require_no_under_collateralized_troves(None, mcr).unwrap();
+        require_no_under_collateralized_troves(Some(mcr), mcr).unwrap();
+        let err = require_no_under_collateralized_troves(Some(Decimal256::percent(109)), mcr)
+            .unwrap_err();
+        assert_eq!(err, StabilityDepositError::UnderCollateralizedTroves {});
+    }
+}
@@ -1,7 +1,10 @@
-use cosmwasm_std::Addr;
+use cosmwasm_std::{Addr, Decimal256, Uint128};
+use cw20::Cw20ReceiveMsg;
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::asset::AssetInfo;
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMsg {
     pub name: String,
@@ -11,48 +14,104 @@ pub struct InstantiateMsg {
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
-    ProvideToSP {},
-    WithdrawFromSP {},
-    WithdrawJUNOGainToTrove {},
-    RegisterFrontEnd {},
-    Offset {},
-    SetAddresses {
-        borrower_operations_address: String,
-        trove_manager_address: String,
-        active_pool_address: String,
-        ultra_token_address: String,
-        sorted_troves_address: String,
-        price_feed_address: String,
-    },
+    /// Deposit the ULTRA sent with a `Cw20HookMsg::ProvideToSP`
+    Receive(Cw20ReceiveMsg),
+    /// Withdraw `amount` ULTRA, or the whole compounded deposit if it is lower, and the gains.
+    /// Withdrawing a non-zero amount fails while there are troves with ICR < MCR
+    WithdrawFromSP { amount: Uint128 },
+    /// Move the `collateral` gain of the caller to its trove of that collateral type, the
+    /// other gains being paid out to the caller
+    WithdrawCollGainToTrove { collateral: AssetInfo },
+    /// Register the caller as a front end leaving `kickback_rate` of the reward gains to the
+    /// depositors it tags. The caller must have no deposit, and the rate can't be changed
+    RegisterFrontEnd { kickback_rate: Decimal256 },
+    /// Offset the debt of liquidated troves of `collateral` against the deposits.
+    /// Can only be called by the trove manager
+    Offset { collateral: AssetInfo },
+    /// Point the contract to the address registry its peers are resolved from.
+    /// Can only be called by the owner while the addresses are not locked
+    SetAddressRegistry { address_registry: String },
+    /// Refresh the cached peer addresses from the address registry.
+    /// Can be called by anyone while the addresses are not locked
+    SyncAddresses {},
+    /// Propose a new owner, who becomes owner once it accepts.
+    /// Can only be called by the owner
+    ProposeOwner { owner: String },
+    /// Become the owner. Can only be called by the pending owner
+    AcceptOwner {},
+    /// Leave the contract without owner. Can only be called by the owner
+    RenounceOwnership {},
+    /// Permanently prevent the addresses from being changed again.
+    /// Can only be called by the owner once the addresses are synced
+    LockAddresses {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Deposit the received ULTRA, tagging the deposit with `front_end` if it is the first
+    /// one. Front ends can't deposit
+    ProvideToSP { front_end: Option<String> },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetParams {},
+    GetOwnership {},
     GetCurrentEpoch {},
     GetCurrentScale {},
-    GetDeposits { input: String },
-    GetDepositSnapshot { input: String },
-    GetFrontEnds { input: String },
-    GetFrontEndStakes { input: String },
-    GetFrontEndSnapshots { input: String },
-    GetFrontEndRewardGain { frontend: String },
-    GetDepositorJUNOGain { depositor: String },
-    GetDepositorRewardGain { depositor: String },
-    GetLastJUNOErrorOffset {},
+    GetDeposits {
+        input: String,
+    },
+    GetDepositSnapshot {
+        input: String,
+    },
+    /// Returns the kickback rate and registration status of `front_end`
+    GetFrontEnd {
+        front_end: String,
+    },
+    /// Returns the sum of the deposits tagged with `front_end` when its stake was last updated
+    GetFrontEndStake {
+        front_end: String,
+    },
+    /// Returns the sums when the stake of `front_end` was last updated
+    GetFrontEndSnapshot {
+        front_end: String,
+    },
+    GetFrontEndRewardGain {
+        front_end: String,
+    },
+    GetDepositorCollGain {
+        collateral: AssetInfo,
+        depositor: String,
+    },
+    GetDepositorRewardGain {
+        depositor: String,
+    },
+    GetLastCollErrorOffset {
+        collateral: AssetInfo,
+    },
     GetLastRewardError {},
     GetLastUltraLossErrorOffset {},
-    GetJUNO {},
+    /// Returns the amount of `collateral` held by the pool
+    GetCollateral {
+        collateral: AssetInfo,
+    },
     GetTotalUltraDeposits {},
-    GetCompoundedFrontEndStake {},
-    GetCompoundedUltraDeposit {},
+    GetCompoundedFrontEndStake {
+        front_end: String,
+    },
+    GetCompoundedUltraDeposit {
+        depositor: String,
+    },
     GetBorrowerOperationsAddress {},
     GetTroveManagerAddress {},
     GetActivePoolAddress {},
     GetUltraTokenAddress {},
     GetSortedTrovesAddress {},
     GetPriceFeedAddress {},
+    GetCommunityIssuanceAddress {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -0,0 +1,301 @@
+use cosmwasm_std::{Addr, Decimal256, StdError, StdResult, Uint128, Uint256};
+use cw20::Cw20ReceiveMsg;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+use crate::asset::AssetInfo;
+use crate::ultra_math::uint256_to_uint128;
+
+/// Route the gains of a collateral type are sold through
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct CollateralSwap {
+    pub collateral: AssetInfo,
+    /// WasmSwap pool trading the collateral against ULTRA
+    pub swap_pool: Addr,
+    /// JunoSwap oracle of the swap pool, pricing the collateral in ULTRA
+    pub oracle: Addr,
+}
+
+/// cw20 token minted by the vault to represent the shares of its deposit
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct ShareTokenParams {
+    pub code_id: u64,
+    pub name: String,
+    pub symbol: String,
+    pub decimals: u8,
+}
+
+#[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
+pub struct InstantiateMsg {
+    pub name: String,
+    pub owner: String,
+    pub share_token: ShareTokenParams,
+    pub stability_pool: String,
+    pub ultra_token: String,
+    /// Address registry listing the collateral types the stability pool pays gains in
+    pub address_registry: String,
+    /// Routes the collateral gains are sold through, at most one per collateral type
+    pub collateral_swaps: Vec<CollateralSwap>,
+    /// cw20 token issued by the community issuance to the stability pool depositors
+    pub reward_token: String,
+    /// WasmSwap pool the reward tokens are sold to for ULTRA
+    pub reward_swap_pool: String,
+    /// JunoSwap oracle of the reward swap pool, pricing the reward tokens in ULTRA
+    pub reward_oracle: String,
+    /// Front end the deposit of the vault is tagged with
+    pub front_end: Option<String>,
+    /// Maximum difference between the value of a swap input at the TWAP price and the actual
+    /// output of the swap, swap fees and price impact included
+    pub max_slippage: Decimal256,
+    /// Maximum difference between the average price of a swap and the pool's spot price
+    pub max_price_impact: Decimal256,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum ExecuteMsg {
+    /// Deposit the ULTRA sent with a `Cw20HookMsg::Deposit`, or redeem the shares sent with a
+    /// `Cw20HookMsg::Redeem`
+    Receive(Cw20ReceiveMsg),
+    /// Claim the collateral and reward token gains of the vault, swap them to ULTRA and deposit
+    /// it back into the stability pool. Can be called by anyone, typically a keeper
+    Harvest {},
+    /// Deposit the ULTRA held by the vault into the stability pool.
+    /// Can only be called by the vault itself, at the end of a harvest
+    Compound {},
+    /// Update the swap limits. Can only be called by the owner
+    UpdateConfig {
+        max_slippage: Option<Decimal256>,
+        max_price_impact: Option<Decimal256>,
+    },
+    /// Set the route the gains of a registered collateral type are sold through.
+    /// Can only be called by the owner
+    SetCollateralSwap { swap: CollateralSwap },
+    /// Propose a new owner, who becomes owner once it accepts.
+    /// Can only be called by the owner
+    ProposeOwner { owner: String },
+    /// Become the owner. Can only be called by the pending owner
+    AcceptOwner {},
+    /// Leave the vault without owner. Can only be called by the owner
+    RenounceOwnership {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum Cw20HookMsg {
+    /// Deposit the received ULTRA and mint shares to the sender
+    Deposit {},
+    /// Burn the received shares and send their part of the ULTRA and of the collateral and reward
+    /// token gains of the vault to the sender. Fails while the stability pool blocks withdrawals
+    Redeem {},
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum QueryMsg {
+    GetParams {},
+    /// Returns the owner and pending owner
+    GetOwnership {},
+    GetConfig {},
+    GetShareTokenAddress {},
+    /// Lists the routes the collateral gains are sold through
+    ListCollateralSwaps {},
+    /// Returns the value in ULTRA of the assets of the vault: its compounded deposit, the ULTRA
+    /// it holds and its collateral and reward token gains, claimed or not, valued at their TWAP
+    /// price. Fails while a collateral gain has no route
+    GetTotalAssets {},
+    /// Returns the shares minted for a deposit of `assets` ULTRA, the minimum shares locked by
+    /// the first deposit excluded
+    ConvertToShares {
+        assets: Uint128,
+    },
+    /// Returns the value in ULTRA of the assets sent for redeeming `shares`
+    ConvertToAssets {
+        shares: Uint128,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct MigrateMsg {}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+#[serde(rename_all = "snake_case")]
+pub enum SudoMsg {
+    /// Update the contract parameters
+    /// Can only be called by governance
+    UpdateParams {
+        name: Option<String>,
+        owner: Option<Addr>,
+    },
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ParamsResponse {
+    pub name: String,
+    pub owner: Addr,
+}
+
+#[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
+pub struct ConfigResponse {
+    pub stability_pool: Addr,
+    pub ultra_token: Addr,
+    pub address_registry: Addr,
+    pub reward_token: Addr,
+    pub reward_swap_pool: Addr,
+    pub reward_oracle: Addr,
+    pub front_end: Option<Addr>,
+    pub max_slippage: Decimal256,
+    pub max_price_impact: Decimal256,
+}
+
+/// Shares minted to the vault itself on the first deposit and never redeemed. Donating ULTRA
+/// to the vault then mostly benefits these shares, so that inflating the share price enough to
+/// round the next deposits down costs the donor about a thousand times what it takes from them
+pub const MINIMUM_SHARES: Uint128 = Uint128::new(1_000);
+
+/// Shares minted for a deposit of `assets`, one per ULTRA for the first deposit.
+/// Fails if the shares are worth nothing, as they would be diluted by any deposit
+pub fn convert_to_shares(
+    assets: Uint128,
+    total_assets: Uint128,
+    total_shares: Uint128,
+) -> StdResult<Uint128> {
+    if total_shares.is_zero() {
+        return Ok(assets);
+    }
+    if total_assets.is_zero() {
+        return Err(StdError::generic_err(
+            "Shares of the vault are backed by no assets",
+        ));
+    }
+    uint256_to_uint128(assets.full_mul(total_shares) / Uint256::from(total_assets))
+}
+
+/// Part of `total_assets` sent for redeeming `shares`, rounded down
+pub fn convert_to_assets(
+    shares: Uint128,
+    total_assets: Uint128,
+    total_shares: Uint128,
+) -> StdResult<Uint128> {
+    if total_shares.is_zero() {
+        return Ok(Uint128::zero());
+    }
+    uint256_to_uint128(shares.full_mul(total_assets) / Uint256::from(total_shares))
+}
+
+/// Difference between the spot price of a constant product pool holding `reserve_in` and
+/// `reserve_out`, and the average price of swapping `input` to `output`, swap fees included
+pub fn price_impact(
+    input: Uint128,
+    output: Uint128,
+    reserve_in: Uint128,
+    reserve_out: Uint128,
+) -> StdResult<Decimal256> {
+    if input.is_zero() || reserve_out.is_zero() {
+        return Ok(Decimal256::zero());
+    }
+    let execution_to_spot = Decimal256::from_ratio(
+        Uint256::from(output).checked_mul(Uint256::from(reserve_in))?,
+        Uint256::from(input).checked_mul(Uint256::from(reserve_out))?,
+    );
+    Ok(one_minus(execution_to_spot))
+}
+
+/// Minimum output accepted for a swap whose input is worth `expected`
+pub fn min_output(expected: Uint128, max_slippage: Decimal256) -> StdResult<Uint128> {
+    uint256_to_uint128(Uint256::from(expected) * one_minus(max_slippage))
+}
+
+/// `1 - value`, floored at zero
+fn one_minus(value: Decimal256) -> Decimal256 {
+    if value >= Decimal256::one() {
+        return Decimal256::zero();
+    }
+    Decimal256::one() - value
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn shares_conversions() {
+        // First deposit
+        assert_eq!(
+            convert_to_shares(Uint128::new(1_000), Uint128::zero(), Uint128::zero()).unwrap(),
+            Uint128::new(1_000)
+        );
+        // The deposit grew by half through harvests
+        let (total_assets, total_shares) = (Uint128::new(1_500), Uint128::new(1_000));
+        assert_eq!(
+            convert_to_shares(Uint128::new(300), total_assets, total_shares).unwrap(),
+            Uint128::new(200)
+        );
+        assert_eq!(
+            convert_to_assets(Uint128::new(200), total_assets, total_shares).unwrap(),
+            Uint128::new(300)
+        );
+        // Rounding favors the vault
+        assert_eq!(
+            convert_to_shares(Uint128::new(2), total_assets, total_shares).unwrap(),
+            Uint128::new(1)
+        );
+        assert_eq!(
+            convert_to_assets(Uint128::new(1), Uint128::new(2), Uint128::new(3)).unwrap(),
+            Uint128::zero()
+        );
+        assert_eq!(
+            convert_to_assets(Uint128::new(1), Uint128::zero(), Uint128::zero()).unwrap(),
+            Uint128::zero()
+        );
+        // The whole deposit was lost to liquidations
+        convert_to_shares(Uint128::new(1_000), Uint128::zero(), Uint128::new(1_000)).unwrap_err();
+    }
+
+    #[test]
+    fn price_impact_of_swaps() {
+        // Swapping 10 in a 1000/2000 pool returns 19.8 without fees
+        assert_eq!(
+            price_impact(
+                Uint128::new(10),
+                Uint128::new(19),
+                Uint128::new(1_000),
+                Uint128::new(2_000)
+            )
+            .unwrap(),
+            Decimal256::percent(5)
+        );
+        assert_eq!(
+            price_impact(
+                Uint128::new(10),
+                Uint128::new(20),
+                Uint128::new(1_000),
+                Uint128::new(2_000)
+            )
+            .unwrap(),
+            Decimal256::zero()
+        );
+        assert_eq!(
+            price_impact(
+                Uint128::zero(),
+                Uint128::zero(),
+                Uint128::new(1_000),
+                Uint128::new(2_000)
+            )
+            .unwrap(),
+            Decimal256::zero()
+        );
+    }
+
+    #[test]
+    fn slippage() {
+        assert_eq!(
+            min_output(Uint128::new(1_000), Decimal256::percent(1)).unwrap(),
+            Uint128::new(990)
+        );
+        assert_eq!(
+            min_output(Uint128::new(1_000), Decimal256::percent(200)).unwrap(),
+            Uint128::zero()
+        );
+    }
+}
@@ -1,7 +1,9 @@
-use cosmwasm_std::{Addr, Uint128};
+use cosmwasm_std::{Addr, Decimal256, Uint128, Uint256};
 use schemars::JsonSchema;
 use serde::{Deserialize, Serialize};
 
+use crate::asset::AssetInfo;
+
 #[derive(Serialize, Deserialize, JsonSchema, Debug, Clone, PartialEq)]
 pub struct InstantiateMsg {
     pub name: String,
@@ -12,49 +14,66 @@ pub struct InstantiateMsg {
 #[serde(rename_all = "snake_case")]
 pub enum ExecuteMsg {
     ///--- Trove Liquidation functions ---
+    // Troves are keyed by their collateral and their owner, each collateral type having its own
+    // pools, sorted troves and price feed registered in the address registry.
     // Single liquidation function. Closes the trove if its ICR is lower than the minimum collateral ratio.
     Liquidate {
+        collateral: AssetInfo,
         borrower: String,
     },
     // Liquidate a sequence of troves. Closes a maximum number of n under-collateralized Troves,
     // starting from the one with the lowest collateral ratio in the system, and moving upwards
     LiquidateTroves {
+        collateral: AssetInfo,
         n: Uint128,
     },
     // Attempt to liquidate a custom list of troves provided by the caller.
-    BatchLiquidateTroves {},
+    BatchLiquidateTroves {
+        collateral: AssetInfo,
+        borrowers: Vec<String>,
+    },
     // Send ultra_amount $ULTRA to the system and redeem the corresponding amount of collateral from as many Troves
     // as are needed to fill the redemption request.
+    // The last trove is only partially redeemed if its NICR after redemption matches
+    // partial_redemption_hint_nicr, it's then reinserted between the upper and lower hints.
     RedeemCollateral {
+        collateral: AssetInfo,
         ultra_amount: Uint128,
         first_redemption_hint: String,
         upper_partial_redemption_hint: String,
         lower_partial_redemption_hint: String,
+        partial_redemption_hint_nicr: Uint256,
         max_iterations: Uint128,
-        max_fee_percentage: Uint128,
+        max_fee_percentage: Decimal256,
     },
     // Add the borrowers's coll and debt rewards earned from redistributions, to their Trove
     ApplyPendingRewards {
+        collateral: AssetInfo,
         borrower: String,
     },
-    // Update borrower's snapshots of L_JUNO and L_ULTRADebt to reflect the current values
+    // Update borrower's snapshots of L_Coll and L_ULTRADebt to reflect the current values
     UpdateTroveRewardSnapshots {
+        collateral: AssetInfo,
         borrower: String,
     },
     // Remove borrower's stake from the totalStakes sum, and set their stake to 0
     RemoveStake {
+        collateral: AssetInfo,
         borrower: String,
     },
     // Update borrower's stake based on their latest collateral value
     UpdateStakeAndTotalStakes {
+        collateral: AssetInfo,
         borrower: String,
     },
     // Close a Trove
     CloseTrove {
+        collateral: AssetInfo,
         borrower: String,
     },
     // Push the owner's address to the Trove owners list, and record the corresponding array index on the Trove struct
     AddTroveOwnerToArray {
+        collateral: AssetInfo,
         borrower: String,
     },
 
@@ -63,67 +82,132 @@ pub enum ExecuteMsg {
 
     /// --- Trove property setters, called by BorrowerOperations ---
     SetTroveStatus {
+        collateral: AssetInfo,
         borrower: String,
         num: Uint128,
     },
     IncreaseTroveColl {
+        collateral: AssetInfo,
         borrower: String,
         coll_increase: Uint128,
     },
     DecreaseTroveColl {
+        collateral: AssetInfo,
         borrower: String,
         coll_decrease: Uint128,
     },
     IncreaseTroveDebt {
+        collateral: AssetInfo,
         borrower: String,
         debt_increase: Uint128,
     },
     DecreaseTroveDebt {
+        collateral: AssetInfo,
         borrower: String,
         debt_decrease: Uint128,
     },
 
-    SetAddresses {
-        borrower_operations_address: String,
-        active_pool_address: String,
-        default_pool_address: String,
-        stability_pool_address: String,
-        coll_surplus_pool_address: String,
-        ultra_token_address: String,
-        sorted_troves_address: String,
-        price_feed_address: String,
-    },
+    /// Point the contract to the address registry its peers are resolved from.
+    /// Can only be called by the owner while the addresses are not locked
+    SetAddressRegistry {
+        address_registry: String,
+    },
+    /// Refresh the cached peer addresses from the address registry.
+    /// Can be called by anyone while the addresses are not locked
+    SyncAddresses {},
+    /// Propose a new owner, who becomes owner once it accepts.
+    /// Can only be called by the owner
+    ProposeOwner {
+        owner: String,
+    },
+    /// Become the owner. Can only be called by the pending owner
+    AcceptOwner {},
+    /// Leave the contract without owner. Can only be called by the owner
+    RenounceOwnership {},
+    /// Permanently prevent the addresses from being changed again.
+    /// Can only be called by the owner once the addresses are synced
+    LockAddresses {},
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
 #[serde(rename_all = "snake_case")]
 pub enum QueryMsg {
     GetParams {},
-    GetTroveFromTroveOwnersArray { index: Uint128 },
-    GetTroveOwnersCount {},
-    GetNominalICR { borrower: String },
-    GetCurrentICR { borrower: String, price: Uint128 },
-    GetPendingJUNOReward {},
-    GetPendingULTRADebtReward {},
-    GetEntireDebtAndColl { borrower: String },
-    GetTCR {},
-    GetBorrowingFee { ultra_debt: Uint128 },
-    GetBorrowingFeeWithDecay { ultra_debt: Uint128 },
+    GetOwnership {},
+    GetTroveFromTroveOwnersArray {
+        collateral: AssetInfo,
+        index: Uint128,
+    },
+    GetTroveOwnersCount {
+        collateral: AssetInfo,
+    },
+    GetNominalICR {
+        collateral: AssetInfo,
+        borrower: String,
+    },
+    GetCurrentICR {
+        collateral: AssetInfo,
+        borrower: String,
+        price: Uint128,
+    },
+    GetPendingCollReward {
+        collateral: AssetInfo,
+        borrower: String,
+    },
+    GetPendingULTRADebtReward {
+        collateral: AssetInfo,
+        borrower: String,
+    },
+    GetEntireDebtAndColl {
+        collateral: AssetInfo,
+        borrower: String,
+    },
+    /// Returns the total collateral ratio of a collateral type
+    GetTCR {
+        collateral: AssetInfo,
+    },
+    GetBorrowingFee {
+        ultra_debt: Uint128,
+    },
+    GetBorrowingFeeWithDecay {
+        ultra_debt: Uint128,
+    },
     GetBorrowingRate {},
     GetBorrowingRateWithDecay {},
     GetRedemptionRate {},
     GetRedemptionRateWithDecay {},
-    GetRedemptionFeeWithDecay { juno_drawn: Uint128 },
-    GetTroveStatus {},
-    GetTroveStake {},
-    GetTroveDebt {},
-    GetTroveColl {},
+    GetRedemptionFeeWithDecay {
+        collateral: AssetInfo,
+        coll_drawn: Uint128,
+    },
+    GetTroveStatus {
+        collateral: AssetInfo,
+        borrower: String,
+    },
+    GetTroveStake {
+        collateral: AssetInfo,
+        borrower: String,
+    },
+    GetTroveDebt {
+        collateral: AssetInfo,
+        borrower: String,
+    },
+    GetTroveColl {
+        collateral: AssetInfo,
+        borrower: String,
+    },
     GetBorrowerOperationsAddress {},
     GetTroveManagerAddress {},
-    GetActivePoolAddress {},
+    GetActivePoolAddress {
+        collateral: AssetInfo,
+    },
     GetULTRATokenAddress {},
-    GetSortedTrovesAddress {},
-    GetPriceFeedAddress {},
+    GetSortedTrovesAddress {
+        collateral: AssetInfo,
+    },
+    GetPriceFeedAddress {
+        collateral: AssetInfo,
+    },
 }
 
 #[derive(Serialize, Deserialize, Clone, Debug, PartialEq, JsonSchema)]
@@ -1,4 +1,73 @@
-use cosmwasm_std::{Decimal256, StdError, StdResult, Uint128};
+use std::ops::Sub;
+
+use cosmwasm_std::{Decimal256, StdError, StdResult, Uint128, Uint256, Uint512};
+
+/// 1e18, the atomics of `Decimal256::one()`
+pub const DECIMAL_PRECISION: Uint256 = Uint256::from_u128(1_000_000_000_000_000_000u128);
+
+/// 1e20, the precision nominal collateral ratios are expressed with
+pub const NICR_PRECISION: Uint256 = Uint256::from_u128(100_000_000_000_000_000_000u128);
+
+/// `dec_pow` caps its exponent to 1000 years worth of minutes, which is more than enough to
+/// decay any base rate to zero while bounding the number of iterations
+pub const MAX_DEC_POW_MINUTES: u64 = 525_600_000;
+
+pub fn min<T: PartialOrd>(a: T, b: T) -> T {
+    if a < b {
+        a
+    } else {
+        b
+    }
+}
+
+pub fn max<T: PartialOrd>(a: T, b: T) -> T {
+    if a >= b {
+        a
+    } else {
+        b
+    }
+}
+
+pub fn get_absolute_difference<T: PartialOrd + Sub<Output = T>>(a: T, b: T) -> T {
+    if a >= b {
+        a - b
+    } else {
+        b - a
+    }
+}
+
+/// Multiplies two decimals, rounding the result half up rather than truncating it
+pub fn dec_mul(x: Decimal256, y: Decimal256) -> StdResult<Decimal256> {
+    let half_precision = Uint512::from(DECIMAL_PRECISION / Uint256::from(2u8));
+    let prod = x
+        .atomics()
+        .full_mul(y.atomics())
+        .checked_add(half_precision)?
+        / Uint512::from(DECIMAL_PRECISION);
+    Ok(Decimal256::new(Uint256::try_from(prod)?))
+}
+
+/// Raises `base` to the power of `minutes` by squaring, each step being rounded by `dec_mul`.
+///
+/// Used to decay the base rate over the minutes elapsed since the last fee operation, the
+/// exponent is capped to `MAX_DEC_POW_MINUTES`.
+pub fn dec_pow(base: Decimal256, minutes: u64) -> StdResult<Decimal256> {
+    let mut n = min(minutes, MAX_DEC_POW_MINUTES);
+    if n == 0 {
+        return Ok(Decimal256::one());
+    }
+
+    let mut x = base;
+    let mut y = Decimal256::one();
+    while n > 1 {
+        if n % 2 == 1 {
+            y = dec_mul(x, y)?;
+        }
+        x = dec_mul(x, x)?;
+        n /= 2;
+    }
+    dec_mul(x, y)
+}
 
 pub fn compute_cr(coll: Uint128, debt: Uint128, price: Decimal256) -> StdResult<Decimal256> {
     if debt != Uint128::zero() {
@@ -10,3 +79,278 @@ pub fn compute_cr(coll: Uint128, debt: Uint128, price: Decimal256) -> StdResult<
         Ok(Decimal256::MAX)
     }
 }
+
+/// Collateral ratio independent of the price, with `NICR_PRECISION`.
+/// Troves without debt get the maximum value
+pub fn compute_nominal_cr(coll: Uint128, debt: Uint128) -> StdResult<Uint256> {
+    if debt.is_zero() {
+        return Ok(Uint256::MAX);
+    }
+    let nicr = Uint256::from(coll).checked_mul(NICR_PRECISION)? / Uint256::from(debt);
+    Ok(nicr)
+}
+
+/// Square root of `value`, rounded down. Computed with Newton's method
+pub fn sqrt_uint512(value: Uint512) -> Uint512 {
+    if value.is_zero() {
+        return value;
+    }
+    let two = Uint512::from(2u128);
+    // Starts from ceil(value / 2) so that the first step can't overflow
+    let mut x = value;
+    let mut y = value - value / two;
+    while y < x {
+        x = y;
+        y = (x + value / x) / two;
+    }
+    x
+}
+
+pub fn uint128_to_uint256(value: Uint128) -> Uint256 {
+    Uint256::from(value)
+}
+
+/// Fails if `value` does not fit in 128 bits
+pub fn uint256_to_uint128(value: Uint256) -> StdResult<Uint128> {
+    Ok(Uint128::try_from(value)?)
+}
+
+pub fn uint128_to_decimal256(value: Uint128) -> Decimal256 {
+    Decimal256::from_ratio(value, 1u8)
+}
+
+/// Fails if `value` is greater than `Decimal256::MAX`
+pub fn uint256_to_decimal256(value: Uint256) -> StdResult<Decimal256> {
+    Ok(Decimal256::new(value.checked_mul(DECIMAL_PRECISION)?))
+}
+
+/// Truncates the fractional part, fails if the integer part does not fit in 128 bits
+pub fn decimal256_to_uint128(value: Decimal256) -> StdResult<Uint128> {
+    uint256_to_uint128(value.atomics() / DECIMAL_PRECISION)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn dec(atomics: u128) -> Decimal256 {
+        Decimal256::new(Uint256::from(atomics))
+    }
+
+    /// Deterministic pseudo-random values for the property tests
+    fn samples(count: usize, seed: u64) -> Vec<u128> {
+        let mut state = seed;
+        (0..count)
+            .map(|_| {
+                state = state
+                    .wrapping_mul(6_364_136_223_846_793_005)
+                    .wrapping_add(1_442_695_040_888_963_407);
+                // Spread the samples over very different orders of magnitude
+                let shift = (state >> 58) as u32 + 64;
+                (state as u128 * 0x9e37_79b9_7f4a_7c15) >> (128 - shift)
+            })
+            .collect()
+    }
+
+    #[test]
+    fn dec_mul_rounds_half_up() {
+        assert_eq!(
+            dec_mul(dec(5), dec(100_000_000_000_000_000)).unwrap(),
+            dec(1)
+        );
+        assert_eq!(
+            dec_mul(dec(4), dec(100_000_000_000_000_000)).unwrap(),
+            dec(0)
+        );
+        assert_eq!(
+            dec_mul(Decimal256::percent(150), Decimal256::percent(150)).unwrap(),
+            Decimal256::percent(225)
+        );
+        dec_mul(Decimal256::MAX, Decimal256::percent(200)).unwrap_err();
+    }
+
+    #[test]
+    fn dec_mul_properties() {
+        let values = samples(200, 7);
+        for pair in values.windows(2) {
+            let (x, y) = (dec(pair[0]), dec(pair[1]));
+            let prod = dec_mul(x, y).unwrap();
+            assert_eq!(prod, dec_mul(y, x).unwrap());
+            assert_eq!(dec_mul(x, Decimal256::one()).unwrap(), x);
+            assert_eq!(dec_mul(x, Decimal256::zero()).unwrap(), Decimal256::zero());
+            // At most one atomic unit away from the truncated product
+            let truncated = x.checked_mul(y).unwrap();
+            assert!(prod - truncated <= dec(1));
+        }
+    }
+
+    #[test]
+    fn dec_pow_edge_cases() {
+        let base = dec(999_037_758_833_783_000);
+        assert_eq!(dec_pow(base, 0).unwrap(), Decimal256::one());
+        assert_eq!(dec_pow(Decimal256::zero(), 0).unwrap(), Decimal256::one());
+        assert_eq!(dec_pow(base, 1).unwrap(), base);
+        assert_eq!(dec_pow(Decimal256::zero(), 5).unwrap(), Decimal256::zero());
+        assert_eq!(
+            dec_pow(Decimal256::one(), 1_000_000).unwrap(),
+            Decimal256::one()
+        );
+        assert_eq!(dec_pow(base, 2).unwrap(), dec_mul(base, base).unwrap());
+        assert_eq!(
+            dec_pow(base, 3).unwrap(),
+            dec_mul(base, dec_mul(base, base).unwrap()).unwrap()
+        );
+        // The exponent is capped
+        assert_eq!(
+            dec_pow(base, u64::MAX).unwrap(),
+            dec_pow(base, MAX_DEC_POW_MINUTES).unwrap()
+        );
+    }
+
+    #[test]
+    fn dec_pow_golden_vectors() {
+        // Exact powers truncated to 18 decimals, as in Liquity's LiquityMath tests
+        // decPow results must be within 1e-9 of them
+        let vectors: [(u128, u64, u128); 7] = [
+            // The minute decay factor halves the base rate in 12 hours
+            (999_037_758_833_783_000, 720, 499_999_999_999_860_015),
+            (999_037_758_833_783_000, 1_440, 249_999_999_999_860_015),
+            (500_000_000_000_000_000, 10, 976_562_500_000_000),
+            (900_000_000_000_000_000, 100, 26_561_398_887_587),
+            (123_456_789_000_000_000, 3, 1_881_676_371_789_154),
+            (999_999_000_000_000_000, 525_600, 591_200_382_239_283_333),
+            (999_999_999_999_999_999, 1_000_000, 999_999_999_999_000_000),
+        ];
+        for (base, minutes, expected) in vectors {
+            let res = dec_pow(dec(base), minutes).unwrap();
+            assert!(
+                get_absolute_difference(res, dec(expected)) < dec(1_000_000_000),
+                "{}^{} = {}, expected {}",
+                dec(base),
+                minutes,
+                res,
+                dec(expected)
+            );
+        }
+    }
+
+    #[test]
+    fn dec_pow_properties() {
+        let decay = dec(999_037_758_833_783_000);
+        let mut previous = Decimal256::one();
+        for minutes in samples(50, 11).into_iter().map(|m| (m % 100_000) as u64) {
+            let res = dec_pow(decay, minutes).unwrap();
+            // Splitting the exponent gives the same result, up to the rounding error
+            let split = dec_mul(
+                dec_pow(decay, minutes / 2).unwrap(),
+                dec_pow(decay, minutes - minutes / 2).unwrap(),
+            )
+            .unwrap();
+            assert!(get_absolute_difference(res, split) < dec(1_000_000_000));
+            previous = min(previous, res);
+        }
+        // Bases below one decay
+        assert!(dec_pow(decay, 100_000).unwrap() <= previous);
+    }
+
+    #[test]
+    fn nominal_cr() {
+        assert_eq!(
+            compute_nominal_cr(Uint128::new(200), Uint128::new(100)).unwrap(),
+            Uint256::from(2u8) * NICR_PRECISION
+        );
+        assert_eq!(
+            compute_nominal_cr(Uint128::new(1), Uint128::new(3)).unwrap(),
+            Uint256::from(33_333_333_333_333_333_333u128)
+        );
+        assert_eq!(
+            compute_nominal_cr(Uint128::MAX, Uint128::new(1)).unwrap(),
+            Uint256::from(Uint128::MAX) * NICR_PRECISION
+        );
+        assert_eq!(
+            compute_nominal_cr(Uint128::new(200), Uint128::zero()).unwrap(),
+            Uint256::MAX
+        );
+    }
+
+    #[test]
+    fn cr() {
+        assert_eq!(
+            compute_cr(
+                Uint128::new(300),
+                Uint128::new(100),
+                Decimal256::percent(50)
+            )
+            .unwrap(),
+            Decimal256::percent(150)
+        );
+        assert_eq!(
+            compute_cr(Uint128::new(300), Uint128::zero(), Decimal256::one()).unwrap(),
+            Decimal256::MAX
+        );
+    }
+
+    #[test]
+    fn min_max_and_absolute_difference() {
+        for pair in samples(100, 3).windows(2) {
+            let (a, b) = (Uint256::from(pair[0]), Uint256::from(pair[1]));
+            assert!(min(a, b) <= max(a, b));
+            assert_eq!(min(a, b) + get_absolute_difference(a, b), max(a, b));
+            assert_eq!(get_absolute_difference(a, b), get_absolute_difference(b, a));
+        }
+        assert_eq!(
+            get_absolute_difference(Decimal256::percent(20), Decimal256::percent(150)),
+            Decimal256::percent(130)
+        );
+    }
+
+    #[test]
+    fn conversions() {
+        for value in samples(100, 5).into_iter().map(Uint128::new) {
+            assert_eq!(
+                uint256_to_uint128(uint128_to_uint256(value)).unwrap(),
+                value
+            );
+            assert_eq!(
+                decimal256_to_uint128(uint128_to_decimal256(value)).unwrap(),
+                value
+            );
+            assert_eq!(
+                uint256_to_decimal256(uint128_to_uint256(value)).unwrap(),
+                uint128_to_decimal256(value)
+            );
+        }
+        assert_eq!(
+            decimal256_to_uint128(Decimal256::percent(199)).unwrap(),
+            Uint128::new(1)
+        );
+        uint256_to_uint128(Uint256::from(Uint128::MAX) + Uint256::from(1u8)).unwrap_err();
+        uint256_to_decimal256(Uint256::MAX).unwrap_err();
+        decimal256_to_uint128(Decimal256::MAX).unwrap_err();
+    }
+
+    #[test]
+    fn sqrt() {
+        for (value, root) in [
+            (0u128, 0u128),
+            (1, 1),
+            (2, 1),
+            (3, 1),
+            (4, 2),
+            (99, 9),
+            (100, 10),
+        ] {
+            assert_eq!(sqrt_uint512(Uint512::from(value)), Uint512::from(root));
+        }
+        for value in samples(100, 6) {
+            let root = sqrt_uint512(Uint512::from(value));
+            let next = root + Uint512::from(1u128);
+            assert!(root * root <= Uint512::from(value));
+            assert!(next * next > Uint512::from(value));
+        }
+        // The square of the largest 256 bits integer
+        let max = Uint512::from(Uint256::MAX);
+        assert_eq!(sqrt_uint512(max * max), max);
+        assert_eq!(sqrt_uint512(Uint512::MAX), Uint512::from(Uint256::MAX));
+    }
+}
@@ -0,0 +1,53 @@
+use cosmwasm_std::{Addr, MessageInfo, StdError, Storage};
+
+use crate::state::{load_ownership, SUDO_PARAMS};
+
+/// Roles the protocol contracts hold towards a pool
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Role {
+    BorrowerOperations,
+    TroveManager,
+    StabilityPool,
+    ActivePool,
+    DefaultPool,
+}
+
+/// Implemented by the address set of each pool to expose the contract holding a role
+pub trait RoleAddresses {
+    /// Returns the address holding `role`, or `None` if the pool does not know this role
+    fn address_of(&self, role: Role) -> Option<&Addr>;
+
+    /// Returns **true** if `sender` holds any of `roles`
+    fn has_any_role(&self, sender: &Addr, roles: &[Role]) -> bool {
+        roles
+            .iter()
+            .any(|role| self.address_of(*role) == Some(sender))
+    }
+}
+
+/// Checks that the caller holds one of `roles`, returns `err` otherwise
+pub fn only_roles<A: RoleAddresses, E>(
+    addresses: &A,
+    info: &MessageInfo,
+    roles: &[Role],
+    err: E,
+) -> Result<Addr, E> {
+    if !addresses.has_any_role(&info.sender, roles) {
+        return Err(err);
+    }
+    Ok(info.sender.clone())
+}
+
+/// Checks that the caller is the owner stored in the sudo params, returns `err` otherwise.
+/// Always returns `err` once ownership has been renounced
+pub fn only_owner<E: From<StdError>>(
+    store: &dyn Storage,
+    info: &MessageInfo,
+    err: E,
+) -> Result<Addr, E> {
+    let params = SUDO_PARAMS.load(store)?;
+    if load_ownership(store)?.renounced || params.owner != info.sender {
+        return Err(err);
+    }
+    Ok(info.sender.clone())
+}
@@ -0,0 +1,32 @@
+use cosmwasm_std::StdError;
+use thiserror::Error;
+
+#[derive(Error, Debug, PartialEq)]
+pub enum PoolError {
+    #[error("{0}")]
+    Std(#[from] StdError),
+
+    #[error("Pool: Only {denom} can be sent to the pool")]
+    InvalidFunds { denom: String },
+
+    #[error("Pool: No {denom} was sent along with the message")]
+    NoFundsReceived { denom: String },
+
+    #[error("Pool: No owner has been proposed")]
+    NoPendingOwner {},
+
+    #[error("Pool: Caller is not the pending owner")]
+    CallerIsNotPendingOwner {},
+
+    #[error("Pool: Addresses are locked")]
+    AddressesLocked {},
+
+    #[error("Pool: Address registry is not set")]
+    AddressRegistryNotSet {},
+
+    #[error("Pool: Collateral {collateral} is not supported")]
+    UnsupportedCollateral { collateral: String },
+
+    #[error("Pool: Token {token} is not the collateral of the pool")]
+    InvalidCollateralToken { token: String },
+}
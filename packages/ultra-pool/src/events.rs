@@ -0,0 +1,53 @@
+use cosmwasm_std::{Addr, Response, Uint128};
+
+/// Standard events emitted by the pools
+pub enum PoolEvent {
+    IncreaseULTRADebt { amount: Uint128 },
+    DecreaseULTRADebt { amount: Uint128 },
+    ReceiveCollateral { sender: Addr, amount: Uint128 },
+    SendCollateral { recipient: Addr, amount: Uint128 },
+    UpdateParams {},
+    ProposeOwner { owner: Addr },
+    AcceptOwner { owner: Addr },
+    RenounceOwnership {},
+    LockAddresses {},
+    SetAddressRegistry { address_registry: Addr },
+}
+
+impl PoolEvent {
+    /// Returns a response carrying the attributes of the event
+    pub fn into_response(self) -> Response {
+        match self {
+            PoolEvent::IncreaseULTRADebt { amount } => Response::new()
+                .add_attribute("action", "increase_ultra_debt")
+                .add_attribute("amount", amount),
+            PoolEvent::DecreaseULTRADebt { amount } => Response::new()
+                .add_attribute("action", "decrease_ultra_debt")
+                .add_attribute("amount", amount),
+            PoolEvent::ReceiveCollateral { sender, amount } => Response::new()
+                .add_attribute("action", "receive_collateral")
+                .add_attribute("sender", sender)
+                .add_attribute("amount", amount),
+            PoolEvent::SendCollateral { recipient, amount } => Response::new()
+                .add_attribute("action", "send_collateral")
+                .add_attribute("recipient", recipient)
+                .add_attribute("amount", amount),
+            PoolEvent::UpdateParams {} => Response::new().add_attribute("action", "update_params"),
+            PoolEvent::ProposeOwner { owner } => Response::new()
+                .add_attribute("action", "propose_owner")
+                .add_attribute("pending_owner", owner),
+            PoolEvent::AcceptOwner { owner } => Response::new()
+                .add_attribute("action", "accept_owner")
+                .add_attribute("owner", owner),
+            PoolEvent::RenounceOwnership {} => {
+                Response::new().add_attribute("action", "renounce_ownership")
+            }
+            PoolEvent::LockAddresses {} => {
+                Response::new().add_attribute("action", "lock_addresses")
+            }
+            PoolEvent::SetAddressRegistry { address_registry } => Response::new()
+                .add_attribute("action", "set_address_registry")
+                .add_attribute("address_registry", address_registry),
+        }
+    }
+}
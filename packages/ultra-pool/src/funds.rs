@@ -0,0 +1,155 @@
+use cosmwasm_std::{Api, MessageInfo, StdResult, Uint128};
+use ultra_base::asset::{native_asset, AssetInfo};
+
+use crate::PoolError;
+
+/// Returns the amount of `denom` sent along with the message.
+/// Fails if no `denom` was sent or if any other coin was attached.
+pub fn must_pay(info: &MessageInfo, denom: &str) -> Result<Uint128, PoolError> {
+    if info.funds.iter().any(|coin| coin.denom != denom) {
+        return Err(PoolError::InvalidFunds {
+            denom: denom.to_string(),
+        });
+    }
+
+    let amount = info
+        .funds
+        .iter()
+        .find(|coin| coin.denom == denom)
+        .map(|coin| coin.amount)
+        .unwrap_or_default();
+    if amount.is_zero() {
+        return Err(PoolError::NoFundsReceived {
+            denom: denom.to_string(),
+        });
+    }
+    native_asset(denom.to_string(), amount).assert_sent_native_token_balance(info)?;
+
+    Ok(amount)
+}
+
+/// Returns the amount of native `collateral` sent along with the message.
+/// Cw20 collateral is received through the cw20 hook of the pool instead
+pub fn must_pay_collateral(
+    info: &MessageInfo,
+    collateral: &AssetInfo,
+) -> Result<Uint128, PoolError> {
+    match collateral {
+        AssetInfo::NativeToken { denom } => must_pay(info, denom),
+        AssetInfo::Cw20Token { contract_addr } => Err(PoolError::InvalidFunds {
+            denom: contract_addr.to_string(),
+        }),
+    }
+}
+
+/// Returns the `amount` of cw20 tokens received from `info.sender`.
+/// Fails if the token is not the cw20 `collateral` or if nothing was received
+pub fn must_receive_collateral(
+    info: &MessageInfo,
+    collateral: &AssetInfo,
+    amount: Uint128,
+) -> Result<Uint128, PoolError> {
+    match collateral {
+        AssetInfo::Cw20Token { contract_addr } if *contract_addr == info.sender => {
+            if amount.is_zero() {
+                return Err(PoolError::NoFundsReceived {
+                    denom: contract_addr.to_string(),
+                });
+            }
+            Ok(amount)
+        }
+        _ => Err(PoolError::InvalidCollateralToken {
+            token: info.sender.to_string(),
+        }),
+    }
+}
+
+/// Returns `collateral` with the address of a cw20 token validated
+pub fn validate_collateral(api: &dyn Api, collateral: AssetInfo) -> StdResult<AssetInfo> {
+    match collateral {
+        AssetInfo::NativeToken { denom } => Ok(AssetInfo::NativeToken { denom }),
+        AssetInfo::Cw20Token { contract_addr } => Ok(AssetInfo::Cw20Token {
+            contract_addr: api.addr_validate(contract_addr.as_str())?,
+        }),
+    }
+}
+
+/// Returns the denom of a native `collateral`, for the operations only native collateral
+/// supports such as staking
+pub fn collateral_denom(collateral: &AssetInfo) -> Result<&str, PoolError> {
+    match collateral {
+        AssetInfo::NativeToken { denom } => Ok(denom),
+        AssetInfo::Cw20Token { contract_addr } => Err(PoolError::UnsupportedCollateral {
+            collateral: contract_addr.to_string(),
+        }),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::testing::mock_info;
+    use cosmwasm_std::{coins, Addr};
+
+    #[test]
+    fn must_pay_single_denom() {
+        let info = mock_info("sender", &coins(100, "ujuno"));
+        assert_eq!(must_pay(&info, "ujuno").unwrap(), Uint128::new(100u128));
+
+        let info = mock_info("sender", &[]);
+        assert_eq!(
+            must_pay(&info, "ujuno").unwrap_err(),
+            PoolError::NoFundsReceived {
+                denom: "ujuno".to_string()
+            }
+        );
+
+        let info = mock_info("sender", &coins(100, "uatom"));
+        assert_eq!(
+            must_pay(&info, "ujuno").unwrap_err(),
+            PoolError::InvalidFunds {
+                denom: "ujuno".to_string()
+            }
+        );
+    }
+
+    #[test]
+    fn collateral_is_received_from_its_token_only() {
+        let token = AssetInfo::Cw20Token {
+            contract_addr: Addr::unchecked("collateral-token"),
+        };
+        let info = mock_info("collateral-token", &[]);
+        assert_eq!(
+            must_receive_collateral(&info, &token, Uint128::new(100u128)).unwrap(),
+            Uint128::new(100u128)
+        );
+        assert_eq!(
+            must_receive_collateral(&info, &token, Uint128::zero()).unwrap_err(),
+            PoolError::NoFundsReceived {
+                denom: "collateral-token".to_string()
+            }
+        );
+
+        let info = mock_info("other-token", &[]);
+        assert_eq!(
+            must_receive_collateral(&info, &token, Uint128::new(100u128)).unwrap_err(),
+            PoolError::InvalidCollateralToken {
+                token: "other-token".to_string()
+            }
+        );
+
+        // Native collateral is only sent along with the messages
+        let juno = AssetInfo::NativeToken {
+            denom: "ujuno".to_string(),
+        };
+        let info = mock_info("ujuno", &[]);
+        must_receive_collateral(&info, &juno, Uint128::new(100u128)).unwrap_err();
+        let info = mock_info("sender", &coins(100, "collateral-token"));
+        assert_eq!(
+            must_pay_collateral(&info, &token).unwrap_err(),
+            PoolError::InvalidFunds {
+                denom: "collateral-token".to_string()
+            }
+        );
+    }
+}
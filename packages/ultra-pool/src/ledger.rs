@@ -0,0 +1,101 @@
+use cosmwasm_std::{StdError, StdResult, Uint128};
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+
+/// Collateral and debt recorded by the active and default pools
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct AssetsInPool {
+    /// Stored as `juno` by the pools deployed before they held other collateral types
+    #[serde(alias = "juno")]
+    pub coll: Uint128,
+    pub ultra_debt: Uint128,
+}
+
+impl AssetsInPool {
+    pub fn increase_coll(&mut self, amount: Uint128) -> StdResult<()> {
+        self.coll = self.coll.checked_add(amount).map_err(StdError::overflow)?;
+        Ok(())
+    }
+
+    pub fn decrease_coll(&mut self, amount: Uint128) -> StdResult<()> {
+        self.coll = self.coll.checked_sub(amount).map_err(StdError::overflow)?;
+        Ok(())
+    }
+
+    pub fn increase_debt(&mut self, amount: Uint128) -> StdResult<()> {
+        self.ultra_debt = self
+            .ultra_debt
+            .checked_add(amount)
+            .map_err(StdError::overflow)?;
+        Ok(())
+    }
+
+    pub fn decrease_debt(&mut self, amount: Uint128) -> StdResult<()> {
+        self.ultra_debt = self
+            .ultra_debt
+            .checked_sub(amount)
+            .map_err(StdError::overflow)?;
+        Ok(())
+    }
+}
+
+/// Collateral recorded by pools that hold no debt
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug, Default)]
+pub struct TotalCollsInPool {
+    #[serde(alias = "juno")]
+    pub coll: Uint128,
+}
+
+impl TotalCollsInPool {
+    pub fn increase_coll(&mut self, amount: Uint128) -> StdResult<()> {
+        self.coll = self.coll.checked_add(amount).map_err(StdError::overflow)?;
+        Ok(())
+    }
+
+    pub fn decrease_coll(&mut self, amount: Uint128) -> StdResult<()> {
+        self.coll = self.coll.checked_sub(amount).map_err(StdError::overflow)?;
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use cosmwasm_std::from_slice;
+
+    #[test]
+    fn assets_in_pool_checked_updates() {
+        let mut assets = AssetsInPool::default();
+
+        assets.increase_coll(Uint128::new(100u128)).unwrap();
+        assets.increase_debt(Uint128::new(50u128)).unwrap();
+        assets.decrease_coll(Uint128::new(40u128)).unwrap();
+        assets.decrease_debt(Uint128::new(50u128)).unwrap();
+        assert_eq!(
+            assets,
+            AssetsInPool {
+                coll: Uint128::new(60u128),
+                ultra_debt: Uint128::zero(),
+            }
+        );
+
+        assets.decrease_coll(Uint128::new(61u128)).unwrap_err();
+        assets.decrease_debt(Uint128::new(1u128)).unwrap_err();
+        assets.increase_coll(Uint128::MAX).unwrap_err();
+        assert_eq!(assets.coll, Uint128::new(60u128));
+    }
+
+    #[test]
+    fn legacy_ledgers_are_loaded() {
+        let assets: AssetsInPool = from_slice(br#"{"juno":"100","ultra_debt":"50"}"#).unwrap();
+        assert_eq!(
+            assets,
+            AssetsInPool {
+                coll: Uint128::new(100u128),
+                ultra_debt: Uint128::new(50u128),
+            }
+        );
+        let total: TotalCollsInPool = from_slice(br#"{"juno":"100"}"#).unwrap();
+        assert_eq!(total.coll, Uint128::new(100u128));
+    }
+}
@@ -0,0 +1,11 @@
+pub mod access;
+mod error;
+pub mod events;
+pub mod funds;
+pub mod ledger;
+pub mod ownership;
+pub mod registry;
+pub mod state;
+pub mod sudo;
+
+pub use crate::error::PoolError;
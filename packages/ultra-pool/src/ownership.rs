@@ -0,0 +1,171 @@
+use cosmwasm_std::{Addr, MessageInfo, Response, StdResult, Storage};
+use ultra_base::ownership::OwnershipResponse;
+
+use crate::error::PoolError;
+use crate::events::PoolEvent;
+use crate::state::{load_ownership, OWNERSHIP, SUDO_PARAMS};
+
+// The functions below don't check the caller is the owner, except `accept_owner`
+// which must be called by the pending owner. Pools check it with `access::only_owner`
+// first so that they can return their own error.
+
+/// Proposes `owner` as new owner, replacing any previous proposal
+pub fn propose_owner(store: &mut dyn Storage, owner: Addr) -> StdResult<Response> {
+    let mut ownership = load_ownership(store)?;
+    ownership.pending_owner = Some(owner.clone());
+    OWNERSHIP.save(store, &ownership)?;
+
+    Ok(PoolEvent::ProposeOwner { owner }.into_response())
+}
+
+/// Makes the pending owner the new owner, must be called by the pending owner
+pub fn accept_owner(store: &mut dyn Storage, info: &MessageInfo) -> Result<Response, PoolError> {
+    let mut ownership = load_ownership(store)?;
+    match ownership.pending_owner {
+        None => return Err(PoolError::NoPendingOwner {}),
+        Some(ref pending_owner) if *pending_owner != info.sender => {
+            return Err(PoolError::CallerIsNotPendingOwner {})
+        }
+        Some(_) => {}
+    }
+
+    let mut params = SUDO_PARAMS.load(store)?;
+    params.owner = info.sender.clone();
+    SUDO_PARAMS.save(store, &params)?;
+
+    ownership.pending_owner = None;
+    OWNERSHIP.save(store, &ownership)?;
+
+    Ok(PoolEvent::AcceptOwner {
+        owner: info.sender.clone(),
+    }
+    .into_response())
+}
+
+/// Leaves the pool without owner, only governance can give it a new one
+pub fn renounce_ownership(store: &mut dyn Storage) -> StdResult<Response> {
+    let mut ownership = load_ownership(store)?;
+    ownership.pending_owner = None;
+    ownership.renounced = true;
+    OWNERSHIP.save(store, &ownership)?;
+
+    Ok(PoolEvent::RenounceOwnership {}.into_response())
+}
+
+/// Permanently locks the addresses set of the pool
+pub fn lock_addresses(store: &mut dyn Storage) -> StdResult<Response> {
+    let mut ownership = load_ownership(store)?;
+    ownership.addresses_locked = true;
+    OWNERSHIP.save(store, &ownership)?;
+
+    Ok(PoolEvent::LockAddresses {}.into_response())
+}
+
+/// Returns an error once the addresses set has been locked
+pub fn ensure_addresses_unlocked(store: &dyn Storage) -> Result<(), PoolError> {
+    if load_ownership(store)?.addresses_locked {
+        return Err(PoolError::AddressesLocked {});
+    }
+    Ok(())
+}
+
+pub fn query_ownership(store: &dyn Storage) -> StdResult<OwnershipResponse> {
+    let params = SUDO_PARAMS.load(store)?;
+    let ownership = load_ownership(store)?;
+
+    Ok(OwnershipResponse {
+        owner: (!ownership.renounced).then_some(params.owner),
+        pending_owner: ownership.pending_owner,
+        addresses_locked: ownership.addresses_locked,
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    use crate::access::only_owner;
+    use crate::state::SudoParams;
+    use cosmwasm_std::testing::{mock_info, MockStorage};
+    use cosmwasm_std::StdError;
+
+    const OWNER: &str = "owner";
+    const NEW_OWNER: &str = "new-owner";
+
+    fn setup() -> MockStorage {
+        let mut store = MockStorage::new();
+        SUDO_PARAMS
+            .save(
+                &mut store,
+                &SudoParams {
+                    name: String::from("Pool"),
+                    owner: Addr::unchecked(OWNER),
+                },
+            )
+            .unwrap();
+        store
+    }
+
+    fn owner_of(store: &MockStorage) -> Option<Addr> {
+        query_ownership(store).unwrap().owner
+    }
+
+    #[test]
+    fn two_step_ownership_transfer() {
+        let mut store = setup();
+
+        let err = accept_owner(&mut store, &mock_info(NEW_OWNER, &[])).unwrap_err();
+        assert_eq!(err, PoolError::NoPendingOwner {});
+
+        propose_owner(&mut store, Addr::unchecked(NEW_OWNER)).unwrap();
+        assert_eq!(
+            query_ownership(&store).unwrap(),
+            OwnershipResponse {
+                owner: Some(Addr::unchecked(OWNER)),
+                pending_owner: Some(Addr::unchecked(NEW_OWNER)),
+                addresses_locked: false,
+            }
+        );
+
+        let err = accept_owner(&mut store, &mock_info(OWNER, &[])).unwrap_err();
+        assert_eq!(err, PoolError::CallerIsNotPendingOwner {});
+
+        accept_owner(&mut store, &mock_info(NEW_OWNER, &[])).unwrap();
+        assert_eq!(owner_of(&store), Some(Addr::unchecked(NEW_OWNER)));
+        assert_eq!(query_ownership(&store).unwrap().pending_owner, None);
+    }
+
+    #[test]
+    fn renounced_pool_has_no_owner() {
+        let mut store = setup();
+
+        propose_owner(&mut store, Addr::unchecked(NEW_OWNER)).unwrap();
+        renounce_ownership(&mut store).unwrap();
+        assert_eq!(owner_of(&store), None);
+
+        // The pending proposal is dropped along with the ownership
+        let err = accept_owner(&mut store, &mock_info(NEW_OWNER, &[])).unwrap_err();
+        assert_eq!(err, PoolError::NoPendingOwner {});
+
+        let err = only_owner(
+            &store,
+            &mock_info(OWNER, &[]),
+            StdError::generic_err("unauthorized"),
+        )
+        .unwrap_err();
+        assert_eq!(err, StdError::generic_err("unauthorized"));
+    }
+
+    #[test]
+    fn locked_addresses_stay_locked() {
+        let mut store = setup();
+
+        ensure_addresses_unlocked(&store).unwrap();
+        lock_addresses(&mut store).unwrap();
+        assert_eq!(
+            ensure_addresses_unlocked(&store).unwrap_err(),
+            PoolError::AddressesLocked {}
+        );
+        assert!(query_ownership(&store).unwrap().addresses_locked);
+    }
+}
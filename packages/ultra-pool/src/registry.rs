@@ -0,0 +1,25 @@
+use cosmwasm_std::{Addr, Response, StdResult, Storage};
+use cw_storage_plus::Item;
+
+use crate::error::PoolError;
+use crate::events::PoolEvent;
+
+/// Address registry the pool resolves its peers from
+pub const ADDRESS_REGISTRY: Item<Addr> = Item::new("address-registry");
+
+/// Points the pool to `address_registry`, the caller must be checked by the pool
+pub fn set_address_registry(
+    store: &mut dyn Storage,
+    address_registry: Addr,
+) -> StdResult<Response> {
+    ADDRESS_REGISTRY.save(store, &address_registry)?;
+
+    Ok(PoolEvent::SetAddressRegistry { address_registry }.into_response())
+}
+
+/// Returns the address registry of the pool
+pub fn load_address_registry(store: &dyn Storage) -> Result<Addr, PoolError> {
+    ADDRESS_REGISTRY
+        .may_load(store)?
+        .ok_or(PoolError::AddressRegistryNotSet {})
+}
@@ -0,0 +1,40 @@
+use cosmwasm_std::{Addr, StdResult, Storage};
+use cw_storage_plus::Item;
+use schemars::JsonSchema;
+use serde::{Deserialize, Serialize};
+use ultra_base::asset::{native_asset_info, AssetInfo, UJUNO_DENOM};
+
+#[derive(Serialize, Deserialize, Clone, PartialEq, JsonSchema, Debug)]
+pub struct SudoParams {
+    pub name: String,
+    pub owner: Addr,
+}
+
+pub const SUDO_PARAMS: Item<SudoParams> = Item::new("sudo-params");
+
+/// Collateral type held by the pool
+pub const COLLATERAL: Item<AssetInfo> = Item::new("collateral");
+
+/// Loads the collateral of the pool, pools deployed before it existed hold JUNO
+pub fn load_collateral(store: &dyn Storage) -> StdResult<AssetInfo> {
+    Ok(COLLATERAL
+        .may_load(store)?
+        .unwrap_or_else(|| native_asset_info(UJUNO_DENOM.to_string())))
+}
+
+#[derive(Serialize, Deserialize, Clone, Default, PartialEq, JsonSchema, Debug)]
+pub struct Ownership {
+    /// Address proposed as new owner, it becomes owner once it accepts
+    pub pending_owner: Option<Addr>,
+    /// Set once the owner renounced, no address is owner afterwards
+    pub renounced: bool,
+    /// Set once the owner locked the addresses set, it can't be changed afterwards
+    pub addresses_locked: bool,
+}
+
+pub const OWNERSHIP: Item<Ownership> = Item::new("ownership");
+
+/// Loads the ownership state, pools deployed before it existed have the default one
+pub fn load_ownership(store: &dyn Storage) -> StdResult<Ownership> {
+    Ok(OWNERSHIP.may_load(store)?.unwrap_or_default())
+}
@@ -0,0 +1,28 @@
+use cosmwasm_std::{Addr, Response, StdResult, Storage};
+
+use crate::events::PoolEvent;
+use crate::state::{load_ownership, OWNERSHIP, SUDO_PARAMS};
+
+/// Only governance can update contract params.
+/// Setting an owner drops any pending proposal and restores a renounced ownership
+pub fn sudo_update_params(
+    store: &mut dyn Storage,
+    name: Option<String>,
+    owner: Option<Addr>,
+) -> StdResult<Response> {
+    let mut params = SUDO_PARAMS.load(store)?;
+
+    params.name = name.unwrap_or(params.name);
+    if let Some(owner) = owner {
+        params.owner = owner;
+
+        let mut ownership = load_ownership(store)?;
+        ownership.pending_owner = None;
+        ownership.renounced = false;
+        OWNERSHIP.save(store, &ownership)?;
+    }
+
+    SUDO_PARAMS.save(store, &params)?;
+
+    Ok(PoolEvent::UpdateParams {}.into_response())
+}